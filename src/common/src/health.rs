@@ -0,0 +1,303 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use super::*;
+
+///
+/// a daemon is unhealthy when no message arrived from liquid tap for this period.
+pub const STALE_TAP_SECONDS: f64 = 300.0;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Health {
+    pub healthy: bool,
+    pub problems: Vec<String>,
+    #[serde(default)]
+    pub paused: bool,
+    pub tap: TapHealth,
+    #[serde(default)]
+    pub database: Option<DatabaseHealth>,
+    #[serde(default)]
+    pub buffers: BTreeMap<String, BufferHealth>,
+    #[serde(default)]
+    pub trader: Option<TraderHealth>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TapHealth {
+    pub connected: bool,
+    pub reconnects: u64,
+    ///
+    /// seconds since the last message keyed by the channel
+    pub last_message_age: BTreeMap<String, f64>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DatabaseHealth {
+    pub reachable: bool,
+    pub write_backlog: i64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BufferHealth {
+    pub length: usize,
+    pub bytes: usize,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TraderHealth {
+    pub mode: String,
+    pub buy_price: Option<i32>,
+    pub sell_price: Option<i32>,
+    pub success_rate: f64,
+    pub offset: i32,
+}
+
+#[derive(Debug, Default)]
+struct TapState {
+    connected: bool,
+    last_message: HashMap<String, Instant>,
+}
+
+static TAP_STATE: Lazy<Mutex<TapState>> = Lazy::new(|| Mutex::new(TapState::default()));
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+///
+/// a paused daemon keeps its connections but stops writing and trading.
+pub fn set_paused(paused: bool) {
+    PAUSED.store(paused, Ordering::SeqCst);
+}
+
+pub fn is_paused() -> bool {
+    PAUSED.load(Ordering::SeqCst)
+}
+
+pub fn set_tap_connected(connected: bool) {
+    TAP_STATE.lock().expect("poisoned!").connected = connected;
+}
+
+pub fn record_tap_message(channel: &str) {
+    TAP_STATE
+        .lock()
+        .expect("poisoned!")
+        .last_message
+        .insert(String::from(channel), Instant::now());
+}
+
+pub fn tap_health() -> TapHealth {
+    let state = TAP_STATE.lock().expect("poisoned!");
+    TapHealth {
+        connected: state.connected,
+        reconnects: metrics::value(metrics::TAP_RECONNECTS_TOTAL, &[]).unwrap_or_default() as u64,
+        last_message_age: state
+            .last_message
+            .iter()
+            .map(|(channel, at)| (channel.clone(), at.elapsed().as_secs_f64()))
+            .collect(),
+    }
+}
+
+impl Health {
+    ///
+    /// build a report from the tap state of this process.
+    pub fn new() -> Self {
+        Self {
+            paused: is_paused(),
+            tap: tap_health(),
+            ..Default::default()
+        }
+    }
+
+    ///
+    /// decide `healthy` and `problems` from the collected fields.
+    pub fn evaluate(mut self) -> Self {
+        let mut problems = Vec::new();
+        if !self.tap.connected {
+            problems.push(String::from("liquid tap is disconnected."));
+        }
+        match self
+            .tap
+            .last_message_age
+            .values()
+            .cloned()
+            .fold(None, |min: Option<f64>, age| match min {
+                Some(min) if min < age => Some(min),
+                _ => Some(age),
+            }) {
+            Some(age) if age > STALE_TAP_SECONDS => {
+                problems.push(format!(
+                    "no message from liquid tap for {:.0} seconds.",
+                    age
+                ));
+            }
+            None => problems.push(String::from("no message from liquid tap yet.")),
+            _ => {}
+        }
+        if let Some(database) = &self.database {
+            if !database.reachable {
+                problems.push(String::from("database is unreachable."));
+            }
+        }
+        self.healthy = problems.is_empty();
+        self.problems = problems;
+        self
+    }
+}
+
+///
+/// print a response of the status command and return the exit code.
+/// the code is 1 when the daemon reported problems.
+pub fn print_status(data: &HashMap<String, String>, json: bool, show_metrics: bool) -> i32 {
+    let health = match data.get(common_constants::DEFINED_COMMAND_OPTION_HEALTH) {
+        Some(content) => match serde_json::from_str::<Health>(content) {
+            Ok(result) => Some(result),
+            Err(result) => {
+                eprintln!(
+                    "{}",
+                    error_message_colored!("failed to parse health!\ndetails : {:?}", result)
+                );
+                None
+            }
+        },
+        None => None,
+    };
+    let healthy = health.as_ref().map(|e| e.healthy).unwrap_or(false);
+    let field = |key: &str| data.get(key).cloned().unwrap_or_default();
+
+    if json {
+        let mut object = serde_json::json!({
+            "name": field("name"),
+            "version": field("version"),
+            "description": field("description"),
+            "status": console::strip_ansi_codes(&field("status")),
+            "healthy": healthy,
+            "health": health,
+        });
+        if show_metrics {
+            object["metrics"] =
+                serde_json::Value::String(field(common_constants::DEFINED_COMMAND_OPTION_METRICS));
+        }
+        println!("{}", object);
+    } else {
+        println!(
+            "name : {}\nversion : {}\ndescription : {}\nstatus : {}",
+            field("name"),
+            field("version"),
+            field("description"),
+            field("status")
+        );
+        if let Some(health) = &health {
+            if health.paused {
+                println!("paused : {}", console::style("yes").yellow());
+            }
+            if health.healthy {
+                println!("health : {}", console::style("healthy").green());
+            } else {
+                println!("health : {}", console::style("unhealthy").red());
+                for problem in &health.problems {
+                    println!("    {}", problem);
+                }
+            }
+            println!(
+                "tap : {}, reconnects : {}",
+                if health.tap.connected {
+                    "connected"
+                } else {
+                    "disconnected"
+                },
+                health.tap.reconnects
+            );
+            for (channel, age) in &health.tap.last_message_age {
+                println!("    {} : {:.1}s ago", channel, age);
+            }
+            if let Some(database) = &health.database {
+                println!(
+                    "database : {}, write backlog : {}",
+                    if database.reachable {
+                        "reachable"
+                    } else {
+                        "unreachable"
+                    },
+                    database.write_backlog
+                );
+            }
+            if !health.buffers.is_empty() {
+                println!("buffers :");
+                for (name, buffer) in &health.buffers {
+                    println!(
+                        "    {} : {} items, {} bytes",
+                        name, buffer.length, buffer.bytes
+                    );
+                }
+            }
+            if let Some(trader) = &health.trader {
+                println!(
+                    "trader : {}\n    quotes : {} / {}\n    success rate : {:.3}\n    offset : {}",
+                    trader.mode,
+                    trader
+                        .buy_price
+                        .map(|e| e.to_string())
+                        .unwrap_or_else(|| String::from("-")),
+                    trader
+                        .sell_price
+                        .map(|e| e.to_string())
+                        .unwrap_or_else(|| String::from("-")),
+                    trader.success_rate,
+                    trader.offset
+                );
+            }
+        }
+        if show_metrics {
+            if let Some(content) = data.get(common_constants::DEFINED_COMMAND_OPTION_METRICS) {
+                print!("{}", content);
+            }
+        }
+    }
+
+    if healthy {
+        0
+    } else {
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluate_health() {
+        let mut health = Health {
+            tap: TapHealth {
+                connected: true,
+                reconnects: 0,
+                last_message_age: [(String::from("executions_cash_btcjpy"), 1.0)]
+                    .iter()
+                    .cloned()
+                    .collect(),
+            },
+            database: Some(DatabaseHealth {
+                reachable: true,
+                write_backlog: 0,
+            }),
+            ..Default::default()
+        }
+        .evaluate();
+        assert!(health.healthy);
+
+        health.tap.last_message_age.insert(
+            String::from("executions_cash_btcjpy"),
+            STALE_TAP_SECONDS + 1.0,
+        );
+        health.database = Some(DatabaseHealth {
+            reachable: false,
+            write_backlog: 3,
+        });
+        let health = health.evaluate();
+        assert!(!health.healthy);
+        assert_eq!(health.problems.len(), 2);
+    }
+}
@@ -1,6 +1,15 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::result::Result;
 
+pub mod health;
+pub mod logging;
+pub mod metrics;
+pub mod symbol;
+pub mod tail;
+
+pub use symbol::Symbol;
+
 pub mod common_constants {
     ///
     /// defined envoirnmental variables name
@@ -17,6 +26,7 @@ pub mod common_constants {
     pub const STDOUT_EXTENSION: &str = ".stdout";
     pub const STDERR_EXTENSION: &str = ".stderr";
     pub const PID_EXTENSION: &str = ".pid";
+    pub const JSON_LOG_EXTENSION: &str = ".jsonl";
 
     ///
     /// defined commands definition
@@ -25,11 +35,25 @@ pub mod common_constants {
     pub const DEFINED_COMMAND_SHUTDOWN: &str = "shutdown";
     pub const DEFINED_COMMAND_RESPONSE: &str = "response";
     pub const DEFINED_COMMAND_GET_STATUS: &str = "status";
+    pub const DEFINED_COMMAND_SET_LOG_LEVEL: &str = "log_level";
+    pub const DEFINED_COMMAND_RELOAD: &str = "reload";
+    pub const DEFINED_COMMAND_PAUSE: &str = "pause";
+    pub const DEFINED_COMMAND_RESUME: &str = "resume";
+    pub const DEFINED_COMMAND_SUBSCRIBE: &str = "subscribe";
+    pub const DEFINED_COMMAND_GET_CONFIG: &str = "config";
+    pub const DEFINED_COMMAND_SPOOL: &str = "spool";
 
     ///
     /// defined commands option definition
     pub const DEFINED_COMMAND_OPTION_SUCCESS: &str = "success";
     pub const DEFINED_COMMAND_OPTION_ERROR: &str = "error";
+    pub const DEFINED_COMMAND_OPTION_DIRECTIVE: &str = "directive";
+    pub const DEFINED_COMMAND_OPTION_METRICS: &str = "metrics";
+    pub const DEFINED_COMMAND_OPTION_HEALTH: &str = "health";
+    pub const DEFINED_COMMAND_OPTION_PATH: &str = "path";
+    pub const DEFINED_COMMAND_OPTION_EVENT: &str = "event";
+    pub const DEFINED_COMMAND_OPTION_CONFIG: &str = "config";
+    pub const DEFINED_COMMAND_OPTION_DRAIN: &str = "drain";
 
     ///
     /// database name
@@ -41,6 +65,13 @@ pub mod common_constants {
     pub const DATABASE_COLLECTION_EXECUTIONS: &str = "executions";
     pub const DATABASE_COLLECTION_ORDER_BOOK_BUY: &str = "order_book_buy";
     pub const DATABASE_COLLECTION_ORDER_BOOK_SELL: &str = "order_book_sell";
+    pub const DATABASE_COLLECTION_ORDER_BOOK_BUY_DIFFS: &str = "order_book_buy_diffs";
+    pub const DATABASE_COLLECTION_ORDER_BOOK_SELL_DIFFS: &str = "order_book_sell_diffs";
+    pub const DATABASE_COLLECTION_CANDLES_1S: &str = "candles_1s";
+    pub const DATABASE_COLLECTION_CANDLES_1M: &str = "candles_1m";
+    pub const DATABASE_COLLECTION_CANDLES_5M: &str = "candles_5m";
+    pub const DATABASE_COLLECTION_CANDLES_1H: &str = "candles_1h";
+    pub const DATABASE_COLLECTION_CANDLES_1D: &str = "candles_1d";
 }
 
 #[macro_export]
@@ -91,6 +122,18 @@ macro_rules! concat_vectors {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Enviornment {
     pub general: General,
+    ///
+    /// localhost port serving prometheus metrics keyed by the program name, such as `liquid_loggerd = 9101`.
+    #[serde(default)]
+    pub metrics: HashMap<String, u16>,
+    #[serde(default)]
+    pub writer: Writer,
+    #[serde(default)]
+    pub spool: Spool,
+    #[serde(default)]
+    pub retention: Retention,
+    #[serde(default)]
+    pub order_book: OrderBookStorage,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -100,6 +143,127 @@ pub struct General {
     pub etc_directory_path: String,
     pub log_directory_path: String,
     pub database_url: String,
+    #[serde(default)]
+    pub log_directive: Option<String>,
+}
+
+///
+/// write buffering of liquid_loggerd.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Writer {
+    ///
+    /// a collection is flushed when its buffer reaches this number of records.
+    pub batch_size: usize,
+    ///
+    /// milliseconds after which buffered records are flushed regardless of the size.
+    pub flush_interval: u64,
+    ///
+    /// records accepted before the buffers. producers wait while it is full.
+    pub queue_capacity: usize,
+}
+
+impl Default for Writer {
+    fn default() -> Self {
+        Self {
+            batch_size: 500,
+            flush_interval: 1000,
+            queue_capacity: 10000,
+        }
+    }
+}
+
+///
+/// disk spool of liquid_loggerd under [working_directory_path]spool/.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Spool {
+    ///
+    /// records are dropped instead of spooled once the segments reach this size in bytes.
+    pub max_bytes: u64,
+    ///
+    /// a new segment file is started when the current one reaches this size in bytes.
+    pub segment_bytes: u64,
+    ///
+    /// batches are spooled instead of inserted while more records than this are queued.
+    pub backlog_threshold: usize,
+    ///
+    /// milliseconds between attempts to replay the spool into the database.
+    pub replay_interval: u64,
+}
+
+impl Default for Spool {
+    fn default() -> Self {
+        Self {
+            max_bytes: 1 << 30,
+            segment_bytes: 16 << 20,
+            backlog_threshold: 50000,
+            replay_interval: 5000,
+        }
+    }
+}
+
+///
+/// expiry of the collections enforced by liquid_loggerd and `zeniesctl retention`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Retention {
+    ///
+    /// milliseconds between the enforcements by liquid_loggerd. 0 disables them.
+    pub interval: u64,
+    ///
+    /// policies keyed by the collection such as `order_book_buy`. the others are kept forever.
+    pub collections: HashMap<String, RetentionPolicy>,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            interval: 3_600_000,
+            collections: HashMap::new(),
+        }
+    }
+}
+
+///
+/// records are kept at full resolution for `full_days`, downsampled, and deleted after `delete_days`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    ///
+    /// days kept at full resolution. nothing is downsampled when it is not set.
+    pub full_days: Option<f64>,
+    ///
+    /// seconds between the records kept after `full_days`.
+    /// 0 keeps none of them, such as executions whose candles remain.
+    pub downsample: f64,
+    ///
+    /// days after which the records are deleted. nothing is deleted when it is not set.
+    pub delete_days: Option<f64>,
+}
+
+///
+/// how liquid_loggerd stores the order books.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OrderBookStorage {
+    ///
+    /// store the changed levels of each update in order_book_*_diffs between the snapshots.
+    /// every update is stored as a snapshot when it is not set.
+    /// the snapshots cannot be downsampled by the retention while it is set.
+    pub diffs: bool,
+    ///
+    /// seconds between the snapshots. the first update of each period is stored in full.
+    pub snapshot_interval: f64,
+}
+
+impl Default for OrderBookStorage {
+    fn default() -> Self {
+        Self {
+            diffs: false,
+            snapshot_interval: 60.0,
+        }
+    }
 }
 
 pub async fn load_env(path: &str) -> Result<Enviornment, String> {
@@ -124,6 +288,77 @@ pub async fn load_env(path: &str) -> Result<Enviornment, String> {
     Ok(config)
 }
 
+///
+/// write the process id to [etc_directory_path][name].pid so that zeniesctl can find the process.
+pub fn write_pid_file(etc_directory_path: &str, name: &str) -> Result<(), String> {
+    if let Err(result) = std::fs::write(
+        String::from(etc_directory_path) + name + common_constants::PID_EXTENSION,
+        std::process::id().to_string(),
+    ) {
+        return Err(error_message!(
+            "failed to write the pid file!\ndetails : {:?}",
+            result
+        ));
+    }
+    Ok(())
+}
+
+///
+/// render the configuration in use as toml.
+/// the program config is placed under [name] and every `secret_key` is redacted.
+pub fn effective_config<T: Serialize>(
+    env: &Enviornment,
+    name: &str,
+    config: Option<&T>,
+    directive: &str,
+) -> Result<String, String> {
+    fn redact(value: &mut toml::Value) {
+        if let toml::Value::Table(table) = value {
+            for (key, value) in table.iter_mut() {
+                if key == "secret_key" {
+                    *value = toml::Value::String(String::from("<redacted>"));
+                } else {
+                    redact(value);
+                }
+            }
+        }
+    }
+
+    let mut env = env.clone();
+    env.general.log_directive = Some(String::from(directive));
+    let mut value = match toml::Value::try_from(&env) {
+        Ok(result) => result,
+        Err(result) => {
+            return Err(error_message!(
+                "failed to serialize the environment!\ndetails : {:?}",
+                result
+            ));
+        }
+    };
+    if let Some(config) = config {
+        let config = match toml::Value::try_from(config) {
+            Ok(result) => result,
+            Err(result) => {
+                return Err(error_message!(
+                    "failed to serialize the config!\ndetails : {:?}",
+                    result
+                ));
+            }
+        };
+        if let toml::Value::Table(table) = &mut value {
+            table.insert(String::from(name), config);
+        }
+    }
+    redact(&mut value);
+    match toml::to_string(&value) {
+        Ok(result) => Ok(result),
+        Err(result) => Err(error_message!(
+            "failed to render the config!\ndetails : {:?}",
+            result
+        )),
+    }
+}
+
 #[macro_export]
 macro_rules! breakable_block {
     ($block:block) => {
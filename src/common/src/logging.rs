@@ -0,0 +1,130 @@
+use std::sync::{Arc, Mutex};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+use super::*;
+
+///
+/// handle to change the logging filter of a running daemon.
+/// directives follow the `RUST_LOG` syntax such as `info,liquid_loggerd=debug,database=trace`.
+#[derive(Clone, Debug)]
+pub struct LogHandle {
+    _handle: reload::Handle<EnvFilter, Registry>,
+    _directive: Arc<Mutex<String>>,
+}
+
+impl LogHandle {
+    pub fn set_directive(&self, directive: &str) -> Result<(), String> {
+        let filter = match EnvFilter::try_new(directive) {
+            Ok(result) => result,
+            Err(result) => {
+                return Err(error_message!(
+                    "invalid directive!\ndetails : {:?}\ndirective : {}",
+                    result,
+                    directive
+                ));
+            }
+        };
+        if let Err(result) = self._handle.reload(filter) {
+            return Err(error_message!(
+                "failed to reload the filter!\ndetails : {:?}",
+                result
+            ));
+        }
+        *self._directive.lock().expect("poisoned!") = String::from(directive);
+        Ok(())
+    }
+
+    ///
+    /// re-read the environment file and apply its `log_directive`.
+    pub async fn reload(&self, env_path: &str) -> Result<String, String> {
+        let env = match load_env(env_path).await {
+            Ok(result) => result,
+            Err(result) => {
+                return Err(error_message!("failed to reload!\ndetails : {}", result));
+            }
+        };
+        if let Some(directive) = &env.general.log_directive {
+            self.set_directive(directive)?;
+        }
+        Ok(self.directive())
+    }
+
+    pub fn directive(&self) -> String {
+        self._directive.lock().expect("poisoned!").clone()
+    }
+}
+
+///
+/// initialize structured logging.
+/// events are written to [log_directory_path][name].jsonl as json lines and to the journal with their fields.
+/// records emitted by the `log` crate are forwarded to the same subscriber.
+pub fn init(
+    name: &str,
+    version: &str,
+    log_directory_path: &str,
+    directive: &str,
+) -> Result<LogHandle, String> {
+    let filter = match EnvFilter::try_new(directive) {
+        Ok(result) => result,
+        Err(result) => {
+            return Err(error_message!(
+                "invalid directive!\ndetails : {:?}\ndirective : {}",
+                result,
+                directive
+            ));
+        }
+    };
+    let (filter, handle) = reload::Layer::new(filter);
+
+    let file = match std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(String::from(log_directory_path) + name + common_constants::JSON_LOG_EXTENSION)
+    {
+        Ok(result) => result,
+        Err(result) => {
+            return Err(error_message!(
+                "failed to open the log file!\ndetails : {:?}",
+                result
+            ));
+        }
+    };
+    let json_layer = tracing_subscriber::fmt::layer()
+        .json()
+        .with_current_span(true)
+        .with_span_list(true)
+        .with_ansi(false)
+        .with_writer(Mutex::new(file));
+
+    let journald_layer = match tracing_journald::layer() {
+        Ok(result) => result
+            .with_syslog_identifier(String::from(name))
+            .with_custom_fields([("VERSION", version)]),
+        Err(result) => {
+            return Err(error_message!(
+                "failed to connect to the journal!\ndetails : {:?}",
+                result
+            ));
+        }
+    };
+
+    if let Err(result) = tracing_subscriber::registry()
+        .with(filter)
+        .with(json_layer)
+        .with(journald_layer)
+        .try_init()
+    {
+        return Err(error_message!(
+            "failed to initialize the subscriber!\ndetails : {:?}",
+            result
+        ));
+    }
+    // the filter decides, so let every record of the log crate through.
+    log::set_max_level(log::LevelFilter::Trace);
+
+    Ok(LogHandle {
+        _handle: handle,
+        _directive: Arc::new(Mutex::new(String::from(directive))),
+    })
+}
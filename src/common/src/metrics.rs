@@ -0,0 +1,483 @@
+use once_cell::sync::Lazy;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::Mutex;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use super::*;
+
+///
+/// metric names recorded by the daemons
+pub const TAP_MESSAGES_TOTAL: &str = "zenies_tap_messages_total";
+pub const TAP_PARSE_FAILURES_TOTAL: &str = "zenies_tap_parse_failures_total";
+pub const TAP_RECONNECTS_TOTAL: &str = "zenies_tap_reconnects_total";
+pub const TASK_QUEUE_DEPTH: &str = "zenies_task_queue_depth";
+pub const DATABASE_INSERT_DURATION_SECONDS: &str = "zenies_database_insert_duration_seconds";
+pub const DATABASE_INSERTED_DOCUMENTS_TOTAL: &str = "zenies_database_inserted_documents_total";
+pub const DATABASE_INSERT_FAILURES_TOTAL: &str = "zenies_database_insert_failures_total";
+pub const DATABASE_DUPLICATE_DOCUMENTS_TOTAL: &str = "zenies_database_duplicate_documents_total";
+pub const WRITE_BATCH_SIZE: &str = "zenies_write_batch_size";
+pub const WRITE_BUFFER_WAIT_SECONDS: &str = "zenies_write_buffer_wait_seconds";
+pub const WRITE_BUFFERED_RECORDS: &str = "zenies_write_buffered_records";
+pub const SPOOL_BYTES: &str = "zenies_spool_bytes";
+pub const SPOOLED_RECORDS_TOTAL: &str = "zenies_spooled_records_total";
+pub const SPOOL_REPLAYED_RECORDS_TOTAL: &str = "zenies_spool_replayed_records_total";
+pub const SPOOL_DROPPED_RECORDS_TOTAL: &str = "zenies_spool_dropped_records_total";
+pub const SPOOL_CORRUPT_ENTRIES_TOTAL: &str = "zenies_spool_corrupt_entries_total";
+pub const RETENTION_REMOVED_RECORDS_TOTAL: &str = "zenies_retention_removed_records_total";
+pub const ORDERS_POSTED_TOTAL: &str = "zenies_orders_posted_total";
+pub const ORDERS_FILLED_TOTAL: &str = "zenies_orders_filled_total";
+pub const ORDER_ROUND_TRIP_SECONDS: &str = "zenies_order_round_trip_seconds";
+pub const ORDER_FILL_RATE: &str = "zenies_order_fill_rate";
+
+///
+/// upper bounds of histogram buckets in seconds
+pub const DEFAULT_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+///
+/// upper bounds of histogram buckets counting records
+pub const SIZE_BUCKETS: [f64; 11] = [
+    1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0,
+];
+
+///
+/// histograms observed with SIZE_BUCKETS instead of DEFAULT_BUCKETS
+const SIZE_HISTOGRAMS: [&str; 1] = [WRITE_BATCH_SIZE];
+
+fn buckets_of(name: &str) -> &'static [f64] {
+    if SIZE_HISTOGRAMS.contains(&name) {
+        &SIZE_BUCKETS
+    } else {
+        &DEFAULT_BUCKETS
+    }
+}
+
+const HELPS: &[(&str, &str)] = &[
+    (TAP_MESSAGES_TOTAL, "messages received from liquid tap."),
+    (
+        TAP_PARSE_FAILURES_TOTAL,
+        "messages from liquid tap which could not be parsed.",
+    ),
+    (TAP_RECONNECTS_TOTAL, "reconnections to liquid tap."),
+    (
+        TASK_QUEUE_DEPTH,
+        "records waiting to be written to the database.",
+    ),
+    (
+        DATABASE_INSERT_DURATION_SECONDS,
+        "latency of inserting documents.",
+    ),
+    (DATABASE_INSERTED_DOCUMENTS_TOTAL, "inserted documents."),
+    (DATABASE_INSERT_FAILURES_TOTAL, "failed insertions."),
+    (
+        DATABASE_DUPLICATE_DOCUMENTS_TOTAL,
+        "documents skipped because their key was already stored.",
+    ),
+    (
+        WRITE_BATCH_SIZE,
+        "records inserted by a flush of a write buffer.",
+    ),
+    (
+        WRITE_BUFFER_WAIT_SECONDS,
+        "time the oldest record of a batch waited before the flush.",
+    ),
+    (WRITE_BUFFERED_RECORDS, "records held in a write buffer."),
+    (SPOOL_BYTES, "size of the spool segments on disk."),
+    (SPOOLED_RECORDS_TOTAL, "records written to the spool."),
+    (
+        SPOOL_REPLAYED_RECORDS_TOTAL,
+        "spooled records inserted into the database.",
+    ),
+    (
+        SPOOL_DROPPED_RECORDS_TOTAL,
+        "records lost because the spool was full.",
+    ),
+    (
+        SPOOL_CORRUPT_ENTRIES_TOTAL,
+        "spool entries skipped by the integrity check.",
+    ),
+    (
+        RETENTION_REMOVED_RECORDS_TOTAL,
+        "records removed by the retention policies.",
+    ),
+    (ORDERS_POSTED_TOTAL, "orders accepted by the exchange."),
+    (ORDERS_FILLED_TOTAL, "orders filled in limited_time."),
+    (
+        ORDER_ROUND_TRIP_SECONDS,
+        "time from posting orders until the exchange responds.",
+    ),
+    (ORDER_FILL_RATE, "succeeded trades over whole trades."),
+];
+
+type Labels = Vec<(String, String)>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+#[derive(Clone, Debug)]
+enum Series {
+    Value(f64),
+    Histogram {
+        buckets: Vec<u64>,
+        sum: f64,
+        count: u64,
+    },
+}
+
+#[derive(Clone, Debug)]
+struct Family {
+    kind: Kind,
+    series: BTreeMap<Labels, Series>,
+}
+
+///
+/// registry of counters, gauges and histograms.
+/// use the free functions of this module to record into the registry shared by the process.
+#[derive(Debug, Default)]
+pub struct Registry {
+    _families: Mutex<BTreeMap<String, Family>>,
+}
+
+static REGISTRY: Lazy<Registry> = Lazy::new(Registry::new);
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn update<F: FnOnce(&mut Series)>(
+        &self,
+        name: &str,
+        kind: Kind,
+        labels: &[(&str, &str)],
+        update: F,
+    ) {
+        let mut families = self._families.lock().expect("poisoned!");
+        let family = families.entry(String::from(name)).or_insert(Family {
+            kind,
+            series: BTreeMap::new(),
+        });
+        if family.kind != kind {
+            tracing::warn!(name = name, "metric was recorded with another kind!");
+            return;
+        }
+        let labels = labels
+            .iter()
+            .map(|(key, value)| (String::from(*key), String::from(*value)))
+            .collect::<Labels>();
+        let series = family.series.entry(labels).or_insert_with(|| match kind {
+            Kind::Histogram => Series::Histogram {
+                buckets: vec![0; buckets_of(name).len()],
+                sum: 0.0,
+                count: 0,
+            },
+            _ => Series::Value(0.0),
+        });
+        update(series);
+    }
+
+    pub fn counter_increment(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.update(name, Kind::Counter, labels, |series| {
+            if let Series::Value(current) = series {
+                *current += value;
+            }
+        });
+    }
+
+    pub fn gauge_set(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.update(name, Kind::Gauge, labels, |series| {
+            if let Series::Value(current) = series {
+                *current = value;
+            }
+        });
+    }
+
+    pub fn gauge_add(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.update(name, Kind::Gauge, labels, |series| {
+            if let Series::Value(current) = series {
+                *current += value;
+            }
+        });
+    }
+
+    pub fn histogram_observe(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.update(name, Kind::Histogram, labels, |series| {
+            if let Series::Histogram {
+                buckets,
+                sum,
+                count,
+            } = series
+            {
+                for (bucket, bound) in buckets.iter_mut().zip(buckets_of(name).iter()) {
+                    if value <= *bound {
+                        *bucket += 1;
+                    }
+                }
+                *sum += value;
+                *count += 1;
+            }
+        });
+    }
+
+    ///
+    /// current value of a counter or a gauge.
+    pub fn value(&self, name: &str, labels: &[(&str, &str)]) -> Option<f64> {
+        let families = self._families.lock().expect("poisoned!");
+        let labels = labels
+            .iter()
+            .map(|(key, value)| (String::from(*key), String::from(*value)))
+            .collect::<Labels>();
+        match families.get(name)?.series.get(&labels)? {
+            Series::Value(value) => Some(*value),
+            Series::Histogram { .. } => None,
+        }
+    }
+
+    ///
+    /// render all metrics in prometheus text format.
+    pub fn render(&self) -> String {
+        let families = self._families.lock().expect("poisoned!");
+        let mut buffer = String::new();
+        for (name, family) in families.iter() {
+            if let Some((_, help)) = HELPS.iter().find(|(key, _)| key == name) {
+                let _ = writeln!(buffer, "# HELP {} {}", name, help);
+            }
+            let kind = match family.kind {
+                Kind::Counter => "counter",
+                Kind::Gauge => "gauge",
+                Kind::Histogram => "histogram",
+            };
+            let _ = writeln!(buffer, "# TYPE {} {}", name, kind);
+            for (labels, series) in family.series.iter() {
+                match series {
+                    Series::Value(value) => {
+                        let _ =
+                            writeln!(buffer, "{}{} {}", name, format_labels(labels, None), value);
+                    }
+                    Series::Histogram {
+                        buckets,
+                        sum,
+                        count,
+                    } => {
+                        for (bucket, bound) in buckets.iter().zip(buckets_of(name).iter()) {
+                            let _ = writeln!(
+                                buffer,
+                                "{}_bucket{} {}",
+                                name,
+                                format_labels(labels, Some(&bound.to_string())),
+                                bucket
+                            );
+                        }
+                        let _ = writeln!(
+                            buffer,
+                            "{}_bucket{} {}",
+                            name,
+                            format_labels(labels, Some("+Inf")),
+                            count
+                        );
+                        let _ = writeln!(
+                            buffer,
+                            "{}_sum{} {}",
+                            name,
+                            format_labels(labels, None),
+                            sum
+                        );
+                        let _ = writeln!(
+                            buffer,
+                            "{}_count{} {}",
+                            name,
+                            format_labels(labels, None),
+                            count
+                        );
+                    }
+                }
+            }
+        }
+        buffer
+    }
+}
+
+fn format_labels(labels: &[(String, String)], le: Option<&str>) -> String {
+    let mut pairs = labels
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "{}=\"{}\"",
+                key,
+                value
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+                    .replace('\n', "\\n")
+            )
+        })
+        .collect::<Vec<String>>();
+    if let Some(le) = le {
+        pairs.push(format!("le=\"{}\"", le));
+    }
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+pub fn counter_increment(name: &str, labels: &[(&str, &str)], value: f64) {
+    REGISTRY.counter_increment(name, labels, value);
+}
+
+pub fn gauge_set(name: &str, labels: &[(&str, &str)], value: f64) {
+    REGISTRY.gauge_set(name, labels, value);
+}
+
+pub fn gauge_add(name: &str, labels: &[(&str, &str)], value: f64) {
+    REGISTRY.gauge_add(name, labels, value);
+}
+
+pub fn histogram_observe(name: &str, labels: &[(&str, &str)], value: f64) {
+    REGISTRY.histogram_observe(name, labels, value);
+}
+
+pub fn value(name: &str, labels: &[(&str, &str)]) -> Option<f64> {
+    REGISTRY.value(name, labels)
+}
+
+pub fn render() -> String {
+    REGISTRY.render()
+}
+
+///
+/// how long the listener waits after failing to accept a connection.
+const ACCEPT_BACKOFF: std::time::Duration = std::time::Duration::from_millis(100);
+
+///
+/// serve the registry at http://127.0.0.1:[port]/metrics.
+/// the listener runs on a spawned task until the runtime shuts down.
+pub async fn serve(port: u16) -> Result<tokio::task::JoinHandle<()>, String> {
+    let listener = match tokio::net::TcpListener::bind(("127.0.0.1", port)).await {
+        Ok(result) => result,
+        Err(result) => {
+            return Err(error_message!(
+                "failed to bind the metrics port!\ndetails : {:?}\nport : {}",
+                result,
+                port
+            ));
+        }
+    };
+    Ok(tokio::spawn(async move {
+        loop {
+            let (stream, _) = match listener.accept().await {
+                Ok(result) => result,
+                Err(result) => {
+                    tracing::warn!(details = ?result, "failed to accept a metrics request!");
+                    // such as running out of file descriptors, which would not clear at once.
+                    tokio::time::sleep(ACCEPT_BACKOFF).await;
+                    continue;
+                }
+            };
+            tokio::spawn(async move {
+                if let Err(result) = respond(stream).await {
+                    tracing::debug!(details = %result, "failed to respond metrics!");
+                }
+            });
+        }
+    }))
+}
+
+async fn respond(mut stream: tokio::net::TcpStream) -> Result<(), String> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") && request.len() < 8192 {
+        match stream.read(&mut buffer).await {
+            Ok(0) => break,
+            Ok(size) => request.extend_from_slice(&buffer[..size]),
+            Err(result) => {
+                return Err(error_message!(
+                    "failed to read the request!\ndetails : {:?}",
+                    result
+                ));
+            }
+        }
+    }
+    let request = String::from_utf8_lossy(&request);
+    let response = match request.lines().next() {
+        Some(line) if line.starts_with("GET /metrics ") || line.starts_with("GET / ") => {
+            let body = render();
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+        }
+        _ => {
+            String::from("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+        }
+    };
+    if let Err(result) = stream.write_all(response.as_bytes()).await {
+        return Err(error_message!(
+            "failed to write the response!\ndetails : {:?}",
+            result
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_prometheus_text() {
+        let registry = Registry::new();
+        registry.counter_increment(TAP_MESSAGES_TOTAL, &[("channel", "executions")], 1.0);
+        registry.counter_increment(TAP_MESSAGES_TOTAL, &[("channel", "executions")], 2.0);
+        registry.gauge_set(TASK_QUEUE_DEPTH, &[], 4.0);
+        registry.gauge_add(TASK_QUEUE_DEPTH, &[], -1.0);
+        registry.histogram_observe(DATABASE_INSERT_DURATION_SECONDS, &[], 0.02);
+        registry.histogram_observe(DATABASE_INSERT_DURATION_SECONDS, &[], 20.0);
+        registry.histogram_observe(WRITE_BATCH_SIZE, &[], 300.0);
+
+        assert_eq!(
+            registry.value(TAP_MESSAGES_TOTAL, &[("channel", "executions")]),
+            Some(3.0)
+        );
+        assert_eq!(registry.value(TASK_QUEUE_DEPTH, &[]), Some(3.0));
+
+        let text = registry.render();
+        assert!(text.contains("# TYPE zenies_tap_messages_total counter\n"));
+        assert!(text.contains("zenies_tap_messages_total{channel=\"executions\"} 3\n"));
+        assert!(text.contains("zenies_task_queue_depth 3\n"));
+        assert!(text.contains("zenies_database_insert_duration_seconds_bucket{le=\"0.01\"} 0\n"));
+        assert!(text.contains("zenies_database_insert_duration_seconds_bucket{le=\"0.025\"} 1\n"));
+        assert!(text.contains("zenies_database_insert_duration_seconds_bucket{le=\"+Inf\"} 2\n"));
+        assert!(text.contains("zenies_database_insert_duration_seconds_count 2\n"));
+        assert!(text.contains("zenies_write_batch_size_bucket{le=\"250\"} 0\n"));
+        assert!(text.contains("zenies_write_batch_size_bucket{le=\"500\"} 1\n"));
+    }
+
+    #[tokio::test]
+    async fn serve_metrics_over_http() {
+        counter_increment(TAP_RECONNECTS_TOTAL, &[], 1.0);
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+        serve(port).await.unwrap();
+
+        let mut stream = tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("zenies_tap_reconnects_total"));
+    }
+}
@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+use std::result::Result;
+
+use crate::error_message;
+
+///
+/// separates the exchange and the product, such as `liquid:btc_jpy`.
+pub const SYMBOL_SEPARATOR: char = ':';
+
+///
+/// a product of an exchange. each symbol is recorded in its own database.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Symbol {
+    pub exchange: String,
+    ///
+    /// lower case words joined by `_`, such as `btc_jpy`.
+    pub product: String,
+}
+
+impl Default for Symbol {
+    ///
+    /// the product recorded by liquid_loggerd.
+    fn default() -> Self {
+        Self {
+            exchange: String::from("liquid"),
+            product: String::from("btc_jpy"),
+        }
+    }
+}
+
+impl Symbol {
+    pub fn new(exchange: &str, product: &str) -> Result<Self, String> {
+        for name in [exchange, product] {
+            if name.is_empty()
+                || name.starts_with('_')
+                || name.ends_with('_')
+                || name.contains("__")
+                || !name
+                    .chars()
+                    .all(|e| e.is_ascii_lowercase() || e.is_ascii_digit() || e == '_')
+            {
+                return Err(error_message!(
+                    "invalid symbol! names must be lower case words joined by _\nsymbol : {}{}{}",
+                    exchange,
+                    SYMBOL_SEPARATOR,
+                    product
+                ));
+            }
+        }
+        Ok(Self {
+            exchange: String::from(exchange),
+            product: String::from(product),
+        })
+    }
+
+    ///
+    /// parse `exchange:product`.
+    pub fn generate_from_string(symbol: &str) -> Result<Self, String> {
+        match symbol.split_once(SYMBOL_SEPARATOR) {
+            Some((exchange, product)) => Self::new(exchange, product),
+            None => Err(error_message!(
+                "invalid symbol! such as liquid:btc_jpy\nsymbol : {}",
+                symbol
+            )),
+        }
+    }
+
+    ///
+    /// the database of the symbol, the product and the exchange in camel case such as `BtcJpy_In_Liquid`.
+    pub fn database_name(&self) -> String {
+        format!(
+            "{}{}{}",
+            camel_case(&self.product),
+            DATABASE_NAME_INFIX,
+            camel_case(&self.exchange)
+        )
+    }
+
+    ///
+    /// the symbol of a database named by `database_name`. other databases are none.
+    pub fn generate_from_database_name(name: &str) -> Option<Self> {
+        let (product, exchange) = name.split_once(DATABASE_NAME_INFIX)?;
+        Self::new(&snake_case(exchange)?, &snake_case(product)?).ok()
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+const DATABASE_NAME_INFIX: &str = "_In_";
+
+fn camel_case(name: &str) -> String {
+    name.split('_')
+        .map(|e| {
+            let mut characters = e.chars();
+            match characters.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + characters.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+///
+/// the inverse of `camel_case`. none if it does not start with an upper case letter.
+fn snake_case(name: &str) -> Option<String> {
+    if !name.starts_with(|e: char| e.is_ascii_uppercase()) {
+        return None;
+    }
+    let mut snake = String::with_capacity(name.len() + 4);
+    for character in name.chars() {
+        if character.is_ascii_uppercase() {
+            if !snake.is_empty() {
+                snake.push('_');
+            }
+            snake.push(character.to_ascii_lowercase());
+        } else {
+            snake.push(character);
+        }
+    }
+    Some(snake)
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}{}", self.exchange, SYMBOL_SEPARATOR, self.product)
+    }
+}
+
+impl TryFrom<String> for Symbol {
+    type Error = String;
+
+    fn try_from(symbol: String) -> Result<Self, Self::Error> {
+        Self::generate_from_string(&symbol)
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(symbol: Symbol) -> Self {
+        symbol.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common_constants;
+
+    #[test]
+    fn database_names() {
+        let symbol = Symbol::default();
+        assert_eq!(symbol.database_name(), common_constants::DATABASE_NAME);
+        assert_eq!(symbol.to_string(), "liquid:btc_jpy");
+        assert_eq!(Symbol::generate_from_string("liquid:btc_jpy"), Ok(symbol));
+
+        let symbol = Symbol::new("bitflyer", "fx_btc_jpy").unwrap();
+        assert_eq!(symbol.database_name(), "FxBtcJpy_In_Bitflyer");
+        assert_eq!(
+            Symbol::generate_from_database_name("FxBtcJpy_In_Bitflyer"),
+            Some(symbol)
+        );
+        assert_eq!(Symbol::generate_from_database_name("admin"), None);
+        assert_eq!(
+            Symbol::generate_from_database_name("btcjpy_In_Liquid"),
+            None
+        );
+
+        assert!(Symbol::generate_from_string("btc_jpy").is_err());
+        assert!(Symbol::generate_from_string("liquid:BTC_JPY").is_err());
+        assert!(Symbol::generate_from_string("liquid:btc__jpy").is_err());
+    }
+}
@@ -0,0 +1,46 @@
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+///
+/// events kept for a slow subscriber before it starts to miss them
+pub const CAPACITY: usize = 1024;
+
+static EVENTS: Lazy<broadcast::Sender<String>> = Lazy::new(|| broadcast::channel(CAPACITY).0);
+
+///
+/// publish an event to the subscribers of the `subscribe` command.
+/// nothing is serialized while nobody is subscribing.
+pub fn publish<T: Serialize>(event: &T) {
+    if EVENTS.receiver_count() == 0 {
+        return;
+    }
+    match serde_json::to_string(event) {
+        Ok(result) => {
+            let _ = EVENTS.send(result);
+        }
+        Err(result) => {
+            tracing::debug!(details = ?result, "failed to serialize an event!");
+        }
+    }
+}
+
+pub fn subscribe() -> broadcast::Receiver<String> {
+    EVENTS.subscribe()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn publish_to_subscribers() {
+        publish(&"nobody is listening");
+        let mut receiver = subscribe();
+        publish(&[("channel", "executions_cash_btcjpy")]);
+        assert_eq!(
+            receiver.recv().await.unwrap(),
+            "[[\"channel\",\"executions_cash_btcjpy\"]]"
+        );
+    }
+}
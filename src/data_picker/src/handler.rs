@@ -1,95 +1,40 @@
 use super::*;
 
+///
+/// the time of the oldest record when `order` is 1 and of the newest when it is -1.
+async fn edge_time(
+    database: &dyn Store,
+    collection: &str,
+    order: i64,
+) -> Result<DateTime<Utc>, String> {
+    let field = store::time_field(collection)?;
+    let records = database
+        .query(collection, &Timeline::all().order(order).limit(Some(1)))
+        .await?;
+    let timestamp = match records
+        .first()
+        .and_then(|e| e.get(field))
+        .and_then(|e| e.as_f64())
+    {
+        Some(content) => content,
+        None => return Err(error_message!("{} is empty!", collection)),
+    };
+    Ok(Utc.timestamp_nanos((timestamp * 1_000_000_000.0) as i64))
+}
+
 pub async fn from_handler(
-    database: &Database,
+    database: &dyn Store,
     collection: &str,
     from: &str,
 ) -> Result<DateTime<Utc>, ()> {
     match from {
-        "oldest" => {
-            let pipeline = match create_aggregate_timeline_pipeline(collection, 1, 1) {
-                Ok(result) => result,
-                Err(result) => {
-                    eprintln!("failed to create pipeline\ndetails : {}", result);
-                    return Err(());
-                }
-            };
-            let start_time_timestamp = match collection {
-                common_constants::DATABASE_COLLECTION_TICKER => {
-                    let mut cursor = match stream::DatabaseStream::<data::Ticker>::aggregate(
-                        database, None, pipeline, None,
-                    )
-                    .await
-                    {
-                        Ok(result) => result,
-                        Err(result) => {
-                            eprintln!("failed to get min timestamp\ndetails : {}", result);
-                            return Err(());
-                        }
-                    };
-                    match cursor.next().await {
-                        Some(content) => content.expect("failed to get!").timestamp,
-                        None => {
-                            eprintln!("failed to get min timestamp");
-                            return Err(());
-                        }
-                    }
-                }
-
-                common_constants::DATABASE_COLLECTION_EXECUTIONS => {
-                    let mut cursor = match stream::DatabaseStream::<data::Execution>::aggregate(
-                        database, None, pipeline, None,
-                    )
-                    .await
-                    {
-                        Ok(result) => result,
-                        Err(result) => {
-                            eprintln!("failed to get min timestamp\ndetails : {}", result);
-                            return Err(());
-                        }
-                    };
-                    match cursor.next().await {
-                        Some(content) => content.expect("failed to get!").timestamp,
-                        None => {
-                            eprintln!("failed to get min timestamp");
-                            return Err(());
-                        }
-                    }
-                }
-                common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY
-                | common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL => {
-                    let mut cursor = match stream::DatabaseStream::<data::OrderBook>::aggregate(
-                        database,
-                        Some(collection),
-                        pipeline,
-                        None,
-                    )
-                    .await
-                    {
-                        Ok(result) => result,
-                        Err(result) => {
-                            eprintln!("failed to get min timestamp\ndetails : {}", result);
-                            return Err(());
-                        }
-                    };
-                    match cursor.next().await {
-                        Some(content) => content.expect("failed to get!").received_at,
-                        None => {
-                            eprintln!("failed to get min timestamp");
-                            return Err(());
-                        }
-                    }
-                }
-                _ => {
-                    eprintln!("invalid collection!");
-                    return Err(());
-                }
-            };
-            Ok(Utc.timestamp(
-                start_time_timestamp.trunc() as i64,
-                (start_time_timestamp.fract() * 1_000_000_000.0) as u32,
-            ))
-        }
+        "oldest" => match edge_time(database, collection, 1).await {
+            Ok(result) => Ok(result),
+            Err(result) => {
+                eprintln!("failed to get min timestamp\ndetails : {}", result);
+                Err(())
+            }
+        },
 
         _ => match DateTime::parse_from_rfc3339(from) {
             Ok(result) => Ok(result.with_timezone(&Utc)),
@@ -102,95 +47,18 @@ pub async fn from_handler(
 }
 
 pub async fn to_handler(
-    database: &Database,
+    database: &dyn Store,
     collection: &str,
     to: &str,
 ) -> Result<DateTime<Utc>, ()> {
     match to {
-        "newest" => {
-            let pipeline = match create_aggregate_timeline_pipeline(collection, -1, 1) {
-                Ok(result) => result,
-                Err(result) => {
-                    eprintln!("failed to create pipeline\ndetails : {}", result);
-                    return Err(());
-                }
-            };
-            let end_time_timestamp = match collection {
-                common_constants::DATABASE_COLLECTION_TICKER => {
-                    let mut cursor = match stream::DatabaseStream::<data::Ticker>::aggregate(
-                        database, None, pipeline, None,
-                    )
-                    .await
-                    {
-                        Ok(result) => result,
-                        Err(result) => {
-                            eprintln!("failed to get min timestamp\ndetails : {}", result);
-                            return Err(());
-                        }
-                    };
-                    match cursor.next().await {
-                        Some(content) => content.expect("failed to get!").timestamp,
-                        None => {
-                            eprintln!("failed to get min timestamp");
-                            return Err(());
-                        }
-                    }
-                }
-
-                common_constants::DATABASE_COLLECTION_EXECUTIONS => {
-                    let mut cursor = match stream::DatabaseStream::<data::Execution>::aggregate(
-                        database, None, pipeline, None,
-                    )
-                    .await
-                    {
-                        Ok(result) => result,
-                        Err(result) => {
-                            eprintln!("failed to get min timestamp\ndetails : {}", result);
-                            return Err(());
-                        }
-                    };
-                    match cursor.next().await {
-                        Some(content) => content.expect("failed to get!").timestamp,
-                        None => {
-                            eprintln!("failed to get min timestamp");
-                            return Err(());
-                        }
-                    }
-                }
-                common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY
-                | common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL => {
-                    let mut cursor = match stream::DatabaseStream::<data::OrderBook>::aggregate(
-                        database,
-                        Some(collection),
-                        pipeline,
-                        None,
-                    )
-                    .await
-                    {
-                        Ok(result) => result,
-                        Err(result) => {
-                            eprintln!("failed to get min timestamp\ndetails : {}", result);
-                            return Err(());
-                        }
-                    };
-                    match cursor.next().await {
-                        Some(content) => content.expect("failed to get!").received_at,
-                        None => {
-                            eprintln!("failed to get min timestamp");
-                            return Err(());
-                        }
-                    }
-                }
-                _ => {
-                    eprintln!("invalid collection!");
-                    return Err(());
-                }
-            };
-            Ok(Utc.timestamp(
-                end_time_timestamp.trunc() as i64,
-                (end_time_timestamp.fract() * 1_000_000_000.0) as u32,
-            ))
-        }
+        "newest" => match edge_time(database, collection, -1).await {
+            Ok(result) => Ok(result),
+            Err(result) => {
+                eprintln!("failed to get max timestamp\ndetails : {}", result);
+                Err(())
+            }
+        },
 
         _ => match DateTime::parse_from_rfc3339(to) {
             Ok(result) => Ok(result.with_timezone(&Utc)),
@@ -201,3 +69,114 @@ pub async fn to_handler(
         },
     }
 }
+
+///
+/// write the records as a compressed dataset. returns the number of the records.
+pub async fn write_dataset<T: serde::Serialize>(
+    target: &str,
+    header: &DatasetHeader,
+    records: &[T],
+) -> Result<u64, String> {
+    let mut writer = DatasetWriter::create(target, header).await?;
+    for record in records {
+        writer.push_record(record).await?;
+    }
+    writer.finish().await
+}
+
+///
+/// write the records as a parquet file. returns the number of the rows.
+pub async fn write_parquet<T: columnar::Columnar + Send>(
+    target: &str,
+    records: Vec<T>,
+    options: &columnar::ParquetOptions,
+) -> Result<u64, String> {
+    columnar::write_parquet(
+        target,
+        futures::stream::iter(records.into_iter().map(Ok)),
+        options,
+    )
+    .await
+}
+
+///
+/// the records of the timeline, skipping the records within `period` seconds after the last kept one.
+pub async fn pick<T: Record + Send + 'static>(
+    database: &dyn Store,
+    collection: &str,
+    timeline: &Timeline,
+    period: f64,
+) -> Result<Vec<T>, String> {
+    let document_count = database.count(collection, timeline).await? as usize;
+    let mut record_stream = match database.stream_records::<T>(collection, timeline).await {
+        Ok(result) => result,
+        Err(result) => {
+            return Err(error_message!("failed to request!\ndetails : {}", result));
+        }
+    };
+    let bar = ProgressBar::new(document_count as u64);
+    bar.set_style(ProgressStyle::default_bar()
+        .template("Downloading {spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta})",).progress_chars("#>-"));
+    let mut data = Vec::with_capacity(document_count);
+    let mut latest_time = 0.0;
+    loop {
+        tokio::select! {
+            Some(result) = record_stream.next() => {
+                bar.inc(1);
+                match result {
+                    Ok(result) => {
+                        if result.time() >= latest_time {
+                            latest_time = result.time() + period;
+                            data.push(result);
+                        }
+                    }
+                    Err(result) => {
+                        eprintln!("got malformed data!\ndetails : {}", result);
+                    }
+                }
+            }
+            else => break,
+        }
+    }
+    bar.finish();
+    Ok(data)
+}
+
+///
+/// write the records of `collection` from `start_time` to `end_time` as the type of the target file.
+pub async fn export<T: Record + columnar::Columnar + Send>(
+    target: &str,
+    collection: &str,
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    data: Vec<T>,
+    options: &columnar::ParquetOptions,
+) -> Result<(), String> {
+    match target.split('.').next_back() {
+        Some("csv") => {
+            let mut csv_writer = match csv::Writer::from_path(target) {
+                Ok(result) => result,
+                Err(_) => {
+                    return Err(error_message!("failed to create TARGET file."));
+                }
+            };
+            for d in data {
+                if let Err(result) = csv_writer.serialize(d) {
+                    return Err(error_message!("failed to write! {:?}", result));
+                }
+            }
+            Ok(())
+        }
+        Some("zst") => {
+            let header = DatasetHeader::new(collection, start_time, end_time);
+            write_dataset(target, &header, &data).await.map(|_| ())
+        }
+        Some("parquet") => write_parquet(target, data, options).await.map(|_| ()),
+        Some("json") => {
+            local::LocalRecords::from_records(collection, start_time, end_time, data)?
+                .write(target)
+                .await
+        }
+        _ => Err(error_message!("{} is not supported!", target)),
+    }
+}
@@ -5,7 +5,7 @@ use chrono::DateTime;
 use clap::{Arg, Command};
 use common::*;
 use database::*;
-use futures::stream::{Stream, StreamExt};
+use futures::stream::StreamExt;
 use indicatif::{ProgressBar, ProgressStyle};
 
 #[tokio::main]
@@ -21,15 +21,38 @@ async fn main() {
                 .long("period")
                 .help("skipping period in seconds. It must be float number.")
         )
+        .arg(
+            Arg::new("ROW_GROUP_ROWS")
+                .value_name("ROWS")
+                .long("row-group-rows")
+                .help("rows of a row group of parquet files. 1048576 by default.")
+        )
+        .arg(
+            Arg::new("COMPRESSION")
+                .value_name("COMPRESSION")
+                .long("compression")
+                .help("compression of parquet files. none, snappy or zstd. snappy by default.")
+        )
+        .arg(
+            Arg::new("long")
+                .long("long")
+                .help("write the order books of parquet files as a row per price level.")
+        )
+        .arg(
+            Arg::new("SYMBOL")
+                .value_name("SYMBOL")
+                .long("symbol")
+                .help("exchange and product such as liquid:btc_jpy. liquid:btc_jpy by default.")
+        )
         .arg(
             Arg::new("URL")
                 .required(true)
-                .help("mongodb url")
+                .help("database url such as mongodb://... or sqlite://...")
         )
         .arg(
             Arg::new("TARGET")
                 .required(true)
-                .help("output file path. .csv and .json are supported.")
+                .help("output file path. .csv, .json, .zst and .parquet are supported.")
         )
         .arg(
             Arg::new("COLLECTION")
@@ -55,7 +78,17 @@ async fn main() {
         matches.value_of("FROM"),
         matches.value_of("TO"),
     ) {
-        let database = match Database::new(url, common_constants::DATABASE_NAME).await {
+        let symbol = match matches.value_of("SYMBOL") {
+            Some(result) => match Symbol::generate_from_string(result) {
+                Ok(result) => result,
+                Err(result) => {
+                    eprintln!("{}", result);
+                    return;
+                }
+            },
+            None => Symbol::default(),
+        };
+        let database = match open_store_for(url, &symbol).await {
             Ok(result) => result,
             Err(result) => {
                 eprintln!("{}", result);
@@ -91,7 +124,7 @@ async fn main() {
         }
 
         match file_type {
-            "csv" | "json" => {}
+            "csv" | "json" | "zst" | "parquet" => {}
             _ => {
                 eprintln!("{} is not supported!", file_type);
                 return;
@@ -109,9 +142,29 @@ async fn main() {
             None => 0.0f64,
         };
 
+        let mut options = columnar::ParquetOptions::default();
+        if let Some(rows) = matches.value_of("ROW_GROUP_ROWS") {
+            options.row_group_rows = match rows.parse::<usize>() {
+                Ok(result) => result,
+                Err(result) => {
+                    eprintln!("ROWS must be a positive integer! {}", result);
+                    return;
+                }
+            };
+        }
+        if let Some(compression) = matches.value_of("COMPRESSION") {
+            options.compression = match columnar::compression_from_string(compression) {
+                Ok(result) => result,
+                Err(result) => {
+                    eprintln!("{}", result);
+                    return;
+                }
+            };
+        }
+
         let (start_time, end_time) = tokio::join!(
-            handler::from_handler(&database, collection, from),
-            handler::to_handler(&database, collection, to)
+            handler::from_handler(database.as_ref(), collection, from),
+            handler::to_handler(database.as_ref(), collection, to)
         );
         let start_time = match start_time {
             Ok(result) => result,
@@ -127,277 +180,53 @@ async fn main() {
             return;
         }
 
-        let query = match create_duration_query(collection, &start_time, &end_time) {
-            Ok(content) => content,
-            Err(result) => {
-                eprintln!("failed to create query!\ndetails : {}", result);
-                return;
-            }
-        };
-
-        let options = match create_find_option_timeline(collection, 1, None) {
-            Ok(content) => content,
-            Err(result) => {
-                eprintln!("failed to create query!\ndetails : {}", result);
-                return;
-            }
-        };
-
-        match collection {
-            common_constants::DATABASE_COLLECTION_TICKER => {
-                let mut ticker_stream = match stream::DatabaseStream::<data::Ticker>::request(
-                    &database,
-                    None,
-                    query.clone(),
-                    options.clone(),
-                )
-                .await
-                {
-                    Ok(result) => result,
-                    Err(result) => {
-                        eprintln!("failed to request!\ndetails : {}", result);
-                        return;
-                    }
-                };
-                let bar = ProgressBar::new(ticker_stream.size_hint().0 as u64);
-                bar.set_style(ProgressStyle::default_bar()
-                    .template("Downloading {spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta})",).progress_chars("#>-"));
-                let mut data = Vec::with_capacity(ticker_stream.size_hint().0);
-                let mut latest_time = 0.0;
-                loop {
-                    tokio::select! {
-                        Some(result) = ticker_stream.next() => {
-                            bar.inc(1);
-                            match result {
-                                Ok(result) => {
-                                    if result.timestamp >= latest_time {
-                                        latest_time = result.timestamp + period;
-                                        data.push(result);
-                                    }
-                                }
-                                Err(result) => {
-                                    eprintln!("got malformed data!\ndetails : {}", result);
-                                }
-                            }
-                        }
-                        else => break,
-                    }
-                }
-                bar.finish();
-
-                match file_type {
-                    "csv" => {
-                        let mut csv_writer = match csv::Writer::from_path(target) {
-                            Ok(result) => result,
-                            Err(_) => {
-                                eprintln!("failed to create TARGET file.");
-                                return;
-                            }
-                        };
-                        for d in data {
-                            if let Err(result) = csv_writer.serialize(d) {
-                                eprintln!("failed to write! {:?}", result);
-                                return;
-                            }
-                        }
-                    }
-
-                    "json" => {
-                        let ticker = local::LocalTicker {
-                            collection: String::from(collection),
-                            downloaded_at: Utc::now(),
-                            start: start_time,
-                            end: end_time,
-                            size: data.len() as i32,
-                            ticker: data,
-                        };
-                        match ticker.write(target).await {
-                            Ok(result) => result,
-                            Err(result) => {
-                                eprintln!("failed to serialize! {:?}", result);
-                                return;
-                            }
-                        }
-                    }
-
-                    _ => {
-                        eprintln!("{} is not supported!", file_type);
-                        return;
-                    }
-                }
-            }
-
-            common_constants::DATABASE_COLLECTION_EXECUTIONS => {
-                let mut execution_stream = match stream::DatabaseStream::<data::Execution>::request(
-                    &database,
-                    None,
-                    query.clone(),
-                    options.clone(),
-                )
-                .await
-                {
-                    Ok(result) => result,
-                    Err(result) => {
-                        eprintln!("failed to request!\ndetails : {}", result);
-                        return;
-                    }
-                };
-                let bar = ProgressBar::new(execution_stream.size_hint().0 as u64);
-                bar.set_style(ProgressStyle::default_bar()
-                    .template("Downloading {spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta})",).progress_chars("#>-"));
-                let mut data = Vec::with_capacity(execution_stream.size_hint().0);
-                let mut latest_time = 0.0;
-                loop {
-                    tokio::select! {
-                        Some(result) = execution_stream.next() => {
-                            bar.inc(1);
-                            match result {
-                                Ok(result) => {
-                                    if result.timestamp >= latest_time {
-                                        latest_time = result.timestamp + period;
-                                        data.push(result);
-                                    }
-                                }
-                                Err(result) => {
-                                    eprintln!("got malformed data!\ndetails : {}", result);
-                                }
-                            }
-                        }
-                        else => break,
-                    }
-                }
-                bar.finish();
-
-                match file_type {
-                    "csv" => {
-                        let mut csv_writer = match csv::Writer::from_path(target) {
-                            Ok(result) => result,
-                            Err(_) => {
-                                eprintln!("failed to create TARGET file.");
-                                return;
-                            }
-                        };
-                        for d in data {
-                            if let Err(result) = csv_writer.serialize(d) {
-                                eprintln!("failed to write! {:?}", result);
-                                return;
-                            }
-                        }
-                    }
-
-                    "json" => {
-                        let ticker = local::LocalExecutions {
-                            collection: String::from(collection),
-                            downloaded_at: Utc::now(),
-                            start: start_time,
-                            end: end_time,
-                            size: data.len() as i32,
-                            executions: data,
-                        };
-                        match ticker.write(target).await {
-                            Ok(result) => result,
-                            Err(result) => {
-                                eprintln!("failed to serialize! {:?}", result);
-                                return;
-                            }
-                        }
-                    }
-                    _ => {
-                        eprintln!("{} is not supported!", file_type);
-                        return;
-                    }
+        let timeline = Timeline::new(&start_time, &end_time);
+        let exported = async {
+            match collection {
+                common_constants::DATABASE_COLLECTION_TICKER => {
+                    let data = handler::pick::<data::Ticker>(
+                        database.as_ref(),
+                        collection,
+                        &timeline,
+                        period,
+                    )
+                    .await?;
+                    handler::export(target, collection, start_time, end_time, data, &options).await
                 }
-            }
-
-            common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY
-            | common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL => {
-                let mut order_book_stream =
-                    match stream::DatabaseStream::<data::OrderBook>::request(
-                        &database,
-                        Some(collection),
-                        query.clone(),
-                        options.clone(),
+                common_constants::DATABASE_COLLECTION_EXECUTIONS => {
+                    let data = handler::pick::<data::Execution>(
+                        database.as_ref(),
+                        collection,
+                        &timeline,
+                        period,
                     )
-                    .await
-                    {
-                        Ok(result) => result,
-                        Err(result) => {
-                            eprintln!("failed to request!\ndetails : {}", result);
-                            return;
-                        }
-                    };
-                let bar = ProgressBar::new(order_book_stream.size_hint().0 as u64);
-                bar.set_style(ProgressStyle::default_bar()
-                    .template("Downloading {spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta})",).progress_chars("#>-"));
-                let mut data = Vec::with_capacity(order_book_stream.size_hint().0);
-                let mut latest_time = 0.0;
-                loop {
-                    tokio::select! {
-                        Some(result) = order_book_stream.next() => {
-                            bar.inc(1);
-                            match result {
-                                Ok(result) => {
-                                    if result.received_at >= latest_time {
-                                        latest_time = result.received_at + period;
-                                        data.push(result);
-                                    }
-                                }
-                                Err(result) => {
-                                    eprintln!("got malformed data!\ndetails : {}", result);
-                                }
-                            }
-                        }
-                        else => break,
-                    }
+                    .await?;
+                    handler::export(target, collection, start_time, end_time, data, &options).await
                 }
-                bar.finish();
-
-                match file_type {
-                    "csv" => {
-                        let mut csv_writer = match csv::Writer::from_path(target) {
-                            Ok(result) => result,
-                            Err(_) => {
-                                eprintln!("failed to create TARGET file.");
-                                return;
-                            }
-                        };
-                        for d in data {
-                            if let Err(result) = csv_writer.serialize(d) {
-                                eprintln!("failed to write! {:?}", result);
-                                return;
-                            }
-                        }
-                    }
-
-                    "json" => {
-                        let ticker = local::LocalOrderBook {
-                            collection: String::from(collection),
-                            downloaded_at: Utc::now(),
-                            start: start_time,
-                            end: end_time,
-                            size: data.len() as i32,
-                            order_book: data,
-                        };
-                        match ticker.write(target).await {
-                            Ok(result) => result,
-                            Err(result) => {
-                                eprintln!("failed to serialize! {:?}", result);
-                                return;
-                            }
-                        }
-                    }
-
-                    _ => {
-                        eprintln!("{} is not supported!", file_type);
-                        return;
+                common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY
+                | common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL => {
+                    let data = handler::pick::<data::OrderBook>(
+                        database.as_ref(),
+                        collection,
+                        &timeline,
+                        period,
+                    )
+                    .await?;
+                    if file_type == "parquet" && matches.is_present("long") {
+                        let levels = columnar::OrderLevel::flatten(&data);
+                        handler::write_parquet(target, levels, &options)
+                            .await
+                            .map(|_| ())
+                    } else {
+                        handler::export(target, collection, start_time, end_time, data, &options)
+                            .await
                     }
                 }
+                _ => Err(error_message!("invalid collection!")),
             }
-
-            _ => {
-                eprintln!("invalid collection!");
-                return;
-            }
+        };
+        if let Err(result) = exported.await {
+            eprintln!("{}", result);
         }
     }
 }
@@ -8,7 +8,6 @@ use common::*;
 use database::*;
 use futures::stream::StreamExt;
 use indicatif::{ProgressBar, ProgressStyle};
-use mongodb::bson::Document;
 use tokio::sync::mpsc;
 
 #[tokio::main]
@@ -17,20 +16,32 @@ async fn main() {
         .version(env!("CARGO_PKG_VERSION"))
         .author(env!("CARGO_PKG_AUTHORS"))
         .about(env!("CARGO_PKG_DESCRIPTION"))
+        .arg(
+            Arg::new("SYMBOL")
+                .value_name("SYMBOL")
+                .long("symbol")
+                .help("exchange and product such as liquid:btc_jpy. liquid:btc_jpy by default.")
+        )
+        .arg(
+            Arg::new("DESTINATION_SYMBOL")
+                .value_name("SYMBOL")
+                .long("destination-symbol")
+                .help("symbol of the destination database. the same as --symbol by default.")
+        )
         .arg(
             Arg::new("SOURCE")
                 .required(true)
-                .help("source mongodb url")
+                .help("source database url such as mongodb://... or sqlite://...")
         )
         .arg(
             Arg::new("DESTINATION")
                 .required(true)
-                .help("destination mongodb url")
+                .help("destination database url such as mongodb://... or sqlite://...")
         )
         .arg(
             Arg::new("COLLECTION")
                 .required(true)
-                .help("mongodb collection. ticker or executions.")
+                .help("collection such as ticker, executions, order_book_buy or candles_1m.")
         )
         .arg(
             Arg::new("FROM")
@@ -51,36 +62,63 @@ async fn main() {
         matches.value_of("FROM"),
         matches.value_of("TO"),
     ) {
-        let source_database = match Database::new(source, common_constants::DATABASE_NAME).await {
-            Ok(result) => result,
-            Err(result) => {
-                eprintln!("{}", result);
-                return;
-            }
+        let symbol = match matches.value_of("SYMBOL") {
+            Some(result) => match Symbol::generate_from_string(result) {
+                Ok(result) => result,
+                Err(result) => {
+                    eprintln!("{}", result);
+                    return;
+                }
+            },
+            None => Symbol::default(),
         };
-        let destination_database =
-            match Database::new(destination, common_constants::DATABASE_NAME).await {
+        let destination_symbol = match matches.value_of("DESTINATION_SYMBOL") {
+            Some(result) => match Symbol::generate_from_string(result) {
                 Ok(result) => result,
                 Err(result) => {
                     eprintln!("{}", result);
                     return;
                 }
-            };
+            },
+            None => symbol.clone(),
+        };
+        let source_database = match open_store_for(source, &symbol).await {
+            Ok(result) => result,
+            Err(result) => {
+                eprintln!("{}", result);
+                return;
+            }
+        };
+        let destination_database = match open_store_for(destination, &destination_symbol).await {
+            Ok(result) => result,
+            Err(result) => {
+                eprintln!("{}", result);
+                return;
+            }
+        };
 
-        match collection {
-            common_constants::DATABASE_COLLECTION_TICKER
-            | common_constants::DATABASE_COLLECTION_EXECUTIONS
-            | common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY
-            | common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL => {}
-            _ => {
-                eprintln!("{} is a invalid collection", collection);
+        // every collection of a record type, since the records are handled as json objects.
+        if record::record_type(collection).is_err() {
+            eprintln!("{} is a invalid collection", collection);
+            return;
+        }
+
+        // the unique keys make a rerun skip the records already transferred.
+        match destination_database.check_schema(true).await {
+            Ok(reports) => {
+                for report in reports.iter().filter(|e| !e.is_ok()) {
+                    eprintln!("{} : {}", report.collection, report.problems.join(" "));
+                }
+            }
+            Err(result) => {
+                eprintln!("failed to check the schema!\ndetails : {}", result);
                 return;
             }
         }
 
         let (start_time, end_time) = tokio::join!(
-            handler::from_handler(&source_database, collection, from),
-            handler::to_handler(&source_database, collection, to)
+            handler::from_handler(source_database.as_ref(), collection, from),
+            handler::to_handler(source_database.as_ref(), collection, to)
         );
         let start_time = match start_time {
             Ok(result) => result,
@@ -96,26 +134,8 @@ async fn main() {
             return;
         }
 
-        let query = match create_duration_query(collection, &start_time, &end_time) {
-            Ok(content) => content,
-            Err(result) => {
-                eprintln!("failed to create query!\ndetails : {}", result);
-                return;
-            }
-        };
-
-        let options = match create_find_option_timeline(collection, 1, None) {
-            Ok(content) => content,
-            Err(result) => {
-                eprintln!("failed to create query!\ndetails : {}", result);
-                return;
-            }
-        };
-
-        let document_count = match source_database
-            .count_documents(collection, query.clone(), None)
-            .await
-        {
+        let timeline = Timeline::new(&start_time, &end_time);
+        let document_count = match source_database.count(collection, &timeline).await {
             Ok(result) => result,
             Err(result) => {
                 println!("{}", result);
@@ -123,10 +143,7 @@ async fn main() {
             }
         };
 
-        let mut cursor = match source_database
-            .request_raw(collection, query, Some(options))
-            .await
-        {
+        let mut cursor = match source_database.stream(collection, &timeline).await {
             Ok(result) => result,
             Err(result) => {
                 eprintln!("{}", result);
@@ -142,7 +159,7 @@ async fn main() {
         )
         .progress_chars("#>-"));
 
-        let destination_database_arc = std::sync::Arc::new(destination_database);
+        let destination_database_arc = destination_database;
         let collection_arc = std::sync::Arc::new(String::from(collection));
         let mut executors = Vec::new();
         let mut counter = 0;
@@ -160,13 +177,13 @@ async fn main() {
             tokio::select! {
                 Some(result) = cursor.next() => {
                     match result {
-                        Ok(document) => {
+                        Ok(record) => {
                             bar.inc(1);
                             let destination_database_ref = destination_database_arc.clone();
                             let collection_ref = collection_arc.clone();
                             executors[counter % cpu_cores].1.send(tokio::task::spawn(transferer::transferer(
                                 destination_database_ref,
-                                document,
+                                record,
                                 collection_ref.to_string(),
                             ))).expect("failed to send");
                         }
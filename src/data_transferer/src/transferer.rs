@@ -12,14 +12,11 @@ pub async fn executor(mut receiver: mpsc::UnboundedReceiver<tokio::task::JoinHan
 }
 
 pub async fn transferer(
-    destination_database: std::sync::Arc<Database>,
-    content: Document,
+    destination_database: std::sync::Arc<dyn Store>,
+    content: serde_json::Value,
     collection: String,
 ) {
-    if let Err(result) = destination_database
-        .create_by_documents(&collection, &[content], None)
-        .await
-    {
+    if let Err(result) = destination_database.insert(&collection, &[content]).await {
         println!("failed to insert! {:?}", result);
         return;
     }
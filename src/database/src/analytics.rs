@@ -0,0 +1,517 @@
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use super::backtest::{BacktestReport, PnlPoint};
+use super::order_book::BookSide;
+use super::*;
+
+const SECONDS_PER_YEAR: f64 = 365.0 * 24.0 * 60.0 * 60.0;
+
+///
+/// the periods the statistics of a run are measured over, in seconds.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AnalyticsConfig {
+    ///
+    /// the equity is resampled at this interval for the returns of the ratios
+    pub return_interval: f64,
+    ///
+    /// how long after a fill the mid is compared with its price
+    pub markout: f64,
+}
+
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        Self {
+            return_interval: 60.0 * 60.0,
+            markout: 60.0,
+        }
+    }
+}
+
+///
+/// the position over a run, time weighted.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct InventoryStats {
+    pub mean: f64,
+    pub mean_absolute: f64,
+    pub min: f64,
+    pub max: f64,
+    pub last: f64,
+}
+
+///
+/// the performance of a backtest, in jpy unless noted.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Analytics {
+    pub start: f64,
+    pub end: f64,
+    pub initial_equity: f64,
+    pub final_equity: f64,
+    ///
+    /// the change of the equity, after the fees
+    pub total_pnl: f64,
+    ///
+    /// the trades closed at their average price, before the fees
+    pub realized_pnl: f64,
+    ///
+    /// the position left at the last mid price
+    pub unrealized_pnl: f64,
+    pub fees: f64,
+    ///
+    /// annualized from the returns of each `return_interval`
+    pub sharpe: f64,
+    pub sortino: f64,
+    ///
+    /// the largest fall from a peak of the equity, as a fraction of the peak
+    pub max_drawdown: f64,
+    ///
+    /// the longest time in seconds below a peak of the equity
+    pub max_drawdown_duration: f64,
+    ///
+    /// the traded value
+    pub volume: f64,
+    ///
+    /// the traded value over the mean equity
+    pub turnover: f64,
+    pub orders: u64,
+    pub fills: usize,
+    ///
+    /// the orders filled at least partly, over the orders placed
+    pub fill_rate: f64,
+    ///
+    /// the filled quantity over the placed quantity
+    pub quantity_fill_rate: f64,
+    pub inventory: InventoryStats,
+    ///
+    /// the mean move of the mid against the fills after `markout` seconds, per btc
+    pub adverse_selection: f64,
+    ///
+    /// the mean of the mid after `markout` seconds less the price of the fills, per btc in their direction
+    pub markout: f64,
+    ///
+    /// the change of the equity in each hour of the day in utc
+    pub pnl_by_hour: Vec<f64>,
+}
+
+impl Analytics {
+    pub fn calculate(report: &BacktestReport, config: &AnalyticsConfig) -> Result<Self, String> {
+        let pnl = &report.pnl;
+        let (first, last) = match (pnl.first(), pnl.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Err(error_message!("no equity was sampled!")),
+        };
+        if config.return_interval <= 0.0 {
+            return Err(error_message!(
+                "invalid return interval! {}",
+                config.return_interval
+            ));
+        }
+
+        let (realized_pnl, position, open_price) = realize(first, report);
+        let returns = returns(pnl, config.return_interval);
+        let (max_drawdown, max_drawdown_duration) = drawdown(pnl);
+        let volume = report
+            .fills
+            .iter()
+            .map(|e| e.price as f64 * e.quantity)
+            .sum::<f64>();
+        let mean_equity = pnl.iter().map(|e| e.equity).sum::<f64>() / pnl.len() as f64;
+        let filled_orders = report
+            .fills
+            .iter()
+            .map(|e| e.order_id)
+            .collect::<HashSet<_>>()
+            .len();
+        let filled_quantity = report.fills.iter().map(|e| e.quantity).sum::<f64>();
+        let (adverse_selection, markout) = markouts(report, config.markout);
+
+        Ok(Self {
+            start: first.timestamp,
+            end: last.timestamp,
+            initial_equity: first.equity,
+            final_equity: last.equity,
+            total_pnl: last.equity - first.equity,
+            realized_pnl,
+            unrealized_pnl: position * (last.mid - open_price),
+            fees: report.fees,
+            sharpe: ratio(&returns, config.return_interval, false),
+            sortino: ratio(&returns, config.return_interval, true),
+            max_drawdown,
+            max_drawdown_duration,
+            volume,
+            turnover: if mean_equity != 0.0 {
+                volume / mean_equity
+            } else {
+                0.0
+            },
+            orders: report.orders,
+            fills: report.fills.len(),
+            fill_rate: fraction(filled_orders as f64, report.orders as f64),
+            quantity_fill_rate: fraction(filled_quantity, report.placed_quantity),
+            inventory: inventory(pnl),
+            adverse_selection,
+            markout,
+            pnl_by_hour: pnl_by_hour(pnl),
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        match serde_json::to_string_pretty(self) {
+            Ok(result) => Ok(result),
+            Err(result) => Err(error_message!(
+                "failed to serialize!\ndetails : {:?}",
+                result
+            )),
+        }
+    }
+
+    ///
+    /// a self-contained page of the summary, the equity curve, the drawdown and the pnl by hour.
+    pub fn to_html(&self, title: &str, report: &BacktestReport) -> String {
+        let equity = report
+            .pnl
+            .iter()
+            .map(|e| (e.timestamp, e.equity))
+            .collect::<Vec<_>>();
+        let mut peak = f64::MIN;
+        let drawdown = report
+            .pnl
+            .iter()
+            .map(|e| {
+                peak = peak.max(e.equity);
+                (e.timestamp, e.equity - peak)
+            })
+            .collect::<Vec<_>>();
+
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+             <style>body{{font-family:sans-serif;margin:2em}}table{{border-collapse:collapse}}\
+             td{{border:1px solid #ccc;padding:2px 8px}}td.value{{text-align:right}}</style>\n\
+             </head>\n<body>\n<h1>{}</h1>\n",
+            escape(title),
+            escape(title)
+        );
+        html.push_str("<table>\n");
+        for (name, value) in self.rows() {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td class=\"value\">{}</td></tr>",
+                name, value
+            );
+        }
+        html.push_str("</table>\n<h2>equity</h2>\n");
+        html.push_str(&line_chart(&equity, "#1f77b4"));
+        html.push_str("<h2>drawdown</h2>\n");
+        html.push_str(&line_chart(&drawdown, "#d62728"));
+        html.push_str("<h2>pnl by hour (utc)</h2>\n");
+        html.push_str(&bar_chart(&self.pnl_by_hour));
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    fn rows(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("initial equity", format!("{:.0}", self.initial_equity)),
+            ("final equity", format!("{:.0}", self.final_equity)),
+            ("total pnl", format!("{:.0}", self.total_pnl)),
+            ("realized pnl", format!("{:.0}", self.realized_pnl)),
+            ("unrealized pnl", format!("{:.0}", self.unrealized_pnl)),
+            ("fees", format!("{:.0}", self.fees)),
+            ("sharpe", format!("{:.3}", self.sharpe)),
+            ("sortino", format!("{:.3}", self.sortino)),
+            ("max drawdown", format!("{:.2}%", self.max_drawdown * 100.0)),
+            (
+                "max drawdown duration",
+                format!("{:.0}s", self.max_drawdown_duration),
+            ),
+            ("volume", format!("{:.0}", self.volume)),
+            ("turnover", format!("{:.2}", self.turnover)),
+            ("orders", format!("{}", self.orders)),
+            ("fills", format!("{}", self.fills)),
+            ("fill rate", format!("{:.2}%", self.fill_rate * 100.0)),
+            (
+                "quantity fill rate",
+                format!("{:.2}%", self.quantity_fill_rate * 100.0),
+            ),
+            ("mean inventory", format!("{:.4}", self.inventory.mean)),
+            (
+                "inventory range",
+                format!("{:.4} .. {:.4}", self.inventory.min, self.inventory.max),
+            ),
+            (
+                "adverse selection",
+                format!("{:.1}", self.adverse_selection),
+            ),
+            ("markout", format!("{:.1}", self.markout)),
+        ]
+    }
+}
+
+///
+/// write `<path>.json` and `<path>.html` of a run.
+pub async fn write_report(
+    path: &str,
+    title: &str,
+    analytics: &Analytics,
+    report: &BacktestReport,
+) -> Result<(), String> {
+    let json = analytics.to_json()?;
+    if let Err(result) = tokio::fs::write(format!("{}.json", path), json).await {
+        return Err(error_message!("failed to write!\ndetails : {:?}", result));
+    }
+    if let Err(result) =
+        tokio::fs::write(format!("{}.html", path), analytics.to_html(title, report)).await
+    {
+        return Err(error_message!("failed to write!\ndetails : {:?}", result));
+    }
+    Ok(())
+}
+
+fn fraction(numerator: f64, denominator: f64) -> f64 {
+    if denominator > 0.0 {
+        numerator / denominator
+    } else {
+        0.0
+    }
+}
+
+fn direction(side: BookSide) -> f64 {
+    match side {
+        BookSide::Buy => 1.0,
+        BookSide::Sell => -1.0,
+    }
+}
+
+///
+/// (realized pnl, position, its average price) of the fills, starting from the first inventory at the first mid.
+fn realize(first: &PnlPoint, report: &BacktestReport) -> (f64, f64, f64) {
+    let mut realized = 0.0;
+    let mut position = first.inventory;
+    let mut open_price = first.mid;
+    for fill in &report.fills {
+        let quantity = direction(fill.side) * fill.quantity;
+        let price = fill.price as f64;
+        if position == 0.0 || position.signum() == quantity.signum() {
+            open_price = (open_price * position + price * quantity) / (position + quantity);
+            position += quantity;
+            continue;
+        }
+        realized += quantity.abs().min(position.abs()) * (price - open_price) * position.signum();
+        position += quantity;
+        if position.abs() < 1e-12 {
+            position = 0.0;
+        } else if position.signum() == quantity.signum() {
+            open_price = price;
+        }
+    }
+    (realized, position, open_price)
+}
+
+///
+/// the last point at or before `timestamp`.
+fn at(pnl: &[PnlPoint], timestamp: f64) -> Option<&PnlPoint> {
+    match pnl.partition_point(|e| e.timestamp <= timestamp) {
+        0 => None,
+        index => Some(&pnl[index - 1]),
+    }
+}
+
+///
+/// the last mid at or before `timestamp`.
+fn mid_at(mids: &[(f64, f64)], timestamp: f64) -> Option<f64> {
+    match mids.partition_point(|e| e.0 <= timestamp) {
+        0 => None,
+        index => Some(mids[index - 1].1),
+    }
+}
+
+fn returns(pnl: &[PnlPoint], interval: f64) -> Vec<f64> {
+    let (start, end) = (pnl[0].timestamp, pnl[pnl.len() - 1].timestamp);
+    let mut equity = Vec::new();
+    let mut time = start;
+    while time <= end {
+        if let Some(point) = at(pnl, time) {
+            equity.push(point.equity);
+        }
+        time += interval;
+    }
+    equity
+        .windows(2)
+        .filter(|e| e[0] != 0.0)
+        .map(|e| e[1] / e[0] - 1.0)
+        .collect()
+}
+
+///
+/// the sharpe ratio, or the sortino ratio which only counts the falls as the risk.
+fn ratio(returns: &[f64], interval: f64, downside: bool) -> f64 {
+    if returns.len() < 2 {
+        return 0.0;
+    }
+    let n = returns.len() as f64;
+    let mean = returns.iter().sum::<f64>() / n;
+    let risk = if downside {
+        (returns.iter().map(|e| e.min(0.0).powi(2)).sum::<f64>() / n).sqrt()
+    } else {
+        (returns.iter().map(|e| (e - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt()
+    };
+    if risk > 0.0 {
+        mean / risk * (SECONDS_PER_YEAR / interval).sqrt()
+    } else {
+        0.0
+    }
+}
+
+///
+/// (the largest fall as a fraction of its peak, the longest time below a peak)
+fn drawdown(pnl: &[PnlPoint]) -> (f64, f64) {
+    let mut peak = pnl[0].equity;
+    let mut peak_at = pnl[0].timestamp;
+    let mut max_drawdown: f64 = 0.0;
+    let mut max_duration: f64 = 0.0;
+    for point in pnl {
+        if point.equity >= peak {
+            peak = point.equity;
+            peak_at = point.timestamp;
+            continue;
+        }
+        if peak > 0.0 {
+            max_drawdown = max_drawdown.max((peak - point.equity) / peak);
+        }
+        max_duration = max_duration.max(point.timestamp - peak_at);
+    }
+    (max_drawdown, max_duration)
+}
+
+fn inventory(pnl: &[PnlPoint]) -> InventoryStats {
+    let last = pnl[pnl.len() - 1].inventory;
+    let duration = pnl[pnl.len() - 1].timestamp - pnl[0].timestamp;
+    let (mut mean, mut mean_absolute) = (0.0, 0.0);
+    if duration > 0.0 {
+        for e in pnl.windows(2) {
+            let weight = (e[1].timestamp - e[0].timestamp) / duration;
+            mean += e[0].inventory * weight;
+            mean_absolute += e[0].inventory.abs() * weight;
+        }
+    } else {
+        mean = last;
+        mean_absolute = last.abs();
+    }
+    InventoryStats {
+        mean,
+        mean_absolute,
+        min: pnl.iter().map(|e| e.inventory).fold(f64::MAX, f64::min),
+        max: pnl.iter().map(|e| e.inventory).fold(f64::MIN, f64::max),
+        last,
+    }
+}
+
+///
+/// (adverse selection, markout) weighted by the quantity of the fills which were followed for `horizon`.
+/// the mids are taken from every change of the mid, not from the equity samples.
+fn markouts(report: &BacktestReport, horizon: f64) -> (f64, f64) {
+    let pnl = &report.pnl;
+    let end = pnl[pnl.len() - 1].timestamp;
+    let (mut adverse, mut markout, mut quantity) = (0.0, 0.0, 0.0);
+    for fill in report.fills.iter().filter(|e| e.timestamp + horizon <= end) {
+        if let (Some(before), Some(after)) = (
+            mid_at(&report.mids, fill.timestamp),
+            mid_at(&report.mids, fill.timestamp + horizon),
+        ) {
+            let direction = direction(fill.side);
+            adverse -= direction * (after - before) * fill.quantity;
+            markout += direction * (after - fill.price as f64) * fill.quantity;
+            quantity += fill.quantity;
+        }
+    }
+    (fraction(adverse, quantity), fraction(markout, quantity))
+}
+
+fn pnl_by_hour(pnl: &[PnlPoint]) -> Vec<f64> {
+    let mut hours = vec![0.0; 24];
+    for e in pnl.windows(2) {
+        let hour = ((e[1].timestamp as i64).rem_euclid(24 * 60 * 60) / (60 * 60)) as usize;
+        hours[hour] += e[1].equity - e[0].equity;
+    }
+    hours
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const CHART_WIDTH: f64 = 800.0;
+const CHART_HEIGHT: f64 = 240.0;
+
+fn line_chart(points: &[(f64, f64)], color: &str) -> String {
+    if points.is_empty() {
+        return String::from("<p>no data</p>\n");
+    }
+    let (x_min, x_max) = (points[0].0, points[points.len() - 1].0);
+    let y_min = points.iter().map(|e| e.1).fold(f64::MAX, f64::min);
+    let y_max = points.iter().map(|e| e.1).fold(f64::MIN, f64::max);
+    let x_range = if x_max > x_min { x_max - x_min } else { 1.0 };
+    let y_range = if y_max > y_min { y_max - y_min } else { 1.0 };
+    let path = points
+        .iter()
+        .map(|(x, y)| {
+            format!(
+                "{:.1},{:.1}",
+                (x - x_min) / x_range * CHART_WIDTH,
+                CHART_HEIGHT - (y - y_min) / y_range * CHART_HEIGHT
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\
+         <rect width=\"{w}\" height=\"{h}\" fill=\"#fafafa\" stroke=\"#ccc\"/>\
+         <polyline fill=\"none\" stroke=\"{c}\" stroke-width=\"1.5\" points=\"{p}\"/>\
+         <text x=\"4\" y=\"14\" font-size=\"12\">{max:.0}</text>\
+         <text x=\"4\" y=\"{b}\" font-size=\"12\">{min:.0}</text></svg>\n",
+        w = CHART_WIDTH,
+        h = CHART_HEIGHT,
+        c = color,
+        p = path,
+        max = y_max,
+        min = y_min,
+        b = CHART_HEIGHT - 4.0
+    )
+}
+
+fn bar_chart(values: &[f64]) -> String {
+    let scale = values.iter().map(|e| e.abs()).fold(0.0, f64::max);
+    let scale = if scale > 0.0 { scale } else { 1.0 };
+    let width = CHART_WIDTH / values.len().max(1) as f64;
+    let middle = CHART_HEIGHT / 2.0;
+    let mut bars = String::new();
+    for (i, value) in values.iter().enumerate() {
+        let height = value.abs() / scale * middle;
+        let _ = write!(
+            bars,
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"><title>{}h : {:.0}</title></rect>",
+            i as f64 * width + 1.0,
+            if *value >= 0.0 { middle - height } else { middle },
+            width - 2.0,
+            height,
+            if *value >= 0.0 { "#2ca02c" } else { "#d62728" },
+            i,
+            value
+        );
+    }
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\
+         <rect width=\"{w}\" height=\"{h}\" fill=\"#fafafa\" stroke=\"#ccc\"/>{b}\
+         <line x1=\"0\" y1=\"{m}\" x2=\"{w}\" y2=\"{m}\" stroke=\"#888\"/></svg>\n",
+        w = CHART_WIDTH,
+        h = CHART_HEIGHT,
+        b = bars,
+        m = middle
+    )
+}
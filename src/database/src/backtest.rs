@@ -0,0 +1,614 @@
+use futures::stream::StreamExt;
+use liquid::data::Order;
+use serde::Deserialize;
+use std::borrow::Borrow;
+
+use super::data::Execution;
+pub use super::fee::Liquidity;
+use super::margin::MarginAccount;
+use super::order_book::{book_stream, Book, BookSide};
+use super::simulation::Simulation;
+use super::store::{Store, Timeline};
+use super::*;
+
+///
+/// amounts below this are treated as nothing, against the rounding of partial fills.
+const EPSILON: f64 = 1e-9;
+
+///
+/// the most an execution is received apart from its timestamp in seconds.
+/// the executions are stored by their timestamp, so their window is widened by this before they are taken by `received_at`.
+const RECEIVE_DELAY: f64 = 60.0;
+
+///
+/// the delays of the exchange in seconds, the sampling of the pnl history and the limits of an order.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BacktestConfig {
+    ///
+    /// from sending an order until it rests in the book
+    pub entry_latency: f64,
+    ///
+    /// from sending a cancel until the order leaves the book
+    pub cancel_latency: f64,
+    ///
+    /// the interval of the equity samples. 0 samples at every event.
+    pub equity_interval: f64,
+    ///
+    /// the quantities an order can have
+    pub min_quantity: f64,
+    pub max_quantity: f64,
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        Self {
+            entry_latency: 0.05,
+            cancel_latency: 0.05,
+            equity_interval: 60.0,
+            min_quantity: 0.0001,
+            max_quantity: 25.0,
+        }
+    }
+}
+
+///
+/// a stored update of the market, replayed in the order of `received_at`.
+#[derive(Clone, Debug)]
+pub enum MarketEvent {
+    Execution(Execution),
+    Book(Book),
+}
+
+impl MarketEvent {
+    pub fn received_at(&self) -> f64 {
+        match self {
+            MarketEvent::Execution(content) => content.received_at,
+            MarketEvent::Book(content) => content.received_at,
+        }
+    }
+}
+
+pub type OrderId = u64;
+
+///
+/// a limit order of the strategy resting in the simulated book.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LimitOrder {
+    pub id: OrderId,
+    pub side: BookSide,
+    pub price: i32,
+    pub quantity: f64,
+    pub filled: f64,
+    ///
+    /// the time the strategy sent the order
+    pub submitted_at: f64,
+    ///
+    /// the amount resting before the order at its price
+    pub queue_ahead: f64,
+}
+
+impl LimitOrder {
+    pub fn remaining(&self) -> f64 {
+        self.quantity - self.filled
+    }
+
+    ///
+    /// whether an execution at `price` went through the price of the order.
+    fn traded_through(&self, price: i32) -> bool {
+        match self.side {
+            BookSide::Buy => price < self.price,
+            BookSide::Sell => price > self.price,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Fill {
+    pub order_id: OrderId,
+    pub timestamp: f64,
+    pub side: BookSide,
+    pub price: i32,
+    pub quantity: f64,
+    pub liquidity: Liquidity,
+    ///
+    /// the fee charged by the simulation in jpy, negative for a rebate.
+    pub fee: f64,
+}
+
+///
+/// the balances and their value at the mid price of the book.
+/// the equity is after the fees, which are also kept as their sum so far.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PnlPoint {
+    pub timestamp: f64,
+    pub jpy: f64,
+    pub btc: f64,
+    pub mid: f64,
+    pub equity: f64,
+    pub fees: f64,
+    ///
+    /// the btc held and the leveraged position
+    pub inventory: f64,
+}
+
+#[derive(Clone, Debug)]
+enum Action {
+    Place(LimitOrder),
+    Cancel(OrderId),
+}
+
+///
+/// the simulated exchange: the book of the market, the orders of the strategy and the requests on the way.
+#[derive(Clone, Debug, Default)]
+pub struct Exchange {
+    config: BacktestConfig,
+    now: f64,
+    book: Book,
+    orders: Vec<LimitOrder>,
+    ///
+    /// (the time it reaches the exchange, the request) ordered by the time
+    pending: Vec<(f64, Action)>,
+    next_id: OrderId,
+    placed_quantity: f64,
+}
+
+///
+/// what a strategy sees and does at an event.
+pub struct Context<'a> {
+    exchange: &'a mut Exchange,
+    simulation: &'a Simulation,
+}
+
+impl<'a> Context<'a> {
+    pub fn now(&self) -> f64 {
+        self.exchange.now
+    }
+
+    pub fn book(&self) -> &Book {
+        &self.exchange.book
+    }
+
+    ///
+    /// (jpy, btc)
+    pub fn assets(&self) -> (f64, f64) {
+        self.simulation.assets()
+    }
+
+    ///
+    /// the leveraged position, when the simulation trades on margin.
+    pub fn margin(&self) -> Option<&MarginAccount> {
+        self.simulation.margin()
+    }
+
+    ///
+    /// the orders resting in the book. the orders on the way are not included.
+    pub fn open_orders(&self) -> &[LimitOrder] {
+        &self.exchange.orders
+    }
+
+    ///
+    /// send a limit order, which reaches the book after the entry latency.
+    pub fn place(&mut self, side: BookSide, price: i32, quantity: f64) -> Result<OrderId, String> {
+        let config = &self.exchange.config;
+        if !(config.min_quantity..=config.max_quantity).contains(&quantity) || price <= 0 {
+            return Err(error_message!(
+                "invalid order! price : {}, quantity : {}",
+                price,
+                quantity
+            ));
+        }
+        let exchange = &mut self.exchange;
+        exchange.next_id += 1;
+        let order = LimitOrder {
+            id: exchange.next_id,
+            side,
+            price,
+            quantity,
+            filled: 0.0,
+            submitted_at: exchange.now,
+            queue_ahead: 0.0,
+        };
+        exchange.placed_quantity += quantity;
+        let at = exchange.now + exchange.config.entry_latency;
+        exchange.request(at, Action::Place(order));
+        Ok(exchange.next_id)
+    }
+
+    ///
+    /// send a cancel, which removes the order after the cancel latency. it may be filled meanwhile.
+    pub fn cancel(&mut self, id: OrderId) {
+        let at = self.exchange.now + self.exchange.config.cancel_latency;
+        self.exchange.request(at, Action::Cancel(id));
+    }
+}
+
+impl Exchange {
+    fn request(&mut self, at: f64, action: Action) {
+        let index = self.pending.partition_point(|(e, _)| *e <= at);
+        self.pending.insert(index, (at, action));
+    }
+
+    ///
+    /// the requests reaching the exchange until `time`.
+    fn arrive(&mut self, time: f64, fills: &mut Vec<Fill>) {
+        while let Some((at, _)) = self.pending.first() {
+            if *at > time {
+                break;
+            }
+            let (at, action) = self.pending.remove(0);
+            match action {
+                Action::Place(order) => self.rest(at, order, fills),
+                Action::Cancel(id) => {
+                    self.orders.retain(|e| e.id != id);
+                    // an order cancelled before it arrives never rests.
+                    self.pending
+                        .retain(|(_, e)| !matches!(e, Action::Place(order) if order.id == id));
+                }
+            }
+        }
+    }
+
+    ///
+    /// an arriving order takes the levels it crosses and rests behind the amount at its price.
+    fn rest(&mut self, time: f64, mut order: LimitOrder, fills: &mut Vec<Fill>) {
+        let opposite = match order.side {
+            BookSide::Buy => &self.book.sell,
+            BookSide::Sell => &self.book.buy,
+        };
+        for level in opposite {
+            if order.remaining() <= EPSILON || !crosses(order.side, order.price, level.price) {
+                break;
+            }
+            let quantity = order.remaining().min(level.amount);
+            order.filled += quantity;
+            fills.push(Fill {
+                order_id: order.id,
+                timestamp: time,
+                side: order.side,
+                price: level.price,
+                quantity,
+                liquidity: Liquidity::Taker,
+                fee: 0.0,
+            });
+        }
+        if order.remaining() > EPSILON {
+            order.queue_ahead = level_amount(self.own_side(order.side), order.price);
+            self.orders.push(order);
+        }
+    }
+
+    fn own_side(&self, side: BookSide) -> &[Order] {
+        match side {
+            BookSide::Buy => &self.book.buy,
+            BookSide::Sell => &self.book.sell,
+        }
+    }
+
+    ///
+    /// an execution fills the orders it went through, and at their price the amount after the queue,
+    /// up to its own quantity. the better prices are filled first, and the orders of a price in the
+    /// order they were placed.
+    fn execute(&mut self, execution: &Execution, fills: &mut Vec<Fill>) {
+        let side = match execution.taker_side.as_str() {
+            "buy" => BookSide::Sell,
+            "sell" => BookSide::Buy,
+            _ => return,
+        };
+        let mut candidates = (0..self.orders.len())
+            .filter(|&i| {
+                let order = &self.orders[i];
+                order.side == side
+                    && (order.traded_through(execution.price) || order.price == execution.price)
+            })
+            .collect::<Vec<usize>>();
+        candidates.sort_by(|&a, &b| {
+            let (a, b) = (&self.orders[a], &self.orders[b]);
+            let price = match side {
+                BookSide::Buy => b.price.cmp(&a.price),
+                BookSide::Sell => a.price.cmp(&b.price),
+            };
+            price
+                .then(
+                    a.submitted_at
+                        .partial_cmp(&b.submitted_at)
+                        .unwrap_or(std::cmp::Ordering::Equal),
+                )
+                .then(a.id.cmp(&b.id))
+        });
+        let mut left = execution.quantity;
+        // the public amount traded at the price, before every own order there.
+        let mut consumed = 0.0;
+        for i in candidates {
+            let order = &mut self.orders[i];
+            if order.price == execution.price {
+                let ahead = (order.queue_ahead - consumed).max(0.0).min(left);
+                consumed += ahead;
+                left -= ahead;
+            }
+            let quantity = order.remaining().min(left);
+            left -= quantity;
+            if quantity > EPSILON {
+                order.filled += quantity;
+                fills.push(Fill {
+                    order_id: order.id,
+                    timestamp: execution.received_at,
+                    side,
+                    price: order.price,
+                    quantity,
+                    liquidity: Liquidity::Maker,
+                    fee: 0.0,
+                });
+            }
+        }
+        for order in self
+            .orders
+            .iter_mut()
+            .filter(|e| e.side == side && e.price == execution.price)
+        {
+            order.queue_ahead = (order.queue_ahead - consumed).max(0.0);
+        }
+        self.orders.retain(|e| e.remaining() > EPSILON);
+    }
+
+    ///
+    /// the amount before an order cannot be more than the amount left at its price.
+    fn update_book(&mut self, book: &Book) {
+        self.book = book.clone();
+        for order in self.orders.iter_mut() {
+            let ladder = match order.side {
+                BookSide::Buy => &self.book.buy,
+                BookSide::Sell => &self.book.sell,
+            };
+            order.queue_ahead = order.queue_ahead.min(level_amount(ladder, order.price));
+        }
+    }
+
+    ///
+    /// the middle of the best prices. none while a side is empty.
+    pub fn mid(&self) -> Option<f64> {
+        match (self.book.buy.first(), self.book.sell.first()) {
+            (Some(buy), Some(sell)) => Some((buy.price + sell.price) as f64 / 2.0),
+            _ => None,
+        }
+    }
+}
+
+fn crosses(side: BookSide, price: i32, opposite: i32) -> bool {
+    match side {
+        BookSide::Buy => opposite <= price,
+        BookSide::Sell => opposite >= price,
+    }
+}
+
+fn level_amount(ladder: &[Order], price: i32) -> f64 {
+    ladder
+        .iter()
+        .find(|e| e.price == price)
+        .map(|e| e.amount)
+        .unwrap_or(0.0)
+}
+
+///
+/// a strategy replayed by `Backtester`. the fills of an event are delivered before the event.
+pub trait Strategy {
+    fn on_book(&mut self, _book: &Book, _context: &mut Context) {}
+    fn on_execution(&mut self, _execution: &Execution, _context: &mut Context) {}
+    fn on_fill(&mut self, _fill: &Fill, _context: &mut Context) {}
+}
+
+///
+/// the fills of a run and the balances over it.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BacktestReport {
+    pub fills: Vec<Fill>,
+    pub pnl: Vec<PnlPoint>,
+    ///
+    /// (timestamp, mid) at every change of the mid, which the markouts are measured with.
+    pub mids: Vec<(f64, f64)>,
+    ///
+    /// the fees of the fills in jpy, less the rebates.
+    pub fees: f64,
+    ///
+    /// the number and the quantity of the orders placed by the strategy
+    pub orders: u64,
+    pub placed_quantity: f64,
+}
+
+///
+/// replays market events against the resting orders of a strategy, settling the fills in a `Simulation`.
+pub struct Backtester {
+    exchange: Exchange,
+    simulation: Simulation,
+    report: BacktestReport,
+    last_sample: Option<f64>,
+    mid: f64,
+}
+
+impl Backtester {
+    pub fn new(config: BacktestConfig, simulation: Simulation) -> Self {
+        Self {
+            exchange: Exchange {
+                config,
+                ..Exchange::default()
+            },
+            simulation,
+            report: BacktestReport::default(),
+            last_sample: None,
+            mid: 0.0,
+        }
+    }
+
+    pub fn simulation(&self) -> &Simulation {
+        &self.simulation
+    }
+
+    pub fn report(&self) -> &BacktestReport {
+        &self.report
+    }
+
+    pub fn open_orders(&self) -> &[LimitOrder] {
+        &self.exchange.orders
+    }
+
+    ///
+    /// replay the events, which have to be in the order of `received_at`.
+    pub fn run<S: Strategy, E: Borrow<MarketEvent>>(
+        &mut self,
+        strategy: &mut S,
+        events: impl IntoIterator<Item = E>,
+    ) -> Result<(), String> {
+        for event in events {
+            self.step(strategy, event.borrow())?;
+        }
+        self.sample(true);
+        Ok(())
+    }
+
+    pub fn step<S: Strategy>(
+        &mut self,
+        strategy: &mut S,
+        event: &MarketEvent,
+    ) -> Result<(), String> {
+        let time = event.received_at();
+        if time < self.exchange.now {
+            return Err(error_message!(
+                "the events are out of order! {} after {}",
+                time,
+                self.exchange.now
+            ));
+        }
+        let mut fills = Vec::new();
+        self.exchange.arrive(time, &mut fills);
+        self.exchange.now = time;
+        match event {
+            MarketEvent::Execution(content) => self.exchange.execute(content, &mut fills),
+            MarketEvent::Book(content) => self.exchange.update_book(content),
+        }
+        if let Some(mid) = self.exchange.mid() {
+            if mid != self.mid {
+                self.report.mids.push((time, mid));
+            }
+            self.mid = mid;
+        }
+        if self.mid > 0.0 && self.simulation.accrue(time, self.mid) {
+            self.last_sample = None;
+        }
+
+        let mut settled = Vec::with_capacity(fills.len());
+        for mut fill in fills {
+            // a fill which the balances cannot pay for rejects the rest of its order.
+            match self.simulation.fill_at(
+                fill.timestamp,
+                fill.side.name(),
+                fill.price,
+                fill.quantity,
+                fill.liquidity,
+            ) {
+                Ok(fee) => {
+                    fill.fee = fee;
+                    self.report.fees += fee;
+                    settled.push(fill);
+                }
+                Err(_) => self.exchange.orders.retain(|e| e.id != fill.order_id),
+            }
+        }
+        if !settled.is_empty() {
+            self.last_sample = None;
+        }
+
+        let mut context = Context {
+            exchange: &mut self.exchange,
+            simulation: &self.simulation,
+        };
+        for fill in &settled {
+            strategy.on_fill(fill, &mut context);
+        }
+        self.report.fills.extend(settled);
+        match event {
+            MarketEvent::Execution(content) => strategy.on_execution(content, &mut context),
+            MarketEvent::Book(content) => strategy.on_book(content, &mut context),
+        }
+        self.report.orders = self.exchange.next_id;
+        self.report.placed_quantity = self.exchange.placed_quantity;
+        self.sample(false);
+        Ok(())
+    }
+
+    ///
+    /// record the equity when the interval passed, after a fill, or when `force`d.
+    fn sample(&mut self, force: bool) {
+        let now = self.exchange.now;
+        let due = match self.last_sample {
+            Some(last) => now - last >= self.exchange.config.equity_interval,
+            None => true,
+        };
+        if !(force || due) {
+            return;
+        }
+        let (jpy, btc) = self.simulation.assets();
+        self.report.pnl.push(PnlPoint {
+            timestamp: now,
+            jpy,
+            btc,
+            mid: self.mid,
+            equity: self.simulation.equity(self.mid),
+            fees: self.report.fees,
+            inventory: self.simulation.inventory(),
+        });
+        self.last_sample = Some(now);
+    }
+
+    pub fn finish(self) -> (BacktestReport, Simulation) {
+        (self.report, self.simulation)
+    }
+}
+
+///
+/// the executions and the books received in the timeline, merged in the order of `received_at`.
+pub async fn load_events(
+    database: &dyn Store,
+    timeline: &Timeline,
+) -> Result<Vec<MarketEvent>, String> {
+    let range = Timeline {
+        start: timeline.start.map(|e| e - RECEIVE_DELAY),
+        end: timeline.end.map(|e| e + RECEIVE_DELAY),
+        order: 1,
+        limit: None,
+    };
+    let received = |e: &Execution| {
+        !matches!(timeline.start, Some(start) if e.received_at < start)
+            && !matches!(timeline.end, Some(end) if e.received_at > end)
+    };
+    let mut executions = database
+        .query_records::<Execution>(common_constants::DATABASE_COLLECTION_EXECUTIONS, &range)
+        .await?
+        .into_iter()
+        .filter(received)
+        .collect::<Vec<Execution>>();
+    executions.sort_by(|a, b| {
+        a.received_at
+            .partial_cmp(&b.received_at)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    if let Some(limit) = timeline.limit.filter(|e| *e >= 0) {
+        executions.truncate(limit as usize);
+    }
+    let mut books = Vec::new();
+    let mut stream = book_stream(database, timeline).await?;
+    while let Some(book) = stream.next().await {
+        books.push(book?);
+    }
+    let mut events = executions
+        .into_iter()
+        .map(MarketEvent::Execution)
+        .chain(books.into_iter().map(MarketEvent::Book))
+        .collect::<Vec<MarketEvent>>();
+    // a stable sort keeps the order each collection was stored in.
+    events.sort_by(|a, b| {
+        a.received_at()
+            .partial_cmp(&b.received_at())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Ok(events)
+}
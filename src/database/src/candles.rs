@@ -0,0 +1,268 @@
+use futures::stream::BoxStream;
+
+use super::data::{Candle, Execution};
+use super::store::{Store, Timeline};
+use super::*;
+
+const DAY_SECONDS: f64 = 86_400.0;
+
+///
+/// the period of a candle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+pub enum Resolution {
+    Second,
+    Minute,
+    FiveMinutes,
+    Hour,
+    Day,
+}
+
+impl Resolution {
+    ///
+    /// from the finest. each resolution is rolled up from the previous one.
+    pub const ALL: [Resolution; 5] = [
+        Resolution::Second,
+        Resolution::Minute,
+        Resolution::FiveMinutes,
+        Resolution::Hour,
+        Resolution::Day,
+    ];
+
+    pub fn seconds(&self) -> f64 {
+        match *self {
+            Resolution::Second => 1.0,
+            Resolution::Minute => 60.0,
+            Resolution::FiveMinutes => 300.0,
+            Resolution::Hour => 3_600.0,
+            Resolution::Day => DAY_SECONDS,
+        }
+    }
+
+    pub fn generate_resolution_string(&self) -> &'static str {
+        match *self {
+            Resolution::Second => "1s",
+            Resolution::Minute => "1m",
+            Resolution::FiveMinutes => "5m",
+            Resolution::Hour => "1h",
+            Resolution::Day => "1d",
+        }
+    }
+
+    pub fn generate_from_string(resolution: &str) -> Result<Self, String> {
+        match resolution {
+            "1s" => Ok(Resolution::Second),
+            "1m" => Ok(Resolution::Minute),
+            "5m" => Ok(Resolution::FiveMinutes),
+            "1h" => Ok(Resolution::Hour),
+            "1d" => Ok(Resolution::Day),
+            _ => Err(error_message!(
+                "invalid resolution! 1s, 1m, 5m, 1h or 1d\nresolution : {}",
+                resolution
+            )),
+        }
+    }
+
+    pub fn collection(&self) -> &'static str {
+        match *self {
+            Resolution::Second => common_constants::DATABASE_COLLECTION_CANDLES_1S,
+            Resolution::Minute => common_constants::DATABASE_COLLECTION_CANDLES_1M,
+            Resolution::FiveMinutes => common_constants::DATABASE_COLLECTION_CANDLES_5M,
+            Resolution::Hour => common_constants::DATABASE_COLLECTION_CANDLES_1H,
+            Resolution::Day => common_constants::DATABASE_COLLECTION_CANDLES_1D,
+        }
+    }
+
+    ///
+    /// the resolution rolled up into this one. 1s candles are built from executions.
+    pub fn source(&self) -> Option<Resolution> {
+        match *self {
+            Resolution::Second => None,
+            Resolution::Minute => Some(Resolution::Second),
+            Resolution::FiveMinutes => Some(Resolution::Minute),
+            Resolution::Hour => Some(Resolution::FiveMinutes),
+            Resolution::Day => Some(Resolution::Hour),
+        }
+    }
+
+    ///
+    /// the start of the period including `time`. days start at 00:00 UTC.
+    pub fn floor(&self, time: f64) -> f64 {
+        (time / self.seconds()).floor() * self.seconds()
+    }
+}
+
+impl Candle {
+    fn from_execution(timestamp: f64, execution: &Execution) -> Self {
+        let is_buy = execution.taker_side == "buy";
+        Self {
+            timestamp,
+            open: execution.price,
+            high: execution.price,
+            low: execution.price,
+            close: execution.price,
+            volume: execution.quantity,
+            vwap: execution.price as f64,
+            trades: 1,
+            buy_volume: if is_buy { execution.quantity } else { 0.0 },
+            sell_volume: if is_buy { 0.0 } else { execution.quantity },
+        }
+    }
+
+    ///
+    /// extend the candle by a later one.
+    fn merge(&mut self, later: &Candle) {
+        let volume = self.volume + later.volume;
+        if volume > 0.0 {
+            self.vwap = (self.vwap * self.volume + later.vwap * later.volume) / volume;
+        }
+        self.high = self.high.max(later.high);
+        self.low = self.low.min(later.low);
+        self.close = later.close;
+        self.volume = volume;
+        self.trades += later.trades;
+        self.buy_volume += later.buy_volume;
+        self.sell_volume += later.sell_volume;
+    }
+}
+
+///
+/// merge the candles of the same period. `candles` have to be ordered by time.
+fn fold(candles: impl Iterator<Item = Candle>) -> Vec<Candle> {
+    let mut folded: Vec<Candle> = Vec::new();
+    for candle in candles {
+        match folded.last_mut() {
+            Some(last) if last.timestamp == candle.timestamp => last.merge(&candle),
+            _ => folded.push(candle),
+        }
+    }
+    folded
+}
+
+///
+/// candles of the executions ordered by time. periods without executions have no candle.
+pub fn aggregate(executions: &[Execution], resolution: Resolution) -> Vec<Candle> {
+    fold(
+        executions
+            .iter()
+            .map(|e| Candle::from_execution(resolution.floor(e.timestamp), e)),
+    )
+}
+
+///
+/// candles of a coarser resolution from finer candles ordered by time.
+pub fn rollup(candles: &[Candle], resolution: Resolution) -> Vec<Candle> {
+    fold(candles.iter().map(|e| Candle {
+        timestamp: resolution.floor(e.timestamp),
+        ..e.clone()
+    }))
+}
+
+///
+/// rebuild the candles of every resolution whose period includes a time from `start` to `end`.
+/// returns the number of the written candles.
+pub async fn update(database: &dyn Store, start: f64, end: f64) -> Result<u64, String> {
+    let mut written = 0;
+    for resolution in Resolution::ALL {
+        let from = resolution.floor(start);
+        let to = resolution.floor(end) + resolution.seconds();
+        let timeline = Timeline {
+            start: Some(from),
+            end: Some(to),
+            ..Timeline::all()
+        };
+        let candles = match resolution.source() {
+            None => {
+                let mut executions = database
+                    .query_records::<Execution>(
+                        common_constants::DATABASE_COLLECTION_EXECUTIONS,
+                        &timeline,
+                    )
+                    .await?;
+                executions.retain(|e| e.timestamp < to);
+                aggregate(&executions, resolution)
+            }
+            Some(source) => {
+                let mut candles = database
+                    .query_records::<Candle>(source.collection(), &timeline)
+                    .await?;
+                candles.retain(|e| e.timestamp < to);
+                rollup(&candles, resolution)
+            }
+        };
+        if !candles.is_empty() {
+            written += database
+                .upsert_records(resolution.collection(), &candles)
+                .await?;
+        }
+    }
+    Ok(written)
+}
+
+///
+/// rebuild the candles of the days from `timeline.start` to `timeline.end` a day at a time.
+/// open bounds are the oldest and the newest executions.
+pub async fn rebuild(database: &dyn Store, timeline: &Timeline) -> Result<u64, String> {
+    let edge = |order: i64| async move {
+        let executions = database
+            .query_records::<Execution>(
+                common_constants::DATABASE_COLLECTION_EXECUTIONS,
+                &Timeline::all().order(order).limit(Some(1)),
+            )
+            .await?;
+        match executions.first() {
+            Some(content) => Ok(content.timestamp),
+            None => Err(error_message!("executions is empty!")),
+        }
+    };
+    let start = match timeline.start {
+        Some(content) => content,
+        None => edge(1).await?,
+    };
+    let end = match timeline.end {
+        Some(content) => content,
+        None => edge(-1).await?,
+    };
+
+    let mut written = 0;
+    let mut day = Resolution::Day.floor(start);
+    while day <= end {
+        // the last second of the day
+        written += update(database, day, day + DAY_SECONDS - 1.0).await?;
+        day += DAY_SECONDS;
+    }
+    Ok(written)
+}
+
+///
+/// the candles of `resolution` in the timeline.
+pub async fn query(
+    database: &dyn Store,
+    resolution: Resolution,
+    timeline: &Timeline,
+) -> Result<Vec<Candle>, String> {
+    database
+        .query_records::<Candle>(resolution.collection(), timeline)
+        .await
+}
+
+pub async fn stream(
+    database: &dyn Store,
+    resolution: Resolution,
+    timeline: &Timeline,
+) -> Result<BoxStream<'static, Result<Candle, String>>, String> {
+    database
+        .stream_records::<Candle>(resolution.collection(), timeline)
+        .await
+}
+
+///
+/// the newest `num` candles of `resolution`, newest first.
+pub async fn latest(
+    database: &dyn Store,
+    resolution: Resolution,
+    num: i64,
+) -> Result<Vec<Candle>, String> {
+    database
+        .latest_records::<Candle>(resolution.collection(), num)
+        .await
+}
@@ -0,0 +1,148 @@
+use mongodb::Client;
+use serde::Deserialize;
+
+use super::schema::COLLECTIONS;
+use super::store::{self, Store, Timeline};
+use super::*;
+
+///
+/// the stored range of a collection of a symbol.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CatalogEntry {
+    pub symbol: Symbol,
+    pub collection: String,
+    pub records: u64,
+    ///
+    /// the time of the oldest record
+    pub first: f64,
+    ///
+    /// the time of the newest record
+    pub last: f64,
+}
+
+///
+/// the symbols stored at the url, found by the names of the databases or the files.
+pub async fn symbols(url: &str) -> Result<Vec<Symbol>, String> {
+    let mut symbols = if let Some(path) = url.strip_prefix(store::SQLITE_SCHEME) {
+        sqlite_symbols(path)?
+    } else if url.starts_with(store::MONGODB_SCHEME) || url.starts_with(store::MONGODB_SRV_SCHEME) {
+        mongodb_symbols(url).await?
+    } else {
+        return Err(error_message!("unsupported database url!\nurl : {}", url));
+    };
+    symbols.sort();
+    symbols.dedup();
+    Ok(symbols)
+}
+
+async fn mongodb_symbols(url: &str) -> Result<Vec<Symbol>, String> {
+    let client = match Client::with_uri_str(url).await {
+        Ok(result) => result,
+        Err(result) => {
+            return Err(error_message!(
+                "failed to connect a mongodb server!\n{:?}",
+                result
+            ));
+        }
+    };
+    match client.list_database_names(None, None).await {
+        Ok(result) => Ok(result
+            .iter()
+            .filter_map(|e| Symbol::generate_from_database_name(e))
+            .collect()),
+        Err(result) => Err(error_message!(
+            "failed to list the databases!\ndetails : {:?}",
+            result
+        )),
+    }
+}
+
+fn sqlite_symbols(path: &str) -> Result<Vec<Symbol>, String> {
+    if path == store::SQLITE_MEMORY {
+        return Ok(vec![Symbol::default()]);
+    }
+    let file = std::path::Path::new(path);
+    let mut symbols = Vec::new();
+    if file.exists() {
+        symbols.push(Symbol::default());
+    }
+    let directory = match file.parent() {
+        Some(content) if !content.as_os_str().is_empty() => content,
+        _ => std::path::Path::new("."),
+    };
+    let entries = match std::fs::read_dir(directory) {
+        Ok(result) => result,
+        Err(result) => {
+            return Err(error_message!(
+                "failed to read the directory!\ndetails : {:?}\npath : {}",
+                result,
+                directory.display()
+            ));
+        }
+    };
+    // the files named by `store::sqlite_path`, whose names cannot contain dots but the separators.
+    let stem = file
+        .file_stem()
+        .map(|e| e.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = file
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let database = name
+            .strip_prefix(&stem)
+            .and_then(|e| e.strip_prefix('.'))
+            .and_then(|e| e.strip_suffix(extension.as_str()));
+        if let Some(symbol) = database.and_then(Symbol::generate_from_database_name) {
+            symbols.push(symbol);
+        }
+    }
+    Ok(symbols)
+}
+
+///
+/// the stored range of every collection of the symbols. empty collections are skipped.
+pub async fn catalog(url: &str, symbols: &[Symbol]) -> Result<Vec<CatalogEntry>, String> {
+    let mut entries = Vec::new();
+    for symbol in symbols {
+        let database = store::open_store_for(url, symbol).await?;
+        for collection in COLLECTIONS {
+            if let Some(entry) = collection_entry(database.as_ref(), symbol, collection).await? {
+                entries.push(entry);
+            }
+        }
+    }
+    Ok(entries)
+}
+
+async fn collection_entry(
+    database: &dyn Store,
+    symbol: &Symbol,
+    collection: &str,
+) -> Result<Option<CatalogEntry>, String> {
+    let field = store::time_field(collection)?;
+    let time_of = |records: Vec<serde_json::Value>| {
+        records
+            .first()
+            .and_then(|e| e.get(field))
+            .and_then(|e| e.as_f64())
+    };
+    let first = time_of(
+        database
+            .query(collection, &Timeline::all().limit(Some(1)))
+            .await?,
+    );
+    let last = time_of(database.latest(collection, 1).await?);
+    match (first, last) {
+        (Some(first), Some(last)) => Ok(Some(CatalogEntry {
+            symbol: symbol.clone(),
+            collection: String::from(collection),
+            records: database.count(collection, &Timeline::all()).await?,
+            first,
+            last,
+        })),
+        _ => Ok(None),
+    }
+}
@@ -0,0 +1,625 @@
+use arrow::array::{
+    Array, ArrayRef, Float64Array, Int32Array, Int64Array, ListArray, StringArray, StructArray,
+    UInt32Array, UInt64Array,
+};
+use arrow::buffer::OffsetBuffer;
+use arrow::datatypes::{DataType, Field, Fields, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use futures::stream::{BoxStream, Stream, StreamExt};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use parquet::file::properties::WriterProperties;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+pub use parquet::basic::Compression;
+
+use super::data::{Execution, OrderBook, Ticker};
+use super::store::Store;
+use super::*;
+
+///
+/// number of rows converted together while writing and reading.
+pub const BATCH_ROWS: usize = 8192;
+
+///
+/// number of records inserted at once by `import_parquet`.
+const IMPORT_CHUNK: usize = 10_000;
+
+///
+/// number of batches buffered between the reading thread and a stream.
+const STREAM_BUFFER: usize = 4;
+
+///
+/// records which can be converted to and from arrow record batches.
+pub trait Columnar: Sized {
+    fn schema() -> SchemaRef;
+    fn to_batch(records: &[Self]) -> Result<RecordBatch, String>;
+    fn from_batch(batch: &RecordBatch) -> Result<Vec<Self>, String>;
+}
+
+///
+/// a price level of an order book, one row per level in the long layout.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OrderLevel {
+    pub received_at: f64,
+    ///
+    /// the position in the order book from 0
+    pub level: u32,
+    pub price: i32,
+    pub amount: f64,
+}
+
+impl OrderLevel {
+    pub fn flatten(order_books: &[OrderBook]) -> Vec<Self> {
+        order_books
+            .iter()
+            .flat_map(|book| {
+                book.orders
+                    .iter()
+                    .enumerate()
+                    .map(move |(level, order)| Self {
+                        received_at: book.received_at,
+                        level: level as u32,
+                        price: order.price,
+                        amount: order.amount,
+                    })
+            })
+            .collect()
+    }
+
+    ///
+    /// append the levels to the order books. a level 0 or a new time starts an order book.
+    pub fn extend(order_books: &mut Vec<OrderBook>, levels: &[Self]) {
+        for level in levels {
+            let order = liquid::data::Order {
+                price: level.price,
+                amount: level.amount,
+            };
+            match order_books.last_mut() {
+                Some(book) if book.received_at == level.received_at && level.level != 0 => {
+                    book.orders.push(order);
+                }
+                _ => order_books.push(OrderBook {
+                    received_at: level.received_at,
+                    orders: vec![order],
+                }),
+            }
+        }
+    }
+
+    pub fn collect(levels: &[Self]) -> Vec<OrderBook> {
+        let mut order_books = Vec::new();
+        Self::extend(&mut order_books, levels);
+        order_books
+    }
+}
+
+///
+/// options of the parquet files.
+#[derive(Clone, Debug)]
+pub struct ParquetOptions {
+    pub row_group_rows: usize,
+    pub compression: Compression,
+}
+
+impl Default for ParquetOptions {
+    fn default() -> Self {
+        Self {
+            row_group_rows: 1024 * 1024,
+            compression: Compression::SNAPPY,
+        }
+    }
+}
+
+///
+/// none, snappy or zstd
+pub fn compression_from_string(name: &str) -> Result<Compression, String> {
+    match name {
+        "none" => Ok(Compression::UNCOMPRESSED),
+        "snappy" => Ok(Compression::SNAPPY),
+        "zstd" => Ok(Compression::ZSTD(Default::default())),
+        _ => Err(error_message!("invalid compression! {}", name)),
+    }
+}
+
+fn column<'a, A: Array + 'static>(batch: &'a RecordBatch, name: &str) -> Result<&'a A, String> {
+    match batch
+        .column_by_name(name)
+        .and_then(|e| e.as_any().downcast_ref::<A>())
+    {
+        Some(content) => Ok(content),
+        None => Err(error_message!("invalid column! {}", name)),
+    }
+}
+
+fn to_batch(schema: SchemaRef, columns: Vec<ArrayRef>) -> Result<RecordBatch, String> {
+    match RecordBatch::try_new(schema, columns) {
+        Ok(result) => Ok(result),
+        Err(result) => Err(error_message!(
+            "failed to build a record batch!\ndetails : {:?}",
+            result
+        )),
+    }
+}
+
+fn f64_column<T>(records: &[T], value: impl Fn(&T) -> f64) -> ArrayRef {
+    Arc::new(Float64Array::from_iter_values(records.iter().map(value)))
+}
+
+fn i32_column<T>(records: &[T], value: impl Fn(&T) -> i32) -> ArrayRef {
+    Arc::new(Int32Array::from_iter_values(records.iter().map(value)))
+}
+
+fn i64_column<T>(records: &[T], value: impl Fn(&T) -> i64) -> ArrayRef {
+    Arc::new(Int64Array::from_iter_values(records.iter().map(value)))
+}
+
+impl Columnar for Ticker {
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("received_at", DataType::Float64, false),
+            Field::new("timestamp", DataType::Float64, false),
+            Field::new("last_traded_price", DataType::Int32, false),
+            Field::new("last_traded_quantity", DataType::Float64, false),
+            Field::new("last_price_24h", DataType::Int32, false),
+            Field::new("average_price_24h", DataType::Float64, false),
+            Field::new("volume_24h", DataType::Float64, false),
+            Field::new("market_ask", DataType::Int32, false),
+            Field::new("market_bid", DataType::Int32, false),
+            Field::new("low_market_price_24h", DataType::Int32, false),
+            Field::new("high_market_price_24h", DataType::Int32, false),
+        ]))
+    }
+
+    fn to_batch(records: &[Self]) -> Result<RecordBatch, String> {
+        to_batch(
+            Self::schema(),
+            vec![
+                f64_column(records, |e| e.received_at),
+                f64_column(records, |e| e.timestamp),
+                i32_column(records, |e| e.last_traded_price),
+                f64_column(records, |e| e.last_traded_quantity),
+                i32_column(records, |e| e.last_price_24h),
+                f64_column(records, |e| e.average_price_24h),
+                f64_column(records, |e| e.volume_24h),
+                i32_column(records, |e| e.market_ask),
+                i32_column(records, |e| e.market_bid),
+                i32_column(records, |e| e.low_market_price_24h),
+                i32_column(records, |e| e.high_market_price_24h),
+            ],
+        )
+    }
+
+    fn from_batch(batch: &RecordBatch) -> Result<Vec<Self>, String> {
+        let received_at = column::<Float64Array>(batch, "received_at")?;
+        let timestamp = column::<Float64Array>(batch, "timestamp")?;
+        let last_traded_price = column::<Int32Array>(batch, "last_traded_price")?;
+        let last_traded_quantity = column::<Float64Array>(batch, "last_traded_quantity")?;
+        let last_price_24h = column::<Int32Array>(batch, "last_price_24h")?;
+        let average_price_24h = column::<Float64Array>(batch, "average_price_24h")?;
+        let volume_24h = column::<Float64Array>(batch, "volume_24h")?;
+        let market_ask = column::<Int32Array>(batch, "market_ask")?;
+        let market_bid = column::<Int32Array>(batch, "market_bid")?;
+        let low_market_price_24h = column::<Int32Array>(batch, "low_market_price_24h")?;
+        let high_market_price_24h = column::<Int32Array>(batch, "high_market_price_24h")?;
+        Ok((0..batch.num_rows())
+            .map(|i| Self {
+                received_at: received_at.value(i),
+                timestamp: timestamp.value(i),
+                last_traded_price: last_traded_price.value(i),
+                last_traded_quantity: last_traded_quantity.value(i),
+                last_price_24h: last_price_24h.value(i),
+                average_price_24h: average_price_24h.value(i),
+                volume_24h: volume_24h.value(i),
+                market_ask: market_ask.value(i),
+                market_bid: market_bid.value(i),
+                low_market_price_24h: low_market_price_24h.value(i),
+                high_market_price_24h: high_market_price_24h.value(i),
+            })
+            .collect())
+    }
+}
+
+///
+/// the files written before created_at was widened hold it as i32, wrapped from the u32 of the exchange.
+fn created_at(batch: &RecordBatch) -> Result<Vec<i64>, String> {
+    if let Ok(content) = column::<Int64Array>(batch, "created_at") {
+        return Ok(content.values().to_vec());
+    }
+    let content = column::<Int32Array>(batch, "created_at")?;
+    Ok(content
+        .values()
+        .iter()
+        .map(|e| i64::from(*e as u32))
+        .collect())
+}
+
+impl Columnar for Execution {
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("received_at", DataType::Float64, false),
+            Field::new("timestamp", DataType::Float64, false),
+            Field::new("created_at", DataType::Int64, false),
+            Field::new("price", DataType::Int32, false),
+            Field::new("quantity", DataType::Float64, false),
+            Field::new("taker_side", DataType::Utf8, false),
+            Field::new("id", DataType::UInt64, true),
+        ]))
+    }
+
+    fn to_batch(records: &[Self]) -> Result<RecordBatch, String> {
+        to_batch(
+            Self::schema(),
+            vec![
+                f64_column(records, |e| e.received_at),
+                f64_column(records, |e| e.timestamp),
+                i64_column(records, |e| e.created_at),
+                i32_column(records, |e| e.price),
+                f64_column(records, |e| e.quantity),
+                Arc::new(StringArray::from_iter_values(
+                    records.iter().map(|e| &e.taker_side),
+                )),
+                Arc::new(records.iter().map(|e| e.id).collect::<UInt64Array>()),
+            ],
+        )
+    }
+
+    fn from_batch(batch: &RecordBatch) -> Result<Vec<Self>, String> {
+        let received_at = column::<Float64Array>(batch, "received_at")?;
+        let timestamp = column::<Float64Array>(batch, "timestamp")?;
+        let created_at = created_at(batch)?;
+        let price = column::<Int32Array>(batch, "price")?;
+        let quantity = column::<Float64Array>(batch, "quantity")?;
+        let taker_side = column::<StringArray>(batch, "taker_side")?;
+        let id = column::<UInt64Array>(batch, "id")?;
+        Ok((0..batch.num_rows())
+            .map(|i| Self {
+                received_at: received_at.value(i),
+                timestamp: timestamp.value(i),
+                created_at: created_at[i],
+                price: price.value(i),
+                quantity: quantity.value(i),
+                taker_side: String::from(taker_side.value(i)),
+                id: if id.is_null(i) {
+                    None
+                } else {
+                    Some(id.value(i))
+                },
+            })
+            .collect())
+    }
+}
+
+fn order_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("price", DataType::Int32, false),
+        Field::new("amount", DataType::Float64, false),
+    ])
+}
+
+fn order_item() -> Arc<Field> {
+    Arc::new(Field::new("item", DataType::Struct(order_fields()), false))
+}
+
+///
+/// the nested layout, a list of (price, amount) per order book.
+impl Columnar for OrderBook {
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("received_at", DataType::Float64, false),
+            Field::new("orders", DataType::List(order_item()), false),
+        ]))
+    }
+
+    fn to_batch(records: &[Self]) -> Result<RecordBatch, String> {
+        let orders = records.iter().flat_map(|e| e.orders.iter());
+        let entries = StructArray::new(
+            order_fields(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(
+                    orders.clone().map(|e| e.price),
+                )),
+                Arc::new(Float64Array::from_iter_values(orders.map(|e| e.amount))),
+            ],
+            None,
+        );
+        let list = match ListArray::try_new(
+            order_item(),
+            OffsetBuffer::from_lengths(records.iter().map(|e| e.orders.len())),
+            Arc::new(entries),
+            None,
+        ) {
+            Ok(result) => result,
+            Err(result) => {
+                return Err(error_message!(
+                    "failed to build a list!\ndetails : {:?}",
+                    result
+                ));
+            }
+        };
+        to_batch(
+            Self::schema(),
+            vec![f64_column(records, |e| e.received_at), Arc::new(list)],
+        )
+    }
+
+    fn from_batch(batch: &RecordBatch) -> Result<Vec<Self>, String> {
+        let received_at = column::<Float64Array>(batch, "received_at")?;
+        let orders = column::<ListArray>(batch, "orders")?;
+        let mut records = Vec::with_capacity(batch.num_rows());
+        for i in 0..batch.num_rows() {
+            let entries = orders.value(i);
+            let entries = match entries.as_any().downcast_ref::<StructArray>() {
+                Some(content) => content,
+                None => return Err(error_message!("invalid column! orders")),
+            };
+            let price = entries
+                .column_by_name("price")
+                .and_then(|e| e.as_any().downcast_ref::<Int32Array>());
+            let amount = entries
+                .column_by_name("amount")
+                .and_then(|e| e.as_any().downcast_ref::<Float64Array>());
+            let (price, amount) = match (price, amount) {
+                (Some(price), Some(amount)) => (price, amount),
+                _ => return Err(error_message!("invalid column! orders")),
+            };
+            records.push(Self {
+                received_at: received_at.value(i),
+                orders: (0..entries.len())
+                    .map(|j| liquid::data::Order {
+                        price: price.value(j),
+                        amount: amount.value(j),
+                    })
+                    .collect(),
+            });
+        }
+        Ok(records)
+    }
+}
+
+///
+/// the long layout, a row per price level.
+impl Columnar for OrderLevel {
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("received_at", DataType::Float64, false),
+            Field::new("level", DataType::UInt32, false),
+            Field::new("price", DataType::Int32, false),
+            Field::new("amount", DataType::Float64, false),
+        ]))
+    }
+
+    fn to_batch(records: &[Self]) -> Result<RecordBatch, String> {
+        to_batch(
+            Self::schema(),
+            vec![
+                f64_column(records, |e| e.received_at),
+                Arc::new(UInt32Array::from_iter_values(
+                    records.iter().map(|e| e.level),
+                )),
+                i32_column(records, |e| e.price),
+                f64_column(records, |e| e.amount),
+            ],
+        )
+    }
+
+    fn from_batch(batch: &RecordBatch) -> Result<Vec<Self>, String> {
+        let received_at = column::<Float64Array>(batch, "received_at")?;
+        let level = column::<UInt32Array>(batch, "level")?;
+        let price = column::<Int32Array>(batch, "price")?;
+        let amount = column::<Float64Array>(batch, "amount")?;
+        Ok((0..batch.num_rows())
+            .map(|i| Self {
+                received_at: received_at.value(i),
+                level: level.value(i),
+                price: price.value(i),
+                amount: amount.value(i),
+            })
+            .collect())
+    }
+}
+
+///
+/// group the records of a stream into record batches of `rows` rows.
+pub fn record_batches<'a, T, S>(
+    stream: S,
+    rows: usize,
+) -> BoxStream<'a, Result<RecordBatch, String>>
+where
+    T: Columnar + Send + 'a,
+    S: Stream<Item = Result<T, String>> + Send + 'a,
+{
+    stream
+        .chunks(rows.max(1))
+        .map(|chunk| {
+            let records = chunk.into_iter().collect::<Result<Vec<T>, String>>()?;
+            T::to_batch(&records)
+        })
+        .boxed()
+}
+
+///
+/// write the records of a stream to a parquet file. returns the number of the rows.
+pub async fn write_parquet<T, S>(
+    path: &str,
+    stream: S,
+    options: &ParquetOptions,
+) -> Result<u64, String>
+where
+    T: Columnar + Send,
+    S: Stream<Item = Result<T, String>> + Send,
+{
+    let file = match std::fs::File::create(path) {
+        Ok(result) => result,
+        Err(result) => {
+            return Err(error_message!("failed to create!\ndetails : {:?}", result));
+        }
+    };
+    let properties = WriterProperties::builder()
+        .set_max_row_group_size(options.row_group_rows.max(1))
+        .set_compression(options.compression)
+        .build();
+    let mut writer = match ArrowWriter::try_new(file, T::schema(), Some(properties)) {
+        Ok(result) => result,
+        Err(result) => {
+            return Err(error_message!(
+                "failed to create a parquet writer!\ndetails : {:?}",
+                result
+            ));
+        }
+    };
+    let mut rows = 0;
+    let mut batches = record_batches(stream, BATCH_ROWS.min(options.row_group_rows));
+    while let Some(batch) = batches.next().await {
+        let batch = batch?;
+        rows += batch.num_rows() as u64;
+        // the encoding and the compression run out of the runtime.
+        writer = tokio::task::spawn_blocking(move || match writer.write(&batch) {
+            Ok(_) => Ok(writer),
+            Err(result) => Err(error_message!("failed to write!\ndetails : {:?}", result)),
+        })
+        .await
+        .expect("failed to run blocking task!")?;
+    }
+    tokio::task::spawn_blocking(move || match writer.close() {
+        Ok(_) => Ok(rows),
+        Err(result) => Err(error_message!("failed to write!\ndetails : {:?}", result)),
+    })
+    .await
+    .expect("failed to run blocking task!")
+}
+
+///
+/// the record batches of a parquet file, read batch by batch.
+pub fn read_batches(path: &str) -> BoxStream<'static, Result<RecordBatch, String>> {
+    let path = String::from(path);
+    let (transmitter, receiver) = tokio::sync::mpsc::channel(STREAM_BUFFER);
+    tokio::task::spawn_blocking(move || {
+        let reader = std::fs::File::open(&path)
+            .map_err(|e| error_message!("failed to read!\ndetails : {:?}", e))
+            .and_then(|file| {
+                ParquetRecordBatchReaderBuilder::try_new(file)
+                    .and_then(|e| e.with_batch_size(BATCH_ROWS).build())
+                    .map_err(|e| {
+                        error_message!("failed to read a parquet file!\ndetails : {:?}", e)
+                    })
+            });
+        let reader = match reader {
+            Ok(result) => result,
+            Err(result) => {
+                let _ = transmitter.blocking_send(Err(result));
+                return;
+            }
+        };
+        for batch in reader {
+            let batch =
+                batch.map_err(|e| error_message!("failed to read a batch!\ndetails : {:?}", e));
+            // the receiver was dropped.
+            if transmitter.blocking_send(batch).is_err() {
+                return;
+            }
+        }
+    });
+
+    futures::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|e| (e, receiver))
+    })
+    .boxed()
+}
+
+///
+/// the records of a parquet file.
+pub fn read_parquet<T: Columnar + Send + 'static>(
+    path: &str,
+) -> BoxStream<'static, Result<T, String>> {
+    read_batches(path)
+        .flat_map(|batch| {
+            let records = match batch.and_then(|e| T::from_batch(&e)) {
+                Ok(result) => result.into_iter().map(Ok).collect(),
+                Err(result) => vec![Err(result)],
+            };
+            futures::stream::iter(records)
+        })
+        .boxed()
+}
+
+///
+/// the order books of a parquet file of the nested or the long layout.
+pub fn read_order_books(path: &str) -> BoxStream<'static, Result<OrderBook, String>> {
+    // (batches, the order book which may continue in the next batch, the order books to yield)
+    let state = (read_batches(path).fuse(), Vec::new(), VecDeque::new());
+    futures::stream::unfold(
+        state,
+        |(mut batches, mut pending, mut ready): (_, Vec<OrderBook>, VecDeque<OrderBook>)| async move {
+            loop {
+                if let Some(book) = ready.pop_front() {
+                    return Some((Ok(book), (batches, pending, ready)));
+                }
+                let batch = match batches.next().await {
+                    Some(content) => content,
+                    None if pending.is_empty() => return None,
+                    None => {
+                        ready.extend(pending.drain(..));
+                        continue;
+                    }
+                };
+                let books = batch.and_then(|batch| {
+                    if batch.column_by_name("level").is_none() {
+                        return OrderBook::from_batch(&batch);
+                    }
+                    OrderLevel::extend(&mut pending, &OrderLevel::from_batch(&batch)?);
+                    let last = pending.pop();
+                    let books = std::mem::take(&mut pending);
+                    pending.extend(last);
+                    Ok(books)
+                });
+                match books {
+                    Ok(result) => ready.extend(result),
+                    Err(result) => return Some((Err(result), (batches, pending, ready))),
+                }
+            }
+        },
+    )
+    .boxed()
+}
+
+async fn import<T>(
+    database: &dyn Store,
+    collection: &str,
+    records: BoxStream<'static, Result<T, String>>,
+) -> Result<u64, String>
+where
+    T: Serialize + std::fmt::Debug + Send + 'static,
+{
+    let mut chunks = records.chunks(IMPORT_CHUNK);
+    let mut inserted = 0;
+    while let Some(chunk) = chunks.next().await {
+        let records = chunk.into_iter().collect::<Result<Vec<T>, String>>()?;
+        inserted += database.insert_records(collection, &records).await?;
+    }
+    Ok(inserted)
+}
+
+///
+/// insert the records of a parquet file written by `write_parquet`. returns the inserted records.
+pub async fn import_parquet(
+    database: &dyn Store,
+    collection: &str,
+    path: &str,
+) -> Result<u64, String> {
+    match collection {
+        common_constants::DATABASE_COLLECTION_TICKER => {
+            import(database, collection, read_parquet::<Ticker>(path)).await
+        }
+        common_constants::DATABASE_COLLECTION_EXECUTIONS => {
+            import(database, collection, read_parquet::<Execution>(path)).await
+        }
+        common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY
+        | common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL => {
+            import(database, collection, read_order_books(path)).await
+        }
+        _ => Err(error_message!("invalid collection!\n{}", collection)),
+    }
+}
@@ -88,10 +88,14 @@ pub struct Execution {
     //pub _id: mongodb::bson::oid::ObjectId,
     pub received_at: f64,
     pub timestamp: f64,
-    pub created_at: i32,
+    pub created_at: i64,
     pub price: i32,
     pub quantity: f64,
     pub taker_side: String,
+    ///
+    /// the exchange execution id. None for the executions stored before it was kept.
+    #[serde(default)]
+    pub id: Option<u64>,
 }
 
 /*impl Serialize for Execution {
@@ -124,7 +128,7 @@ impl Generater for Execution {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct OrderBook {
     //pub _id: mongodb::bson::oid::ObjectId,
     pub received_at: f64,
@@ -144,6 +148,41 @@ impl Generater for OrderBook {
     }
 }
 
+///
+/// OHLCV bar of the executions in a period.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Candle {
+    ///
+    /// the start of the period
+    pub timestamp: f64,
+    pub open: i32,
+    pub high: i32,
+    pub low: i32,
+    pub close: i32,
+    pub volume: f64,
+    ///
+    /// volume weighted average price
+    pub vwap: f64,
+    pub trades: u64,
+    ///
+    /// volume of the executions whose taker bought
+    pub buy_volume: f64,
+    pub sell_volume: f64,
+}
+
+impl Generater for Candle {
+    type Output = Self;
+    fn generate_from_document(doc: bson::Document) -> Result<Self, String> {
+        match bson::from_document(doc) as bson::de::Result<data::Candle> {
+            Ok(result) => Ok(result),
+            Err(result) => Err(error_message!(
+                "failed to deserialize!\ndetails : {:?}",
+                result
+            )),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SimulationExecution {
     pub timestamp: f64,
@@ -151,4 +190,8 @@ pub struct SimulationExecution {
     pub quantity: f64,
     pub order: String,
     pub result: Result<(), String>,
+    ///
+    /// the fee paid in jpy, negative for a rebate.
+    #[serde(default)]
+    pub fee: f64,
 }
@@ -0,0 +1,504 @@
+use chrono::DateTime;
+use futures::stream::{BoxStream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, SeekFrom};
+use tokio::fs::File;
+use tokio::io::{AsyncWriteExt, BufWriter};
+
+use super::store::{from_value, time_field, RecordStream, Timeline};
+use super::*;
+
+///
+/// the first and the last bytes of a dataset file.
+const MAGIC: &[u8; 4] = b"ZNDS";
+
+///
+/// version of the layout of the file, not of the records.
+pub const FORMAT_VERSION: u32 = 1;
+
+///
+/// number of records compressed together by default.
+pub const BLOCK_RECORDS: usize = 4096;
+
+const COMPRESSION_LEVEL: i32 = 3;
+
+///
+/// number of records buffered between the reading thread and a stream.
+const STREAM_BUFFER: usize = 1024;
+
+///
+/// what a dataset holds. written at the top of the file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DatasetHeader {
+    pub collection: String,
+    pub downloaded_at: DateTime<Utc>,
+    ///
+    /// the requested range. the stored range of each block is in the index.
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    ///
+    /// the schema version of the records without their own.
+    pub schema_version: u32,
+}
+
+impl DatasetHeader {
+    pub fn new(collection: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Self {
+            collection: String::from(collection),
+            downloaded_at: Utc::now(),
+            start,
+            end,
+            schema_version: migrations::current_version(collection),
+        }
+    }
+}
+
+///
+/// the position and the time range of a compressed block.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BlockIndex {
+    pub offset: u64,
+    pub length: u64,
+    pub records: u64,
+    pub first: f64,
+    pub last: f64,
+}
+
+///
+/// file layout
+/// MAGIC | FORMAT_VERSION u32 | header length u32 | header json
+/// block length u32 | zstd(newline delimited json records), repeated
+/// index json | index length u64 | MAGIC
+pub struct DatasetWriter {
+    _file: BufWriter<File>,
+    _field: &'static str,
+    _block_records: usize,
+    _block: Vec<u8>,
+    _pending: Option<BlockIndex>,
+    _index: Vec<BlockIndex>,
+    _offset: u64,
+}
+
+impl DatasetWriter {
+    pub async fn create(path: &str, header: &DatasetHeader) -> Result<Self, String> {
+        let field = time_field(&header.collection)?;
+        let header = match serde_json::to_vec(header) {
+            Ok(result) => result,
+            Err(result) => {
+                return Err(error_message!(
+                    "failed to serialize!\ndetails : {:?}",
+                    result
+                ));
+            }
+        };
+        let file = match File::create(path).await {
+            Ok(result) => result,
+            Err(result) => {
+                return Err(error_message!("failed to create!\ndetails : {:?}", result));
+            }
+        };
+        let mut writer = Self {
+            _file: BufWriter::new(file),
+            _field: field,
+            _block_records: BLOCK_RECORDS,
+            _block: Vec::new(),
+            _pending: None,
+            _index: Vec::new(),
+            _offset: 0,
+        };
+        let mut top = Vec::with_capacity(12 + header.len());
+        top.extend_from_slice(MAGIC);
+        top.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        top.extend_from_slice(&(header.len() as u32).to_le_bytes());
+        top.extend_from_slice(&header);
+        writer.write(&top).await?;
+        Ok(writer)
+    }
+
+    pub fn block_records(mut self, records: usize) -> Self {
+        self._block_records = records.max(1);
+        self
+    }
+
+    async fn write(&mut self, bytes: &[u8]) -> Result<(), String> {
+        if let Err(result) = self._file.write_all(bytes).await {
+            return Err(error_message!("failed to write!\ndetails : {:?}", result));
+        }
+        self._offset += bytes.len() as u64;
+        Ok(())
+    }
+
+    ///
+    /// append a record. records must be ordered by the time field of the collection.
+    pub async fn push(&mut self, record: &serde_json::Value) -> Result<(), String> {
+        let time = match record.get(self._field).and_then(|e| e.as_f64()) {
+            Some(result) => result,
+            None => {
+                return Err(error_message!(
+                    "the record has no {}!\nreceived data : {}",
+                    self._field,
+                    record
+                ));
+            }
+        };
+        let previous = self
+            ._pending
+            .as_ref()
+            .or_else(|| self._index.last())
+            .map(|e| e.last);
+        if let Some(previous) = previous.filter(|e| time < *e) {
+            return Err(error_message!(
+                "records must be ordered by {}! {} after {}",
+                self._field,
+                time,
+                previous
+            ));
+        }
+        if let Err(result) = serde_json::to_writer(&mut self._block, record) {
+            return Err(error_message!(
+                "failed to serialize!\ndetails : {:?}",
+                result
+            ));
+        }
+        self._block.push(b'\n');
+        let pending = self._pending.get_or_insert(BlockIndex {
+            offset: 0,
+            length: 0,
+            records: 0,
+            first: time,
+            last: time,
+        });
+        pending.records += 1;
+        pending.last = time;
+        if pending.records as usize >= self._block_records {
+            self.flush_block().await?;
+        }
+        Ok(())
+    }
+
+    pub async fn push_record<T: Serialize>(&mut self, record: &T) -> Result<(), String> {
+        match serde_json::to_value(record) {
+            Ok(result) => self.push(&result).await,
+            Err(result) => Err(error_message!(
+                "failed to serialize!\ndetails : {:?}",
+                result
+            )),
+        }
+    }
+
+    async fn flush_block(&mut self) -> Result<(), String> {
+        let mut block = match self._pending.take() {
+            Some(result) => result,
+            None => return Ok(()),
+        };
+        let records = std::mem::take(&mut self._block);
+        let compressed = match tokio::task::spawn_blocking(move || {
+            zstd::bulk::compress(&records, COMPRESSION_LEVEL)
+        })
+        .await
+        .expect("failed to run blocking task!")
+        {
+            Ok(result) => result,
+            Err(result) => {
+                return Err(error_message!(
+                    "failed to compress!\ndetails : {:?}",
+                    result
+                ));
+            }
+        };
+        block.offset = self._offset;
+        block.length = compressed.len() as u64;
+        self.write(&(compressed.len() as u32).to_le_bytes()).await?;
+        self.write(&compressed).await?;
+        self._index.push(block);
+        Ok(())
+    }
+
+    ///
+    /// write the remaining records and the index. returns the number of the records.
+    pub async fn finish(mut self) -> Result<u64, String> {
+        self.flush_block().await?;
+        let index = match serde_json::to_vec(&self._index) {
+            Ok(result) => result,
+            Err(result) => {
+                return Err(error_message!(
+                    "failed to serialize!\ndetails : {:?}",
+                    result
+                ));
+            }
+        };
+        self.write(&index).await?;
+        self.write(&(index.len() as u64).to_le_bytes()).await?;
+        self.write(MAGIC).await?;
+        if let Err(result) = self._file.flush().await {
+            return Err(error_message!("failed to write!\ndetails : {:?}", result));
+        }
+        Ok(self._index.iter().map(|e| e.records).sum())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DatasetReader {
+    _path: String,
+    pub header: DatasetHeader,
+    pub index: Vec<BlockIndex>,
+}
+
+fn read_exact(file: &mut std::fs::File, length: usize) -> Result<Vec<u8>, String> {
+    let mut buffer = vec![0; length];
+    match file.read_exact(&mut buffer) {
+        Ok(_) => Ok(buffer),
+        Err(result) => Err(error_message!("failed to read!\ndetails : {:?}", result)),
+    }
+}
+
+fn seek(file: &mut std::fs::File, position: SeekFrom) -> Result<u64, String> {
+    match file.seek(position) {
+        Ok(result) => Ok(result),
+        Err(result) => Err(error_message!("failed to seek!\ndetails : {:?}", result)),
+    }
+}
+
+fn parse<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+    match serde_json::from_slice(bytes) {
+        Ok(result) => Ok(result),
+        Err(result) => Err(error_message!(
+            "failed to deserialize!\ndetails : {:?}",
+            result
+        )),
+    }
+}
+
+fn to_u32(bytes: &[u8]) -> u32 {
+    let mut buffer = [0; 4];
+    buffer.copy_from_slice(&bytes[..4]);
+    u32::from_le_bytes(buffer)
+}
+
+///
+/// the decompressed records of a block.
+fn read_block(
+    file: &mut std::fs::File,
+    block: &BlockIndex,
+) -> Result<Vec<serde_json::Value>, String> {
+    seek(file, SeekFrom::Start(block.offset))?;
+    let length = to_u32(&read_exact(file, 4)?) as usize;
+    let compressed = read_exact(file, length)?;
+    let records = match zstd::stream::decode_all(&compressed[..]) {
+        Ok(result) => result,
+        Err(result) => {
+            return Err(error_message!(
+                "failed to decompress!\ndetails : {:?}",
+                result
+            ));
+        }
+    };
+    records
+        .split(|e| *e == b'\n')
+        .filter(|e| !e.is_empty())
+        .map(parse)
+        .collect()
+}
+
+impl DatasetReader {
+    ///
+    /// read the header and the index. the records are read by the streams.
+    pub async fn open(path: &str) -> Result<Self, String> {
+        let path = String::from(path);
+        tokio::task::spawn_blocking(move || {
+            let mut file = match std::fs::File::open(&path) {
+                Ok(result) => result,
+                Err(result) => {
+                    return Err(error_message!("failed to read!\ndetails : {:?}", result));
+                }
+            };
+            let top = read_exact(&mut file, 12)?;
+            if &top[..4] != MAGIC {
+                return Err(error_message!("{} is not a dataset!", path));
+            }
+            let version = to_u32(&top[4..]);
+            if version != FORMAT_VERSION {
+                return Err(error_message!(
+                    "unsupported dataset format version! {}",
+                    version
+                ));
+            }
+            let header: DatasetHeader = parse(&read_exact(&mut file, to_u32(&top[8..]) as usize)?)?;
+
+            let end = seek(&mut file, SeekFrom::End(-12))?;
+            let bottom = read_exact(&mut file, 12)?;
+            if &bottom[8..] != MAGIC {
+                return Err(error_message!("{} was not finished!", path));
+            }
+            let mut length = [0; 8];
+            length.copy_from_slice(&bottom[..8]);
+            let length = u64::from_le_bytes(length);
+            seek(&mut file, SeekFrom::Start(end - length))?;
+            let index = parse(&read_exact(&mut file, length as usize)?)?;
+            Ok(Self {
+                _path: path,
+                header,
+                index,
+            })
+        })
+        .await
+        .expect("failed to run blocking task!")
+    }
+
+    pub fn len(&self) -> u64 {
+        self.index.iter().map(|e| e.records).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///
+    /// the records in the timeline. only the blocks overlapping it are read.
+    pub fn stream(&self, timeline: &Timeline) -> Result<RecordStream, String> {
+        let field = time_field(&self.header.collection)?;
+        let start = timeline.start.unwrap_or(f64::MIN);
+        let end = timeline.end.unwrap_or(f64::MAX);
+        let descending = timeline.order < 0;
+        let mut remaining = timeline.limit.filter(|e| *e >= 0);
+        let mut blocks = self
+            .index
+            .iter()
+            .filter(|e| e.last >= start && e.first <= end)
+            .cloned()
+            .collect::<Vec<BlockIndex>>();
+        if descending {
+            blocks.reverse();
+        }
+        let path = self._path.clone();
+        let header = self.header.clone();
+        let (transmitter, receiver) = tokio::sync::mpsc::channel(STREAM_BUFFER);
+        tokio::task::spawn_blocking(move || {
+            let mut file = match std::fs::File::open(&path) {
+                Ok(result) => result,
+                Err(result) => {
+                    let _ = transmitter.blocking_send(Err(error_message!(
+                        "failed to read!\ndetails : {:?}",
+                        result
+                    )));
+                    return;
+                }
+            };
+            for block in blocks {
+                let mut records = match read_block(&mut file, &block) {
+                    Ok(result) => result,
+                    Err(result) => {
+                        let _ = transmitter.blocking_send(Err(result));
+                        return;
+                    }
+                };
+                if descending {
+                    records.reverse();
+                }
+                for mut record in records {
+                    let time = record
+                        .get(field)
+                        .and_then(|e| e.as_f64())
+                        .unwrap_or_default();
+                    if time < start || time > end {
+                        continue;
+                    }
+                    if remaining == Some(0) {
+                        return;
+                    }
+                    remaining = remaining.map(|e| e - 1);
+                    if let Some(content) = record.as_object_mut() {
+                        content
+                            .entry(migrations::SCHEMA_VERSION_FIELD)
+                            .or_insert_with(|| serde_json::Value::from(header.schema_version));
+                    }
+                    let record = migrations::on_read(&header.collection, record);
+                    // the receiver was dropped.
+                    if transmitter.blocking_send(Ok(record)).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        Ok(
+            futures::stream::unfold(receiver, |mut receiver| async move {
+                receiver.recv().await.map(|e| (e, receiver))
+            })
+            .boxed(),
+        )
+    }
+
+    pub fn stream_records<T: DeserializeOwned + Send + 'static>(
+        &self,
+        timeline: &Timeline,
+    ) -> Result<BoxStream<'static, Result<T, String>>, String> {
+        Ok(self
+            .stream(timeline)?
+            .map(|e| e.and_then(from_value))
+            .boxed())
+    }
+}
+
+///
+/// the whole-file json written by `local::LocalRecords`, whose records are named by their type.
+#[derive(Deserialize)]
+struct LocalDataset {
+    collection: String,
+    downloaded_at: DateTime<Utc>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    #[serde(flatten)]
+    fields: serde_json::Map<String, serde_json::Value>,
+}
+
+///
+/// convert a json file of `local` to a dataset. returns the number of the records.
+pub async fn convert_json(source: &str, target: &str) -> Result<u64, String> {
+    let source = String::from(source);
+    let mut local = tokio::task::spawn_blocking(move || {
+        let reader = match std::fs::File::open(source) {
+            Ok(result) => std::io::BufReader::new(result),
+            Err(result) => {
+                return Err(error_message!("failed to read!\ndetails : {:?}", result));
+            }
+        };
+        match serde_json::from_reader::<_, LocalDataset>(reader) {
+            Ok(result) => Ok(result),
+            Err(result) => Err(error_message!("failed to read!\ndetails : {:?}", result)),
+        }
+    })
+    .await
+    .expect("failed to run blocking task!")?;
+
+    let record_type = record::record_type(&local.collection)?;
+    let field = record_type.time_field;
+    let mut records = match local.fields.remove(record_type.name) {
+        Some(serde_json::Value::Array(content)) => content,
+        _ => {
+            return Err(error_message!(
+                "no {} in the file!\ncollection : {}",
+                record_type.name,
+                local.collection
+            ));
+        }
+    };
+    records.sort_by(|a, b| {
+        let time = |e: &serde_json::Value| e.get(field).and_then(|e| e.as_f64());
+        time(a)
+            .partial_cmp(&time(b))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    // the json files carry no version and are upgraded when they are read.
+    let header = DatasetHeader {
+        downloaded_at: local.downloaded_at,
+        schema_version: 0,
+        ..DatasetHeader::new(&local.collection, local.start, local.end)
+    };
+    let mut writer = DatasetWriter::create(target, &header).await?;
+    for record in &records {
+        writer.push(record).await?;
+    }
+    writer.finish().await
+}
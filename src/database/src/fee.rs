@@ -0,0 +1,128 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use super::*;
+
+///
+/// how an order was filled, deciding its fee rate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Liquidity {
+    ///
+    /// the order rested in the book and was taken by an execution
+    Maker,
+    ///
+    /// the order crossed the book when it arrived
+    Taker,
+}
+
+///
+/// fees as fractions of the traded value in jpy. a negative rate is a rebate.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeeRate {
+    pub maker: f64,
+    pub taker: f64,
+}
+
+impl FeeRate {
+    pub fn new(maker: f64, taker: f64) -> Self {
+        Self { maker, taker }
+    }
+
+    pub fn of(&self, liquidity: Liquidity) -> f64 {
+        match liquidity {
+            Liquidity::Maker => self.maker,
+            Liquidity::Taker => self.taker,
+        }
+    }
+}
+
+///
+/// the rates applied once the traded volume in jpy reached `volume`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FeeTier {
+    pub volume: f64,
+    #[serde(flatten)]
+    pub rate: FeeRate,
+}
+
+///
+/// the rates of a product. the base rates apply below the first tier.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeeTable {
+    #[serde(flatten)]
+    pub base: FeeRate,
+    #[serde(default)]
+    pub tiers: Vec<FeeTier>,
+}
+
+impl FeeTable {
+    pub fn flat(maker: f64, taker: f64) -> Self {
+        Self {
+            base: FeeRate::new(maker, taker),
+            tiers: Vec::new(),
+        }
+    }
+
+    pub fn with_tier(mut self, volume: f64, maker: f64, taker: f64) -> Self {
+        self.tiers.push(FeeTier {
+            volume,
+            rate: FeeRate::new(maker, taker),
+        });
+        self
+    }
+
+    ///
+    /// the rates of the highest tier reached by `volume`.
+    pub fn rate(&self, volume: f64) -> FeeRate {
+        self.tiers
+            .iter()
+            .filter(|e| e.volume <= volume)
+            .max_by(|a, b| {
+                a.volume
+                    .partial_cmp(&b.volume)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .map(|e| e.rate)
+            .unwrap_or(self.base)
+    }
+
+    ///
+    /// the fee in jpy of trading `value` jpy after `volume` jpy were traded.
+    pub fn fee(&self, liquidity: Liquidity, volume: f64, value: f64) -> f64 {
+        value * self.rate(volume).of(liquidity)
+    }
+}
+
+///
+/// the fee table of an exchange and the tables of the products which differ from it,
+/// keyed by the product of a symbol such as `btc_jpy`. the default schedule is free.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeeSchedule {
+    #[serde(flatten)]
+    pub default: FeeTable,
+    #[serde(default)]
+    pub products: HashMap<String, FeeTable>,
+}
+
+impl FeeSchedule {
+    pub fn new(default: FeeTable) -> Self {
+        Self {
+            default,
+            products: HashMap::new(),
+        }
+    }
+
+    pub fn with_product(mut self, product: &str, table: FeeTable) -> Self {
+        self.products.insert(String::from(product), table);
+        self
+    }
+
+    pub fn table(&self, product: &str) -> &FeeTable {
+        self.products.get(product).unwrap_or(&self.default)
+    }
+
+    pub fn fee(&self, product: &str, liquidity: Liquidity, volume: f64, value: f64) -> f64 {
+        self.table(product).fee(liquidity, volume, value)
+    }
+}
@@ -8,15 +8,88 @@ use std::result::Result;
 
 use common::*;
 
+pub mod analytics;
+pub mod backtest;
+pub mod candles;
+pub mod catalog;
+pub mod columnar;
 mod constants;
 pub mod data;
+pub mod dataset;
+pub mod fee;
 pub mod list;
 pub mod local;
+pub mod margin;
+pub mod migrations;
+pub mod order_book;
+pub mod query;
+pub mod record;
+pub mod retention;
+pub mod schema;
 pub mod simulation;
+pub mod sqlite;
+pub mod store;
 pub mod stream;
+pub mod sweep;
 #[cfg(test)]
 mod test;
 
+pub use analytics::{Analytics, AnalyticsConfig};
+pub use backtest::{Backtester, MarketEvent, Strategy};
+pub use catalog::CatalogEntry;
+pub use dataset::{DatasetHeader, DatasetReader, DatasetWriter};
+pub use fee::{FeeSchedule, FeeTable, Liquidity};
+pub use margin::{FundingCurrency, MarginAccount, MarginConfig};
+pub use migrations::{MigrationReport, MigrationStatus};
+pub use order_book::{Book, BookRecorder, BookSide, OrderBookDiff};
+pub use query::Query;
+pub use record::{Record, RecordType};
+pub use retention::RetentionReport;
+pub use schema::SchemaReport;
+pub use store::{open_store, open_store_for, ResumeToken, Store, Tailed, Timeline};
+pub use sweep::{SearchSpace, SweepConfig};
+
+fn record_insert(
+    collection_name: &str,
+    documents: usize,
+    started_at: std::time::Instant,
+    succeeded: bool,
+) {
+    let labels = [("collection", collection_name)];
+    metrics::histogram_observe(
+        metrics::DATABASE_INSERT_DURATION_SECONDS,
+        &labels,
+        started_at.elapsed().as_secs_f64(),
+    );
+    if succeeded {
+        metrics::counter_increment(
+            metrics::DATABASE_INSERTED_DOCUMENTS_TOTAL,
+            &labels,
+            documents as f64,
+        );
+    } else {
+        metrics::counter_increment(metrics::DATABASE_INSERT_FAILURES_TOTAL, &labels, 1.0);
+    }
+}
+
+///
+/// a batch was inserted except for `duplicates` documents whose key already existed.
+fn record_upsert(
+    collection_name: &str,
+    documents: usize,
+    duplicates: usize,
+    started_at: std::time::Instant,
+) {
+    record_insert(collection_name, documents, started_at, true);
+    if duplicates > 0 {
+        metrics::counter_increment(
+            metrics::DATABASE_DUPLICATE_DOCUMENTS_TOTAL,
+            &[("collection", collection_name)],
+            duplicates as f64,
+        );
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Database {
     _client: Client,
@@ -43,6 +116,18 @@ impl Database {
         })
     }
 
+    pub async fn ping(&self) -> Result<(), String> {
+        match self._database.run_command(doc! {"ping": 1}, None).await {
+            Ok(_) => Ok(()),
+            Err(result) => Err(error_message!("failed to ping!\ndetails : {:?}", result)),
+        }
+    }
+
+    #[tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(collection = collection_name, documents = data.len())
+    )]
     pub async fn create<'a, D: Serialize + std::fmt::Debug + Sync>(
         &self,
         collection_name: &str,
@@ -64,7 +149,10 @@ impl Database {
             }
         }
 
-        match collection.insert_many(bson_doc, option).await {
+        let started_at = std::time::Instant::now();
+        let inserted = collection.insert_many(bson_doc, option).await;
+        record_insert(collection_name, data.len(), started_at, inserted.is_ok());
+        match inserted {
             Ok(result) => Ok(result),
             Err(result) => Err(error_message!(
                 "failed to insert posts!\ndetails : {:?}",
@@ -73,6 +161,23 @@ impl Database {
         }
     }
 
+    ///
+    /// insert the records into the collection of their type, the first one unless it is given.
+    pub async fn create_records<T: Record + std::fmt::Debug + Sync>(
+        &self,
+        collection: Option<&str>,
+        records: &[T],
+        option: Option<options::InsertManyOptions>,
+    ) -> Result<results::InsertManyResult, String> {
+        self.create(record::collection_of::<T>(collection)?, records, option)
+            .await
+    }
+
+    #[tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(collection = collection_name, documents = data.len())
+    )]
     pub async fn create_by_documents(
         &self,
         collection_name: &str,
@@ -80,7 +185,10 @@ impl Database {
         option: Option<options::InsertManyOptions>,
     ) -> Result<results::InsertManyResult, String> {
         let collection = self._database.collection::<Document>(collection_name);
-        match collection.insert_many(data, option).await {
+        let started_at = std::time::Instant::now();
+        let inserted = collection.insert_many(data, option).await;
+        record_insert(collection_name, data.len(), started_at, inserted.is_ok());
+        match inserted {
             Ok(result) => Ok(result),
             Err(result) => Err(error_message!(
                 "failed to insert posts!\ndetails : {:?}",
@@ -264,80 +372,3 @@ pub fn generate_to_order_book(doc: bson::Document) -> Result<data::OrderBook, St
         )),
     }
 }
-
-pub fn create_duration_query<Z: TimeZone>(
-    collection: &str,
-    start_time: &DateTime<Z>,
-    end_time: &DateTime<Z>,
-) -> Result<Document, String> {
-    match collection {
-        common_constants::DATABASE_COLLECTION_TICKER
-        | common_constants::DATABASE_COLLECTION_EXECUTIONS => Ok(doc! {
-            "timestamp": {
-             "$gte": start_time.timestamp_nanos() as f64 / 1_000_000_000.0,
-             "$lte": end_time.timestamp_nanos() as f64 / 1_000_000_000.0,
-            }
-        }),
-        common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY
-        | common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL => Ok(doc! {
-            "received_at": {
-             "$gte": start_time.timestamp_nanos() as f64 / 1_000_000_000.0,
-             "$lte": end_time.timestamp_nanos() as f64 / 1_000_000_000.0,
-            }
-        }),
-        _ => Err(error_message!("invalid collection!\n{}", collection)),
-    }
-}
-
-pub fn create_find_option_timeline(
-    collection: &str,
-    order: i64,
-    limit: Option<i64>,
-) -> Result<FindOptions, String> {
-    Ok(FindOptions::builder()
-        .sort(match collection {
-            common_constants::DATABASE_COLLECTION_TICKER
-            | common_constants::DATABASE_COLLECTION_EXECUTIONS => {
-                doc! { "timestamp": order }
-            }
-            common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY
-            | common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL => {
-                doc! { "received_at": order }
-            }
-            _ => {
-                return Err(error_message!("invalid collection!"));
-            }
-        })
-        .limit(limit)
-        .build())
-}
-
-pub fn create_aggregate_timeline_pipeline(
-    collection: &str,
-    order: i64,
-    num: i64,
-) -> Result<Vec<Document>, String> {
-    let mut pipeline = match collection {
-        common_constants::DATABASE_COLLECTION_TICKER
-        | common_constants::DATABASE_COLLECTION_EXECUTIONS => {
-            vec![doc! {
-                "$sort": {
-                    "timestamp": order
-                }
-            }]
-        }
-        common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY
-        | common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL => {
-            vec![doc! {
-                "$sort": {
-                    "received_at": order
-                }
-            }]
-        }
-        _ => return Err(error_message!("invalid collection!\n{}", collection)),
-    };
-    pipeline.push(doc! {
-        "$limit": num
-    });
-    Ok(pipeline)
-}
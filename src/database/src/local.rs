@@ -1,104 +1,72 @@
+use super::record::Record;
 use super::*;
 use chrono::DateTime;
+use serde::de::{self, Deserializer};
+use serde::ser::{SerializeStruct, Serializer};
 use serde::{Deserialize, Serialize};
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct LocalTicker {
+///
+/// the records of a collection in a period, kept as a json file.
+/// the records are written under the name of their type, such as `ticker`.
+#[derive(Clone, Debug)]
+pub struct LocalRecords<T: Record> {
     pub collection: String,
     pub downloaded_at: DateTime<Utc>,
     pub start: DateTime<Utc>,
     pub end: DateTime<Utc>,
     pub size: i32,
-    pub ticker: Vec<data::Ticker>,
+    pub records: Vec<T>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct LocalExecutions {
-    pub collection: String,
-    pub downloaded_at: DateTime<Utc>,
-    pub start: DateTime<Utc>,
-    pub end: DateTime<Utc>,
-    pub size: i32,
-    pub executions: Vec<data::Execution>,
-}
+pub type LocalTicker = LocalRecords<data::Ticker>;
+pub type LocalExecutions = LocalRecords<data::Execution>;
+pub type LocalOrderBook = LocalRecords<data::OrderBook>;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct LocalOrderBook {
-    pub collection: String,
-    pub downloaded_at: DateTime<Utc>,
-    pub start: DateTime<Utc>,
-    pub end: DateTime<Utc>,
-    pub size: i32,
-    pub order_book: Vec<data::OrderBook>,
-}
-
-impl LocalTicker {
-    pub fn new() -> Self {
-        Self {
-            collection: String::from("none"),
-            downloaded_at: Utc::now(),
-            start: Utc::now(),
-            end: Utc::now(),
-            size: 0,
-            ticker: Vec::new(),
-        }
-    }
-
-    pub async fn read(path: &str) -> Result<Self, String> {
-        let path_copied = String::from(path);
-        tokio::task::spawn_blocking(move || {
-            let reader = match std::fs::File::open(path_copied) {
-                Ok(result) => result,
-                Err(result) => {
-                    return Err(error_message!("failed to read!\ndetails : {:?}", result));
-                }
-            };
-            let data = match serde_json::from_reader(reader) {
-                Ok(result) => result,
-                Err(result) => {
-                    return Err(error_message!("failed to read!\ndetails : {:?}", result));
-                }
-            };
-            Ok(data)
-        })
-        .await
-        .expect("failed to run blocking task!")
+impl<T: Record> Serialize for LocalRecords<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("LocalRecords", 6)?;
+        state.serialize_field("collection", &self.collection)?;
+        state.serialize_field("downloaded_at", &self.downloaded_at)?;
+        state.serialize_field("start", &self.start)?;
+        state.serialize_field("end", &self.end)?;
+        state.serialize_field("size", &self.size)?;
+        state.serialize_field(T::NAME, &self.records)?;
+        state.end()
     }
+}
 
-    pub async fn write(&self, path: &str) -> Result<(), String> {
-        let path_copied = String::from(path);
-        let task = tokio::spawn(File::create(path_copied));
-        let json = match serde_json::to_string(self) {
-            Ok(result) => result,
-            Err(result) => {
-                return Err(error_message!(
-                    "failed to serialize!\ndetails : {:?}",
-                    result
-                ))
-            }
-        };
-        let mut file = match task.await.expect("failed to excute a task!") {
-            Ok(result) => result,
-            Err(result) => {
-                return Err(error_message!("failed to create!\ndetails : {:?}", result));
-            }
-        };
-        if let Err(result) = file.write_all(json.as_bytes()).await {
-            return Err(error_message!("failed to write!\ndetails : {:?}", result));
-        }
-        Ok(())
-    }
+#[derive(Deserialize)]
+struct LocalFile {
+    collection: String,
+    downloaded_at: DateTime<Utc>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    size: i32,
+    #[serde(flatten)]
+    records: serde_json::Map<String, serde_json::Value>,
 }
 
-impl Default for local::LocalTicker {
-    fn default() -> Self {
-        Self::new()
+impl<'de, T: Record> Deserialize<'de> for LocalRecords<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut file = LocalFile::deserialize(deserializer)?;
+        let records = match file.records.remove(T::NAME) {
+            Some(content) => serde_json::from_value(content).map_err(de::Error::custom)?,
+            None => return Err(de::Error::missing_field(T::NAME)),
+        };
+        Ok(Self {
+            collection: file.collection,
+            downloaded_at: file.downloaded_at,
+            start: file.start,
+            end: file.end,
+            size: file.size,
+            records,
+        })
     }
 }
 
-impl LocalExecutions {
+impl<T: Record> LocalRecords<T> {
     pub fn new() -> Self {
         Self {
             collection: String::from("none"),
@@ -106,75 +74,32 @@ impl LocalExecutions {
             start: Utc::now(),
             end: Utc::now(),
             size: 0,
-            executions: Vec::new(),
-        }
-    }
-
-    pub async fn read(path: &str) -> Result<Self, String> {
-        let path_copied = String::from(path);
-        tokio::task::spawn_blocking(move || {
-            let reader = match std::fs::File::open(path_copied) {
-                Ok(result) => result,
-                Err(result) => {
-                    return Err(error_message!("failed to read!\ndetails : {:?}", result));
-                }
-            };
-            let data = match serde_json::from_reader(reader) {
-                Ok(result) => result,
-                Err(result) => {
-                    return Err(error_message!("failed to read!\ndetails : {:?}", result));
-                }
-            };
-            Ok(data)
-        })
-        .await
-        .expect("failed to run blocking task!")
-    }
-
-    pub async fn write(&self, path: &str) -> Result<(), String> {
-        let path_copied = String::from(path);
-        let task = tokio::spawn(File::create(path_copied));
-        let json = match serde_json::to_string(self) {
-            Ok(result) => result,
-            Err(result) => {
-                return Err(error_message!(
-                    "failed to serialize!\ndetails : {:?}",
-                    result
-                ))
-            }
-        };
-        let mut file = match task.await.expect("failed to excute a task!") {
-            Ok(result) => result,
-            Err(result) => {
-                return Err(error_message!("failed to create!\ndetails : {:?}", result));
-            }
-        };
-        if let Err(result) = file.write_all(json.as_bytes()).await {
-            return Err(error_message!("failed to write!\ndetails : {:?}", result));
+            records: Vec::new(),
         }
-        Ok(())
     }
-}
-
-impl Default for local::LocalExecutions {
-    fn default() -> Self {
-        Self::new()
-    }
-}
 
-impl LocalOrderBook {
-    pub fn new() -> Self {
-        Self {
-            collection: String::from("none"),
+    ///
+    /// the records of `collection` from `start` to `end`, downloaded now.
+    pub fn from_records(
+        collection: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        records: Vec<T>,
+    ) -> Result<Self, String> {
+        Ok(Self {
+            collection: String::from(record::collection_of::<T>(Some(collection))?),
             downloaded_at: Utc::now(),
-            start: Utc::now(),
-            end: Utc::now(),
-            size: 0,
-            order_book: Vec::new(),
-        }
+            start,
+            end,
+            size: records.len() as i32,
+            records,
+        })
     }
 
-    pub async fn read(path: &str) -> Result<Self, String> {
+    pub async fn read(path: &str) -> Result<Self, String>
+    where
+        T: Send + 'static,
+    {
         let path_copied = String::from(path);
         tokio::task::spawn_blocking(move || {
             let reader = match std::fs::File::open(path_copied) {
@@ -216,11 +141,15 @@ impl LocalOrderBook {
         if let Err(result) = file.write_all(json.as_bytes()).await {
             return Err(error_message!("failed to write!\ndetails : {:?}", result));
         }
+        // tokio writes in the background until the file is flushed.
+        if let Err(result) = file.flush().await {
+            return Err(error_message!("failed to write!\ndetails : {:?}", result));
+        }
         Ok(())
     }
 }
 
-impl Default for local::LocalOrderBook {
+impl<T: Record> Default for LocalRecords<T> {
     fn default() -> Self {
         Self::new()
     }
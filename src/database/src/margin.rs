@@ -0,0 +1,207 @@
+use serde::Deserialize;
+
+use super::*;
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+///
+/// the balance the profits, the fees, the interest and the funding of a leveraged account are settled in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FundingCurrency {
+    Jpy,
+    Btc,
+}
+
+impl FundingCurrency {
+    pub fn generate_from_string(currency: &str) -> Result<Self, String> {
+        match currency {
+            "jpy" | "JPY" => Ok(FundingCurrency::Jpy),
+            "btc" | "BTC" => Ok(FundingCurrency::Btc),
+            _ => Err(error_message!("unknown currency!\ndetails : {}", currency)),
+        }
+    }
+}
+
+///
+/// the terms of a leveraged account. the margins are fractions of the notional value of the position.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MarginConfig {
+    pub leverage_level: u32,
+    pub funding_currency: FundingCurrency,
+    ///
+    /// locked to open a position, `1 / leverage_level` by default
+    pub initial_margin: f64,
+    ///
+    /// the position is liquidated when the equity falls below it, half of the initial margin by default
+    pub maintenance_margin: f64,
+    ///
+    /// charged on the notional value per day, for longs and shorts
+    pub interest_rate: f64,
+    ///
+    /// paid by longs to shorts on the notional value at each funding, the other way when negative
+    pub funding_rate: f64,
+    ///
+    /// seconds between the fundings, counted from the unix epoch
+    pub funding_interval: f64,
+}
+
+impl MarginConfig {
+    pub fn new(leverage_level: u32, funding_currency: FundingCurrency) -> Result<Self, String> {
+        if leverage_level == 0 {
+            return Err(error_message!("invalid leverage level! {}", leverage_level));
+        }
+        let initial_margin = 1.0 / leverage_level as f64;
+        Ok(Self {
+            leverage_level,
+            funding_currency,
+            initial_margin,
+            maintenance_margin: initial_margin / 2.0,
+            interest_rate: 0.0,
+            funding_rate: 0.0,
+            funding_interval: 8.0 * 60.0 * 60.0,
+        })
+    }
+
+    pub fn with_margins(mut self, initial_margin: f64, maintenance_margin: f64) -> Self {
+        self.initial_margin = initial_margin;
+        self.maintenance_margin = maintenance_margin;
+        self
+    }
+
+    pub fn with_interest_rate(mut self, interest_rate: f64) -> Self {
+        self.interest_rate = interest_rate;
+        self
+    }
+
+    pub fn with_funding(mut self, funding_rate: f64, funding_interval: f64) -> Self {
+        self.funding_rate = funding_rate;
+        self.funding_interval = funding_interval;
+        self
+    }
+}
+
+///
+/// the net position of a leveraged account and what it paid so far, in jpy.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MarginAccount {
+    pub config: MarginConfig,
+    ///
+    /// positive for a long, negative for a short
+    pub quantity: f64,
+    ///
+    /// the average price the position was opened at
+    pub open_price: f64,
+    pub realized_pnl: f64,
+    pub interest: f64,
+    ///
+    /// negative when the position received more funding than it paid
+    pub funding: f64,
+    ///
+    /// when the position was liquidated
+    pub liquidations: Vec<f64>,
+    last_accrual: Option<f64>,
+}
+
+impl MarginAccount {
+    pub fn new(config: MarginConfig) -> Self {
+        Self {
+            config,
+            quantity: 0.0,
+            open_price: 0.0,
+            realized_pnl: 0.0,
+            interest: 0.0,
+            funding: 0.0,
+            liquidations: Vec::new(),
+            last_accrual: None,
+        }
+    }
+
+    pub fn notional(&self, price: f64) -> f64 {
+        self.quantity.abs() * price
+    }
+
+    pub fn unrealized_pnl(&self, price: f64) -> f64 {
+        self.quantity * (price - self.open_price)
+    }
+
+    pub fn initial_requirement(&self, price: f64) -> f64 {
+        self.notional(price) * self.config.initial_margin
+    }
+
+    pub fn maintenance_requirement(&self, price: f64) -> f64 {
+        self.notional(price) * self.config.maintenance_margin
+    }
+
+    ///
+    /// add `quantity`, negative for a sell, at `price` and return the realized profit.
+    /// the part which reduces the position is closed at the average price.
+    pub(crate) fn trade(&mut self, quantity: f64, price: f64) -> f64 {
+        if self.quantity == 0.0 || self.quantity.signum() == quantity.signum() {
+            let total = self.quantity + quantity;
+            self.open_price = (self.open_price * self.quantity + price * quantity) / total;
+            self.quantity = total;
+            return 0.0;
+        }
+        let closed = quantity.abs().min(self.quantity.abs());
+        let realized = closed * (price - self.open_price) * self.quantity.signum();
+        self.realized_pnl += realized;
+        self.quantity += quantity;
+        if self.quantity.abs() < 1e-12 {
+            self.quantity = 0.0;
+            self.open_price = 0.0;
+        } else if self.quantity.signum() == quantity.signum() {
+            // the position was turned over, the rest is opened at the price.
+            self.open_price = price;
+        }
+        realized
+    }
+
+    ///
+    /// the interest and the funding from the last accrual until `timestamp`, in jpy.
+    /// a `timestamp` before the last accrual accrues nothing and does not move it back.
+    pub(crate) fn accrue(&mut self, timestamp: f64, price: f64) -> f64 {
+        let last = match self.last_accrual {
+            Some(content) if content >= timestamp => return 0.0,
+            Some(content) => content,
+            None => {
+                self.last_accrual = Some(timestamp);
+                return 0.0;
+            }
+        };
+        self.last_accrual = Some(timestamp);
+        let notional = self.notional(price);
+        let interest = notional * self.config.interest_rate * (timestamp - last) / SECONDS_PER_DAY;
+        let fundings = if self.config.funding_interval > 0.0 {
+            ((timestamp / self.config.funding_interval).floor()
+                - (last / self.config.funding_interval).floor())
+            .max(0.0)
+        } else {
+            0.0
+        };
+        let funding = fundings * self.quantity * price * self.config.funding_rate;
+        self.interest += interest;
+        self.funding += funding;
+        interest + funding
+    }
+
+    ///
+    /// the price at which the equity of `jpy` and `btc` and the position meets the maintenance margin.
+    pub fn liquidation_price(&self, jpy: f64, btc: f64) -> Option<f64> {
+        if self.quantity == 0.0 {
+            return None;
+        }
+        // jpy + btc * p + q * (p - o) = |q| * p * m
+        let denominator =
+            btc + self.quantity - self.quantity.abs() * self.config.maintenance_margin;
+        if denominator == 0.0 {
+            return None;
+        }
+        let price = (self.quantity * self.open_price - jpy) / denominator;
+        if price > 0.0 {
+            Some(price)
+        } else {
+            None
+        }
+    }
+}
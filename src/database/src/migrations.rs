@@ -0,0 +1,200 @@
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use super::schema::COLLECTIONS;
+use super::store::Store;
+use super::*;
+
+///
+/// the version of the shape a record was written in. records without it are version 0.
+pub const SCHEMA_VERSION_FIELD: &str = "schema_version";
+
+///
+/// a change of the shape of records from `version` to `version + 1`.
+pub struct Migration {
+    pub collections: &'static [&'static str],
+    pub version: u32,
+    pub description: &'static str,
+    pub upgrade: fn(&mut Map<String, Value>) -> Result<(), String>,
+}
+
+const RAW_COLLECTIONS: [&str; 4] = [
+    common_constants::DATABASE_COLLECTION_TICKER,
+    common_constants::DATABASE_COLLECTION_EXECUTIONS,
+    common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY,
+    common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL,
+];
+
+///
+/// every migration ordered by the version.
+pub const MIGRATIONS: [Migration; 3] = [
+    Migration {
+        collections: &RAW_COLLECTIONS,
+        version: 0,
+        description: "drop the _id written as a hex string by the old serializers.",
+        upgrade: |record| {
+            if matches!(record.get("_id"), Some(Value::String(_))) {
+                record.remove("_id");
+            }
+            Ok(())
+        },
+    },
+    Migration {
+        collections: &[common_constants::DATABASE_COLLECTION_EXECUTIONS],
+        version: 0,
+        description: "add the exchange id as null and store created_at as an integer.",
+        upgrade: |record| {
+            record.entry("id").or_insert(Value::Null);
+            match record.get("created_at") {
+                Some(Value::Number(created_at)) if !created_at.is_i64() => {
+                    let created_at = created_at.as_f64().unwrap_or_default().trunc() as i64;
+                    record.insert(String::from("created_at"), Value::from(created_at));
+                }
+                Some(Value::Number(_)) => {}
+                _ => {
+                    return Err(error_message!("created_at is not a number!"));
+                }
+            }
+            Ok(())
+        },
+    },
+    Migration {
+        collections: &[common_constants::DATABASE_COLLECTION_EXECUTIONS],
+        version: 1,
+        description:
+            "widen created_at to i64, recovering the epoch seconds wrapped into a negative i32.",
+        upgrade: |record| {
+            match record.get("created_at").and_then(|e| e.as_i64()) {
+                Some(created_at) if created_at < 0 => {
+                    let created_at = created_at + (1_i64 << 32);
+                    record.insert(String::from("created_at"), Value::from(created_at));
+                }
+                Some(_) => {}
+                None => {
+                    return Err(error_message!("created_at is not an integer!"));
+                }
+            }
+            Ok(())
+        },
+    },
+];
+
+///
+/// the version records of `collection` are written in, declared by its record type.
+pub fn current_version(collection: &str) -> u32 {
+    match record::record_type(collection) {
+        Ok(result) => result.schema_version,
+        Err(_) => 1,
+    }
+}
+
+fn version_of(record: &Map<String, Value>) -> u32 {
+    record
+        .get(SCHEMA_VERSION_FIELD)
+        .and_then(|e| e.as_u64())
+        .unwrap_or_default() as u32
+}
+
+///
+/// upgrade a record to the current version. returns whether it was changed.
+/// a record of a newer version is left as it is.
+pub fn upgrade(collection: &str, record: &mut Value) -> Result<bool, String> {
+    let record = match record.as_object_mut() {
+        Some(content) => content,
+        None => return Err(error_message!("the record is not an object!")),
+    };
+    let version = version_of(record);
+    let current = current_version(collection);
+    if version >= current {
+        return Ok(false);
+    }
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|e| e.collections.contains(&collection) && e.version >= version)
+    {
+        if let Err(result) = (migration.upgrade)(record) {
+            return Err(error_message!(
+                "failed to upgrade from version {}!\ndetails : {}",
+                migration.version,
+                result
+            ));
+        }
+    }
+    record.insert(String::from(SCHEMA_VERSION_FIELD), Value::from(current));
+    Ok(true)
+}
+
+///
+/// the record with the current version to be stored.
+pub fn stamped(collection: &str, record: &Value) -> Value {
+    let mut record = record.clone();
+    if let Some(content) = record.as_object_mut() {
+        content
+            .entry(SCHEMA_VERSION_FIELD)
+            .or_insert_with(|| Value::from(current_version(collection)));
+    }
+    record
+}
+
+///
+/// the record read from a backend, upgraded and without the version.
+/// a record which cannot be upgraded is returned as it was stored.
+pub fn on_read(collection: &str, mut record: Value) -> Value {
+    let _ = upgrade(collection, &mut record);
+    if let Some(content) = record.as_object_mut() {
+        content.remove(SCHEMA_VERSION_FIELD);
+    }
+    record
+}
+
+///
+/// the number of the records of each stored version in a collection.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MigrationStatus {
+    pub collection: String,
+    pub current_version: u32,
+    ///
+    /// (version, records) ordered by the version
+    pub versions: Vec<(u32, u64)>,
+}
+
+impl MigrationStatus {
+    pub fn outdated(&self) -> u64 {
+        self.versions
+            .iter()
+            .filter(|(version, _)| *version < self.current_version)
+            .map(|(_, count)| count)
+            .sum()
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MigrationReport {
+    pub collection: String,
+    ///
+    /// records upgraded, or which would be upgraded with a dry run
+    pub migrated: u64,
+    ///
+    /// records which could not be upgraded and were left as they are
+    pub failed: u64,
+}
+
+pub async fn status(database: &dyn Store) -> Result<Vec<MigrationStatus>, String> {
+    let mut statuses = Vec::with_capacity(COLLECTIONS.len());
+    for collection in COLLECTIONS {
+        statuses.push(MigrationStatus {
+            collection: String::from(collection),
+            current_version: current_version(collection),
+            versions: database.versions(collection).await?,
+        });
+    }
+    Ok(statuses)
+}
+
+pub async fn up(database: &dyn Store, dry_run: bool) -> Result<Vec<MigrationReport>, String> {
+    let mut reports = Vec::with_capacity(COLLECTIONS.len());
+    for collection in COLLECTIONS {
+        reports.push(database.migrate(collection, dry_run).await?);
+    }
+    Ok(reports)
+}
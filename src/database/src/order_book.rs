@@ -0,0 +1,467 @@
+use futures::stream::{BoxStream, StreamExt};
+use liquid::data::Order;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use super::data::OrderBook;
+use super::store::{from_value, Store, Timeline};
+use super::*;
+
+///
+/// number of diffs inserted at once by `convert`.
+const CONVERT_CHUNK: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BookSide {
+    Buy,
+    Sell,
+}
+
+impl BookSide {
+    pub const ALL: [BookSide; 2] = [BookSide::Buy, BookSide::Sell];
+
+    ///
+    /// `buy` or `sell`, as the taker side of the executions.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BookSide::Buy => "buy",
+            BookSide::Sell => "sell",
+        }
+    }
+
+    pub fn opposite(&self) -> BookSide {
+        match self {
+            BookSide::Buy => BookSide::Sell,
+            BookSide::Sell => BookSide::Buy,
+        }
+    }
+
+    pub fn snapshots(&self) -> &'static str {
+        match self {
+            BookSide::Buy => common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY,
+            BookSide::Sell => common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL,
+        }
+    }
+
+    pub fn diffs(&self) -> &'static str {
+        match self {
+            BookSide::Buy => common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY_DIFFS,
+            BookSide::Sell => common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL_DIFFS,
+        }
+    }
+
+    ///
+    /// the levels ordered from the best price.
+    fn ladder(&self, levels: BTreeMap<i32, f64>) -> Vec<Order> {
+        let orders = levels
+            .into_iter()
+            .map(|(price, amount)| Order { price, amount });
+        match self {
+            BookSide::Buy => orders.rev().collect(),
+            BookSide::Sell => orders.collect(),
+        }
+    }
+}
+
+///
+/// the levels of a side changed by an update.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OrderBookDiff {
+    pub received_at: f64,
+    ///
+    /// the new amount of each changed price. an amount of 0 removes the level.
+    pub changes: Vec<Order>,
+    ///
+    /// `changes` is the whole ladder, for updates which cannot be rebuilt level by level
+    /// such as a ladder out of order.
+    #[serde(default)]
+    pub replace: bool,
+}
+
+impl Generater for OrderBookDiff {
+    type Output = Self;
+    fn generate_from_document(doc: bson::Document) -> Result<Self, String> {
+        match bson::from_document(doc) as bson::de::Result<OrderBookDiff> {
+            Ok(result) => Ok(result),
+            Err(result) => Err(error_message!(
+                "failed to deserialize!\ndetails : {:?}",
+                result
+            )),
+        }
+    }
+}
+
+///
+/// the ladder after a diff.
+pub fn apply(side: BookSide, ladder: &[Order], diff: &OrderBookDiff) -> Vec<Order> {
+    if diff.replace {
+        return diff.changes.clone();
+    }
+    let mut levels = ladder
+        .iter()
+        .map(|e| (e.price, e.amount))
+        .collect::<BTreeMap<i32, f64>>();
+    for change in &diff.changes {
+        if change.amount == 0.0 {
+            levels.remove(&change.price);
+        } else {
+            levels.insert(change.price, change.amount);
+        }
+    }
+    side.ladder(levels)
+}
+
+///
+/// the diff from `previous` to `current`. applying it to `previous` gives exactly `current`.
+pub fn diff(side: BookSide, previous: &[Order], current: &OrderBook) -> OrderBookDiff {
+    let before = previous
+        .iter()
+        .map(|e| (e.price, e.amount))
+        .collect::<BTreeMap<i32, f64>>();
+    let after = current
+        .orders
+        .iter()
+        .map(|e| (e.price, e.amount))
+        .collect::<BTreeMap<i32, f64>>();
+    let mut changes = current
+        .orders
+        .iter()
+        .filter(|e| before.get(&e.price) != Some(&e.amount))
+        .cloned()
+        .collect::<Vec<Order>>();
+    changes.extend(
+        previous
+            .iter()
+            .filter(|e| !after.contains_key(&e.price))
+            .map(|e| Order {
+                price: e.price,
+                amount: 0.0,
+            }),
+    );
+    let diff = OrderBookDiff {
+        received_at: current.received_at,
+        changes,
+        replace: false,
+    };
+    if apply(side, previous, &diff) == current.orders {
+        diff
+    } else {
+        OrderBookDiff {
+            received_at: current.received_at,
+            changes: current.orders.clone(),
+            replace: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BookRecord {
+    Snapshot(OrderBook),
+    Diff(OrderBookDiff),
+}
+
+impl BookRecord {
+    pub fn received_at(&self) -> f64 {
+        match self {
+            BookRecord::Snapshot(content) => content.received_at,
+            BookRecord::Diff(content) => content.received_at,
+        }
+    }
+}
+
+///
+/// decides how each update of a side is stored.
+#[derive(Clone, Debug)]
+pub struct BookRecorder {
+    _side: BookSide,
+    _settings: common::OrderBookStorage,
+    _previous: Option<OrderBook>,
+}
+
+impl BookRecorder {
+    pub fn new(side: BookSide, settings: &common::OrderBookStorage) -> Self {
+        Self {
+            _side: side,
+            _settings: settings.clone(),
+            _previous: None,
+        }
+    }
+
+    pub fn side(&self) -> BookSide {
+        self._side
+    }
+
+    fn period(&self, time: f64) -> f64 {
+        (time / self._settings.snapshot_interval).floor()
+    }
+
+    ///
+    /// the first update and the first of each snapshot interval are snapshots, the rest are diffs.
+    pub fn record(&mut self, book: OrderBook) -> BookRecord {
+        let record = match &self._previous {
+            Some(previous)
+                if self._settings.diffs
+                    && self._settings.snapshot_interval > 0.0
+                    && self.period(previous.received_at) == self.period(book.received_at) =>
+            {
+                BookRecord::Diff(diff(self._side, &previous.orders, &book))
+            }
+            _ => BookRecord::Snapshot(book.clone()),
+        };
+        self._previous = Some(book);
+        record
+    }
+}
+
+///
+/// both sides of the order book at an instant.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Book {
+    ///
+    /// the time of the latest update of either side
+    pub received_at: f64,
+    pub buy: Vec<Order>,
+    pub sell: Vec<Order>,
+}
+
+impl Book {
+    fn side_mut(&mut self, side: BookSide) -> &mut Vec<Order> {
+        match side {
+            BookSide::Buy => &mut self.buy,
+            BookSide::Sell => &mut self.sell,
+        }
+    }
+
+    fn update(&mut self, side: BookSide, record: &BookRecord) {
+        let ladder = self.side_mut(side);
+        *ladder = match record {
+            BookRecord::Snapshot(content) => content.orders.clone(),
+            BookRecord::Diff(content) => apply(side, ladder, content),
+        };
+        self.received_at = self.received_at.max(record.received_at());
+    }
+}
+
+///
+/// a side rebuilt from the latest snapshot at or before `time` and the diffs after it.
+pub async fn side_at(
+    database: &dyn Store,
+    side: BookSide,
+    time: f64,
+) -> Result<Option<OrderBook>, String> {
+    let snapshot = database
+        .query_records::<OrderBook>(
+            side.snapshots(),
+            &Timeline {
+                start: None,
+                end: Some(time),
+                ..Timeline::all()
+            }
+            .order(-1)
+            .limit(Some(1)),
+        )
+        .await?
+        .pop();
+    let mut book = match snapshot {
+        Some(content) => content,
+        None => return Ok(None),
+    };
+    let diffs = database
+        .query_records::<OrderBookDiff>(
+            side.diffs(),
+            &Timeline {
+                start: Some(book.received_at),
+                end: Some(time),
+                ..Timeline::all()
+            },
+        )
+        .await?;
+    let since = book.received_at;
+    for diff in diffs.iter().filter(|e| e.received_at > since) {
+        book.orders = apply(side, &book.orders, diff);
+        book.received_at = diff.received_at;
+    }
+    Ok(Some(book))
+}
+
+///
+/// both sides at `time`. a side without a snapshot before it is empty.
+pub async fn book_at(database: &dyn Store, time: f64) -> Result<Book, String> {
+    let mut book = Book::default();
+    for side in BookSide::ALL {
+        if let Some(content) = side_at(database, side, time).await? {
+            book.update(side, &BookRecord::Snapshot(content));
+        }
+    }
+    Ok(book)
+}
+
+type Updates = BoxStream<'static, Result<(BookSide, BookRecord), String>>;
+
+async fn updates(
+    database: &dyn Store,
+    side: BookSide,
+    timeline: &Timeline,
+) -> Result<Updates, String> {
+    let snapshots = database
+        .stream(side.snapshots(), timeline)
+        .await?
+        .map(move |e| {
+            e.and_then(from_value)
+                .map(|e| (side, BookRecord::Snapshot(e)))
+        });
+    let diffs = database
+        .stream(side.diffs(), timeline)
+        .await?
+        .map(move |e| e.and_then(from_value).map(|e| (side, BookRecord::Diff(e))));
+    Ok(merge(vec![snapshots.boxed(), diffs.boxed()]))
+}
+
+///
+/// merge streams ordered by the time into one.
+fn merge(streams: Vec<Updates>) -> Updates {
+    let heads = streams
+        .into_iter()
+        .map(|e| (e.fuse(), None))
+        .collect::<Vec<_>>();
+    futures::stream::unfold(heads, |mut heads| async move {
+        for (stream, head) in heads.iter_mut() {
+            if head.is_none() {
+                *head = stream.next().await;
+            }
+        }
+        // errors first, then the earliest update.
+        let index = heads
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (_, head))| match head {
+                Some(Ok((_, record))) => Some((i, record.received_at())),
+                Some(Err(_)) => Some((i, f64::MIN)),
+                None => None,
+            })
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|e| e.0)?;
+        let update = heads[index].1.take()?;
+        Some((update, heads))
+    })
+    .boxed()
+}
+
+///
+/// both sides after each update in the timeline, starting from the book at its start.
+/// the updates are always in ascending order.
+pub async fn book_stream(
+    database: &dyn Store,
+    timeline: &Timeline,
+) -> Result<BoxStream<'static, Result<Book, String>>, String> {
+    let start = timeline.start;
+    let book = match start {
+        Some(content) => book_at(database, content).await?,
+        None => Book::default(),
+    };
+    let range = Timeline {
+        order: 1,
+        limit: None,
+        ..timeline.clone()
+    };
+    let mut streams = Vec::with_capacity(BookSide::ALL.len());
+    for side in BookSide::ALL {
+        streams.push(updates(database, side, &range).await?);
+    }
+    let books = merge(streams)
+        // the updates at the start are in the first book already.
+        .filter(move |e| {
+            let skipped = matches!((e, start), (Ok((_, record)), Some(start)) if record.received_at() <= start);
+            futures::future::ready(!skipped)
+        })
+        .scan(book, |book, update| {
+            let next = update.map(|(side, record)| {
+                book.update(side, &record);
+                book.clone()
+            });
+            futures::future::ready(Some(next))
+        });
+    Ok(match timeline.limit {
+        Some(limit) if limit >= 0 => books.take(limit as usize).boxed(),
+        _ => books.boxed(),
+    })
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConversionReport {
+    pub collection: String,
+    ///
+    /// snapshots kept at the start of each interval
+    pub snapshots: u64,
+    ///
+    /// diffs written in place of the other snapshots
+    pub diffs: u64,
+    ///
+    /// snapshots removed after writing the diffs
+    pub removed: u64,
+}
+
+///
+/// replace the snapshots of a side in the timeline by the diffs `BookRecorder` would have written.
+/// nothing is written with `dry_run`.
+pub async fn convert(
+    database: &dyn Store,
+    side: BookSide,
+    timeline: &Timeline,
+    settings: &common::OrderBookStorage,
+    dry_run: bool,
+) -> Result<ConversionReport, String> {
+    if settings.snapshot_interval <= 0.0 {
+        return Err(error_message!(
+            "invalid snapshot interval! {}",
+            settings.snapshot_interval
+        ));
+    }
+    let settings = common::OrderBookStorage {
+        diffs: true,
+        ..settings.clone()
+    };
+    let mut recorder = BookRecorder::new(side, &settings);
+    let mut report = ConversionReport {
+        collection: String::from(side.snapshots()),
+        ..Default::default()
+    };
+    let range = Timeline {
+        order: 1,
+        limit: None,
+        ..timeline.clone()
+    };
+    let mut snapshots = database
+        .stream_records::<OrderBook>(side.snapshots(), &range)
+        .await?;
+    let mut diffs = Vec::with_capacity(CONVERT_CHUNK);
+    while let Some(snapshot) = snapshots.next().await {
+        match recorder.record(snapshot?) {
+            BookRecord::Snapshot(_) => report.snapshots += 1,
+            BookRecord::Diff(content) => {
+                report.diffs += 1;
+                diffs.push(content);
+            }
+        }
+        if diffs.len() >= CONVERT_CHUNK {
+            if !dry_run {
+                database.insert_records(side.diffs(), &diffs).await?;
+            }
+            diffs.clear();
+        }
+    }
+    if !dry_run && !diffs.is_empty() {
+        database.insert_records(side.diffs(), &diffs).await?;
+    }
+    // the snapshots kept by the recorder are the first of each interval.
+    report.removed = database
+        .downsample(
+            side.snapshots(),
+            &range,
+            settings.snapshot_interval,
+            dry_run,
+        )
+        .await?;
+    Ok(report)
+}
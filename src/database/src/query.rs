@@ -0,0 +1,225 @@
+use futures::stream::{BoxStream, StreamExt};
+use std::marker::PhantomData;
+
+use super::record::{self, Record};
+use super::store::{from_value, Store, Timeline};
+use super::stream::DatabaseStream;
+use super::*;
+
+///
+/// the records of a type in a period, compiled to the backend.
+/// the collection and the time field come from the type, such as
+/// `Query::<Ticker>::between(&start, &end).latest(10)`.
+#[derive(Clone, Debug)]
+pub struct Query<T: Record> {
+    collection: &'static str,
+    timeline: Timeline,
+    fields: Option<Vec<String>>,
+    _type: PhantomData<T>,
+}
+
+impl<T: Record> Query<T> {
+    pub fn all() -> Self {
+        Self {
+            collection: T::COLLECTIONS[0],
+            timeline: Timeline::all(),
+            fields: None,
+            _type: PhantomData,
+        }
+    }
+
+    ///
+    /// the records from `start_time` to `end_time`, both inclusive.
+    pub fn between<Z: TimeZone>(start_time: &DateTime<Z>, end_time: &DateTime<Z>) -> Self {
+        Self {
+            timeline: Timeline::new(start_time, end_time),
+            ..Self::all()
+        }
+    }
+
+    pub fn since<Z: TimeZone>(start_time: &DateTime<Z>) -> Self {
+        let mut query = Self::all();
+        query.timeline.start = Some(seconds(start_time));
+        query
+    }
+
+    pub fn until<Z: TimeZone>(end_time: &DateTime<Z>) -> Self {
+        let mut query = Self::all();
+        query.timeline.end = Some(seconds(end_time));
+        query
+    }
+
+    ///
+    /// read another collection of the type, such as the sell side of the order books.
+    pub fn collection(mut self, collection: &str) -> Result<Self, String> {
+        self.collection = record::collection_of::<T>(Some(collection))?;
+        Ok(self)
+    }
+
+    ///
+    /// the newest `num` records, the newest first.
+    pub fn latest(mut self, num: i64) -> Self {
+        self.timeline = self.timeline.order(-1).limit(Some(num));
+        self
+    }
+
+    pub fn limit(mut self, num: i64) -> Self {
+        self.timeline = self.timeline.limit(Some(num));
+        self
+    }
+
+    ///
+    /// 1 for ascending, -1 for descending
+    pub fn order(mut self, order: i64) -> Self {
+        self.timeline = self.timeline.order(order);
+        self
+    }
+
+    ///
+    /// keep only these top level fields of `values` and of the compiled mongodb queries.
+    pub fn fields(mut self, fields: &[&str]) -> Self {
+        self.fields = Some(fields.iter().map(|e| String::from(*e)).collect());
+        self
+    }
+
+    pub fn collection_name(&self) -> &'static str {
+        self.collection
+    }
+
+    pub fn timeline(&self) -> &Timeline {
+        &self.timeline
+    }
+
+    ///
+    /// the mongodb filter selecting the period.
+    pub fn filter(&self) -> Document {
+        time_filter(T::TIME_FIELD, &self.timeline)
+    }
+
+    pub fn find_options(&self) -> FindOptions {
+        find_options(T::TIME_FIELD, &self.timeline, self.projection())
+    }
+
+    ///
+    /// the mongodb aggregation stages selecting and ordering the records,
+    /// to which the stages of a summary are appended.
+    pub fn pipeline(&self) -> Vec<Document> {
+        let mut pipeline = vec![
+            doc! { "$match": self.filter() },
+            doc! { "$sort": { T::TIME_FIELD: self.timeline.order } },
+        ];
+        if let Some(limit) = self.timeline.limit {
+            pipeline.push(doc! { "$limit": limit });
+        }
+        if let Some(projection) = self.projection() {
+            pipeline.push(doc! { "$project": projection });
+        }
+        pipeline
+    }
+
+    fn projection(&self) -> Option<Document> {
+        self.fields.as_ref().map(|fields| {
+            let mut projection = doc! { "_id": 0 };
+            for field in fields {
+                projection.insert(field.as_str(), 1);
+            }
+            projection
+        })
+    }
+
+    pub async fn fetch(&self, database: &dyn Store) -> Result<Vec<T>, String> {
+        database
+            .query(self.collection, &self.timeline)
+            .await?
+            .into_iter()
+            .map(from_value)
+            .collect()
+    }
+
+    ///
+    /// the records as json objects, keeping only the chosen fields.
+    pub async fn values(&self, database: &dyn Store) -> Result<Vec<serde_json::Value>, String> {
+        let values = database.query(self.collection, &self.timeline).await?;
+        Ok(match &self.fields {
+            Some(fields) => values
+                .into_iter()
+                .map(|e| match e {
+                    serde_json::Value::Object(mut object) => serde_json::Value::Object(
+                        fields
+                            .iter()
+                            .filter_map(|field| object.remove_entry(field))
+                            .collect(),
+                    ),
+                    other => other,
+                })
+                .collect(),
+            None => values,
+        })
+    }
+
+    pub async fn stream(
+        &self,
+        database: &dyn Store,
+    ) -> Result<BoxStream<'static, Result<T, String>>, String>
+    where
+        T: 'static,
+    {
+        Ok(database
+            .stream(self.collection, &self.timeline)
+            .await?
+            .map(|e| e.and_then(from_value))
+            .boxed())
+    }
+
+    pub async fn count(&self, database: &dyn Store) -> Result<u64, String> {
+        database.count(self.collection, &self.timeline).await
+    }
+
+    ///
+    /// the records read from mongodb with the compiled filter and options.
+    pub async fn request<'a>(
+        &self,
+        database: &'a Database,
+    ) -> Result<DatabaseStream<'a, T>, String> {
+        DatabaseStream::request(
+            database,
+            Some(self.collection),
+            self.filter(),
+            self.find_options(),
+        )
+        .await
+    }
+}
+
+fn seconds<Z: TimeZone>(time: &DateTime<Z>) -> f64 {
+    time.timestamp_nanos_opt().unwrap_or_default() as f64 / 1_000_000_000.0
+}
+
+///
+/// the records whose `field` is in the timeline, in seconds.
+pub(crate) fn time_filter(field: &str, timeline: &Timeline) -> Document {
+    let mut condition = Document::new();
+    if let Some(start) = timeline.start {
+        condition.insert("$gte", start);
+    }
+    if let Some(end) = timeline.end {
+        condition.insert("$lte", end);
+    }
+    if condition.is_empty() {
+        doc! {}
+    } else {
+        doc! { field: condition }
+    }
+}
+
+pub(crate) fn find_options(
+    field: &str,
+    timeline: &Timeline,
+    projection: Option<Document>,
+) -> FindOptions {
+    FindOptions::builder()
+        .sort(doc! { field: timeline.order })
+        .limit(timeline.limit)
+        .projection(projection)
+        .build()
+}
@@ -0,0 +1,168 @@
+use serde::de::DeserializeOwned;
+
+use super::data::{Candle, Execution, OrderBook, Ticker};
+use super::order_book::OrderBookDiff;
+use super::*;
+
+///
+/// a type stored in the market collections, ordered by a time field in seconds.
+/// the collections are described by the types, so a new kind of record is one impl
+/// and an entry of `record_types`.
+pub trait Record: Generater<Output = Self> + Serialize + DeserializeOwned {
+    ///
+    /// the name of the records in the json files of `local`.
+    const NAME: &'static str;
+    ///
+    /// the collections storing the type. the first one is read unless another is chosen.
+    const COLLECTIONS: &'static [&'static str];
+    const TIME_FIELD: &'static str;
+    ///
+    /// the fields identifying a record in a collection.
+    const KEY: &'static [&'static str];
+    ///
+    /// the version of the shape the records are written in, reached by `migrations::MIGRATIONS`.
+    const SCHEMA_VERSION: u32;
+
+    ///
+    /// the value of the time field.
+    fn time(&self) -> f64;
+}
+
+impl Record for Ticker {
+    const NAME: &'static str = "ticker";
+    const COLLECTIONS: &'static [&'static str] = &[common_constants::DATABASE_COLLECTION_TICKER];
+    const TIME_FIELD: &'static str = "timestamp";
+    ///
+    /// the timestamp of the exchange repeats over updates of different contents.
+    const KEY: &'static [&'static str] = &["received_at"];
+    const SCHEMA_VERSION: u32 = 1;
+
+    fn time(&self) -> f64 {
+        self.timestamp
+    }
+}
+
+impl Record for Execution {
+    const NAME: &'static str = "executions";
+    const COLLECTIONS: &'static [&'static str] =
+        &[common_constants::DATABASE_COLLECTION_EXECUTIONS];
+    const TIME_FIELD: &'static str = "timestamp";
+    const KEY: &'static [&'static str] = &["id"];
+    const SCHEMA_VERSION: u32 = 2;
+
+    fn time(&self) -> f64 {
+        self.timestamp
+    }
+}
+
+impl Record for OrderBook {
+    const NAME: &'static str = "order_book";
+    const COLLECTIONS: &'static [&'static str] = &[
+        common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY,
+        common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL,
+    ];
+    const TIME_FIELD: &'static str = "received_at";
+    const KEY: &'static [&'static str] = &["received_at"];
+    const SCHEMA_VERSION: u32 = 1;
+
+    fn time(&self) -> f64 {
+        self.received_at
+    }
+}
+
+impl Record for OrderBookDiff {
+    const NAME: &'static str = "order_book_diffs";
+    const COLLECTIONS: &'static [&'static str] = &[
+        common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY_DIFFS,
+        common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL_DIFFS,
+    ];
+    const TIME_FIELD: &'static str = "received_at";
+    const KEY: &'static [&'static str] = &["received_at"];
+    const SCHEMA_VERSION: u32 = 1;
+
+    fn time(&self) -> f64 {
+        self.received_at
+    }
+}
+
+impl Record for Candle {
+    const NAME: &'static str = "candles";
+    const COLLECTIONS: &'static [&'static str] = &[
+        common_constants::DATABASE_COLLECTION_CANDLES_1S,
+        common_constants::DATABASE_COLLECTION_CANDLES_1M,
+        common_constants::DATABASE_COLLECTION_CANDLES_5M,
+        common_constants::DATABASE_COLLECTION_CANDLES_1H,
+        common_constants::DATABASE_COLLECTION_CANDLES_1D,
+    ];
+    const TIME_FIELD: &'static str = "timestamp";
+    const KEY: &'static [&'static str] = &["timestamp"];
+    const SCHEMA_VERSION: u32 = 1;
+
+    fn time(&self) -> f64 {
+        self.timestamp
+    }
+}
+
+///
+/// the description of a record type, for the code handling collections by their names.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecordType {
+    pub name: &'static str,
+    pub collections: &'static [&'static str],
+    pub time_field: &'static str,
+    pub key: &'static [&'static str],
+    pub schema_version: u32,
+}
+
+impl RecordType {
+    pub fn of<T: Record>() -> Self {
+        Self {
+            name: T::NAME,
+            collections: T::COLLECTIONS,
+            time_field: T::TIME_FIELD,
+            key: T::KEY,
+            schema_version: T::SCHEMA_VERSION,
+        }
+    }
+}
+
+///
+/// every record type stored in the market collections.
+pub fn record_types() -> [RecordType; 5] {
+    [
+        RecordType::of::<Ticker>(),
+        RecordType::of::<Execution>(),
+        RecordType::of::<OrderBook>(),
+        RecordType::of::<OrderBookDiff>(),
+        RecordType::of::<Candle>(),
+    ]
+}
+
+///
+/// the record type stored in `collection`.
+pub fn record_type(collection: &str) -> Result<RecordType, String> {
+    match record_types()
+        .iter()
+        .copied()
+        .find(|e| e.collections.contains(&collection))
+    {
+        Some(content) => Ok(content),
+        None => Err(error_message!("invalid collection!\n{}", collection)),
+    }
+}
+
+///
+/// the collection of `T` to read or write, the first one when it is not chosen.
+pub fn collection_of<T: Record>(collection: Option<&str>) -> Result<&'static str, String> {
+    match collection {
+        Some(collection) => match T::COLLECTIONS.iter().find(|e| **e == collection) {
+            Some(content) => Ok(content),
+            None => Err(error_message!(
+                "invalid collection for the record!\ncollection : {}\nrecord : {}",
+                collection,
+                T::NAME
+            )),
+        },
+        None => Ok(T::COLLECTIONS[0]),
+    }
+}
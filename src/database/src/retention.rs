@@ -0,0 +1,131 @@
+use serde::Deserialize;
+
+use super::store::{Store, Timeline};
+use super::*;
+
+const DAY_SECONDS: f64 = 86_400.0;
+
+///
+/// the records removed, or found with a dry run, from a collection.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RetentionReport {
+    pub collection: String,
+    ///
+    /// records removed from the part older than `full_days`
+    pub downsampled: u64,
+    ///
+    /// records older than `delete_days`
+    pub deleted: u64,
+}
+
+pub fn validate(
+    collection: &str,
+    policy: &common::RetentionPolicy,
+    order_book: &common::OrderBookStorage,
+) -> Result<(), String> {
+    store::time_field(collection)?;
+    if policy.downsample < 0.0 {
+        return Err(error_message!(
+            "downsample must not be negative!\ncollection : {}",
+            collection
+        ));
+    }
+    // a diff depends on every diff before it, so dropping some of them corrupts the books.
+    if policy.full_days.is_some()
+        && (collection == common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY_DIFFS
+            || collection == common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL_DIFFS)
+    {
+        return Err(error_message!(
+            "order book diffs cannot be downsampled!\ncollection : {}",
+            collection
+        ));
+    }
+    // the diffs are applied to the latest snapshot before them, so the snapshots between them have to be kept.
+    if policy.full_days.is_some()
+        && order_book.diffs
+        && (collection == common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY
+            || collection == common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL)
+    {
+        return Err(error_message!(
+            "order book snapshots cannot be downsampled while the diffs are stored!\ncollection : {}",
+            collection
+        ));
+    }
+    if let (Some(full_days), Some(delete_days)) = (policy.full_days, policy.delete_days) {
+        if delete_days < full_days {
+            return Err(error_message!(
+                "delete_days must not be less than full_days!\ncollection : {}",
+                collection
+            ));
+        }
+    }
+    Ok(())
+}
+
+///
+/// apply the policies to the records older than their periods counted back from `now`.
+/// records older than `delete_days` are deleted first, then the rest older than `full_days` are downsampled.
+/// with `since`, the `now` of the previous enforcement, only the records which became older than `full_days`
+/// after it are downsampled, from the start of the interval they fall in.
+pub async fn enforce(
+    database: &dyn Store,
+    settings: &common::Retention,
+    order_book: &common::OrderBookStorage,
+    now: f64,
+    since: Option<f64>,
+    dry_run: bool,
+) -> Result<Vec<RetentionReport>, String> {
+    let mut collections = settings.collections.iter().collect::<Vec<_>>();
+    collections.sort_by(|a, b| a.0.cmp(b.0));
+    for (collection, policy) in collections.iter() {
+        validate(collection, policy, order_book)?;
+    }
+
+    let mut reports = Vec::with_capacity(collections.len());
+    for (collection, policy) in collections {
+        let mut report = RetentionReport {
+            collection: collection.clone(),
+            ..Default::default()
+        };
+        let delete_before = policy.delete_days.map(|e| now - e * DAY_SECONDS);
+        if let Some(end) = delete_before {
+            let timeline = Timeline {
+                end: Some(end),
+                ..Timeline::all()
+            };
+            report.deleted = database.remove(collection, &timeline, dry_run).await?;
+        }
+        if let Some(full_days) = policy.full_days {
+            let aged_out = since.map(|e| e - full_days * DAY_SECONDS);
+            let mut start = match (delete_before, aged_out) {
+                (Some(a), Some(b)) => Some(a.max(b)),
+                (a, b) => a.or(b),
+            };
+            if policy.downsample > 0.0 {
+                // the record kept for the interval which was downsampled before is kept again.
+                start = start.map(|e| (e / policy.downsample).floor() * policy.downsample);
+            }
+            let timeline = Timeline {
+                start,
+                end: Some(now - full_days * DAY_SECONDS),
+                ..Timeline::all()
+            };
+            report.downsampled = if policy.downsample > 0.0 {
+                database
+                    .downsample(collection, &timeline, policy.downsample, dry_run)
+                    .await?
+            } else {
+                database.remove(collection, &timeline, dry_run).await?
+            };
+        }
+        if !dry_run {
+            metrics::counter_increment(
+                metrics::RETENTION_REMOVED_RECORDS_TOTAL,
+                &[("collection", collection)],
+                (report.downsampled + report.deleted) as f64,
+            );
+        }
+        reports.push(report);
+    }
+    Ok(reports)
+}
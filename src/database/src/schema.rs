@@ -0,0 +1,275 @@
+use futures::stream::TryStreamExt;
+use mongodb::options::IndexOptions;
+use mongodb::IndexModel;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use super::store::{time_field, Timeline};
+use super::*;
+
+pub const COLLECTIONS: [&str; 11] = [
+    common_constants::DATABASE_COLLECTION_TICKER,
+    common_constants::DATABASE_COLLECTION_EXECUTIONS,
+    common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY,
+    common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL,
+    common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY_DIFFS,
+    common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL_DIFFS,
+    common_constants::DATABASE_COLLECTION_CANDLES_1S,
+    common_constants::DATABASE_COLLECTION_CANDLES_1M,
+    common_constants::DATABASE_COLLECTION_CANDLES_5M,
+    common_constants::DATABASE_COLLECTION_CANDLES_1H,
+    common_constants::DATABASE_COLLECTION_CANDLES_1D,
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexSpec {
+    pub name: &'static str,
+    pub field: &'static str,
+    pub unique: bool,
+    ///
+    /// only the documents having a number in the field are indexed.
+    pub partial: bool,
+}
+
+///
+/// the fields identifying a record. a record whose key already exists is not inserted again.
+/// the ticker and the order books are keyed by when they were received, the candles by their period.
+pub fn key_fields(collection: &str) -> Result<&'static [&'static str], String> {
+    Ok(record::record_type(collection)?.key)
+}
+
+///
+/// executions stored before the exchange id was kept are identified by all of their fields.
+pub const LEGACY_EXECUTION_KEY: [&str; 5] =
+    ["timestamp", "created_at", "price", "quantity", "taker_side"];
+
+///
+/// the indexes a collection has to have.
+pub fn indexes(collection: &str) -> Result<Vec<IndexSpec>, String> {
+    let field = time_field(collection)?;
+    let keys = key_fields(collection)?;
+    let mut specs = vec![IndexSpec {
+        name: if field == "timestamp" {
+            "timestamp_1"
+        } else {
+            "received_at_1"
+        },
+        field,
+        unique: keys == [field],
+        partial: false,
+    }];
+    if collection == common_constants::DATABASE_COLLECTION_EXECUTIONS {
+        // executions stored before the id was kept do not have it.
+        specs.push(IndexSpec {
+            name: "id_1",
+            field: "id",
+            unique: true,
+            partial: true,
+        });
+    } else if collection == common_constants::DATABASE_COLLECTION_TICKER {
+        specs.push(IndexSpec {
+            name: "received_at_1",
+            field: "received_at",
+            unique: true,
+            partial: false,
+        });
+    }
+    Ok(specs)
+}
+
+///
+/// the result of checking a collection.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SchemaReport {
+    pub collection: String,
+    ///
+    /// a time-series collection made by hand
+    pub time_series: bool,
+    ///
+    /// collections and indexes created by this check
+    pub created: Vec<String>,
+    ///
+    /// what is still missing or wrong
+    pub problems: Vec<String>,
+}
+
+impl SchemaReport {
+    pub fn new(collection: &str) -> Self {
+        Self {
+            collection: String::from(collection),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl Database {
+    ///
+    /// names of the existing collections and whether each is a time-series collection.
+    async fn collection_types(&self) -> Result<HashMap<String, bool>, String> {
+        let result = match self
+            ._database
+            .run_command(doc! { "listCollections": 1 }, None)
+            .await
+        {
+            Ok(result) => result,
+            Err(result) => {
+                return Err(error_message!(
+                    "failed to list collections!\ndetails : {:?}",
+                    result
+                ));
+            }
+        };
+        let batch = match result
+            .get_document("cursor")
+            .and_then(|e| e.get_array("firstBatch"))
+        {
+            Ok(result) => result,
+            Err(result) => {
+                return Err(error_message!(
+                    "unexpected response!\ndetails : {:?}",
+                    result
+                ));
+            }
+        };
+        Ok(batch
+            .iter()
+            .filter_map(|e| e.as_document())
+            .filter_map(|e| {
+                e.get_str("name").ok().map(|name| {
+                    (
+                        String::from(name),
+                        e.get_str("type").ok() == Some("timeseries"),
+                    )
+                })
+            })
+            .collect())
+    }
+
+    ///
+    /// check the collections and their indexes, creating what is missing if `repair` is set.
+    /// a time-series collection made by hand is reported, as it cannot have the unique indexes.
+    pub async fn check_schema(&self, repair: bool) -> Result<Vec<SchemaReport>, String> {
+        let types = self.collection_types().await?;
+        let mut reports = Vec::with_capacity(COLLECTIONS.len());
+        for collection in COLLECTIONS {
+            let mut report = SchemaReport::new(collection);
+            match types.get(collection) {
+                Some(time_series) => {
+                    report.time_series = *time_series;
+                    if *time_series {
+                        report.problems.push(String::from(
+                            "a time-series collection cannot be deduplicated, upserted, expired or migrated. the data has to be moved into a collection by hand.",
+                        ));
+                    }
+                }
+                None if repair => {
+                    if let Err(result) = self
+                        ._database
+                        .run_command(doc! { "create": collection }, None)
+                        .await
+                    {
+                        return Err(error_message!(
+                            "failed to create a collection!\ndetails : {:?}\ncollection : {}",
+                            result,
+                            collection
+                        ));
+                    }
+                    report.created.push(String::from(collection));
+                }
+                None => {
+                    report.problems.push(String::from("missing collection."));
+                    reports.push(report);
+                    continue;
+                }
+            }
+
+            let handle = self._database.collection::<Document>(collection);
+            let mut existing = HashMap::new();
+            let listed = match handle.list_indexes(None).await {
+                Ok(result) => result.try_collect::<Vec<IndexModel>>().await,
+                Err(result) => Err(result),
+            };
+            match listed {
+                Ok(result) => {
+                    for model in result {
+                        if let Some(options) = model.options {
+                            if let Some(name) = options.name {
+                                existing.insert(name, options.unique.unwrap_or_default());
+                            }
+                        }
+                    }
+                }
+                Err(result) => {
+                    return Err(error_message!(
+                        "failed to list indexes!\ndetails : {:?}\ncollection : {}",
+                        result,
+                        collection
+                    ));
+                }
+            }
+            for spec in indexes(collection)? {
+                // time-series collections cannot have unique indexes.
+                let unique = spec.unique && !report.time_series;
+                let problem = match existing.get(spec.name) {
+                    Some(found) if *found == unique => continue,
+                    Some(_) => format!("index {} has to be unique : {}.", spec.name, unique),
+                    None => format!("missing index {}.", spec.name),
+                };
+                if !repair {
+                    report.problems.push(problem);
+                    continue;
+                }
+                if unique {
+                    // the documents sharing a key are reported instead of failing the index.
+                    let duplicates = self
+                        .duplicate_ids(collection, &Timeline::all(), &[spec.field], true)
+                        .await?;
+                    if !duplicates.is_empty() {
+                        report.problems.push(format!(
+                            "{} documents share a key of index {}. remove them with dedupe first.",
+                            duplicates.len(),
+                            spec.name
+                        ));
+                        continue;
+                    }
+                }
+                if existing.contains_key(spec.name) {
+                    if let Err(result) = handle.drop_index(spec.name, None).await {
+                        report
+                            .problems
+                            .push(format!("failed to drop index {}. {:?}", spec.name, result));
+                        continue;
+                    }
+                }
+                let options = IndexOptions::builder()
+                    .name(String::from(spec.name))
+                    .unique(if unique { Some(true) } else { None })
+                    .partial_filter_expression(if spec.partial {
+                        Some(doc! { spec.field: { "$type": "number" } })
+                    } else {
+                        None
+                    })
+                    .build();
+                let model = IndexModel::builder()
+                    .keys(doc! { spec.field: 1 })
+                    .options(options)
+                    .build();
+                if let Err(result) = handle.create_index(model, None).await {
+                    // such as when duplicates have to be removed before a unique index.
+                    report.problems.push(format!(
+                        "failed to create index {}. {:?}",
+                        spec.name, result
+                    ));
+                    continue;
+                }
+                report.created.push(String::from(spec.name));
+            }
+            reports.push(report);
+        }
+        Ok(reports)
+    }
+}
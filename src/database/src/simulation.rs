@@ -1,3 +1,5 @@
+use super::fee::{FeeSchedule, Liquidity};
+use super::margin::{FundingCurrency, MarginAccount, MarginConfig};
 use super::*;
 
 /// For simulation
@@ -5,6 +7,10 @@ pub struct Simulation {
     history: Vec<data::SimulationExecution>,
     jpy: f64,
     btc: f64,
+    fees: FeeSchedule,
+    product: String,
+    volume: f64,
+    margin: Option<MarginAccount>,
 }
 
 impl Simulation {
@@ -13,13 +19,116 @@ impl Simulation {
             history: Vec::new(),
             jpy,
             btc,
+            fees: FeeSchedule::default(),
+            product: Symbol::default().product,
+            volume: 0.0,
+            margin: None,
         }
     }
 
+    ///
+    /// trade a leveraged position instead of the balances, which keep the margin.
+    /// sells open shorts, and the position is liquidated by `accrue`.
+    pub fn with_margin(mut self, config: MarginConfig) -> Self {
+        self.margin = Some(MarginAccount::new(config));
+        self
+    }
+
+    pub fn margin(&self) -> Option<&MarginAccount> {
+        self.margin.as_ref()
+    }
+
+    ///
+    /// charge the fees of `product` in jpy. `buy_at` and `sell_at` are charged as takers.
+    pub fn with_fees(mut self, fees: FeeSchedule, product: &str) -> Self {
+        self.fees = fees;
+        self.product = String::from(product);
+        self
+    }
+
+    fn fee(&self, liquidity: Liquidity, price: i32, quantity: f64) -> f64 {
+        self.fees.fee(
+            &self.product,
+            liquidity,
+            self.volume,
+            price as f64 * quantity,
+        )
+    }
+
     pub fn assets(&self) -> (f64, f64) {
         (self.jpy, self.btc)
     }
 
+    ///
+    /// the btc held and the leveraged position, which follow the price.
+    pub fn inventory(&self) -> f64 {
+        self.btc + self.margin.as_ref().map_or(0.0, |e| e.quantity)
+    }
+
+    ///
+    /// the value of the balances and the position at `price`.
+    pub fn equity(&self, price: f64) -> f64 {
+        self.jpy
+            + self.btc * price
+            + self
+                .margin
+                .as_ref()
+                .map_or(0.0, |e| e.unrealized_pnl(price))
+    }
+
+    pub fn liquidation_price(&self) -> Option<f64> {
+        self.margin
+            .as_ref()
+            .and_then(|e| e.liquidation_price(self.jpy, self.btc))
+    }
+
+    ///
+    /// charge the interest and the funding of the position until `timestamp`, then liquidate it at `price`
+    /// when the equity is below the maintenance margin. returns whether it was liquidated.
+    pub fn accrue(&mut self, timestamp: f64, price: f64) -> bool {
+        let mut account = match self.margin.take() {
+            Some(content) => content,
+            None => return false,
+        };
+        let paid = account.accrue(timestamp, price);
+        self.settle(account.config.funding_currency, -paid, price);
+        let liquidated = account.quantity != 0.0
+            && self.jpy + self.btc * price + account.unrealized_pnl(price)
+                < account.maintenance_requirement(price);
+        if liquidated {
+            let order = if account.quantity > 0.0 {
+                "sell"
+            } else {
+                "buy"
+            };
+            let quantity = account.quantity.abs();
+            let fee = self.fee(Liquidity::Taker, price.round() as i32, quantity);
+            let realized = account.trade(-account.quantity, price);
+            self.settle(account.config.funding_currency, realized - fee, price);
+            self.volume += price * quantity;
+            account.liquidations.push(timestamp);
+            self.history.push(data::SimulationExecution {
+                timestamp,
+                price: price.round() as i32,
+                quantity,
+                order: String::from(order),
+                result: Ok(()),
+                fee,
+            });
+        }
+        self.margin = Some(account);
+        liquidated
+    }
+
+    ///
+    /// add `amount` jpy to the funding currency, converted at `price`.
+    fn settle(&mut self, currency: FundingCurrency, amount: f64, price: f64) {
+        match currency {
+            FundingCurrency::Jpy => self.jpy += amount,
+            FundingCurrency::Btc => self.btc += amount / price,
+        }
+    }
+
     pub fn deposit_jpy(&mut self, quantity: f64) -> Result<(), String> {
         if quantity > 1.0 {
             self.jpy += quantity;
@@ -81,17 +190,25 @@ impl Simulation {
                 price,
                 quantity,
                 order: String::from("buy"),
+                fee: 0.0,
                 result: Err(error_message!("invalid quanity quntity : {}", quantity)),
             });
             return Err(error_message!("invalid quanity quntity : {}", quantity));
         }
+        if self.margin.is_some() {
+            return self
+                .fill_at(timestamp, "buy", price, quantity, Liquidity::Taker)
+                .map(|_| ());
+        }
 
-        if let Err(result) = self.withdraw_jpy(price as f64 * quantity) {
+        let fee = self.fee(Liquidity::Taker, price, quantity);
+        if let Err(result) = self.withdraw_jpy(price as f64 * quantity + fee) {
             self.history.push(data::SimulationExecution {
                 timestamp,
                 price,
                 quantity,
                 order: String::from("buy"),
+                fee: 0.0,
                 result: Err(error_message!("failed to buy!\ndetails : {}", result)),
             });
             return Err(error_message!("failed to buy!\ndetails : {}", result));
@@ -102,16 +219,19 @@ impl Simulation {
                 price,
                 quantity,
                 order: String::from("buy"),
+                fee: 0.0,
                 result: Err(error_message!("failed to buy!\ndetails : {}", result)),
             });
             return Err(error_message!("failed to buy!\ndetails : {}", result));
         }
+        self.volume += price as f64 * quantity;
         self.history.push(data::SimulationExecution {
             timestamp,
             price,
             quantity,
             order: String::from("buy"),
             result: Ok(()),
+            fee,
         });
         Ok(())
     }
@@ -123,16 +243,24 @@ impl Simulation {
                 price,
                 quantity,
                 order: String::from("sell"),
+                fee: 0.0,
                 result: Err(error_message!("invalid quanity quntity : {}", quantity)),
             });
             return Err(error_message!("invalid quanity quntity : {}", quantity));
         }
-        if let Err(result) = self.deposit_jpy(price as f64 * quantity) {
+        if self.margin.is_some() {
+            return self
+                .fill_at(timestamp, "sell", price, quantity, Liquidity::Taker)
+                .map(|_| ());
+        }
+        let fee = self.fee(Liquidity::Taker, price, quantity);
+        if let Err(result) = self.deposit_jpy(price as f64 * quantity - fee) {
             self.history.push(data::SimulationExecution {
                 timestamp,
                 price,
                 quantity,
                 order: String::from("sell"),
+                fee: 0.0,
                 result: Err(error_message!("failed to sell!\ndetails : {}", result)),
             });
             return Err(error_message!("failed to sell!\ndetails : {}", result));
@@ -143,17 +271,116 @@ impl Simulation {
                 price,
                 quantity,
                 order: String::from("sell"),
+                fee: 0.0,
                 result: Err(error_message!("failed to sell!\ndetails : {}", result)),
             });
             return Err(error_message!("failed to sell!\ndetails : {}", result));
         }
+        self.volume += price as f64 * quantity;
         self.history.push(data::SimulationExecution {
             timestamp,
             price,
             quantity,
             order: String::from("sell"),
             result: Ok(()),
+            fee,
+        });
+        Ok(())
+    }
+
+    ///
+    /// settle a fill of an order, returning its fee. unlike `buy_at` and `sell_at`, partial
+    /// fills below the minimum quantity of an order are accepted, but the balances must not go negative.
+    pub fn fill_at(
+        &mut self,
+        timestamp: f64,
+        order: &str,
+        price: i32,
+        quantity: f64,
+        liquidity: Liquidity,
+    ) -> Result<f64, String> {
+        let value = price as f64 * quantity;
+        let fee = self.fee(liquidity, price, quantity);
+        let result = match order {
+            "buy" | "sell" if self.margin.is_some() => {
+                self.trade_on_margin(timestamp, order, price, quantity, fee)
+            }
+            "buy" if value + fee <= self.jpy => {
+                self.jpy -= value + fee;
+                self.btc += quantity;
+                Ok(())
+            }
+            "sell" if quantity <= self.btc && fee <= self.jpy + value => {
+                self.jpy += value - fee;
+                self.btc -= quantity;
+                Ok(())
+            }
+            "buy" | "sell" => Err(error_message!(
+                "insufficient balance! order : {}, price : {}, quantity : {}, fee : {}, jpy : {}, btc : {}",
+                order,
+                price,
+                quantity,
+                fee,
+                self.jpy,
+                self.btc
+            )),
+            _ => Err(error_message!("invalid order! {}", order)),
+        };
+        let fee = match result {
+            Ok(()) => {
+                self.volume += value;
+                fee
+            }
+            Err(_) => 0.0,
+        };
+        self.history.push(data::SimulationExecution {
+            timestamp,
+            price,
+            quantity,
+            order: String::from(order),
+            result: result.clone(),
+            fee,
         });
+        result.map(|_| fee)
+    }
+
+    ///
+    /// a trade which increases the position needs the initial margin after it.
+    fn trade_on_margin(
+        &mut self,
+        timestamp: f64,
+        order: &str,
+        price: i32,
+        quantity: f64,
+        fee: f64,
+    ) -> Result<(), String> {
+        let mut account = self.margin.clone().expect("no margin account!");
+        let price = price as f64;
+        let quantity = if order == "buy" { quantity } else { -quantity };
+        let opening = (account.quantity + quantity).abs() > account.quantity.abs();
+        let (jpy, btc) = (self.jpy, self.btc);
+        // the interest of the position until the trade, which starts it at the first one.
+        let paid = account.accrue(timestamp, price);
+        let realized = account.trade(quantity, price);
+        self.settle(
+            account.config.funding_currency,
+            realized - fee - paid,
+            price,
+        );
+        let equity = self.jpy + self.btc * price + account.unrealized_pnl(price);
+        if opening && equity < account.initial_requirement(price) {
+            self.jpy = jpy;
+            self.btc = btc;
+            return Err(error_message!(
+                "insufficient margin! order : {}, price : {}, quantity : {}, equity : {}, required : {}",
+                order,
+                price,
+                quantity.abs(),
+                equity,
+                account.initial_requirement(price)
+            ));
+        }
+        self.margin = Some(account);
         Ok(())
     }
 
@@ -162,7 +389,7 @@ impl Simulation {
     }
 
     pub fn evaluate_at(&self, price: i32) -> f64 {
-        self.jpy + self.btc * price as f64
+        self.equity(price as f64)
     }
 
     pub fn get_history(&self) -> &Vec<data::SimulationExecution> {
@@ -179,7 +406,29 @@ impl Simulation {
         sum_price_quantity / sum_quantity
     }
 
+    ///
+    /// the traded value in jpy, which decides the fee tier.
+    pub fn volume(&self) -> f64 {
+        self.volume
+    }
+
+    ///
+    /// the fees paid in jpy, less the rebates.
+    pub fn fees(&self) -> f64 {
+        self.history
+            .iter()
+            .filter(|e| e.result.is_ok())
+            .map(|e| e.fee)
+            .sum()
+    }
+
+    ///
+    /// the profit of the sold quantity after the fees.
     pub fn estimate_profit(&self) -> f64 {
+        self.estimate_gross_profit() - self.fees()
+    }
+
+    pub fn estimate_gross_profit(&self) -> f64 {
         let mut sum_quantity = 0.0;
         for e in self.history.iter().filter(|e| e.order == "sell") {
             sum_quantity += e.quantity;
@@ -0,0 +1,1065 @@
+use async_trait::async_trait;
+use futures::stream::StreamExt;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use super::schema::{self, SchemaReport, COLLECTIONS};
+use super::store::{time_field, RecordStream, ResumeToken, Store, TailStream, Tailed, Timeline};
+use super::*;
+
+///
+/// number of records read at once by a stream. the connection is released between the pages
+/// so that other requests, including other streams, go on while a stream is alive.
+pub(crate) const STREAM_PAGE: i64 = 1024;
+
+///
+/// rows read at once by the polling of a tail.
+const TAIL_CHUNK: i64 = 1024;
+
+///
+/// time to wait for new rows when a tail has caught up.
+const TAIL_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(200);
+
+///
+/// number of records upgraded in a transaction by migrate.
+const MIGRATION_CHUNK: i64 = 10_000;
+
+///
+/// embedded backend keeping each collection in a table of (time, json record).
+#[derive(Clone, Debug)]
+pub struct SqliteStore {
+    _connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStore {
+    ///
+    /// open or create the database file. `:memory:` keeps everything in memory.
+    /// the tables and their indexes are created as well.
+    pub async fn open(path: &str) -> Result<Self, String> {
+        let path = String::from(path);
+        let connection = tokio::task::spawn_blocking(move || {
+            let mut connection = match Connection::open(&path) {
+                Ok(result) => result,
+                Err(result) => {
+                    return Err(error_message!(
+                        "failed to open a sqlite database!\ndetails : {:?}\npath : {}",
+                        result,
+                        path
+                    ));
+                }
+            };
+            let mut statements = String::new();
+            for collection in COLLECTIONS {
+                statements += &format!(
+                    "CREATE TABLE IF NOT EXISTS {} (time REAL NOT NULL, record TEXT NOT NULL);",
+                    collection
+                );
+            }
+            if let Err(result) = connection.execute_batch(&statements) {
+                return Err(error_message!(
+                    "failed to create tables!\ndetails : {:?}",
+                    result
+                ));
+            }
+            // the records sharing a key are left to dedupe, and their collection refuses inserts until then.
+            for collection in COLLECTIONS {
+                let report = check_indexes(&mut connection, collection, true)?;
+                if !report.is_ok() {
+                    tracing::warn!(collection = %report.collection, problems = ?report.problems, "the schema has problems!");
+                }
+            }
+            Ok(connection)
+        })
+        .await
+        .expect("failed to run blocking task!")?;
+
+        Ok(Self {
+            _connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    async fn run<F, T>(&self, task: F) -> Result<T, String>
+    where
+        F: FnOnce(&mut Connection) -> Result<T, String> + Send + 'static,
+        T: Send + 'static,
+    {
+        let connection = self._connection.clone();
+        tokio::task::spawn_blocking(move || task(&mut connection.lock().expect("poisoned!")))
+            .await
+            .expect("failed to run blocking task!")
+    }
+
+    ///
+    /// the records from `start` to `end` inserted until `last_rowid`, ordered by time and rowid.
+    /// they are read by pages after the key of the last record of the previous page.
+    fn stream_rows(
+        &self,
+        collection: &str,
+        (start, end, last_rowid): (f64, f64, i64),
+        order: i64,
+        limit: Option<i64>,
+    ) -> RecordStream {
+        let (comparison, direction) = if order < 0 {
+            ("<", "DESC")
+        } else {
+            (">", "ASC")
+        };
+        let cursor = RowCursor {
+            store: self.clone(),
+            collection: String::from(collection),
+            statement: format!(
+                "SELECT time, rowid, record FROM {0} WHERE time >= ?1 AND time <= ?2 AND rowid <= ?3 \
+                 AND (?4 IS NULL OR time {1} ?4 OR (time = ?4 AND rowid {1} ?5)) \
+                 ORDER BY time {2}, rowid {2} LIMIT ?6",
+                collection, comparison, direction
+            ),
+            bounds: (start, end, last_rowid),
+            after: None,
+            remaining: limit.filter(|e| *e >= 0),
+            records: VecDeque::new(),
+            exhausted: false,
+        };
+        futures::stream::unfold(cursor, |mut cursor| async move {
+            loop {
+                if let Some(record) = cursor.records.pop_front() {
+                    return Some((record, cursor));
+                }
+                if cursor.exhausted || cursor.remaining == Some(0) {
+                    return None;
+                }
+                let size = cursor.remaining.map_or(STREAM_PAGE, |e| e.min(STREAM_PAGE));
+                match cursor.next_page(size).await {
+                    Ok(()) => {}
+                    Err(result) => {
+                        cursor.exhausted = true;
+                        cursor.records.push_back(Err(result));
+                    }
+                }
+            }
+        })
+        .boxed()
+    }
+
+    ///
+    /// the rows inserted after `rowid` in the order of insertion, at most TAIL_CHUNK of them.
+    async fn rows_after(&self, collection: &str, rowid: i64) -> Result<Vec<(i64, String)>, String> {
+        let statement = format!(
+            "SELECT rowid, record FROM {} WHERE rowid > ?1 ORDER BY rowid LIMIT ?2",
+            collection
+        );
+        self.run(move |connection| {
+            let mut statement = match connection.prepare(&statement) {
+                Ok(result) => result,
+                Err(result) => {
+                    return Err(error_message!(
+                        "failed to request query!\ndetails : {:?}",
+                        result
+                    ))
+                }
+            };
+            let rows = match statement.query_map(params![rowid, TAIL_CHUNK], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            }) {
+                Ok(result) => result,
+                Err(result) => {
+                    return Err(error_message!(
+                        "failed to request query!\ndetails : {:?}",
+                        result
+                    ))
+                }
+            };
+            match rows.collect::<Result<Vec<_>, _>>() {
+                Ok(result) => Ok(result),
+                Err(result) => Err(error_message!(
+                    "failed to get a row!\ndetails : {:?}",
+                    result
+                )),
+            }
+        })
+        .await
+    }
+}
+
+///
+/// the position of a stream between its pages.
+struct RowCursor {
+    store: SqliteStore,
+    collection: String,
+    statement: String,
+    bounds: (f64, f64, i64),
+    ///
+    /// the time and the rowid of the last record read
+    after: Option<(f64, i64)>,
+    remaining: Option<i64>,
+    records: VecDeque<Result<serde_json::Value, String>>,
+    exhausted: bool,
+}
+
+impl RowCursor {
+    async fn next_page(&mut self, size: i64) -> Result<(), String> {
+        let (statement, collection) = (self.statement.clone(), self.collection.clone());
+        let (start, end, last_rowid) = self.bounds;
+        let (after_time, after_rowid) = match self.after {
+            Some((time, rowid)) => (Some(time), Some(rowid)),
+            None => (None, None),
+        };
+        let page = self
+            .store
+            .run(move |connection| {
+                let mut statement = match connection.prepare(&statement) {
+                    Ok(result) => result,
+                    Err(result) => {
+                        return Err(error_message!(
+                            "failed to request query!\ndetails : {:?}",
+                            result
+                        ))
+                    }
+                };
+                let rows = match statement.query_map(
+                    params![start, end, last_rowid, after_time, after_rowid, size],
+                    |row| {
+                        Ok((
+                            row.get::<_, f64>(0)?,
+                            row.get::<_, i64>(1)?,
+                            row.get::<_, String>(2)?,
+                        ))
+                    },
+                ) {
+                    Ok(result) => result,
+                    Err(result) => {
+                        return Err(error_message!(
+                            "failed to request query!\ndetails : {:?}",
+                            result
+                        ))
+                    }
+                };
+                let mut page = Vec::new();
+                for row in rows {
+                    match row {
+                        Ok((time, rowid, record)) => {
+                            page.push((time, rowid, parse_record(&collection, &record)))
+                        }
+                        Err(result) => {
+                            return Err(error_message!(
+                                "failed to get a row!\ndetails : {:?}",
+                                result
+                            ))
+                        }
+                    }
+                }
+                Ok(page)
+            })
+            .await?;
+        self.exhausted = (page.len() as i64) < size;
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= page.len() as i64;
+        }
+        if let Some((time, rowid, _)) = page.last() {
+            self.after = Some((*time, *rowid));
+        }
+        self.records
+            .extend(page.into_iter().map(|(_, _, record)| record));
+        Ok(())
+    }
+}
+
+fn select_statement(collection: &str, timeline: &Timeline) -> String {
+    format!(
+        "SELECT record FROM {0} WHERE time >= ?1 AND time <= ?2 ORDER BY time {1}, rowid {1} LIMIT ?3",
+        collection,
+        if timeline.order < 0 { "DESC" } else { "ASC" }
+    )
+}
+
+fn bounds(timeline: &Timeline) -> (f64, f64, i64) {
+    (
+        timeline.start.unwrap_or(f64::MIN),
+        timeline.end.unwrap_or(f64::MAX),
+        // a negative limit means no limit in sqlite.
+        timeline.limit.unwrap_or(-1),
+    )
+}
+
+///
+/// the column or the json expression holding a field.
+/// the time field is kept in its own column.
+fn expression_of(collection: &str, field: &str) -> Result<String, String> {
+    if field == time_field(collection)? {
+        Ok(String::from("time"))
+    } else {
+        Ok(format!("json_extract(record, '$.{}')", field))
+    }
+}
+
+///
+/// the name and the indexed expression of an index.
+fn index_of(collection: &str, spec: &schema::IndexSpec) -> Result<(String, String), String> {
+    let name = if spec.field == time_field(collection)? {
+        format!("{}_time", collection)
+    } else {
+        format!("{}_{}", collection, spec.name)
+    };
+    Ok((name, expression_of(collection, spec.field)?))
+}
+
+///
+/// check the indexes of a collection, creating what is missing if `repair` is set.
+fn check_indexes(
+    connection: &mut Connection,
+    collection: &str,
+    repair: bool,
+) -> Result<SchemaReport, String> {
+    let mut report = SchemaReport::new(collection);
+    for spec in schema::indexes(collection)? {
+        let (name, expression) = index_of(collection, &spec)?;
+        let found = match connection
+            .query_row(
+                "SELECT \"unique\" FROM pragma_index_list(?1) WHERE name = ?2",
+                params![collection, name],
+                |row| row.get::<_, bool>(0),
+            )
+            .optional()
+        {
+            Ok(result) => result,
+            Err(result) => {
+                return Err(error_message!(
+                    "failed to list indexes!\ndetails : {:?}",
+                    result
+                ));
+            }
+        };
+        let problem = match found {
+            Some(unique) if unique == spec.unique => continue,
+            Some(_) => {
+                format!("index {} has to be unique : {}.", spec.name, spec.unique)
+            }
+            None => format!("missing index {}.", spec.name),
+        };
+        if !repair {
+            report.problems.push(problem);
+            continue;
+        }
+        if spec.unique {
+            // the records sharing a key are reported instead of failing the index.
+            let condition = format!("{} IS NOT NULL", expression);
+            let duplicates = match connection.query_row(
+                &format!(
+                    "SELECT COUNT(*) {}",
+                    duplicates_of(collection, &[spec.field], &condition)?
+                ),
+                params![f64::MIN, f64::MAX],
+                |row| row.get::<_, i64>(0),
+            ) {
+                Ok(result) => result,
+                Err(result) => {
+                    return Err(error_message!(
+                        "failed to count duplicates!\ndetails : {:?}",
+                        result
+                    ));
+                }
+            };
+            if duplicates > 0 {
+                report.problems.push(format!(
+                    "{} records share a key of index {}. remove them with dedupe first.",
+                    duplicates, spec.name
+                ));
+                continue;
+            }
+        }
+        let mut statement = String::new();
+        if found.is_some() {
+            statement += &format!("DROP INDEX {};", name);
+        }
+        statement += &format!(
+            "CREATE {}INDEX {} ON {} ({})",
+            if spec.unique { "UNIQUE " } else { "" },
+            name,
+            collection,
+            expression
+        );
+        if spec.partial {
+            statement += &format!(" WHERE {} IS NOT NULL", expression);
+        }
+        // the old index is kept if the new one cannot be created, such as with duplicates.
+        if let Err(result) = connection.transaction().and_then(|transaction| {
+            transaction.execute_batch(&statement)?;
+            transaction.commit()
+        }) {
+            report.problems.push(format!(
+                "failed to create index {}. {:?}",
+                spec.name, result
+            ));
+            continue;
+        }
+        report.created.push(String::from(spec.name));
+    }
+    Ok(report)
+}
+
+///
+/// the unique index of a collection which is missing, leaving its inserts without deduplication.
+fn missing_key_index(
+    connection: &Connection,
+    collection: &str,
+) -> Result<Option<&'static str>, String> {
+    for spec in schema::indexes(collection)? {
+        if !spec.unique {
+            continue;
+        }
+        let (name, _) = index_of(collection, &spec)?;
+        match connection
+            .query_row(
+                "SELECT \"unique\" FROM pragma_index_list(?1) WHERE name = ?2",
+                params![collection, name],
+                |row| row.get::<_, bool>(0),
+            )
+            .optional()
+        {
+            Ok(Some(true)) => continue,
+            Ok(_) => return Ok(Some(spec.name)),
+            Err(result) => {
+                return Err(error_message!(
+                    "failed to list indexes!\ndetails : {:?}",
+                    result
+                ));
+            }
+        }
+    }
+    Ok(None)
+}
+
+///
+/// (the time, the record) of each record.
+fn rows_of(collection: &str, records: &[serde_json::Value]) -> Result<Vec<(f64, String)>, String> {
+    let field = time_field(collection)?;
+    let mut rows = Vec::with_capacity(records.len());
+    for record in records {
+        match record.get(field).and_then(|e| e.as_f64()) {
+            Some(time) => rows.push((time, migrations::stamped(collection, record).to_string())),
+            None => {
+                return Err(error_message!(
+                    "the record has no {}!\nreceived data : {}",
+                    field,
+                    record
+                ));
+            }
+        }
+    }
+    Ok(rows)
+}
+
+///
+/// the rows from ?1 to ?2 meeting `condition` whose `fields` are the same as an earlier row's.
+fn duplicates_of(collection: &str, fields: &[&str], condition: &str) -> Result<String, String> {
+    let mut expressions = Vec::with_capacity(fields.len());
+    for field in fields {
+        expressions.push(expression_of(collection, field)?);
+    }
+    let range = format!("time >= ?1 AND time <= ?2 AND {}", condition);
+    Ok(format!(
+        "FROM {0} WHERE {1} AND rowid NOT IN (SELECT MIN(rowid) FROM {0} WHERE {1} GROUP BY {2})",
+        collection,
+        range,
+        expressions.join(", ")
+    ))
+}
+
+fn to_sql(value: Option<&serde_json::Value>) -> rusqlite::types::Value {
+    use rusqlite::types::Value;
+    match value {
+        Some(serde_json::Value::Bool(content)) => Value::Integer(*content as i64),
+        Some(serde_json::Value::Number(content)) => match content.as_i64() {
+            Some(integer) => Value::Integer(integer),
+            None => Value::Real(content.as_f64().unwrap_or_default()),
+        },
+        Some(serde_json::Value::String(content)) => Value::Text(content.clone()),
+        Some(content @ (serde_json::Value::Array(_) | serde_json::Value::Object(_))) => {
+            Value::Text(content.to_string())
+        }
+        Some(serde_json::Value::Null) | None => Value::Null,
+    }
+}
+
+fn parse_record(collection: &str, record: &str) -> Result<serde_json::Value, String> {
+    match serde_json::from_str(record) {
+        Ok(result) => Ok(migrations::on_read(collection, result)),
+        Err(result) => Err(error_message!(
+            "failed to parse a record!\ndetails : {:?}",
+            result
+        )),
+    }
+}
+
+#[async_trait]
+impl Store for SqliteStore {
+    async fn insert(&self, collection: &str, records: &[serde_json::Value]) -> Result<u64, String> {
+        let rows = rows_of(collection, records)?;
+
+        let collection_name = String::from(collection);
+        let started_at = std::time::Instant::now();
+        let inserted = self
+            .run(move |connection| {
+                if let Some(name) = missing_key_index(connection, &collection_name)? {
+                    return Err(error_message!(
+                        "the records would not be deduplicated without index {}! remove the duplicates with dedupe and check the schema with repair.\ncollection : {}",
+                        name,
+                        collection_name
+                    ));
+                }
+                let transaction = match connection.transaction() {
+                    Ok(result) => result,
+                    Err(result) => {
+                        return Err(error_message!(
+                            "failed to begin a transaction!\ndetails : {:?}",
+                            result
+                        ));
+                    }
+                };
+                let mut inserted = 0;
+                {
+                    // a record whose key is in a unique index is skipped.
+                    let mut statement = match transaction.prepare_cached(&format!(
+                        "INSERT OR IGNORE INTO {} (time, record) VALUES (?1, ?2)",
+                        collection_name
+                    )) {
+                        Ok(result) => result,
+                        Err(result) => {
+                            return Err(error_message!(
+                                "failed to prepare!\ndetails : {:?}",
+                                result
+                            ));
+                        }
+                    };
+                    for (time, record) in &rows {
+                        match statement.execute(params![time, record]) {
+                            Ok(changed) => inserted += changed as u64,
+                            Err(result) => {
+                                return Err(error_message!(
+                                    "failed to insert posts!\ndetails : {:?}",
+                                    result
+                                ));
+                            }
+                        }
+                    }
+                }
+                match transaction.commit() {
+                    Ok(_) => Ok(inserted),
+                    Err(result) => Err(error_message!("failed to commit!\ndetails : {:?}", result)),
+                }
+            })
+            .await;
+        match &inserted {
+            Ok(count) => record_upsert(
+                collection,
+                *count as usize,
+                records.len() - *count as usize,
+                started_at,
+            ),
+            Err(_) => record_insert(collection, records.len(), started_at, false),
+        }
+        inserted
+    }
+
+    async fn upsert(&self, collection: &str, records: &[serde_json::Value]) -> Result<u64, String> {
+        let keys = schema::key_fields(collection)?;
+        let rows = rows_of(collection, records)?;
+        let mut conditions = Vec::with_capacity(keys.len());
+        for (i, key) in keys.iter().enumerate() {
+            conditions.push(format!("{} IS ?{}", expression_of(collection, key)?, i + 1));
+        }
+        let key_values = records
+            .iter()
+            .map(|record| {
+                keys.iter()
+                    .map(|key| to_sql(record.get(*key)))
+                    .collect::<Vec<rusqlite::types::Value>>()
+            })
+            .collect::<Vec<_>>();
+        let delete = format!(
+            "DELETE FROM {} WHERE {}",
+            collection,
+            conditions.join(" AND ")
+        );
+        let insert = format!("INSERT INTO {} (time, record) VALUES (?1, ?2)", collection);
+
+        let started_at = std::time::Instant::now();
+        let replaced = self
+            .run(move |connection| {
+                let transaction = match connection.transaction() {
+                    Ok(result) => result,
+                    Err(result) => {
+                        return Err(error_message!(
+                            "failed to begin a transaction!\ndetails : {:?}",
+                            result
+                        ));
+                    }
+                };
+                for ((time, record), key_values) in rows.iter().zip(key_values.iter()) {
+                    if let Err(result) = transaction
+                        .execute(&delete, rusqlite::params_from_iter(key_values.iter()))
+                        .and_then(|_| transaction.execute(&insert, params![time, record]))
+                    {
+                        return Err(error_message!(
+                            "failed to replace posts!\ndetails : {:?}",
+                            result
+                        ));
+                    }
+                }
+                match transaction.commit() {
+                    Ok(_) => Ok(rows.len() as u64),
+                    Err(result) => Err(error_message!("failed to commit!\ndetails : {:?}", result)),
+                }
+            })
+            .await;
+        record_insert(collection, records.len(), started_at, replaced.is_ok());
+        replaced
+    }
+
+    async fn query(
+        &self,
+        collection: &str,
+        timeline: &Timeline,
+    ) -> Result<Vec<serde_json::Value>, String> {
+        time_field(collection)?;
+        let statement = select_statement(collection, timeline);
+        let collection = String::from(collection);
+        let (start, end, limit) = bounds(timeline);
+        self.run(move |connection| {
+            let mut statement = match connection.prepare(&statement) {
+                Ok(result) => result,
+                Err(result) => {
+                    return Err(error_message!(
+                        "failed to request query!\ndetails : {:?}",
+                        result
+                    ));
+                }
+            };
+            let rows = match statement
+                .query_map(params![start, end, limit], |row| row.get::<_, String>(0))
+            {
+                Ok(result) => result,
+                Err(result) => {
+                    return Err(error_message!(
+                        "failed to request query!\ndetails : {:?}",
+                        result
+                    ));
+                }
+            };
+            let mut records = Vec::new();
+            for row in rows {
+                match row {
+                    Ok(record) => records.push(parse_record(&collection, &record)?),
+                    Err(result) => {
+                        return Err(error_message!(
+                            "failed to get a row!\ndetails : {:?}",
+                            result
+                        ));
+                    }
+                }
+            }
+            Ok(records)
+        })
+        .await
+    }
+
+    async fn stream(&self, collection: &str, timeline: &Timeline) -> Result<RecordStream, String> {
+        time_field(collection)?;
+        let (start, end, _) = bounds(timeline);
+        Ok(self.stream_rows(
+            collection,
+            (start, end, i64::MAX),
+            timeline.order,
+            timeline.limit,
+        ))
+    }
+
+    async fn tail(
+        &self,
+        collection: &str,
+        start: Option<f64>,
+        resume: Option<&ResumeToken>,
+    ) -> Result<TailStream, String> {
+        time_field(collection)?;
+        let (history, last) = match resume {
+            Some(content) => match content.0.get("rowid").and_then(|e| e.as_i64()) {
+                Some(rowid) => (futures::stream::empty().boxed(), rowid),
+                None => {
+                    return Err(error_message!(
+                        "invalid resume token!\ntoken : {:?}",
+                        content
+                    ))
+                }
+            },
+            None => {
+                let statement = format!("SELECT COALESCE(MAX(rowid), 0) FROM {}", collection);
+                let last = self
+                    .run(move |connection| {
+                        match connection.query_row(&statement, [], |row| row.get::<_, i64>(0)) {
+                            Ok(result) => Ok(result),
+                            Err(result) => Err(error_message!(
+                                "failed to request query!\ndetails : {:?}",
+                                result
+                            )),
+                        }
+                    })
+                    .await?;
+                // the rows inserted after `last` are left to the polling.
+                let history = self.stream_rows(
+                    collection,
+                    (start.unwrap_or(f64::MIN), f64::MAX, last),
+                    1,
+                    None,
+                );
+                (history, last)
+            }
+        };
+        let history = history.map(|e| {
+            e.map(|record| Tailed {
+                record,
+                resume: None,
+            })
+        });
+        let live = futures::stream::unfold(
+            Some((
+                self.clone(),
+                String::from(collection),
+                last,
+                VecDeque::<(i64, String)>::new(),
+            )),
+            |state| async move {
+                let (store, collection, mut last, mut rows) = state?;
+                loop {
+                    if let Some((rowid, record)) = rows.pop_front() {
+                        let tailed = parse_record(&collection, &record).map(|record| Tailed {
+                            record,
+                            resume: Some(ResumeToken(serde_json::json!({ "rowid": rowid }))),
+                        });
+                        return Some((tailed, Some((store, collection, last, rows))));
+                    }
+                    match store.rows_after(&collection, last).await {
+                        Ok(result) if result.is_empty() => {
+                            tokio::time::sleep(TAIL_POLL_INTERVAL).await
+                        }
+                        Ok(result) => {
+                            last = result.last().map(|e| e.0).unwrap_or(last);
+                            rows.extend(result);
+                        }
+                        Err(result) => return Some((Err(result), None)),
+                    }
+                }
+            },
+        );
+        Ok(history.chain(live).boxed())
+    }
+
+    async fn count(&self, collection: &str, timeline: &Timeline) -> Result<u64, String> {
+        time_field(collection)?;
+        let statement = format!(
+            "SELECT COUNT(*) FROM {} WHERE time >= ?1 AND time <= ?2",
+            collection
+        );
+        let (start, end, _) = bounds(timeline);
+        self.run(move |connection| {
+            match connection.query_row(&statement, params![start, end], |row| row.get::<_, i64>(0))
+            {
+                Ok(result) => Ok(result as u64),
+                Err(result) => Err(error_message!("failed to count!\ndetails : {:?}", result)),
+            }
+        })
+        .await
+    }
+
+    async fn ping(&self) -> Result<(), String> {
+        self.run(|connection| {
+            match connection.query_row("SELECT 1", [], |row| row.get::<_, i64>(0)) {
+                Ok(_) => Ok(()),
+                Err(result) => Err(error_message!("failed to ping!\ndetails : {:?}", result)),
+            }
+        })
+        .await
+    }
+
+    async fn dedupe(
+        &self,
+        collection: &str,
+        timeline: &Timeline,
+        dry_run: bool,
+    ) -> Result<u64, String> {
+        let keys = schema::key_fields(collection)?;
+        let key = expression_of(collection, keys[0])?;
+        // (the key, the documents grouped by it)
+        let mut groupings = vec![(keys, format!("{} IS NOT NULL", key))];
+        if collection == common_constants::DATABASE_COLLECTION_EXECUTIONS {
+            groupings.push((
+                &schema::LEGACY_EXECUTION_KEY[..],
+                format!("{} IS NULL", key),
+            ));
+        }
+        let mut statements = Vec::with_capacity(groupings.len());
+        for (fields, condition) in groupings {
+            let duplicates = duplicates_of(collection, fields, &condition)?;
+            statements.push(if dry_run {
+                format!("SELECT COUNT(*) {}", duplicates)
+            } else {
+                format!("DELETE {}", duplicates)
+            });
+        }
+        let (start, end, _) = bounds(timeline);
+        self.run(move |connection| {
+            let mut removed = 0;
+            for statement in statements {
+                let counted = if dry_run {
+                    connection.query_row(&statement, params![start, end], |row| {
+                        row.get::<_, i64>(0).map(|e| e as usize)
+                    })
+                } else {
+                    connection.execute(&statement, params![start, end])
+                };
+                match counted {
+                    Ok(result) => removed += result as u64,
+                    Err(result) => {
+                        return Err(error_message!(
+                            "failed to remove duplicates!\ndetails : {:?}",
+                            result
+                        ));
+                    }
+                }
+            }
+            Ok(removed)
+        })
+        .await
+    }
+
+    async fn remove(
+        &self,
+        collection: &str,
+        timeline: &Timeline,
+        dry_run: bool,
+    ) -> Result<u64, String> {
+        time_field(collection)?;
+        let statement = format!(
+            "{} FROM {} WHERE time >= ?1 AND time <= ?2",
+            if dry_run { "SELECT COUNT(*)" } else { "DELETE" },
+            collection
+        );
+        let (start, end, _) = bounds(timeline);
+        self.run(move |connection| {
+            let removed = if dry_run {
+                connection.query_row(&statement, params![start, end], |row| {
+                    row.get::<_, i64>(0).map(|e| e as usize)
+                })
+            } else {
+                connection.execute(&statement, params![start, end])
+            };
+            match removed {
+                Ok(result) => Ok(result as u64),
+                Err(result) => Err(error_message!("failed to remove!\ndetails : {:?}", result)),
+            }
+        })
+        .await
+    }
+
+    async fn downsample(
+        &self,
+        collection: &str,
+        timeline: &Timeline,
+        interval: f64,
+        dry_run: bool,
+    ) -> Result<u64, String> {
+        time_field(collection)?;
+        if interval <= 0.0 {
+            return Err(error_message!("invalid interval! {}", interval));
+        }
+        let range = "time >= ?1 AND time <= ?2";
+        let statement = format!(
+            "{0} FROM {1} WHERE {2} AND rowid NOT IN (SELECT id FROM (SELECT rowid AS id, MIN(time) FROM {1} WHERE {2} GROUP BY CAST(time / ?3 AS INTEGER)))",
+            if dry_run { "SELECT COUNT(*)" } else { "DELETE" },
+            collection,
+            range
+        );
+        let (start, end, _) = bounds(timeline);
+        self.run(move |connection| {
+            let removed = if dry_run {
+                connection.query_row(&statement, params![start, end, interval], |row| {
+                    row.get::<_, i64>(0).map(|e| e as usize)
+                })
+            } else {
+                connection.execute(&statement, params![start, end, interval])
+            };
+            match removed {
+                Ok(result) => Ok(result as u64),
+                Err(result) => Err(error_message!(
+                    "failed to downsample!\ndetails : {:?}",
+                    result
+                )),
+            }
+        })
+        .await
+    }
+
+    async fn versions(&self, collection: &str) -> Result<Vec<(u32, u64)>, String> {
+        time_field(collection)?;
+        let statement = format!(
+            "SELECT COALESCE(json_extract(record, '$.{}'), 0), COUNT(*) FROM {} GROUP BY 1 ORDER BY 1",
+            migrations::SCHEMA_VERSION_FIELD,
+            collection
+        );
+        self.run(move |connection| {
+            let mut statement = match connection.prepare(&statement) {
+                Ok(result) => result,
+                Err(result) => {
+                    return Err(error_message!(
+                        "failed to request query!\ndetails : {:?}",
+                        result
+                    ));
+                }
+            };
+            let rows = match statement.query_map([], |row| {
+                Ok((row.get::<_, i64>(0)? as u32, row.get::<_, i64>(1)? as u64))
+            }) {
+                Ok(result) => result,
+                Err(result) => {
+                    return Err(error_message!(
+                        "failed to request query!\ndetails : {:?}",
+                        result
+                    ));
+                }
+            };
+            let mut versions = Vec::new();
+            for row in rows {
+                match row {
+                    Ok(result) => versions.push(result),
+                    Err(result) => {
+                        return Err(error_message!(
+                            "failed to get a row!\ndetails : {:?}",
+                            result
+                        ));
+                    }
+                }
+            }
+            Ok(versions)
+        })
+        .await
+    }
+
+    async fn migrate(
+        &self,
+        collection: &str,
+        dry_run: bool,
+    ) -> Result<migrations::MigrationReport, String> {
+        time_field(collection)?;
+        let current = migrations::current_version(collection);
+        let select = format!(
+            "SELECT rowid, record FROM {} WHERE rowid > ?1 AND COALESCE(json_extract(record, '$.{}'), 0) < ?2 ORDER BY rowid LIMIT ?3",
+            collection,
+            migrations::SCHEMA_VERSION_FIELD
+        );
+        let update = format!("UPDATE {} SET record = ?1 WHERE rowid = ?2", collection);
+        let collection = String::from(collection);
+        self.run(move |connection| {
+            let mut report = migrations::MigrationReport {
+                collection: collection.clone(),
+                ..Default::default()
+            };
+            let mut last = 0;
+            loop {
+                // (rowid, record) of a chunk, read before writing to release the statement.
+                let rows = {
+                    let mut statement = match connection.prepare(&select) {
+                        Ok(result) => result,
+                        Err(result) => {
+                            return Err(error_message!(
+                                "failed to request query!\ndetails : {:?}",
+                                result
+                            ));
+                        }
+                    };
+                    let rows = match statement
+                        .query_map(params![last, current, MIGRATION_CHUNK], |row| {
+                            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+                        }) {
+                        Ok(result) => result,
+                        Err(result) => {
+                            return Err(error_message!(
+                                "failed to request query!\ndetails : {:?}",
+                                result
+                            ));
+                        }
+                    };
+                    let mut chunk = Vec::new();
+                    for row in rows {
+                        match row {
+                            Ok(result) => chunk.push(result),
+                            Err(result) => {
+                                return Err(error_message!(
+                                    "failed to get a row!\ndetails : {:?}",
+                                    result
+                                ));
+                            }
+                        }
+                    }
+                    chunk
+                };
+                last = match rows.last() {
+                    Some((id, _)) => *id,
+                    None => break,
+                };
+                let mut upgraded = Vec::with_capacity(rows.len());
+                for (id, record) in rows {
+                    let mut record = match serde_json::from_str(&record) {
+                        Ok(result) => result,
+                        Err(_) => {
+                            report.failed += 1;
+                            continue;
+                        }
+                    };
+                    match migrations::upgrade(&collection, &mut record) {
+                        Ok(_) => upgraded.push((id, record.to_string())),
+                        Err(_) => report.failed += 1,
+                    }
+                }
+                report.migrated += upgraded.len() as u64;
+                if dry_run {
+                    continue;
+                }
+                let transaction = match connection.transaction() {
+                    Ok(result) => result,
+                    Err(result) => {
+                        return Err(error_message!(
+                            "failed to begin a transaction!\ndetails : {:?}",
+                            result
+                        ));
+                    }
+                };
+                for (id, record) in upgraded {
+                    if let Err(result) = transaction.execute(&update, params![record, id]) {
+                        return Err(error_message!(
+                            "failed to update a record!\ndetails : {:?}",
+                            result
+                        ));
+                    }
+                }
+                if let Err(result) = transaction.commit() {
+                    return Err(error_message!(
+                        "failed to commit a transaction!\ndetails : {:?}",
+                        result
+                    ));
+                }
+            }
+            Ok(report)
+        })
+        .await
+    }
+
+    async fn check_schema(&self, repair: bool) -> Result<Vec<SchemaReport>, String> {
+        self.run(move |connection| {
+            let mut reports = Vec::with_capacity(COLLECTIONS.len());
+            for collection in COLLECTIONS {
+                reports.push(check_indexes(connection, collection, repair)?);
+            }
+            Ok(reports)
+        })
+        .await
+    }
+}
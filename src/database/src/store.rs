@@ -0,0 +1,925 @@
+use async_trait::async_trait;
+use futures::stream::{BoxStream, StreamExt};
+use mongodb::change_stream::event::ResumeToken as ChangeStreamToken;
+use mongodb::options::ChangeStreamOptions;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use super::*;
+
+pub const MONGODB_SCHEME: &str = "mongodb://";
+pub const MONGODB_SRV_SCHEME: &str = "mongodb+srv://";
+///
+/// such as `sqlite:///var/lib/zenies/market.sqlite3` or `sqlite://:memory:`
+pub const SQLITE_SCHEME: &str = "sqlite://";
+pub const SQLITE_MEMORY: &str = ":memory:";
+
+pub type RecordStream = BoxStream<'static, Result<serde_json::Value, String>>;
+pub type TailStream = BoxStream<'static, Result<Tailed<serde_json::Value>, String>>;
+
+///
+/// a position in the inserts of a collection, opaque but kept as json so that it can be persisted.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ResumeToken(pub serde_json::Value);
+
+///
+/// a record of a tail with the token resuming after it. the historical records have none.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tailed<T> {
+    pub record: T,
+    pub resume: Option<ResumeToken>,
+}
+
+///
+/// the range of records ordered by the time field of a collection.
+/// `timestamp` for ticker and executions, `received_at` for the order books.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Timeline {
+    pub start: Option<f64>,
+    pub end: Option<f64>,
+    ///
+    /// 1 for ascending, -1 for descending
+    pub order: i64,
+    pub limit: Option<i64>,
+}
+
+impl Timeline {
+    pub fn new<Z: TimeZone>(start_time: &DateTime<Z>, end_time: &DateTime<Z>) -> Self {
+        Self {
+            start: Some(
+                start_time.timestamp_nanos_opt().unwrap_or_default() as f64 / 1_000_000_000.0,
+            ),
+            end: Some(end_time.timestamp_nanos_opt().unwrap_or_default() as f64 / 1_000_000_000.0),
+            order: 1,
+            limit: None,
+        }
+    }
+
+    pub fn all() -> Self {
+        Self {
+            start: None,
+            end: None,
+            order: 1,
+            limit: None,
+        }
+    }
+
+    pub fn order(mut self, order: i64) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn limit(mut self, limit: Option<i64>) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+///
+/// storage of the market collections.
+/// records are passed as json objects so that a backend does not leak its document type.
+#[async_trait]
+pub trait Store: Send + Sync + std::fmt::Debug {
+    async fn insert(&self, collection: &str, records: &[serde_json::Value]) -> Result<u64, String>;
+
+    ///
+    /// insert the records, replacing the stored ones having the same key.
+    async fn upsert(&self, collection: &str, records: &[serde_json::Value]) -> Result<u64, String>;
+
+    async fn query(
+        &self,
+        collection: &str,
+        timeline: &Timeline,
+    ) -> Result<Vec<serde_json::Value>, String>;
+
+    ///
+    /// the records of `query` one by one. the store takes other requests, including other streams,
+    /// while a stream is alive, as the order books are rebuilt from several of them at once.
+    async fn stream(&self, collection: &str, timeline: &Timeline) -> Result<RecordStream, String>;
+
+    ///
+    /// the records from `start` in ascending order, then the records inserted afterwards as they arrive,
+    /// without a gap or a duplicate between them. with `resume`, only the records inserted after the token.
+    /// the stream does not end by itself.
+    async fn tail(
+        &self,
+        collection: &str,
+        start: Option<f64>,
+        resume: Option<&ResumeToken>,
+    ) -> Result<TailStream, String>;
+
+    async fn count(&self, collection: &str, timeline: &Timeline) -> Result<u64, String>;
+
+    ///
+    /// the newest `num` records, newest first.
+    async fn latest(&self, collection: &str, num: i64) -> Result<Vec<serde_json::Value>, String> {
+        self.query(collection, &Timeline::all().order(-1).limit(Some(num)))
+            .await
+    }
+
+    async fn ping(&self) -> Result<(), String>;
+
+    ///
+    /// remove the records in the timeline whose key is stored more than once, keeping one of each.
+    /// nothing is removed with `dry_run`. returns the number of the duplicates.
+    async fn dedupe(
+        &self,
+        collection: &str,
+        timeline: &Timeline,
+        dry_run: bool,
+    ) -> Result<u64, String>;
+
+    ///
+    /// remove the records in the timeline. nothing is removed with `dry_run`.
+    /// returns the number of the records.
+    async fn remove(
+        &self,
+        collection: &str,
+        timeline: &Timeline,
+        dry_run: bool,
+    ) -> Result<u64, String>;
+
+    ///
+    /// keep the oldest record of every `interval` seconds in the timeline and remove the others.
+    /// an open start or end stands for the oldest or the newest record.
+    /// nothing is removed with `dry_run`. returns the number of the removed records.
+    async fn downsample(
+        &self,
+        collection: &str,
+        timeline: &Timeline,
+        interval: f64,
+        dry_run: bool,
+    ) -> Result<u64, String>;
+
+    ///
+    /// the number of the records of each stored schema version. 0 for the unversioned records.
+    async fn versions(&self, collection: &str) -> Result<Vec<(u32, u64)>, String>;
+
+    ///
+    /// upgrade the records older than the current schema version in place.
+    /// nothing is written with `dry_run`.
+    async fn migrate(
+        &self,
+        collection: &str,
+        dry_run: bool,
+    ) -> Result<migrations::MigrationReport, String>;
+
+    ///
+    /// verify the collections and indexes declared in schema, creating the missing ones if `repair` is set.
+    async fn check_schema(&self, repair: bool) -> Result<Vec<schema::SchemaReport>, String>;
+
+    ///
+    /// reachability and the number of writes waiting in metrics::TASK_QUEUE_DEPTH.
+    async fn health(&self) -> health::DatabaseHealth {
+        let reachable = matches!(
+            tokio::time::timeout(std::time::Duration::from_secs(2), self.ping()).await,
+            Ok(Ok(()))
+        );
+        health::DatabaseHealth {
+            reachable,
+            write_backlog: metrics::value(metrics::TASK_QUEUE_DEPTH, &[]).unwrap_or_default()
+                as i64,
+        }
+    }
+}
+
+impl<'a> dyn Store + 'a {
+    pub async fn insert_records<T: Serialize + std::fmt::Debug>(
+        &self,
+        collection: &str,
+        records: &[T],
+    ) -> Result<u64, String> {
+        self.insert(collection, &to_values(records)?).await
+    }
+
+    pub async fn upsert_records<T: Serialize + std::fmt::Debug>(
+        &self,
+        collection: &str,
+        records: &[T],
+    ) -> Result<u64, String> {
+        self.upsert(collection, &to_values(records)?).await
+    }
+
+    pub async fn query_records<T: DeserializeOwned>(
+        &self,
+        collection: &str,
+        timeline: &Timeline,
+    ) -> Result<Vec<T>, String> {
+        self.query(collection, timeline)
+            .await?
+            .into_iter()
+            .map(from_value)
+            .collect()
+    }
+
+    pub async fn stream_records<T: DeserializeOwned + Send + 'static>(
+        &self,
+        collection: &str,
+        timeline: &Timeline,
+    ) -> Result<BoxStream<'static, Result<T, String>>, String> {
+        Ok(self
+            .stream(collection, timeline)
+            .await?
+            .map(|e| e.and_then(from_value))
+            .boxed())
+    }
+
+    pub async fn latest_records<T: DeserializeOwned>(
+        &self,
+        collection: &str,
+        num: i64,
+    ) -> Result<Vec<T>, String> {
+        self.latest(collection, num)
+            .await?
+            .into_iter()
+            .map(from_value)
+            .collect()
+    }
+}
+
+fn to_values<T: Serialize + std::fmt::Debug>(
+    records: &[T],
+) -> Result<Vec<serde_json::Value>, String> {
+    let mut values = Vec::with_capacity(records.len());
+    for record in records {
+        match serde_json::to_value(record) {
+            Ok(result) => values.push(result),
+            Err(result) => {
+                return Err(error_message!(
+                    "failed to serialize!\ndetails : {:?}\nreceived data : {:?}",
+                    result,
+                    record
+                ));
+            }
+        }
+    }
+    Ok(values)
+}
+
+pub(crate) fn from_value<T: DeserializeOwned>(value: serde_json::Value) -> Result<T, String> {
+    match serde_json::from_value(value) {
+        Ok(result) => Ok(result),
+        Err(result) => Err(error_message!(
+            "failed to deserialize!\ndetails : {:?}",
+            result
+        )),
+    }
+}
+
+///
+/// the field which orders the records of a collection.
+pub fn time_field(collection: &str) -> Result<&'static str, String> {
+    Ok(record::record_type(collection)?.time_field)
+}
+
+///
+/// open the backend selected by the scheme of `url`.
+pub async fn open_store(url: &str) -> Result<Arc<dyn Store>, String> {
+    open_store_for(url, &Symbol::default()).await
+}
+
+///
+/// the store of a symbol. a database of mongodb or a file of sqlite per symbol.
+pub async fn open_store_for(url: &str, symbol: &Symbol) -> Result<Arc<dyn Store>, String> {
+    if let Some(path) = url.strip_prefix(SQLITE_SCHEME) {
+        Ok(Arc::new(
+            sqlite::SqliteStore::open(&sqlite_path(path, symbol)).await?,
+        ))
+    } else if url.starts_with(MONGODB_SCHEME) || url.starts_with(MONGODB_SRV_SCHEME) {
+        Ok(Arc::new(Database::new(url, &symbol.database_name()).await?))
+    } else {
+        Err(error_message!("unsupported database url!\nurl : {}", url))
+    }
+}
+
+///
+/// the file of a symbol next to the file of the url, such as `market.FxBtcJpy_In_Bitflyer.sqlite3`.
+/// the default symbol and in-memory databases use the path itself.
+pub fn sqlite_path(path: &str, symbol: &Symbol) -> String {
+    if symbol.is_default() || path == SQLITE_MEMORY {
+        return String::from(path);
+    }
+    let file = std::path::Path::new(path);
+    let name = match (file.file_stem(), file.extension()) {
+        (Some(stem), Some(extension)) => format!(
+            "{}.{}.{}",
+            stem.to_string_lossy(),
+            symbol.database_name(),
+            extension.to_string_lossy()
+        ),
+        (Some(stem), None) => format!("{}.{}", stem.to_string_lossy(), symbol.database_name()),
+        _ => symbol.database_name(),
+    };
+    file.with_file_name(name).to_string_lossy().into_owned()
+}
+
+fn timeline_query(collection: &str, timeline: &Timeline) -> Result<Document, String> {
+    Ok(query::time_filter(time_field(collection)?, timeline))
+}
+
+///
+/// the change stream of a tail may deliver the records inserted this long before it was opened,
+/// allowing for the clocks of the writers which generate the ids.
+const TAIL_CLOCK_SKEW: std::time::Duration = std::time::Duration::from_secs(60);
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct TokenHolder {
+    pub(crate) token: ChangeStreamToken,
+}
+
+///
+/// the token of a change stream as json. it goes through the raw bytes since it holds raw bson.
+pub(crate) fn resume_token(token: &ChangeStreamToken) -> Result<ResumeToken, String> {
+    let holder = TokenHolder {
+        token: token.clone(),
+    };
+    let document = match bson::to_vec(&holder)
+        .map_err(|e| format!("{:?}", e))
+        .and_then(|e| bson::from_slice::<Document>(&e).map_err(|e| format!("{:?}", e)))
+    {
+        Ok(result) => result,
+        Err(result) => {
+            return Err(error_message!(
+                "failed to convert a resume token!\ndetails : {}",
+                result
+            ))
+        }
+    };
+    match document.get("token") {
+        Some(content) => Ok(ResumeToken(content.clone().into_relaxed_extjson())),
+        None => Err(error_message!("failed to convert a resume token!")),
+    }
+}
+
+pub(crate) fn change_stream_token(token: &ResumeToken) -> Result<ChangeStreamToken, String> {
+    let converted = bson::Bson::try_from(token.0.clone())
+        .map_err(|e| format!("{:?}", e))
+        .and_then(|e| bson::to_vec(&doc! { "token": e }).map_err(|e| format!("{:?}", e)))
+        .and_then(|e| bson::from_slice::<TokenHolder>(&e).map_err(|e| format!("{:?}", e)));
+    match converted {
+        Ok(result) => Ok(result.token),
+        Err(result) => Err(error_message!(
+            "invalid resume token!\ndetails : {}\ntoken : {:?}",
+            result,
+            token
+        )),
+    }
+}
+
+const DUPLICATE_KEY_ERROR: i32 = 11000;
+
+///
+/// periods of downsample handled by one request, which bounds the ids kept in memory.
+const DOWNSAMPLE_WINDOW: f64 = 3600.0;
+
+///
+/// documents removed by one request of dedupe
+const DEDUPE_CHUNK: usize = 1000;
+
+///
+/// the number of the rejected documents if every one was rejected for a duplicate key.
+fn duplicates_of(error: &mongodb::error::Error) -> Option<usize> {
+    match error.kind.as_ref() {
+        mongodb::error::ErrorKind::BulkWrite(failure) if failure.write_concern_error.is_none() => {
+            let errors = failure.write_errors.as_ref()?;
+            if errors.iter().all(|e| e.code == DUPLICATE_KEY_ERROR) {
+                Some(errors.len())
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+fn document_to_value(mut document: Document) -> serde_json::Value {
+    document.remove("_id");
+    bson::Bson::Document(document).into_relaxed_extjson()
+}
+
+fn to_documents(collection: &str, records: &[serde_json::Value]) -> Result<Vec<Document>, String> {
+    let mut documents = Vec::with_capacity(records.len());
+    for record in records {
+        match bson::to_document(&migrations::stamped(collection, record)) {
+            Ok(result) => documents.push(result),
+            Err(result) => {
+                return Err(error_message!(
+                "failed to convert data to bson document!\ndetails : {:?}\nreceived data : {:?}",
+                result,
+                record
+            ))
+            }
+        }
+    }
+    Ok(documents)
+}
+
+impl Database {
+    ///
+    /// the ids of the documents in the timeline whose `fields` are the same as an earlier document's.
+    /// the documents are those having a number in the first key field if `has_key` is set, the others if not.
+    pub(crate) async fn duplicate_ids(
+        &self,
+        collection: &str,
+        timeline: &Timeline,
+        fields: &[&str],
+        has_key: bool,
+    ) -> Result<Vec<bson::Bson>, String> {
+        let keys = schema::key_fields(collection)?;
+        let mut group = Document::new();
+        for field in fields {
+            group.insert(*field, format!("${}", field));
+        }
+        let pipeline = vec![
+            doc! { "$match": { "$and": [
+                timeline_query(collection, timeline)?,
+                if has_key {
+                    doc! { keys[0]: { "$type": "number" } }
+                } else {
+                    doc! { keys[0]: { "$not": { "$type": "number" } } }
+                },
+            ] } },
+            doc! { "$group": { "_id": group, "ids": { "$push": "$_id" }, "count": { "$sum": 1 } } },
+            doc! { "$match": { "count": { "$gt": 1 } } },
+        ];
+        let mut cursor = self
+            .aggregate(
+                collection,
+                pipeline,
+                Some(
+                    options::AggregateOptions::builder()
+                        .allow_disk_use(true)
+                        .build(),
+                ),
+            )
+            .await?;
+        let mut duplicates = Vec::new();
+        while let Some(group) = cursor.next().await {
+            match group {
+                Ok(group) => {
+                    if let Ok(ids) = group.get_array("ids") {
+                        duplicates.extend(ids.iter().skip(1).cloned());
+                    }
+                }
+                Err(result) => {
+                    return Err(error_message!(
+                        "failed to get a document!\ndetails : {:?}",
+                        result
+                    ));
+                }
+            }
+        }
+        Ok(duplicates)
+    }
+}
+
+#[async_trait]
+impl Store for Database {
+    async fn insert(&self, collection: &str, records: &[serde_json::Value]) -> Result<u64, String> {
+        let documents = to_documents(collection, records)?;
+        // keep inserting after a duplicate key and count the duplicates.
+        let started_at = std::time::Instant::now();
+        let inserted = self
+            ._database
+            .collection::<Document>(collection)
+            .insert_many(
+                documents,
+                options::InsertManyOptions::builder().ordered(false).build(),
+            )
+            .await;
+        match inserted {
+            Ok(result) => {
+                record_upsert(collection, result.inserted_ids.len(), 0, started_at);
+                Ok(result.inserted_ids.len() as u64)
+            }
+            Err(result) => match duplicates_of(&result) {
+                Some(duplicates) => {
+                    let count = records.len().saturating_sub(duplicates);
+                    record_upsert(collection, count, duplicates, started_at);
+                    Ok(count as u64)
+                }
+                None => {
+                    record_insert(collection, records.len(), started_at, false);
+                    Err(error_message!(
+                        "failed to insert posts!\ndetails : {:?}",
+                        result
+                    ))
+                }
+            },
+        }
+    }
+
+    async fn upsert(&self, collection: &str, records: &[serde_json::Value]) -> Result<u64, String> {
+        let keys = schema::key_fields(collection)?;
+        let documents = to_documents(collection, records)?;
+        let handle = self._database.collection::<Document>(collection);
+        let options = options::ReplaceOptions::builder().upsert(true).build();
+        let started_at = std::time::Instant::now();
+        for document in documents {
+            let mut filter = Document::new();
+            for key in keys {
+                filter.insert(*key, document.get(key).cloned().unwrap_or(bson::Bson::Null));
+            }
+            if let Err(result) = handle.replace_one(filter, document, options.clone()).await {
+                record_insert(collection, records.len(), started_at, false);
+                return Err(error_message!(
+                    "failed to replace posts!\ndetails : {:?}",
+                    result
+                ));
+            }
+        }
+        record_insert(collection, records.len(), started_at, true);
+        Ok(records.len() as u64)
+    }
+
+    async fn query(
+        &self,
+        collection: &str,
+        timeline: &Timeline,
+    ) -> Result<Vec<serde_json::Value>, String> {
+        let mut stream = self.stream(collection, timeline).await?;
+        let mut records = Vec::new();
+        while let Some(record) = stream.next().await {
+            records.push(record?);
+        }
+        Ok(records)
+    }
+
+    async fn stream(&self, collection: &str, timeline: &Timeline) -> Result<RecordStream, String> {
+        let query = timeline_query(collection, timeline)?;
+        let option = query::find_options(time_field(collection)?, timeline, None);
+        let cursor = self.request_raw(collection, query, Some(option)).await?;
+        let collection = String::from(collection);
+        Ok(cursor
+            .map(move |e| match e {
+                Ok(document) => Ok(migrations::on_read(
+                    &collection,
+                    document_to_value(document),
+                )),
+                Err(result) => Err(error_message!(
+                    "failed to get a document!\ndetails : {:?}",
+                    result
+                )),
+            })
+            .boxed())
+    }
+
+    async fn tail(
+        &self,
+        collection: &str,
+        start: Option<f64>,
+        resume: Option<&ResumeToken>,
+    ) -> Result<TailStream, String> {
+        time_field(collection)?;
+        let options = ChangeStreamOptions::builder()
+            .resume_after(match resume {
+                Some(content) => Some(change_stream_token(content)?),
+                None => None,
+            })
+            .build();
+        // the change stream is opened first so that nothing inserted while reading the history is missed.
+        let changes = match self
+            ._database
+            .collection::<Document>(collection)
+            .watch(
+                vec![doc! { "$match": { "operationType": "insert" } }],
+                options,
+            )
+            .await
+        {
+            Ok(result) => result,
+            Err(result) => {
+                return Err(error_message!(
+                    "failed to watch a collection!\ndetails : {:?}",
+                    result
+                ))
+            }
+        };
+        // the ids of the historical records which the change stream may deliver again.
+        let seen = Arc::new(std::sync::Mutex::new(HashSet::new()));
+        let recent = bson::DateTime::from_millis(
+            bson::DateTime::now().timestamp_millis() - TAIL_CLOCK_SKEW.as_millis() as i64,
+        );
+
+        let history = match resume {
+            Some(_) => futures::stream::empty().boxed(),
+            None => {
+                let timeline = Timeline {
+                    start,
+                    ..Timeline::all()
+                };
+                let query = timeline_query(collection, &timeline)?;
+                let option = query::find_options(time_field(collection)?, &timeline, None);
+                let cursor = self.request_raw(collection, query, Some(option)).await?;
+                let collection = String::from(collection);
+                let seen = seen.clone();
+                cursor
+                    .map(move |e| match e {
+                        Ok(document) => {
+                            if let Ok(id) = document.get_object_id("_id") {
+                                if id.timestamp() >= recent {
+                                    seen.lock().expect("poisoned!").insert(id);
+                                }
+                            }
+                            Ok(Tailed {
+                                record: migrations::on_read(
+                                    &collection,
+                                    document_to_value(document),
+                                ),
+                                resume: None,
+                            })
+                        }
+                        Err(result) => Err(error_message!(
+                            "failed to get a document!\ndetails : {:?}",
+                            result
+                        )),
+                    })
+                    .boxed()
+            }
+        };
+
+        let collection = String::from(collection);
+        let live = changes.filter_map(move |e| {
+            let tailed = match e {
+                Ok(event) => {
+                    let duplicated = event
+                        .document_key
+                        .as_ref()
+                        .and_then(|e| e.get_object_id("_id").ok())
+                        .map(|e| seen.lock().expect("poisoned!").remove(&e))
+                        .unwrap_or(false);
+                    match event.full_document {
+                        Some(document) if !duplicated => {
+                            Some(resume_token(&event.id).map(|e| Tailed {
+                                record: migrations::on_read(
+                                    &collection,
+                                    document_to_value(document),
+                                ),
+                                resume: Some(e),
+                            }))
+                        }
+                        _ => None,
+                    }
+                }
+                Err(result) => Some(Err(error_message!(
+                    "failed to get a change!\ndetails : {:?}",
+                    result
+                ))),
+            };
+            futures::future::ready(tailed)
+        });
+        Ok(history.chain(live).boxed())
+    }
+
+    async fn count(&self, collection: &str, timeline: &Timeline) -> Result<u64, String> {
+        let query = timeline_query(collection, timeline)?;
+        self.count_documents(collection, query, None).await
+    }
+
+    async fn ping(&self) -> Result<(), String> {
+        Database::ping(self).await
+    }
+
+    async fn dedupe(
+        &self,
+        collection: &str,
+        timeline: &Timeline,
+        dry_run: bool,
+    ) -> Result<u64, String> {
+        let keys = schema::key_fields(collection)?;
+        // (the key, whether the documents have the first key field)
+        let mut groupings = vec![(keys, true)];
+        if collection == common_constants::DATABASE_COLLECTION_EXECUTIONS {
+            groupings.push((&schema::LEGACY_EXECUTION_KEY[..], false));
+        }
+
+        let mut duplicates = Vec::new();
+        for (fields, has_key) in groupings {
+            duplicates.extend(
+                self.duplicate_ids(collection, timeline, fields, has_key)
+                    .await?,
+            );
+        }
+
+        if !dry_run {
+            for chunk in duplicates.chunks(DEDUPE_CHUNK) {
+                self.delete(collection, &doc! { "_id": { "$in": chunk } }, None)
+                    .await?;
+            }
+        }
+        Ok(duplicates.len() as u64)
+    }
+
+    async fn remove(
+        &self,
+        collection: &str,
+        timeline: &Timeline,
+        dry_run: bool,
+    ) -> Result<u64, String> {
+        let query = timeline_query(collection, timeline)?;
+        if dry_run {
+            self.count_documents(collection, query, None).await
+        } else {
+            Ok(self.delete(collection, &query, None).await?.deleted_count)
+        }
+    }
+
+    async fn downsample(
+        &self,
+        collection: &str,
+        timeline: &Timeline,
+        interval: f64,
+        dry_run: bool,
+    ) -> Result<u64, String> {
+        let field = time_field(collection)?;
+        if interval <= 0.0 {
+            return Err(error_message!("invalid interval! {}", interval));
+        }
+        // an open end is the oldest or the newest record, so that the windows are bounded.
+        let mut bounds = [timeline.start, timeline.end];
+        for (bound, order) in bounds.iter_mut().zip([1, -1]) {
+            if bound.is_some() {
+                continue;
+            }
+            *bound = match self
+                .query(
+                    collection,
+                    &Timeline {
+                        order,
+                        limit: Some(1),
+                        ..timeline.clone()
+                    },
+                )
+                .await?
+                .first()
+                .and_then(|e| e.get(field))
+                .and_then(|e| e.as_f64())
+            {
+                Some(content) => Some(content),
+                None => return Ok(0),
+            };
+        }
+        let (start, end) = match bounds {
+            [Some(start), Some(end)] => (start, end),
+            _ => return Ok(0),
+        };
+
+        let mut removed = 0;
+        let mut window = (start / interval).floor() * interval;
+        while window <= end {
+            let next = window + interval * DOWNSAMPLE_WINDOW;
+            let range = doc! { field: { "$gte": start.max(window), "$lte": end, "$lt": next } };
+            let pipeline = vec![
+                doc! { "$match": range.clone() },
+                doc! { "$sort": { field: 1 } },
+                doc! { "$group": {
+                    "_id": { "$floor": { "$divide": [format!("${}", field), interval] } },
+                    "first": { "$first": "$_id" },
+                } },
+            ];
+            let mut cursor = self
+                .aggregate(
+                    collection,
+                    pipeline,
+                    Some(
+                        options::AggregateOptions::builder()
+                            .allow_disk_use(true)
+                            .build(),
+                    ),
+                )
+                .await?;
+            let mut kept = Vec::new();
+            while let Some(group) = cursor.next().await {
+                match group {
+                    Ok(group) => {
+                        if let Some(id) = group.get("first") {
+                            kept.push(id.clone());
+                        }
+                    }
+                    Err(result) => {
+                        return Err(error_message!(
+                            "failed to get a document!\ndetails : {:?}",
+                            result
+                        ));
+                    }
+                }
+            }
+            if !kept.is_empty() {
+                let query = doc! { "$and": [range, { "_id": { "$nin": kept } }] };
+                removed += if dry_run {
+                    self.count_documents(collection, query, None).await?
+                } else {
+                    self.delete(collection, &query, None).await?.deleted_count
+                };
+            }
+            window = next;
+        }
+        Ok(removed)
+    }
+
+    async fn versions(&self, collection: &str) -> Result<Vec<(u32, u64)>, String> {
+        let pipeline = vec![
+            doc! { "$group": {
+                "_id": { "$ifNull": [format!("${}", migrations::SCHEMA_VERSION_FIELD), 0] },
+                "count": { "$sum": 1 },
+            } },
+            doc! { "$sort": { "_id": 1 } },
+        ];
+        let mut cursor = self.aggregate(collection, pipeline, None).await?;
+        let mut versions = Vec::new();
+        while let Some(group) = cursor.next().await {
+            match group {
+                Ok(group) => {
+                    let number = |key: &str| match group.get(key) {
+                        Some(bson::Bson::Int32(content)) => *content as u64,
+                        Some(bson::Bson::Int64(content)) => *content as u64,
+                        Some(bson::Bson::Double(content)) => *content as u64,
+                        _ => 0,
+                    };
+                    versions.push((number("_id") as u32, number("count")));
+                }
+                Err(result) => {
+                    return Err(error_message!(
+                        "failed to get a document!\ndetails : {:?}",
+                        result
+                    ));
+                }
+            }
+        }
+        Ok(versions)
+    }
+
+    async fn migrate(
+        &self,
+        collection: &str,
+        dry_run: bool,
+    ) -> Result<migrations::MigrationReport, String> {
+        let current = migrations::current_version(collection);
+        let filter = doc! { "$or": [
+            { migrations::SCHEMA_VERSION_FIELD: { "$exists": false } },
+            { migrations::SCHEMA_VERSION_FIELD: { "$lt": current } },
+        ] };
+        let handle = self._database.collection::<Document>(collection);
+        let mut cursor = match handle.find(filter, None).await {
+            Ok(result) => result,
+            Err(result) => {
+                return Err(error_message!(
+                    "failed to request query!\ndetails : {:?}",
+                    result
+                ));
+            }
+        };
+        let mut report = migrations::MigrationReport {
+            collection: String::from(collection),
+            ..Default::default()
+        };
+        while let Some(document) = cursor.next().await {
+            let mut document = match document {
+                Ok(result) => result,
+                Err(result) => {
+                    return Err(error_message!(
+                        "failed to get a document!\ndetails : {:?}",
+                        result
+                    ));
+                }
+            };
+            // the id kept by the backend is put back after the upgrade.
+            let id = document.remove("_id");
+            let mut record = bson::Bson::Document(document).into_relaxed_extjson();
+            if migrations::upgrade(collection, &mut record).is_err() {
+                report.failed += 1;
+                continue;
+            }
+            report.migrated += 1;
+            if dry_run {
+                continue;
+            }
+            let mut upgraded = match bson::to_document(&record) {
+                Ok(result) => result,
+                Err(result) => {
+                    return Err(error_message!(
+                        "failed to convert data to bson document!\ndetails : {:?}",
+                        result
+                    ));
+                }
+            };
+            if let Some(id) = id {
+                upgraded.insert("_id", id.clone());
+                if let Err(result) = handle.replace_one(doc! { "_id": id }, upgraded, None).await {
+                    return Err(error_message!(
+                        "failed to replace posts!\ndetails : {:?}",
+                        result
+                    ));
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    async fn check_schema(&self, repair: bool) -> Result<Vec<schema::SchemaReport>, String> {
+        Database::check_schema(self, repair).await
+    }
+}
@@ -1,5 +1,9 @@
+use super::record::{self, Record};
+use super::store::{from_value, ResumeToken, Store, Tailed};
 use super::*;
+use futures::stream::BoxStream;
 use futures::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
 use std::future::Future;
 use std::marker::PhantomData;
 use std::pin::Pin;
@@ -17,30 +21,17 @@ where
 
 impl<'a, T> DatabaseStream<'a, T>
 where
-    T: Generater,
+    T: Record,
 {
+    ///
+    /// the records of the collection of `T`, the first one of the type unless it is given.
     pub async fn request<'b>(
         source_database: &'a Database,
         collection: Option<&str>,
         query: Document,
         option: options::FindOptions,
     ) -> Result<DatabaseStream<'a, T>, String> {
-        let collection = match std::any::type_name::<T>() {
-            "database::data::Ticker" => common_constants::DATABASE_COLLECTION_TICKER,
-            "database::data::Execution" => common_constants::DATABASE_COLLECTION_EXECUTIONS,
-            "database::data::OrderBook" => match collection {
-                Some(content) => content,
-                None => {
-                    return Err(error_message!("unspecified collection!"));
-                }
-            },
-            _ => match collection {
-                Some(content) => content,
-                None => {
-                    return Err(error_message!("unspecified collection!"));
-                }
-            },
-        };
+        let collection = record::collection_of::<T>(collection)?;
 
         let (document_count, cursor) = tokio::join!(
             source_database.count_documents(collection, query.clone(), None),
@@ -69,30 +60,20 @@ where
             _type: PhantomData,
         })
     }
+}
 
+impl<'a, T> DatabaseStream<'a, T>
+where
+    T: Generater,
+{
+    ///
+    /// the results of a pipeline, such as summaries which are not records.
     pub async fn aggregate<'b, D: IntoIterator<Item = bson::Document>>(
         source_database: &'a Database,
-        collection: Option<&str>,
+        collection: &str,
         pipeline: D,
         option: Option<options::AggregateOptions>,
     ) -> Result<DatabaseStream<'a, T>, String> {
-        let collection = match std::any::type_name::<T>() {
-            "database::data::Ticker" => common_constants::DATABASE_COLLECTION_TICKER,
-            "database::data::Execution" => common_constants::DATABASE_COLLECTION_EXECUTIONS,
-            "database::data::OrderBook" => match collection {
-                Some(content) => content,
-                None => {
-                    return Err(error_message!("unspecified collection!"));
-                }
-            },
-            _ => match collection {
-                Some(content) => content,
-                None => {
-                    return Err(error_message!("unspecified collection!"));
-                }
-            },
-        };
-
         let cursor = source_database
             .aggregate(collection, pipeline, option)
             .await;
@@ -114,6 +95,44 @@ where
     }
 }
 
+impl<'a, T> DatabaseStream<'a, T>
+where
+    T: Generater<Output = T> + columnar::Columnar + Send + 'a,
+{
+    ///
+    /// the records grouped into arrow record batches of `rows` rows.
+    pub fn record_batches(
+        self,
+        rows: usize,
+    ) -> futures::stream::BoxStream<'a, Result<arrow::record_batch::RecordBatch, String>> {
+        columnar::record_batches(self, rows)
+    }
+}
+
+///
+/// the records of a collection from `start`, then the records inserted afterwards as they arrive.
+/// the live records carry the token to resume after them, so that a consumer warmed up on the history
+/// continues without a gap or a duplicate.
+pub async fn tail<T: DeserializeOwned>(
+    database: &dyn Store,
+    collection: &str,
+    start: Option<f64>,
+    resume: Option<&ResumeToken>,
+) -> Result<BoxStream<'static, Result<Tailed<T>, String>>, String> {
+    Ok(database
+        .tail(collection, start, resume)
+        .await?
+        .map(|e| {
+            e.and_then(|tailed| {
+                Ok(Tailed {
+                    record: from_value(tailed.record)?,
+                    resume: tailed.resume,
+                })
+            })
+        })
+        .boxed())
+}
+
 impl<T> Unpin for DatabaseStream<'_, T> where T: Generater {}
 
 impl<T> Stream for DatabaseStream<'_, T>
@@ -0,0 +1,360 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use super::analytics::{Analytics, AnalyticsConfig};
+use super::backtest::{BacktestConfig, Backtester, MarketEvent, Strategy};
+use super::simulation::Simulation;
+use super::*;
+
+///
+/// the values of the tunables of a strategy by their names.
+pub type Parameters = BTreeMap<String, f64>;
+
+///
+/// the values a parameter is searched over.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Space {
+    Values(Vec<f64>),
+    ///
+    /// from `min` to `max` by `step`. a random search draws between them, rounded to the step.
+    Range {
+        min: f64,
+        max: f64,
+        step: f64,
+    },
+}
+
+impl Space {
+    pub fn grid(&self) -> Result<Vec<f64>, String> {
+        match self {
+            Space::Values(values) if !values.is_empty() => Ok(values.clone()),
+            Space::Range { min, max, step } if *step > 0.0 && min <= max => {
+                let count = ((max - min) / step + 1e-9).floor() as usize;
+                Ok((0..=count).map(|i| min + i as f64 * step).collect())
+            }
+            _ => Err(error_message!("invalid space! {:?}", self)),
+        }
+    }
+
+    fn sample(&self, rng: &mut StdRng) -> f64 {
+        match self {
+            Space::Values(values) => values[rng.gen_range(0..values.len())],
+            Space::Range { min, max, step } => {
+                let value = rng.gen_range(*min..=*max);
+                (min + ((value - min) / step).round() * step).min(*max)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Search {
+    ///
+    /// every combination of the values
+    Grid,
+    ///
+    /// `runs` combinations drawn with `seed`
+    Random { runs: usize, seed: u64 },
+}
+
+///
+/// the parameters to search and how.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SearchSpace {
+    pub parameters: BTreeMap<String, Space>,
+    pub search: Search,
+}
+
+impl SearchSpace {
+    pub fn candidates(&self) -> Result<Vec<Parameters>, String> {
+        let mut grids = BTreeMap::new();
+        for (name, space) in &self.parameters {
+            grids.insert(name, space.grid()?);
+        }
+        match &self.search {
+            Search::Grid => {
+                Ok(grids
+                    .iter()
+                    .fold(vec![Parameters::new()], |candidates, (name, values)| {
+                        candidates
+                            .iter()
+                            .flat_map(|candidate| {
+                                values.iter().map(move |value| {
+                                    let mut candidate = candidate.clone();
+                                    candidate.insert(String::from(name.as_str()), *value);
+                                    candidate
+                                })
+                            })
+                            .collect()
+                    }))
+            }
+            Search::Random { runs, seed } => {
+                let mut rng = StdRng::seed_from_u64(*seed);
+                Ok((0..*runs)
+                    .map(|_| {
+                        self.parameters
+                            .iter()
+                            .map(|(name, space)| (name.clone(), space.sample(&mut rng)))
+                            .collect()
+                    })
+                    .collect())
+            }
+        }
+    }
+}
+
+///
+/// the statistic the runs are ranked by, the higher the better.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Objective {
+    TotalPnl,
+    Sharpe,
+    Sortino,
+}
+
+impl Objective {
+    pub fn score(&self, analytics: &Analytics) -> f64 {
+        match self {
+            Objective::TotalPnl => analytics.total_pnl,
+            Objective::Sharpe => analytics.sharpe,
+            Objective::Sortino => analytics.sortino,
+        }
+    }
+}
+
+///
+/// the best parameters of each `train` seconds are tested on the `test` seconds after them,
+/// then the windows move by `test` seconds.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WalkForward {
+    pub train: f64,
+    pub test: f64,
+}
+
+impl WalkForward {
+    ///
+    /// (train start, test start, test end) of the windows which fit from `start` to `end`.
+    pub fn windows(&self, start: f64, end: f64) -> Result<Vec<(f64, f64, f64)>, String> {
+        if self.train <= 0.0 || self.test <= 0.0 {
+            return Err(error_message!("invalid walk forward! {:?}", self));
+        }
+        let mut windows = Vec::new();
+        let mut from = start;
+        while from + self.train + self.test <= end {
+            windows.push((from, from + self.train, from + self.train + self.test));
+            from += self.test;
+        }
+        Ok(windows)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SweepConfig {
+    pub backtest: BacktestConfig,
+    pub analytics: AnalyticsConfig,
+    pub objective: Objective,
+    pub walk_forward: Option<WalkForward>,
+}
+
+impl Default for SweepConfig {
+    fn default() -> Self {
+        Self {
+            backtest: BacktestConfig::default(),
+            analytics: AnalyticsConfig::default(),
+            objective: Objective::Sharpe,
+            walk_forward: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    ///
+    /// the whole period without walk forward
+    Full,
+    Train,
+    ///
+    /// the best parameters of the train period, out of the sample
+    Test,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RunResult {
+    ///
+    /// from 1 among the runs of the same window and phase
+    pub rank: usize,
+    pub phase: Phase,
+    pub window: usize,
+    pub start: f64,
+    pub end: f64,
+    pub parameters: Parameters,
+    pub score: f64,
+    pub analytics: Analytics,
+}
+
+///
+/// backtest the candidates on the events in parallel, ranked by the objective.
+/// with walk forward, each window has its train runs followed by its test run.
+pub fn run<S, F, G>(
+    events: &[MarketEvent],
+    candidates: &[Parameters],
+    config: &SweepConfig,
+    new_strategy: F,
+    new_simulation: G,
+) -> Result<Vec<RunResult>, String>
+where
+    S: Strategy,
+    F: Fn(&Parameters) -> Result<S, String> + Sync,
+    G: Fn() -> Simulation + Sync,
+{
+    let (start, end) = match (events.first(), events.last()) {
+        (Some(first), Some(last)) => (first.received_at(), last.received_at()),
+        _ => return Err(error_message!("no events!")),
+    };
+    if candidates.is_empty() {
+        return Err(error_message!("no candidates!"));
+    }
+    let run_all = |phase: Phase, window: usize, from: f64, to: f64, candidates: &[Parameters]| {
+        let events = between(events, from, to);
+        let mut results = candidates
+            .par_iter()
+            .map(|parameters| {
+                let mut strategy = new_strategy(parameters)?;
+                let mut backtester = Backtester::new(config.backtest.clone(), new_simulation());
+                backtester.run(&mut strategy, events)?;
+                let analytics = Analytics::calculate(backtester.report(), &config.analytics)?;
+                Ok(RunResult {
+                    rank: 0,
+                    phase,
+                    window,
+                    start: from,
+                    end: to,
+                    parameters: parameters.clone(),
+                    score: config.objective.score(&analytics),
+                    analytics,
+                })
+            })
+            .collect::<Result<Vec<RunResult>, String>>()?;
+        rank(&mut results);
+        Ok::<_, String>(results)
+    };
+
+    let walk_forward = match &config.walk_forward {
+        Some(content) => content,
+        None => return run_all(Phase::Full, 0, start, end, candidates),
+    };
+    let windows = walk_forward.windows(start, end)?;
+    if windows.is_empty() {
+        return Err(error_message!(
+            "the events are shorter than a window! {:?}",
+            walk_forward
+        ));
+    }
+    let mut results = Vec::new();
+    for (window, (train_start, test_start, test_end)) in windows.into_iter().enumerate() {
+        let train = run_all(Phase::Train, window, train_start, test_start, candidates)?;
+        let best = train[0].parameters.clone();
+        results.extend(train);
+        results.extend(run_all(Phase::Test, window, test_start, test_end, &[best])?);
+    }
+    Ok(results)
+}
+
+///
+/// the events from `from` until before `to`, and the last one at the end of the whole period.
+fn between(events: &[MarketEvent], from: f64, to: f64) -> &[MarketEvent] {
+    let start = events.partition_point(|e| e.received_at() < from);
+    let end = if to >= events[events.len() - 1].received_at() {
+        events.len()
+    } else {
+        events.partition_point(|e| e.received_at() < to)
+    };
+    &events[start..end.max(start)]
+}
+
+fn rank(results: &mut [RunResult]) {
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    for (i, result) in results.iter_mut().enumerate() {
+        result.rank = i + 1;
+    }
+}
+
+///
+/// the results as csv, one row for each run with its parameters and the main statistics.
+pub fn to_csv(results: &[RunResult]) -> String {
+    let names = results
+        .iter()
+        .flat_map(|e| e.parameters.keys().cloned())
+        .collect::<std::collections::BTreeSet<String>>();
+    let mut csv = String::from("rank,phase,window,start,end");
+    for name in &names {
+        let _ = write!(csv, ",{}", name);
+    }
+    csv.push_str(",score,total_pnl,realized_pnl,fees,sharpe,sortino,max_drawdown,max_drawdown_duration,turnover,fill_rate,adverse_selection,mean_inventory\n");
+    for result in results {
+        let _ = write!(
+            csv,
+            "{},{:?},{},{},{}",
+            result.rank, result.phase, result.window, result.start, result.end
+        );
+        for name in &names {
+            match result.parameters.get(name) {
+                Some(value) => {
+                    let _ = write!(csv, ",{}", value);
+                }
+                None => csv.push(','),
+            }
+        }
+        let analytics = &result.analytics;
+        let _ = writeln!(
+            csv,
+            ",{},{},{},{},{},{},{},{},{},{},{},{}",
+            result.score,
+            analytics.total_pnl,
+            analytics.realized_pnl,
+            analytics.fees,
+            analytics.sharpe,
+            analytics.sortino,
+            analytics.max_drawdown,
+            analytics.max_drawdown_duration,
+            analytics.turnover,
+            analytics.fill_rate,
+            analytics.adverse_selection,
+            analytics.inventory.mean
+        );
+    }
+    csv
+}
+
+///
+/// write the table to `<path>.csv` and the runs with all of their analytics to `<path>.json`.
+pub async fn write_results(path: &str, results: &[RunResult]) -> Result<(), String> {
+    if let Err(result) = tokio::fs::write(format!("{}.csv", path), to_csv(results)).await {
+        return Err(error_message!("failed to write!\ndetails : {:?}", result));
+    }
+    let json = match serde_json::to_string_pretty(results) {
+        Ok(result) => result,
+        Err(result) => {
+            return Err(error_message!(
+                "failed to serialize!\ndetails : {:?}",
+                result
+            ))
+        }
+    };
+    if let Err(result) = tokio::fs::write(format!("{}.json", path), json).await {
+        return Err(error_message!("failed to write!\ndetails : {:?}", result));
+    }
+    Ok(())
+}
@@ -132,54 +132,25 @@ async fn check_ticker_deliver() {
         }
     };
 
-    let start_time = Utc.ymd(2021, 8, 1).and_hms(0, 0, 0);
-    let end_time = Utc.ymd(2021, 8, 1).and_hms(0, 0, 1);
+    let start_time = Utc.with_ymd_and_hms(2021, 8, 1, 0, 0, 0).unwrap();
+    let end_time = Utc.with_ymd_and_hms(2021, 8, 1, 0, 0, 1).unwrap();
 
-    let query = create_duration_query(
-        common_constants::DATABASE_COLLECTION_TICKER,
-        &start_time,
-        &end_time,
-    )
-    .expect("failed to create query");
-    let options =
-        create_find_option_timeline(common_constants::DATABASE_COLLECTION_TICKER, 1, None)
-            .expect("failed to create options!");
+    let mut ticker_stream = Query::<data::Ticker>::between(&start_time, &end_time)
+        .request(&database)
+        .await
+        .expect("failed to get");
 
-    let mut ticker_stream = stream::DatabaseStream::<data::Ticker>::request(
-        &database,
-        None,
-        query.clone(),
-        options.clone(),
-    )
-    .await
-    .expect("failed to get");
-
-    let mut executions_stream =
-        stream::DatabaseStream::<data::Execution>::request(&database, None, query, options)
-            .await
-            .expect("failed to get");
+    let mut executions_stream = Query::<data::Execution>::between(&start_time, &end_time)
+        .request(&database)
+        .await
+        .expect("failed to get");
 
-    let query = create_duration_query(
-        common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY,
-        &start_time,
-        &end_time,
-    )
-    .expect("failed to create query");
-    let options = create_find_option_timeline(
-        common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY,
-        1,
-        None,
-    )
-    .expect("failed to create options!");
-
-    let mut order_book_stream = stream::DatabaseStream::<data::OrderBook>::request(
-        &database,
-        Some(common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY),
-        query,
-        options,
-    )
-    .await
-    .expect("failed to get");
+    let mut order_book_stream = Query::<data::OrderBook>::between(&start_time, &end_time)
+        .collection(common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY)
+        .expect("failed to choose the collection!")
+        .request(&database)
+        .await
+        .expect("failed to get");
 
     while let Some(v) = ticker_stream.next().await {
         println!("GOT = {:?}", v);
@@ -204,3 +175,2217 @@ fn check_rayon() {
     let vec = vec![1, 2, 3, 4, 5];
     assert_eq!(Some(&3), vec.par_iter().find_first(|&&x| x > 2));
 }
+
+#[tokio::test]
+async fn check_sqlite_store() {
+    let store = open_store("sqlite://:memory:")
+        .await
+        .expect("failed to open!");
+    let executions = (0..5)
+        .map(|i| data::Execution {
+            received_at: 100.0 + i as f64,
+            timestamp: 100.0 + i as f64,
+            created_at: i64::from(100 + i),
+            price: 5_000_000 + i,
+            quantity: 0.01,
+            taker_side: String::from("buy"),
+            id: Some(i as u64),
+        })
+        .collect::<Vec<data::Execution>>();
+    assert_eq!(
+        store
+            .insert_records(
+                common_constants::DATABASE_COLLECTION_EXECUTIONS,
+                &executions
+            )
+            .await,
+        Ok(5)
+    );
+
+    let timeline = Timeline {
+        start: Some(101.0),
+        end: Some(103.0),
+        order: 1,
+        limit: None,
+    };
+    let found = store
+        .query_records::<data::Execution>(
+            common_constants::DATABASE_COLLECTION_EXECUTIONS,
+            &timeline,
+        )
+        .await
+        .expect("failed to query!");
+    assert_eq!(
+        found.iter().map(|e| e.price).collect::<Vec<i32>>(),
+        vec![5_000_001, 5_000_002, 5_000_003]
+    );
+    assert_eq!(
+        store
+            .count(common_constants::DATABASE_COLLECTION_EXECUTIONS, &timeline)
+            .await,
+        Ok(3)
+    );
+
+    let streamed = store
+        .stream_records::<data::Execution>(
+            common_constants::DATABASE_COLLECTION_EXECUTIONS,
+            &timeline.order(-1).limit(Some(2)),
+        )
+        .await
+        .expect("failed to stream!")
+        .map(|e| e.expect("failed to get!").price)
+        .collect::<Vec<i32>>()
+        .await;
+    assert_eq!(streamed, vec![5_000_003, 5_000_002]);
+
+    let latest = store
+        .latest_records::<data::Execution>(common_constants::DATABASE_COLLECTION_EXECUTIONS, 1)
+        .await
+        .expect("failed to get the latest!");
+    assert_eq!(latest[0].price, 5_000_004);
+
+    assert!(store
+        .insert_records(
+            common_constants::DATABASE_COLLECTION_TICKER,
+            &executions[..0]
+        )
+        .await
+        .is_ok());
+    assert!(store.insert_records("unknown", &executions).await.is_err());
+    assert!(store.ping().await.is_ok());
+}
+
+#[tokio::test]
+async fn check_sqlite_streams() {
+    let store = open_store("sqlite://:memory:")
+        .await
+        .expect("failed to open!");
+    let rows = 3 * sqlite::STREAM_PAGE as i32;
+    let executions = (0..rows)
+        .map(|i| data::Execution {
+            received_at: i as f64,
+            // pairs of the same time are ordered by their rowids.
+            timestamp: (i / 2) as f64,
+            created_at: i64::from(i),
+            price: 5_000_000 + i,
+            quantity: 0.01,
+            taker_side: String::from("buy"),
+            id: Some(i as u64),
+        })
+        .collect::<Vec<data::Execution>>();
+    store
+        .insert_records(
+            common_constants::DATABASE_COLLECTION_EXECUTIONS,
+            &executions,
+        )
+        .await
+        .expect("failed to insert!");
+
+    // both streams and the requests between their records go on together.
+    let read = async {
+        let mut ascending = store
+            .stream_records::<data::Execution>(
+                common_constants::DATABASE_COLLECTION_EXECUTIONS,
+                &Timeline::all(),
+            )
+            .await
+            .expect("failed to stream!");
+        let mut descending = store
+            .stream_records::<data::Execution>(
+                common_constants::DATABASE_COLLECTION_EXECUTIONS,
+                &Timeline::all().order(-1),
+            )
+            .await
+            .expect("failed to stream!");
+        let (mut up, mut down) = (Vec::new(), Vec::new());
+        for i in 0..rows {
+            up.push(
+                ascending
+                    .next()
+                    .await
+                    .expect("too short!")
+                    .expect("failed to get!")
+                    .price,
+            );
+            down.push(
+                descending
+                    .next()
+                    .await
+                    .expect("too short!")
+                    .expect("failed to get!")
+                    .price,
+            );
+            if i % sqlite::STREAM_PAGE as i32 == 0 {
+                store
+                    .count(
+                        common_constants::DATABASE_COLLECTION_EXECUTIONS,
+                        &Timeline::all(),
+                    )
+                    .await
+                    .expect("failed to count!");
+            }
+        }
+        assert!(ascending.next().await.is_none());
+        assert!(descending.next().await.is_none());
+        (up, down)
+    };
+    let (up, down) = tokio::time::timeout(std::time::Duration::from_secs(10), read)
+        .await
+        .expect("the streams blocked each other!");
+    assert_eq!(up, (0..rows).map(|i| 5_000_000 + i).collect::<Vec<i32>>());
+    assert_eq!(
+        down,
+        (0..rows).rev().map(|i| 5_000_000 + i).collect::<Vec<i32>>()
+    );
+
+    let limited = store
+        .stream_records::<data::Execution>(
+            common_constants::DATABASE_COLLECTION_EXECUTIONS,
+            &Timeline::all().limit(Some(sqlite::STREAM_PAGE + 1)),
+        )
+        .await
+        .expect("failed to stream!")
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(limited.len() as i64, sqlite::STREAM_PAGE + 1);
+}
+
+#[tokio::test]
+async fn check_sqlite_schema() {
+    let store = open_store("sqlite://:memory:")
+        .await
+        .expect("failed to open!");
+
+    // every index is created on open, so that the inserts are deduplicated at once.
+    let reports = store.check_schema(false).await.expect("failed to check!");
+    assert_eq!(reports.len(), 11);
+    assert!(reports.iter().all(|e| e.is_ok() && e.created.is_empty()));
+    let ticker = |received_at: f64| data::Ticker {
+        received_at,
+        timestamp: 100.0,
+        last_traded_price: 5_000_000,
+        last_traded_quantity: 0.01,
+        last_price_24h: 5_000_000,
+        average_price_24h: 5_000_000.0,
+        volume_24h: 1.0,
+        market_ask: 5_000_100,
+        market_bid: 5_000_000,
+        low_market_price_24h: 4_900_000,
+        high_market_price_24h: 5_100_000,
+    };
+    let collection = common_constants::DATABASE_COLLECTION_TICKER;
+    // the updates sharing a timestamp of the exchange are told apart by when they were received.
+    assert_eq!(
+        store
+            .insert_records(collection, &[ticker(100.5), ticker(100.7)])
+            .await,
+        Ok(2)
+    );
+    assert_eq!(
+        store.insert_records(collection, &[ticker(100.7)]).await,
+        Ok(0)
+    );
+
+    // a file written before the keys were indexed, with a unique time index of the ticker.
+    let directory = std::env::temp_dir().join("check_sqlite_schema");
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).expect("failed to create!");
+    let path = directory.join("market.sqlite3");
+    let path = path.to_str().expect("invalid path!");
+    let books = common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY;
+    rusqlite::Connection::open(path)
+        .and_then(|connection| {
+            connection.execute_batch(&format!(
+                "CREATE TABLE {0} (time REAL NOT NULL, record TEXT NOT NULL);
+                 CREATE UNIQUE INDEX {0}_time ON {0} (time);
+                 CREATE TABLE {1} (time REAL NOT NULL, record TEXT NOT NULL);
+                 CREATE INDEX {1}_time ON {1} (time);
+                 INSERT INTO {1} VALUES (100.0, '{{\"received_at\":100.0,\"orders\":[]}}');
+                 INSERT INTO {1} VALUES (100.0, '{{\"received_at\":100.0,\"orders\":[]}}');",
+                collection, books
+            ))
+        })
+        .expect("failed to write!");
+    let store = open_store(&format!("sqlite://{}", path))
+        .await
+        .expect("failed to open!");
+    let reports = store.check_schema(false).await.expect("failed to check!");
+    let report = |reports: &[SchemaReport], collection: &str| {
+        reports
+            .iter()
+            .find(|e| e.collection == collection)
+            .cloned()
+            .expect("should contain!")
+    };
+    assert!(report(&reports, collection).is_ok());
+    assert_eq!(
+        report(&reports, books).problems,
+        vec![String::from("index received_at_1 has to be unique : true.")]
+    );
+    assert_eq!(reports.iter().filter(|e| !e.is_ok()).count(), 1);
+
+    // the collection refuses inserts until its duplicates are removed.
+    let book = data::OrderBook {
+        received_at: 100.0,
+        orders: Vec::new(),
+    };
+    assert!(store
+        .insert_records(books, std::slice::from_ref(&book))
+        .await
+        .is_err());
+    let reports = store.check_schema(true).await.expect("failed to repair!");
+    assert_eq!(
+        report(&reports, books).problems,
+        vec![String::from(
+            "1 records share a key of index received_at_1. remove them with dedupe first."
+        )]
+    );
+    assert_eq!(store.dedupe(books, &Timeline::all(), false).await, Ok(1));
+    let reports = store.check_schema(true).await.expect("failed to repair!");
+    assert!(reports.iter().all(|e| e.is_ok()));
+    assert_eq!(report(&reports, books).created, vec!["received_at_1"]);
+    assert_eq!(store.insert_records(books, &[book]).await, Ok(0));
+    let _ = std::fs::remove_dir_all(&directory);
+}
+
+#[tokio::test]
+async fn check_sqlite_dedupe() {
+    let store = open_store("sqlite://:memory:")
+        .await
+        .expect("failed to open!");
+    let executions = |id: Option<u64>| {
+        (0..5)
+            .map(|i| data::Execution {
+                received_at: 100.0 + i as f64,
+                timestamp: 100.0 + i as f64,
+                created_at: i64::from(100 + i),
+                price: 5_000_000 + i,
+                quantity: 0.01,
+                taker_side: String::from("buy"),
+                id: id.map(|e| e + i as u64),
+            })
+            .collect::<Vec<data::Execution>>()
+    };
+    let collection = common_constants::DATABASE_COLLECTION_EXECUTIONS;
+
+    // executions without an id are duplicated like before.
+    for _ in 0..2 {
+        assert_eq!(
+            store.insert_records(collection, &executions(None)).await,
+            Ok(5)
+        );
+    }
+    // the legacy executions are not keyed.
+    let reports = store.check_schema(true).await.expect("failed to repair!");
+    assert!(reports.iter().all(|e| e.is_ok()));
+
+    // the second insertion is skipped by the execution id.
+    assert_eq!(
+        store.insert_records(collection, &executions(Some(1))).await,
+        Ok(5)
+    );
+    assert_eq!(
+        store.insert_records(collection, &executions(Some(1))).await,
+        Ok(0)
+    );
+    assert_eq!(store.count(collection, &Timeline::all()).await, Ok(15));
+
+    // only the legacy duplicates in the range are removed.
+    let timeline = Timeline {
+        start: Some(100.0),
+        end: Some(102.0),
+        order: 1,
+        limit: None,
+    };
+    assert_eq!(store.dedupe(collection, &timeline, true).await, Ok(3));
+    assert_eq!(store.count(collection, &Timeline::all()).await, Ok(15));
+    assert_eq!(store.dedupe(collection, &timeline, false).await, Ok(3));
+    assert_eq!(store.count(collection, &Timeline::all()).await, Ok(12));
+    assert_eq!(
+        store.dedupe(collection, &Timeline::all(), false).await,
+        Ok(2)
+    );
+    assert_eq!(store.count(collection, &Timeline::all()).await, Ok(10));
+}
+
+#[tokio::test]
+async fn check_sqlite_candles() {
+    let store = open_store("sqlite://:memory:")
+        .await
+        .expect("failed to open!");
+    let execution = |i: u64, timestamp: f64, price: i32, taker_side: &str| data::Execution {
+        received_at: timestamp,
+        timestamp,
+        created_at: timestamp as i64,
+        price,
+        quantity: 0.5,
+        taker_side: String::from(taker_side),
+        id: Some(i),
+    };
+    let collection = common_constants::DATABASE_COLLECTION_EXECUTIONS;
+    let executions = vec![
+        execution(1, 60.2, 100, "buy"),
+        execution(2, 60.7, 300, "sell"),
+        execution(3, 61.0, 200, "buy"),
+        execution(4, 170.0, 400, "sell"),
+    ];
+    store
+        .insert_records(collection, &executions)
+        .await
+        .expect("failed to insert!");
+
+    // 1s : 60, 61, 170 / 1m : 60, 120 / 5m, 1h, 1d : 0
+    assert_eq!(candles::update(store.as_ref(), 60.2, 170.0).await, Ok(8));
+    let minutes = candles::query(
+        store.as_ref(),
+        candles::Resolution::Minute,
+        &Timeline::all(),
+    )
+    .await
+    .expect("failed to query!");
+    assert_eq!(
+        minutes[0],
+        data::Candle {
+            timestamp: 60.0,
+            open: 100,
+            high: 300,
+            low: 100,
+            close: 200,
+            volume: 1.5,
+            vwap: 200.0,
+            trades: 3,
+            buy_volume: 1.0,
+            sell_volume: 0.5,
+        }
+    );
+    assert_eq!(minutes[1].timestamp, 120.0);
+
+    // an execution arriving later updates the open periods in place.
+    store
+        .insert_records(collection, &[execution(5, 61.5, 50, "sell")])
+        .await
+        .expect("failed to insert!");
+    assert_eq!(candles::update(store.as_ref(), 61.5, 61.5).await, Ok(5));
+    let days = candles::latest(store.as_ref(), candles::Resolution::Day, 10)
+        .await
+        .expect("failed to query!");
+    assert_eq!(days.len(), 1);
+    assert_eq!(
+        (days[0].open, days[0].low, days[0].close, days[0].trades),
+        (100, 50, 400, 5)
+    );
+    assert_eq!(
+        store
+            .count(candles::Resolution::Second.collection(), &Timeline::all())
+            .await,
+        Ok(3)
+    );
+
+    // rebuilding gives the same candles.
+    assert_eq!(
+        candles::rebuild(store.as_ref(), &Timeline::all()).await,
+        Ok(8)
+    );
+    assert_eq!(
+        candles::latest(store.as_ref(), candles::Resolution::Day, 10).await,
+        Ok(days)
+    );
+}
+
+#[tokio::test]
+async fn check_sqlite_retention() {
+    use order_book::BookRecord;
+
+    let store = open_store("sqlite://:memory:")
+        .await
+        .expect("failed to open!");
+    let day = 86_400.0;
+    let records = |collection: &str| {
+        [1.0, 15.0, 29.0]
+            .iter()
+            .flat_map(|e| {
+                [0.0, 0.2, 0.5, 1.0, 1.7].iter().map(move |offset| {
+                    let time = e * day + offset;
+                    if collection == common_constants::DATABASE_COLLECTION_EXECUTIONS {
+                        serde_json::json!({ "timestamp": time, "price": 5_000_000 })
+                    } else {
+                        serde_json::json!({ "received_at": time, "orders": [] })
+                    }
+                })
+            })
+            .collect::<Vec<serde_json::Value>>()
+    };
+    let settings = common::Retention {
+        collections: vec![
+            (
+                String::from(common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY),
+                common::RetentionPolicy {
+                    full_days: Some(7.0),
+                    downsample: 1.0,
+                    delete_days: Some(20.0),
+                },
+            ),
+            (
+                String::from(common_constants::DATABASE_COLLECTION_EXECUTIONS),
+                common::RetentionPolicy {
+                    full_days: Some(7.0),
+                    downsample: 0.0,
+                    delete_days: None,
+                },
+            ),
+        ]
+        .into_iter()
+        .collect(),
+        ..Default::default()
+    };
+    let storage = common::OrderBookStorage::default();
+    for collection in settings.collections.keys() {
+        store
+            .insert(collection, &records(collection))
+            .await
+            .expect("failed to insert!");
+    }
+
+    // the first day is deleted, one record per second of the 15th day is kept
+    // and the executions are kept for the last 7 days only.
+    let expected = vec![
+        RetentionReport {
+            collection: String::from(common_constants::DATABASE_COLLECTION_EXECUTIONS),
+            downsampled: 10,
+            deleted: 0,
+        },
+        RetentionReport {
+            collection: String::from(common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY),
+            downsampled: 3,
+            deleted: 5,
+        },
+    ];
+    let now = 30.0 * day;
+    assert_eq!(
+        retention::enforce(store.as_ref(), &settings, &storage, now, None, true).await,
+        Ok(expected.clone())
+    );
+    assert_eq!(
+        store
+            .count(
+                common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY,
+                &Timeline::all()
+            )
+            .await,
+        Ok(15)
+    );
+    assert_eq!(
+        retention::enforce(store.as_ref(), &settings, &storage, now, None, false).await,
+        Ok(expected)
+    );
+    let kept = store
+        .query(
+            common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY,
+            &Timeline::all(),
+        )
+        .await
+        .expect("failed to query!")
+        .iter()
+        .filter_map(|e| e.get("received_at").and_then(|e| e.as_f64()))
+        .collect::<Vec<f64>>();
+    assert_eq!(
+        kept,
+        vec![
+            15.0 * day,
+            15.0 * day + 1.0,
+            29.0 * day,
+            29.0 * day + 0.2,
+            29.0 * day + 0.5,
+            29.0 * day + 1.0,
+            29.0 * day + 1.7
+        ]
+    );
+    assert!(
+        retention::enforce(store.as_ref(), &settings, &storage, now, None, false)
+            .await
+            .expect("failed to enforce!")
+            .iter()
+            .all(|e| e.downsampled == 0 && e.deleted == 0)
+    );
+
+    // only the records which aged out since the previous enforcement are downsampled.
+    let books = common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY;
+    let late = [15.0 * day + 0.3, 23.0 * day + 0.5, 23.0 * day + 0.7]
+        .iter()
+        .map(|e| serde_json::json!({ "received_at": e, "orders": [] }))
+        .collect::<Vec<serde_json::Value>>();
+    store.insert(books, &late).await.expect("failed to insert!");
+    assert_eq!(
+        retention::enforce(
+            store.as_ref(),
+            &settings,
+            &storage,
+            now + day,
+            Some(now),
+            false
+        )
+        .await
+        .expect("failed to enforce!")
+        .iter()
+        .map(|e| e.downsampled)
+        .collect::<Vec<u64>>(),
+        vec![0, 1]
+    );
+    assert_eq!(store.count(books, &Timeline::all()).await, Ok(9));
+
+    // delete_days has to be longer than full_days.
+    let mut invalid = settings.clone();
+    for policy in invalid.collections.values_mut() {
+        policy.delete_days = Some(1.0);
+    }
+    assert!(
+        retention::enforce(store.as_ref(), &invalid, &storage, now, None, true)
+            .await
+            .is_err()
+    );
+
+    // the snapshots which the diffs are applied to are kept while the diffs are stored.
+    let store = open_store("sqlite://:memory:")
+        .await
+        .expect("failed to open!");
+    let storage = common::OrderBookStorage {
+        diffs: true,
+        snapshot_interval: 10.0,
+    };
+    let mut recorder = BookRecorder::new(BookSide::Buy, &storage);
+    let times = (0..40)
+        .map(|e| day + e as f64)
+        .chain((0..40).map(|e| 29.0 * day + e as f64));
+    for (i, received_at) in times.enumerate() {
+        let orders = vec![liquid::data::Order {
+            price: 5_000_000 + i as i32,
+            amount: 1.0,
+        }];
+        match recorder.record(data::OrderBook {
+            received_at,
+            orders,
+        }) {
+            BookRecord::Snapshot(content) => store
+                .insert_records(BookSide::Buy.snapshots(), &[content])
+                .await
+                .expect("failed to insert!"),
+            BookRecord::Diff(content) => store
+                .insert_records(BookSide::Buy.diffs(), &[content])
+                .await
+                .expect("failed to insert!"),
+        };
+    }
+    let rebuilt = order_book::book_at(store.as_ref(), 29.0 * day + 35.5)
+        .await
+        .expect("failed to rebuild!");
+    assert_eq!(rebuilt.buy[0].price, 5_000_075);
+    let policy = |full_days: Option<f64>| common::RetentionPolicy {
+        full_days,
+        downsample: 60.0,
+        delete_days: Some(20.0),
+    };
+    let settings = |full_days: Option<f64>| common::Retention {
+        collections: vec![
+            (String::from(BookSide::Buy.snapshots()), policy(full_days)),
+            (String::from(BookSide::Buy.diffs()), policy(None)),
+        ]
+        .into_iter()
+        .collect(),
+        ..Default::default()
+    };
+    assert!(retention::enforce(
+        store.as_ref(),
+        &settings(Some(0.0)),
+        &storage,
+        now,
+        None,
+        false
+    )
+    .await
+    .is_err());
+    assert!(retention::enforce(
+        store.as_ref(),
+        &settings(Some(0.0)),
+        &common::OrderBookStorage::default(),
+        now,
+        None,
+        true
+    )
+    .await
+    .is_ok());
+    assert_eq!(
+        retention::enforce(store.as_ref(), &settings(None), &storage, now, None, false)
+            .await
+            .expect("failed to enforce!")
+            .iter()
+            .map(|e| e.deleted)
+            .sum::<u64>(),
+        40
+    );
+    assert_eq!(
+        order_book::book_at(store.as_ref(), 29.0 * day + 35.5).await,
+        Ok(rebuilt)
+    );
+}
+
+#[tokio::test]
+async fn check_sqlite_migrations() {
+    let store = open_store("sqlite://:memory:")
+        .await
+        .expect("failed to open!");
+    let collection = common_constants::DATABASE_COLLECTION_EXECUTIONS;
+    // records written before the versioning, as the old writers did.
+    let legacy = vec![
+        serde_json::json!({ "timestamp": 1.0, "_id": "5f0c", "created_at": 1.5, "schema_version": 0 }),
+        serde_json::json!({ "timestamp": 2.0, "created_at": 2, "schema_version": 0 }),
+        serde_json::json!({ "timestamp": 3.0, "created_at": "broken", "schema_version": 0 }),
+        // created_at wrapped into a negative i32.
+        serde_json::json!({ "timestamp": 3.5, "id": 6, "created_at": -2_147_483_648_i64, "schema_version": 1 }),
+    ];
+    store
+        .insert(collection, &legacy)
+        .await
+        .expect("failed to insert!");
+    store
+        .insert(
+            collection,
+            &[serde_json::json!({ "timestamp": 4.0, "id": 7, "created_at": 4 })],
+        )
+        .await
+        .expect("failed to insert!");
+
+    // the records are upgraded while reading without being written.
+    let records = store
+        .query(collection, &Timeline::all())
+        .await
+        .expect("failed to query!");
+    assert_eq!(
+        records[0],
+        serde_json::json!({ "timestamp": 1.0, "created_at": 1, "id": null })
+    );
+    assert_eq!(
+        records[3],
+        serde_json::json!({ "timestamp": 3.5, "id": 6, "created_at": 2_147_483_648_i64 })
+    );
+    assert_eq!(
+        records[4],
+        serde_json::json!({ "timestamp": 4.0, "id": 7, "created_at": 4 })
+    );
+
+    let current = migrations::current_version(collection);
+    let status = migrations::status(store.as_ref())
+        .await
+        .expect("failed to get the status!");
+    let executions = status
+        .iter()
+        .find(|e| e.collection == collection)
+        .expect("no status!");
+    assert_eq!(executions.versions, vec![(0, 3), (1, 1), (current, 1)]);
+    assert_eq!(executions.outdated(), 4);
+
+    let expected = MigrationReport {
+        collection: String::from(collection),
+        migrated: 3,
+        failed: 1,
+    };
+    let reports = migrations::up(store.as_ref(), true)
+        .await
+        .expect("failed to migrate!");
+    assert!(reports.contains(&expected));
+    assert_eq!(
+        store.versions(collection).await,
+        Ok(vec![(0, 3), (1, 1), (current, 1)])
+    );
+
+    let reports = migrations::up(store.as_ref(), false)
+        .await
+        .expect("failed to migrate!");
+    assert!(reports.contains(&expected));
+    assert_eq!(
+        store.versions(collection).await,
+        Ok(vec![(0, 1), (current, 4)])
+    );
+
+    // only the record which cannot be upgraded is left.
+    assert_eq!(
+        store.migrate(collection, false).await,
+        Ok(MigrationReport {
+            collection: String::from(collection),
+            migrated: 0,
+            failed: 1,
+        })
+    );
+}
+
+#[tokio::test]
+async fn check_dataset() {
+    let directory = std::env::temp_dir();
+    let path = directory.join("check_dataset.zst");
+    let path = path.to_str().expect("invalid path!");
+    let collection = common_constants::DATABASE_COLLECTION_EXECUTIONS;
+    let start = Utc.timestamp_opt(0, 0).unwrap();
+    let header = DatasetHeader::new(collection, start, Utc.timestamp_opt(100, 0).unwrap());
+    let record = |time: f64| serde_json::json!({ "timestamp": time, "created_at": time as i64, "id": time as i64 });
+
+    let mut writer = DatasetWriter::create(path, &header)
+        .await
+        .expect("failed to create!")
+        .block_records(10);
+    for time in 0..95 {
+        writer
+            .push(&record(time as f64))
+            .await
+            .expect("failed to push!");
+    }
+    assert!(writer.push(&record(1.0)).await.is_err());
+    assert_eq!(writer.finish().await, Ok(95));
+
+    let reader = DatasetReader::open(path).await.expect("failed to open!");
+    assert_eq!(reader.header, header);
+    assert_eq!(reader.index.len(), 10);
+    assert_eq!(reader.len(), 95);
+    let times = |records: Vec<Result<serde_json::Value, String>>| {
+        records
+            .into_iter()
+            .map(|e| e.expect("failed to read!")["timestamp"].as_f64().unwrap())
+            .collect::<Vec<f64>>()
+    };
+    let timeline = Timeline {
+        start: Some(18.0),
+        end: Some(42.0),
+        ..Timeline::all()
+    };
+    let records = reader
+        .stream(&timeline)
+        .expect("failed to stream!")
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(
+        times(records),
+        (18..=42).map(f64::from).collect::<Vec<f64>>()
+    );
+    let records = reader
+        .stream(&timeline.order(-1).limit(Some(3)))
+        .expect("failed to stream!")
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(times(records), vec![42.0, 41.0, 40.0]);
+
+    // a json file of local is converted and upgraded while reading.
+    let source = directory.join("check_dataset.json");
+    let source = source.to_str().expect("invalid path!");
+    std::fs::write(
+        source,
+        serde_json::json!({
+            "collection": collection,
+            "downloaded_at": start,
+            "start": start,
+            "end": start,
+            "size": 2,
+            "executions": [
+                { "timestamp": 2.0, "created_at": 2.5, "_id": "5f0c", "received_at": 2.0,
+                  "price": 5_000_000, "quantity": 0.1, "taker_side": "buy" },
+                { "timestamp": 1.0, "created_at": 1, "received_at": 1.0,
+                  "price": 5_000_000, "quantity": 0.1, "taker_side": "sell" },
+            ],
+        })
+        .to_string(),
+    )
+    .expect("failed to write!");
+    assert_eq!(dataset::convert_json(source, path).await, Ok(2));
+    let records = DatasetReader::open(path)
+        .await
+        .expect("failed to open!")
+        .stream_records::<data::Execution>(&Timeline::all())
+        .expect("failed to stream!")
+        .collect::<Vec<_>>()
+        .await;
+    let created_at = records
+        .into_iter()
+        .map(|e| e.expect("failed to read!").created_at)
+        .collect::<Vec<i64>>();
+    assert_eq!(created_at, vec![1, 2]);
+}
+
+#[tokio::test]
+async fn check_parquet() {
+    let directory = std::env::temp_dir();
+    let path = directory.join("check_parquet.parquet");
+    let path = path.to_str().expect("invalid path!");
+    let options = columnar::ParquetOptions {
+        row_group_rows: 1000,
+        compression: columnar::compression_from_string("zstd").expect("invalid compression!"),
+    };
+
+    let executions = (0..2500)
+        .map(|i| data::Execution {
+            received_at: i as f64,
+            timestamp: i as f64,
+            created_at: i64::from(i),
+            price: 5_000_000 + i,
+            quantity: 0.01,
+            taker_side: String::from(if i % 2 == 0 { "buy" } else { "sell" }),
+            id: if i < 10 { None } else { Some(i as u64) },
+        })
+        .collect::<Vec<data::Execution>>();
+    let written = columnar::write_parquet(
+        path,
+        futures::stream::iter(executions.clone().into_iter().map(Ok)),
+        &options,
+    )
+    .await;
+    assert_eq!(written, Ok(2500));
+    let read = columnar::read_parquet::<data::Execution>(path)
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<Vec<data::Execution>, String>>()
+        .expect("failed to read!");
+    assert_eq!(
+        serde_json::to_value(&read).unwrap(),
+        serde_json::to_value(&executions).unwrap()
+    );
+
+    // the files written with created_at as i32 read back as the u32 of the exchange.
+    let mut columns = <data::Execution as columnar::Columnar>::to_batch(&executions[..2])
+        .expect("failed to build!")
+        .columns()
+        .to_vec();
+    columns[2] = std::sync::Arc::new(arrow::array::Int32Array::from(vec![1, -1]));
+    let schema = arrow::datatypes::Schema::new(
+        <data::Execution as columnar::Columnar>::schema()
+            .fields()
+            .iter()
+            .map(|e| match e.name().as_str() {
+                "created_at" => arrow::datatypes::Field::new(
+                    "created_at",
+                    arrow::datatypes::DataType::Int32,
+                    false,
+                ),
+                _ => e.as_ref().clone(),
+            })
+            .collect::<Vec<_>>(),
+    );
+    let batch = arrow::record_batch::RecordBatch::try_new(std::sync::Arc::new(schema), columns)
+        .expect("failed to build!");
+    let read =
+        <data::Execution as columnar::Columnar>::from_batch(&batch).expect("failed to read!");
+    assert_eq!(
+        read.iter().map(|e| e.created_at).collect::<Vec<i64>>(),
+        vec![1, 4_294_967_295]
+    );
+
+    // the long layout has more rows than a batch and an order book crosses the batches.
+    let order_books = (0..3000)
+        .map(|i| data::OrderBook {
+            received_at: i as f64,
+            orders: (0..3)
+                .map(|level| liquid::data::Order {
+                    price: 5_000_000 + level,
+                    amount: 0.1 * level as f64,
+                })
+                .collect(),
+        })
+        .collect::<Vec<data::OrderBook>>();
+    let store = open_store("sqlite://:memory:")
+        .await
+        .expect("failed to open!");
+    for (levels, collection) in [
+        (false, common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY),
+        (true, common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL),
+    ] {
+        let written = if levels {
+            let levels = columnar::OrderLevel::flatten(&order_books);
+            columnar::write_parquet(
+                path,
+                futures::stream::iter(levels.into_iter().map(Ok)),
+                &options,
+            )
+            .await
+        } else {
+            columnar::write_parquet(
+                path,
+                futures::stream::iter(order_books.clone().into_iter().map(Ok)),
+                &options,
+            )
+            .await
+        };
+        assert_eq!(written, Ok(if levels { 9000 } else { 3000 }));
+        let read = columnar::read_order_books(path)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<data::OrderBook>, String>>()
+            .expect("failed to read!");
+        assert_eq!(
+            serde_json::to_value(&read).unwrap(),
+            serde_json::to_value(&order_books).unwrap()
+        );
+        assert_eq!(
+            columnar::import_parquet(store.as_ref(), collection, path).await,
+            Ok(3000)
+        );
+    }
+}
+
+#[tokio::test]
+async fn check_order_book_diffs() {
+    use liquid::data::Order;
+    use order_book::{BookRecord, BookSide};
+
+    let ladder = |levels: &[(i32, f64)]| {
+        levels
+            .iter()
+            .map(|&(price, amount)| Order { price, amount })
+            .collect::<Vec<Order>>()
+    };
+    let buy = [
+        ladder(&[(100, 1.0), (99, 2.0), (98, 3.0)]),
+        ladder(&[(101, 0.5), (100, 1.0), (98, 4.0)]),
+        ladder(&[(101, 0.5), (97, 1.0)]),
+        // out of order, so that only the whole ladder reproduces it.
+        ladder(&[(97, 1.0), (101, 0.5)]),
+        ladder(&[(102, 1.0), (101, 0.5)]),
+    ];
+    let sell = [
+        ladder(&[(103, 1.0), (104, 2.0)]),
+        ladder(&[(103, 1.5), (104, 2.0)]),
+    ];
+
+    // a diff rebuilds the ladder exactly.
+    for pair in buy.windows(2) {
+        let current = data::OrderBook {
+            received_at: 1.0,
+            orders: pair[1].clone(),
+        };
+        let diff = order_book::diff(BookSide::Buy, &pair[0], &current);
+        assert_eq!(order_book::apply(BookSide::Buy, &pair[0], &diff), pair[1]);
+    }
+    let current = data::OrderBook {
+        received_at: 1.0,
+        orders: buy[2].clone(),
+    };
+    let diff = order_book::diff(BookSide::Buy, &buy[1], &current);
+    assert!(!diff.replace);
+    assert_eq!(diff.changes, ladder(&[(97, 1.0), (100, 0.0), (98, 0.0)]));
+
+    // a snapshot at the first update of every interval, diffs between them.
+    let settings = common::OrderBookStorage {
+        diffs: true,
+        snapshot_interval: 10.0,
+    };
+    let mut recorder = BookRecorder::new(BookSide::Buy, &settings);
+    let times = [1.0, 2.0, 9.0, 11.0, 12.0];
+    let records = buy
+        .iter()
+        .zip(times.iter())
+        .map(|(orders, &received_at)| {
+            recorder.record(data::OrderBook {
+                received_at,
+                orders: orders.clone(),
+            })
+        })
+        .collect::<Vec<BookRecord>>();
+    let kinds = records
+        .iter()
+        .map(|e| matches!(e, BookRecord::Snapshot(_)))
+        .collect::<Vec<bool>>();
+    assert_eq!(kinds, vec![true, false, false, true, false]);
+    let mut disabled = BookRecorder::new(BookSide::Buy, &common::OrderBookStorage::default());
+    for (orders, &received_at) in buy.iter().zip(times.iter()) {
+        let record = disabled.record(data::OrderBook {
+            received_at,
+            orders: orders.clone(),
+        });
+        assert!(matches!(record, BookRecord::Snapshot(_)));
+    }
+
+    // the books are rebuilt at any instant from what the recorders wrote.
+    let store = open_store("sqlite://:memory:")
+        .await
+        .expect("failed to open!");
+    for record in &records {
+        match record {
+            BookRecord::Snapshot(content) => store
+                .insert_records(BookSide::Buy.snapshots(), std::slice::from_ref(content))
+                .await
+                .expect("failed to insert!"),
+            BookRecord::Diff(content) => store
+                .insert_records(BookSide::Buy.diffs(), std::slice::from_ref(content))
+                .await
+                .expect("failed to insert!"),
+        };
+    }
+    let mut recorder = BookRecorder::new(BookSide::Sell, &settings);
+    for (orders, &received_at) in sell.iter().zip([1.5, 5.0].iter()) {
+        match recorder.record(data::OrderBook {
+            received_at,
+            orders: orders.clone(),
+        }) {
+            BookRecord::Snapshot(content) => store
+                .insert_records(BookSide::Sell.snapshots(), &[content])
+                .await
+                .expect("failed to insert!"),
+            BookRecord::Diff(content) => store
+                .insert_records(BookSide::Sell.diffs(), &[content])
+                .await
+                .expect("failed to insert!"),
+        };
+    }
+
+    let book = order_book::book_at(store.as_ref(), 0.5)
+        .await
+        .expect("failed to rebuild!");
+    assert_eq!(book, Book::default());
+    let book = order_book::book_at(store.as_ref(), 9.5)
+        .await
+        .expect("failed to rebuild!");
+    assert_eq!(book.received_at, 9.0);
+    assert_eq!(book.buy, buy[2]);
+    assert_eq!(book.sell, sell[1]);
+    let book = order_book::book_at(store.as_ref(), 12.0)
+        .await
+        .expect("failed to rebuild!");
+    assert_eq!(book.buy, buy[4]);
+
+    // every update after the start yields the whole book.
+    let books = order_book::book_stream(
+        store.as_ref(),
+        &Timeline {
+            start: Some(1.5),
+            end: Some(11.0),
+            ..Timeline::all()
+        },
+    )
+    .await
+    .expect("failed to stream!")
+    .map(|e| e.expect("failed to rebuild!"))
+    .collect::<Vec<Book>>()
+    .await;
+    let updates = books
+        .iter()
+        .map(|e| (e.received_at, e.buy.clone(), e.sell.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        updates,
+        vec![
+            (2.0, buy[1].clone(), sell[0].clone()),
+            (5.0, buy[1].clone(), sell[1].clone()),
+            (9.0, buy[2].clone(), sell[1].clone()),
+            (11.0, buy[3].clone(), sell[1].clone()),
+        ]
+    );
+
+    // stored snapshots are converted to the same records as the recorder writes.
+    let converted = open_store("sqlite://:memory:")
+        .await
+        .expect("failed to open!");
+    let snapshots = buy
+        .iter()
+        .zip(times.iter())
+        .map(|(orders, &received_at)| data::OrderBook {
+            received_at,
+            orders: orders.clone(),
+        })
+        .collect::<Vec<data::OrderBook>>();
+    converted
+        .insert_records(BookSide::Buy.snapshots(), &snapshots)
+        .await
+        .expect("failed to insert!");
+    let report = order_book::convert(
+        converted.as_ref(),
+        BookSide::Buy,
+        &Timeline::all(),
+        &settings,
+        true,
+    )
+    .await
+    .expect("failed to convert!");
+    assert_eq!((report.snapshots, report.diffs, report.removed), (2, 3, 3));
+    assert_eq!(
+        converted
+            .count(BookSide::Buy.diffs(), &Timeline::all())
+            .await
+            .expect("failed to count!"),
+        0
+    );
+    order_book::convert(
+        converted.as_ref(),
+        BookSide::Buy,
+        &Timeline::all(),
+        &settings,
+        false,
+    )
+    .await
+    .expect("failed to convert!");
+    for time in times {
+        assert_eq!(
+            order_book::side_at(converted.as_ref(), BookSide::Buy, time)
+                .await
+                .expect("failed to rebuild!"),
+            order_book::side_at(store.as_ref(), BookSide::Buy, time)
+                .await
+                .expect("failed to rebuild!")
+        );
+    }
+    assert_eq!(
+        converted
+            .count(BookSide::Buy.snapshots(), &Timeline::all())
+            .await
+            .expect("failed to count!"),
+        2
+    );
+
+    // thousands of records of each side are streamed and converted on one connection.
+    let rows = 3 * sqlite::STREAM_PAGE as usize;
+    let large = open_store("sqlite://:memory:")
+        .await
+        .expect("failed to open!");
+    for side in BookSide::ALL {
+        let offset = match side {
+            BookSide::Buy => 0.0,
+            BookSide::Sell => 0.5,
+        };
+        let snapshots = (0..rows)
+            .map(|i| data::OrderBook {
+                received_at: i as f64 + offset,
+                orders: ladder(&[(100 + (i % 7) as i32, 1.0), (90, i as f64)]),
+            })
+            .collect::<Vec<data::OrderBook>>();
+        large
+            .insert_records(side.snapshots(), &snapshots)
+            .await
+            .expect("failed to insert!");
+    }
+    let stream_all = || async {
+        tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            order_book::book_stream(large.as_ref(), &Timeline::all())
+                .await
+                .expect("failed to stream!")
+                .map(|e| e.expect("failed to rebuild!"))
+                .collect::<Vec<Book>>(),
+        )
+        .await
+        .expect("the streams blocked each other!")
+    };
+    let books = stream_all().await;
+    assert_eq!(books.len(), 2 * rows);
+    let last = books.last().expect("should contain!");
+    let expected = ladder(&[
+        (100 + ((rows - 1) % 7) as i32, 1.0),
+        (90, (rows - 1) as f64),
+    ]);
+    assert_eq!(last.received_at, (rows - 1) as f64 + 0.5);
+    assert_eq!((&last.buy, &last.sell), (&expected, &expected));
+
+    let report = tokio::time::timeout(
+        std::time::Duration::from_secs(10),
+        order_book::convert(
+            large.as_ref(),
+            BookSide::Buy,
+            &Timeline::all(),
+            &common::OrderBookStorage {
+                diffs: true,
+                snapshot_interval: 100.0,
+            },
+            false,
+        ),
+    )
+    .await
+    .expect("the conversion blocked the store!")
+    .expect("failed to convert!");
+    let kept = ((rows - 1) / 100 + 1) as u64;
+    assert_eq!(
+        (report.snapshots, report.diffs, report.removed),
+        (kept, rows as u64 - kept, rows as u64 - kept)
+    );
+    assert_eq!(stream_all().await, books);
+}
+
+#[tokio::test]
+async fn check_sqlite_symbols() {
+    let directory = std::env::temp_dir().join("check_sqlite_symbols");
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).expect("failed to create!");
+    let path = directory.join("market.sqlite3");
+    let url = format!(
+        "{}{}",
+        store::SQLITE_SCHEME,
+        path.to_str().expect("invalid path!")
+    );
+    let other = Symbol::new("bitflyer", "fx_btc_jpy").expect("invalid symbol!");
+    assert_eq!(
+        store::sqlite_path(path.to_str().unwrap(), &other),
+        directory
+            .join("market.FxBtcJpy_In_Bitflyer.sqlite3")
+            .to_str()
+            .unwrap()
+    );
+
+    let collection = common_constants::DATABASE_COLLECTION_EXECUTIONS;
+    let record = |time: f64| serde_json::json!({ "timestamp": time, "created_at": time as i64, "id": time as i64 });
+    // the symbols are stored apart from each other.
+    open_store_for(&url, &Symbol::default())
+        .await
+        .expect("failed to open!")
+        .insert(collection, &[record(1.0), record(2.0)])
+        .await
+        .expect("failed to insert!");
+    open_store_for(&url, &other)
+        .await
+        .expect("failed to open!")
+        .insert(collection, &[record(5.0), record(7.0), record(9.0)])
+        .await
+        .expect("failed to insert!");
+    // an unrelated file in the directory is not a symbol.
+    std::fs::write(directory.join("market.backup.sqlite3"), b"").expect("failed to write!");
+
+    let symbols = catalog::symbols(&url).await.expect("failed to list!");
+    assert_eq!(symbols, vec![other.clone(), Symbol::default()]);
+    let entries = catalog::catalog(&url, &symbols)
+        .await
+        .expect("failed to catalog!");
+    assert_eq!(
+        entries,
+        vec![
+            CatalogEntry {
+                symbol: other,
+                collection: String::from(collection),
+                records: 3,
+                first: 5.0,
+                last: 9.0,
+            },
+            CatalogEntry {
+                symbol: Symbol::default(),
+                collection: String::from(collection),
+                records: 2,
+                first: 1.0,
+                last: 2.0,
+            },
+        ]
+    );
+    let _ = std::fs::remove_dir_all(&directory);
+}
+
+#[tokio::test]
+async fn check_sqlite_tail() {
+    let store = open_store("sqlite://:memory:")
+        .await
+        .expect("failed to open!");
+    let collection = common_constants::DATABASE_COLLECTION_EXECUTIONS;
+    let record = |time: f64| {
+        serde_json::json!({
+            "received_at": time,
+            "timestamp": time,
+            "created_at": time as i64,
+            "price": 100,
+            "quantity": 0.1,
+            "taker_side": "buy",
+            "id": time as i64,
+        })
+    };
+    async fn next(tail: &mut store::TailStream) -> Tailed<serde_json::Value> {
+        tokio::time::timeout(std::time::Duration::from_secs(5), tail.next())
+            .await
+            .expect("timed out!")
+            .expect("the tail ended!")
+            .expect("failed to tail!")
+    }
+    store
+        .insert(collection, &[record(1.0), record(3.0), record(2.0)])
+        .await
+        .expect("failed to insert!");
+
+    // the history in the order of the time, then the inserts in the order of insertion.
+    let mut tail = store
+        .tail(collection, Some(2.0), None)
+        .await
+        .expect("failed to tail!");
+    assert_eq!(
+        next(&mut tail).await,
+        Tailed {
+            record: record(2.0),
+            resume: None
+        }
+    );
+    store
+        .insert(collection, &[record(0.5), record(4.0)])
+        .await
+        .expect("failed to insert!");
+    assert_eq!(next(&mut tail).await.record, record(3.0));
+    assert_eq!(next(&mut tail).await.record, record(0.5));
+    let last = next(&mut tail).await;
+    assert_eq!(last.record, record(4.0));
+    drop(tail);
+
+    // resuming skips the history and everything up to the token.
+    store
+        .insert(collection, &[record(5.0)])
+        .await
+        .expect("failed to insert!");
+    let mut tail =
+        stream::tail::<data::Execution>(store.as_ref(), collection, None, last.resume.as_ref())
+            .await
+            .expect("failed to tail!");
+    let resumed = tokio::time::timeout(std::time::Duration::from_secs(5), tail.next())
+        .await
+        .expect("timed out!")
+        .expect("the tail ended!")
+        .expect("failed to tail!");
+    assert_eq!(resumed.record.timestamp, 5.0);
+    assert!(resumed.resume.is_some());
+
+    assert!(store
+        .tail(
+            collection,
+            None,
+            Some(&ResumeToken(serde_json::json!("broken")))
+        )
+        .await
+        .is_err());
+}
+
+#[test]
+fn check_resume_token() {
+    let raw = mongodb::bson::to_vec(
+        &doc! { "token": { "_data": "8263D2A1C3000000012B022C0100296E5A1004" } },
+    )
+    .expect("failed to serialize!");
+    let token = mongodb::bson::from_slice::<store::TokenHolder>(&raw)
+        .expect("failed to deserialize!")
+        .token;
+    let resume = store::resume_token(&token).expect("failed to convert!");
+    assert_eq!(
+        resume,
+        ResumeToken(serde_json::json!({ "_data": "8263D2A1C3000000012B022C0100296E5A1004" }))
+    );
+    assert_eq!(
+        store::change_stream_token(&resume).expect("failed to convert!"),
+        token
+    );
+}
+
+#[tokio::test]
+async fn check_query() {
+    let store = open_store("sqlite://:memory:")
+        .await
+        .expect("failed to open!");
+    let books = (0..4)
+        .map(|i| data::OrderBook {
+            received_at: 100.5 + i as f64,
+            orders: Vec::new(),
+        })
+        .collect::<Vec<data::OrderBook>>();
+    store
+        .insert_records(
+            common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL,
+            &books,
+        )
+        .await
+        .expect("failed to insert!");
+
+    let start_time = Utc.timestamp_opt(101, 0).unwrap();
+    let end_time = Utc.timestamp_opt(103, 0).unwrap();
+    let query = Query::<data::OrderBook>::between(&start_time, &end_time);
+    assert_eq!(
+        query.collection_name(),
+        common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY
+    );
+    assert_eq!(query.count(store.as_ref()).await, Ok(0));
+    let query = query
+        .collection(common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL)
+        .expect("failed to choose the collection!");
+    assert_eq!(
+        query
+            .fetch(store.as_ref())
+            .await
+            .expect("failed to fetch!")
+            .iter()
+            .map(|e| e.received_at)
+            .collect::<Vec<f64>>(),
+        vec![101.5, 102.5]
+    );
+    let latest = query.clone().latest(1);
+    assert_eq!(
+        latest
+            .fetch(store.as_ref())
+            .await
+            .expect("failed to fetch!")[0]
+            .received_at,
+        102.5
+    );
+    assert_eq!(
+        latest
+            .fields(&["received_at"])
+            .values(store.as_ref())
+            .await
+            .expect("failed to get!"),
+        vec![serde_json::json!({ "received_at": 102.5 })]
+    );
+    assert!(Query::<data::OrderBook>::all()
+        .collection(common_constants::DATABASE_COLLECTION_TICKER)
+        .is_err());
+
+    // the timestamps are compared in seconds as floats on every backend.
+    assert_eq!(
+        query.filter(),
+        doc! { "received_at": { "$gte": 101.0, "$lte": 103.0 } }
+    );
+    assert_eq!(
+        Query::<data::Ticker>::since(&start_time)
+            .latest(5)
+            .fields(&["timestamp", "market_bid"])
+            .pipeline(),
+        vec![
+            doc! { "$match": { "timestamp": { "$gte": 101.0 } } },
+            doc! { "$sort": { "timestamp": -1_i64 } },
+            doc! { "$limit": 5_i64 },
+            doc! { "$project": { "_id": 0, "timestamp": 1, "market_bid": 1 } },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn check_record_types() {
+    // every collection is described by a record type, whose version the migrations reach.
+    for collection in schema::COLLECTIONS {
+        let record_type = record::record_type(collection).expect("no record type!");
+        assert_eq!(store::time_field(collection), Ok(record_type.time_field));
+        assert!(migrations::MIGRATIONS
+            .iter()
+            .filter(|e| e.collections.contains(&collection))
+            .all(|e| e.version < record_type.schema_version));
+    }
+    assert!(record::record_type("unknown").is_err());
+    assert_eq!(
+        record::collection_of::<data::OrderBook>(None),
+        Ok(common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY)
+    );
+    assert!(record::collection_of::<data::Ticker>(Some(
+        common_constants::DATABASE_COLLECTION_EXECUTIONS
+    ))
+    .is_err());
+
+    // the local files name the records by their type.
+    let directory = std::env::temp_dir().join("check_record_types");
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).expect("failed to create!");
+    let path = directory.join("candles.json");
+    let path = path.to_str().expect("invalid path!");
+    let start = Utc.timestamp_opt(60, 0).unwrap();
+    let candles = vec![data::Candle {
+        timestamp: 60.0,
+        open: 100,
+        high: 110,
+        low: 90,
+        close: 105,
+        volume: 1.5,
+        vwap: 101.0,
+        trades: 3,
+        buy_volume: 1.0,
+        sell_volume: 0.5,
+    }];
+    let local = local::LocalRecords::from_records(
+        common_constants::DATABASE_COLLECTION_CANDLES_1M,
+        start,
+        start,
+        candles.clone(),
+    )
+    .expect("failed to create!");
+    local.write(path).await.expect("failed to write!");
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(path).expect("failed to read!"))
+            .expect("failed to parse!");
+    assert_eq!(json["size"], 1);
+    assert!(json.get("candles").is_some());
+    assert_eq!(
+        local::LocalRecords::<data::Candle>::read(path)
+            .await
+            .expect("failed to read!")
+            .records,
+        candles
+    );
+    assert!(local::LocalExecutions::read(path).await.is_err());
+    assert!(local::LocalRecords::from_records(
+        common_constants::DATABASE_COLLECTION_TICKER,
+        start,
+        start,
+        candles
+    )
+    .is_err());
+
+    let target = directory.join("candles.zst");
+    assert_eq!(
+        dataset::convert_json(path, target.to_str().expect("invalid path!")).await,
+        Ok(1)
+    );
+    let _ = std::fs::remove_dir_all(&directory);
+}
+
+#[test]
+fn check_backtest() {
+    use backtest::{BacktestConfig, Context, Fill, Liquidity};
+    use liquid::data::Order;
+
+    // the actions of the strategy at each event.
+    type Action = Box<dyn FnMut(&mut Context)>;
+    struct Scripted {
+        actions: Vec<Action>,
+        fills: Vec<Fill>,
+    }
+    impl Scripted {
+        fn act(&mut self, context: &mut Context) {
+            if !self.actions.is_empty() {
+                (self.actions.remove(0))(context);
+            }
+        }
+    }
+    impl Strategy for Scripted {
+        fn on_book(&mut self, _book: &Book, context: &mut Context) {
+            self.act(context);
+        }
+        fn on_execution(&mut self, _execution: &data::Execution, context: &mut Context) {
+            self.act(context);
+        }
+        fn on_fill(&mut self, fill: &Fill, _context: &mut Context) {
+            self.fills.push(fill.clone());
+        }
+    }
+    let book = |time: f64, bid: f64| {
+        MarketEvent::Book(Book {
+            received_at: time,
+            buy: vec![Order {
+                price: 100,
+                amount: bid,
+            }],
+            sell: vec![Order {
+                price: 102,
+                amount: 1.0,
+            }],
+        })
+    };
+    let execution = |time: f64, price: i32, quantity: f64, taker_side: &str| {
+        MarketEvent::Execution(data::Execution {
+            received_at: time,
+            timestamp: time,
+            created_at: time as i64,
+            price,
+            quantity,
+            taker_side: String::from(taker_side),
+            id: None,
+        })
+    };
+
+    let mut strategy = Scripted {
+        actions: vec![
+            Box::new(|context: &mut Context| {
+                assert_eq!(context.place(BookSide::Buy, 100, 0.5), Ok(1));
+                assert!(context.place(BookSide::Buy, 100, 0.0).is_err());
+                assert!(context.place(BookSide::Buy, 100, 2.0).is_err());
+            }),
+            Box::new(|context: &mut Context| {
+                // the order arrived behind the 1.0 resting at its price.
+                assert!((context.open_orders()[0].queue_ahead - 0.2).abs() < 1e-9);
+            }),
+            Box::new(|_: &mut Context| {}),
+            Box::new(|_: &mut Context| {}),
+            Box::new(|context: &mut Context| {
+                assert!(context.open_orders().is_empty());
+                // crossing the best ask, then a cancel which arrives after a trade-through.
+                context
+                    .place(BookSide::Buy, 103, 0.3)
+                    .expect("failed to place!");
+                let id = context
+                    .place(BookSide::Sell, 105, 0.2)
+                    .expect("failed to place!");
+                context.cancel(id);
+            }),
+            Box::new(|_: &mut Context| {}),
+        ],
+        fills: Vec::new(),
+    };
+    let config = BacktestConfig {
+        entry_latency: 0.05,
+        cancel_latency: 0.2,
+        equity_interval: 0.0,
+        min_quantity: 0.0001,
+        max_quantity: 1.0,
+    };
+    let mut backtester = Backtester::new(
+        config.clone(),
+        simulation::Simulation::new(1_000_000.0, 1.0),
+    );
+    backtester
+        .run(
+            &mut strategy,
+            vec![
+                book(1.0, 1.0),
+                execution(1.1, 100, 0.8, "sell"),
+                book(1.2, 0.6),
+                execution(1.3, 100, 0.4, "sell"),
+                execution(1.4, 99, 0.5, "sell"),
+                execution(1.5, 106, 0.15, "buy"),
+                execution(1.55, 106, 0.1, "buy"),
+            ],
+        )
+        .expect("failed to run!");
+
+    let fills = backtester
+        .report()
+        .fills
+        .iter()
+        .map(|e| {
+            (
+                e.order_id,
+                e.price,
+                (e.quantity * 1e6).round() / 1e6,
+                e.liquidity,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        fills,
+        vec![
+            // the volume at the price after the queue, then a trade-through.
+            (1, 100, 0.2, Liquidity::Maker),
+            (1, 100, 0.3, Liquidity::Maker),
+            (2, 102, 0.3, Liquidity::Taker),
+            // trade-throughs fill no more than the volume traded.
+            (3, 105, 0.15, Liquidity::Maker),
+            (3, 105, 0.05, Liquidity::Maker),
+        ]
+    );
+    assert_eq!(strategy.fills.len(), 5);
+    assert_eq!(backtester.report().orders, 3);
+    assert!((backtester.report().placed_quantity - 1.0).abs() < 1e-9);
+    assert!(backtester.open_orders().is_empty());
+    let (jpy, btc) = backtester.simulation().assets();
+    assert!((jpy - (1_000_000.0 - 50.0 - 30.6 + 21.0)).abs() < 1e-6);
+    assert!((btc - 1.6).abs() < 1e-9);
+    let last = backtester.report().pnl.last().expect("no pnl!").clone();
+    assert_eq!(last.timestamp, 1.55);
+    assert_eq!(last.mid, 101.0);
+    assert_eq!(backtester.report().mids, vec![(1.0, 101.0)]);
+    assert_eq!(last.inventory, btc);
+    assert!((last.equity - (jpy + btc * 101.0)).abs() < 1e-6);
+
+    // the events have to be in order.
+    assert!(backtester.step(&mut strategy, &book(0.5, 1.0)).is_err());
+
+    // the better prices are filled first, and the queue of a price is traded once before the
+    // orders there, which are filled in the order they were placed.
+    let mut strategy = Scripted {
+        actions: vec![Box::new(|context: &mut Context| {
+            for (price, quantity) in [(99, 0.3), (100, 0.2), (100, 0.2)] {
+                context
+                    .place(BookSide::Buy, price, quantity)
+                    .expect("failed to place!");
+            }
+        })],
+        fills: Vec::new(),
+    };
+    let mut backtester = Backtester::new(config, simulation::Simulation::new(1_000_000.0, 1.0));
+    backtester
+        .run(
+            &mut strategy,
+            vec![
+                book(1.0, 0.4),
+                execution(1.1, 100, 0.7, "sell"),
+                execution(1.2, 98, 0.35, "sell"),
+            ],
+        )
+        .expect("failed to run!");
+    let fills = backtester
+        .report()
+        .fills
+        .iter()
+        .map(|e| (e.order_id, e.price, (e.quantity * 1e6).round() / 1e6))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        fills,
+        vec![(2, 100, 0.2), (3, 100, 0.1), (3, 100, 0.1), (1, 99, 0.25)]
+    );
+    assert_eq!(backtester.open_orders().len(), 1);
+    assert!((backtester.open_orders()[0].remaining() - 0.05).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn check_load_events() {
+    use liquid::data::Order;
+    use order_book::BookSide;
+
+    let store = open_store("sqlite://:memory:")
+        .await
+        .expect("failed to open!");
+    // the executions are received a while after their timestamps.
+    let executions = [(95.0, 99.0), (99.0, 101.0), (100.0, 105.0), (195.0, 201.0)]
+        .iter()
+        .enumerate()
+        .map(|(i, (timestamp, received_at))| data::Execution {
+            received_at: *received_at,
+            timestamp: *timestamp,
+            created_at: *timestamp as i64,
+            price: 5_000_000,
+            quantity: 0.01,
+            taker_side: String::from("buy"),
+            id: Some(i as u64),
+        })
+        .collect::<Vec<data::Execution>>();
+    store
+        .insert_records(
+            common_constants::DATABASE_COLLECTION_EXECUTIONS,
+            &executions,
+        )
+        .await
+        .expect("failed to insert!");
+    let snapshots = [103.0, 150.0]
+        .iter()
+        .map(|received_at| data::OrderBook {
+            received_at: *received_at,
+            orders: vec![Order {
+                price: 4_999_000,
+                amount: 1.0,
+            }],
+        })
+        .collect::<Vec<data::OrderBook>>();
+    store
+        .insert_records(BookSide::Buy.snapshots(), &snapshots)
+        .await
+        .expect("failed to insert!");
+
+    let events = backtest::load_events(
+        &*store,
+        &Timeline {
+            start: Some(100.0),
+            end: Some(200.0),
+            order: 1,
+            limit: None,
+        },
+    )
+    .await
+    .expect("failed to load!");
+    let times = events
+        .iter()
+        .map(|e| match e {
+            MarketEvent::Execution(content) => ("execution", content.received_at),
+            MarketEvent::Book(content) => ("book", content.received_at),
+        })
+        .collect::<Vec<(&str, f64)>>();
+    assert_eq!(
+        times,
+        vec![
+            ("execution", 101.0),
+            ("book", 103.0),
+            ("execution", 105.0),
+            ("book", 150.0),
+        ]
+    );
+}
+
+#[test]
+fn check_fees() {
+    use fee::{FeeSchedule, FeeTable, Liquidity};
+
+    let table = FeeTable::flat(0.0, 0.001)
+        .with_tier(10_000.0, -0.0001, 0.0008)
+        .with_tier(1_000.0, 0.0, 0.0009);
+    assert_eq!(table.rate(0.0).taker, 0.001);
+    assert_eq!(table.rate(1_000.0).taker, 0.0009);
+    assert_eq!(table.rate(50_000.0).maker, -0.0001);
+    assert_eq!(table.fee(Liquidity::Taker, 0.0, 1_000.0), 1.0);
+
+    // the tables are written flat, with the products which differ.
+    let fees = serde_json::from_str::<FeeSchedule>(
+        r#"{
+            "maker": 0.0,
+            "taker": 0.001,
+            "tiers": [{ "volume": 10000, "maker": -0.0001, "taker": 0.0008 }],
+            "products": { "eth_jpy": { "maker": 0.0005, "taker": 0.002 } }
+        }"#,
+    )
+    .expect("failed to deserialize!");
+    assert_eq!(
+        fees,
+        FeeSchedule::new(FeeTable::flat(0.0, 0.001).with_tier(10_000.0, -0.0001, 0.0008))
+            .with_product("eth_jpy", FeeTable::flat(0.0005, 0.002))
+    );
+    assert_eq!(fees.fee("eth_jpy", Liquidity::Maker, 0.0, 1_000.0), 0.5);
+    assert_eq!(fees.fee("btc_jpy", Liquidity::Maker, 0.0, 1_000.0), 0.0);
+    assert_eq!(
+        FeeSchedule::default().fee("btc_jpy", Liquidity::Taker, 0.0, 1e6),
+        0.0
+    );
+
+    // a taker buy pays, then the volume reaches the tier of the maker rebate.
+    let mut simulation = simulation::Simulation::new(100_000.0, 0.0).with_fees(fees, "btc_jpy");
+    assert!(simulation.buy_at(0.0, 10_000, 1.0).is_ok());
+    assert_eq!(simulation.assets(), (100_000.0 - 10_000.0 - 10.0, 1.0));
+    assert_eq!(
+        simulation.fill_at(1.0, "sell", 11_000, 1.0, Liquidity::Maker),
+        Ok(-1.1)
+    );
+    assert!((simulation.assets().0 - (90_000.0 - 10.0 + 11_000.0 + 1.1)).abs() < 1e-9);
+    assert_eq!(simulation.volume(), 21_000.0);
+    let history = simulation.get_history();
+    assert_eq!(history[0].fee, 10.0);
+    assert_eq!(history[1].fee, -1.1);
+    assert!((simulation.fees() - 8.9).abs() < 1e-9);
+    assert!((simulation.estimate_gross_profit() - 1_000.0).abs() < 1e-9);
+    assert!((simulation.estimate_profit() - (1_000.0 - 8.9)).abs() < 1e-9);
+
+    // the fee has to be paid as well.
+    assert!(simulation
+        .fill_at(2.0, "buy", 10_000, 10.1, Liquidity::Taker)
+        .is_err());
+    assert_eq!(simulation.get_history()[2].fee, 0.0);
+}
+
+#[test]
+fn check_margin() {
+    use margin::{FundingCurrency, MarginConfig};
+
+    assert!(MarginConfig::new(0, FundingCurrency::Jpy).is_err());
+    let config = MarginConfig::new(4, FundingCurrency::Jpy)
+        .expect("failed to create!")
+        .with_interest_rate(0.001)
+        .with_funding(0.0001, 3600.0);
+    assert_eq!(config.initial_margin, 0.25);
+    assert_eq!(config.maintenance_margin, 0.125);
+
+    // a short within the initial margin, then one beyond it.
+    let mut simulation = simulation::Simulation::new(100_000.0, 0.0).with_margin(config);
+    assert!(simulation.sell_at(0.0, 1_000_000, 0.3).is_ok());
+    assert!(simulation.sell_at(0.0, 1_000_000, 0.2).is_err());
+    let account = simulation.margin().expect("no margin!").clone();
+    assert_eq!((account.quantity, account.open_price), (-0.3, 1_000_000.0));
+    assert_eq!(simulation.assets(), (100_000.0, 0.0));
+    assert!((simulation.liquidation_price().unwrap() - 400_000.0 / 0.3375).abs() < 1e-6);
+
+    // an hour of interest, and a funding paid by the longs.
+    assert!(!simulation.accrue(3600.0, 1_000_000.0));
+    let account = simulation.margin().unwrap();
+    assert!((account.interest - 12.5).abs() < 1e-9);
+    assert!((account.funding + 30.0).abs() < 1e-9);
+    assert!((simulation.assets().0 - 100_017.5).abs() < 1e-9);
+
+    // an event out of order neither accrues nor moves the accrual back.
+    assert!(!simulation.accrue(1800.0, 1_000_000.0));
+    assert!(!simulation.accrue(3600.0, 1_000_000.0));
+    let account = simulation.margin().unwrap();
+    assert!((account.interest - 12.5).abs() < 1e-9);
+    assert!((account.funding + 30.0).abs() < 1e-9);
+    assert!((simulation.assets().0 - 100_017.5).abs() < 1e-9);
+
+    // closing a part realizes its profit.
+    assert!(simulation.buy_at(3700.0, 900_000, 0.1).is_ok());
+    assert!((simulation.margin().unwrap().realized_pnl - 10_000.0).abs() < 1e-6);
+    let interest = 270_000.0 * 0.001 * 100.0 / 86_400.0;
+    assert!((simulation.assets().0 - (110_017.5 - interest)).abs() < 1e-9);
+    assert!((simulation.inventory() + 0.2).abs() < 1e-9);
+
+    // the price rises until the equity is below the maintenance margin.
+    assert!(simulation.accrue(3800.0, 1_400_000.0));
+    let account = simulation.margin().unwrap();
+    assert_eq!(account.quantity, 0.0);
+    assert_eq!(account.liquidations, vec![3800.0]);
+    assert!((account.realized_pnl + 70_000.0).abs() < 1e-6);
+    let last = simulation.get_history().last().unwrap();
+    assert_eq!((last.order.as_str(), last.price), ("buy", 1_400_000));
+    assert!(
+        (simulation.equity(1_400_000.0)
+            - (110_017.5 - interest - 80_000.0 - 280_000.0 * 0.001 * 100.0 / 86_400.0))
+            .abs()
+            < 1e-6
+    );
+    assert_eq!(simulation.liquidation_price(), None);
+
+    // the profits of a btc funded account are paid in btc.
+    let config = MarginConfig::new(2, FundingCurrency::Btc).expect("failed to create!");
+    let mut simulation = simulation::Simulation::new(0.0, 1.0).with_margin(config);
+    assert!(simulation.buy_at(0.0, 1_000_000, 1.0).is_ok());
+    assert!(simulation.buy_at(0.0, 1_000_000, 1.5).is_err());
+    assert!((simulation.inventory() - 2.0).abs() < 1e-9);
+    assert!(simulation.sell_at(1.0, 1_100_000, 1.0).is_ok());
+    assert!((simulation.assets().1 - (1.0 + 100_000.0 / 1_100_000.0)).abs() < 1e-9);
+}
+
+#[test]
+fn check_analytics() {
+    use analytics::{Analytics, AnalyticsConfig};
+    use backtest::{BacktestReport, Fill, Liquidity, PnlPoint};
+
+    let point = |timestamp: f64, equity: f64, mid: f64, inventory: f64| PnlPoint {
+        timestamp,
+        jpy: equity - inventory * mid,
+        btc: inventory,
+        mid,
+        equity,
+        fees: 0.0,
+        inventory,
+    };
+    let fill = |order_id: u64, timestamp: f64, side: BookSide, price: i32| Fill {
+        order_id,
+        timestamp,
+        side,
+        price,
+        quantity: 1.0,
+        liquidity: Liquidity::Maker,
+        fee: 0.0,
+    };
+    let report = BacktestReport {
+        fills: vec![
+            fill(1, 3600.0, BookSide::Buy, 100),
+            fill(2, 10800.0, BookSide::Sell, 110),
+        ],
+        pnl: vec![
+            point(0.0, 1000.0, 100.0, 0.0),
+            point(3600.0, 1100.0, 100.0, 1.0),
+            point(7200.0, 990.0, 90.0, 1.0),
+            point(10800.0, 1210.0, 110.0, 0.0),
+        ],
+        mids: vec![(0.0, 100.0), (7200.0, 90.0), (10800.0, 110.0)],
+        fees: 0.0,
+        orders: 3,
+        placed_quantity: 3.0,
+    };
+    let config = AnalyticsConfig {
+        return_interval: 3600.0,
+        markout: 3600.0,
+    };
+    let analytics = Analytics::calculate(&report, &config).expect("failed to calculate!");
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+
+    assert!(close(analytics.total_pnl, 210.0));
+    assert!(close(analytics.realized_pnl, 10.0));
+    assert!(close(analytics.unrealized_pnl, 0.0));
+    assert!(close(analytics.max_drawdown, 0.1));
+    assert!(close(analytics.max_drawdown_duration, 3600.0));
+    assert!(close(analytics.volume, 210.0));
+    assert!(close(analytics.turnover, 210.0 / 1075.0));
+    assert!(close(analytics.fill_rate, 2.0 / 3.0));
+    assert!(close(analytics.quantity_fill_rate, 2.0 / 3.0));
+    assert!(close(analytics.inventory.mean, 2.0 / 3.0));
+    assert_eq!(
+        (analytics.inventory.min, analytics.inventory.max),
+        (0.0, 1.0)
+    );
+    // the mid fell by 10 an hour after the buy. the sell was not followed long enough.
+    assert!(close(analytics.adverse_selection, 10.0));
+    assert!(close(analytics.markout, -10.0));
+    assert!(close(analytics.pnl_by_hour[1], 100.0));
+    assert!(close(analytics.pnl_by_hour[2], -110.0));
+    assert!(close(analytics.pnl_by_hour[3], 220.0));
+
+    let returns = [0.1, 990.0 / 1100.0 - 1.0, 1210.0 / 990.0 - 1.0];
+    let mean = returns.iter().sum::<f64>() / 3.0;
+    let deviation = (returns.iter().map(|e| (e - mean).powi(2)).sum::<f64>() / 2.0).sqrt();
+    assert!(close(
+        analytics.sharpe,
+        mean / deviation * (365.0_f64 * 24.0).sqrt()
+    ));
+    assert!(analytics.sortino > analytics.sharpe);
+
+    let json = analytics.to_json().expect("failed to serialize!");
+    assert_eq!(
+        serde_json::from_str::<Analytics>(&json).expect("failed to deserialize!"),
+        analytics
+    );
+    let html = analytics.to_html("spread <1>", &report);
+    assert!(html.contains("<title>spread &lt;1&gt;</title>"));
+    assert_eq!(html.matches("<svg").count(), 3);
+
+    assert!(Analytics::calculate(&BacktestReport::default(), &config).is_err());
+
+    // the mid moves between the equity samples, and the markouts follow it.
+    let report = BacktestReport {
+        mids: vec![
+            (0.0, 100.0),
+            (3630.0, 95.0),
+            (3700.0, 104.0),
+            (7200.0, 90.0),
+            (10800.0, 110.0),
+        ],
+        ..report
+    };
+    let config = AnalyticsConfig {
+        return_interval: 3600.0,
+        markout: 60.0,
+    };
+    let analytics = Analytics::calculate(&report, &config).expect("failed to calculate!");
+    assert!(close(analytics.adverse_selection, 5.0));
+    assert!(close(analytics.markout, -5.0));
+}
+
+#[test]
+fn check_sweep() {
+    use backtest::Context;
+    use liquid::data::Order;
+    use sweep::{Objective, Phase, Search, Space, SweepConfig, WalkForward};
+
+    // quotes both sides `offset` away from the best prices on the first book.
+    struct Quote {
+        offset: i32,
+        quantity: f64,
+        placed: bool,
+    }
+    impl Strategy for Quote {
+        fn on_book(&mut self, book: &Book, context: &mut Context) {
+            if !self.placed {
+                self.placed = true;
+                let _ = context.place(
+                    BookSide::Buy,
+                    book.buy[0].price - self.offset,
+                    self.quantity,
+                );
+                let _ = context.place(
+                    BookSide::Sell,
+                    book.sell[0].price + self.offset,
+                    self.quantity,
+                );
+            }
+        }
+    }
+    let book = |time: f64| {
+        MarketEvent::Book(Book {
+            received_at: time,
+            buy: vec![Order {
+                price: 100,
+                amount: 1.0,
+            }],
+            sell: vec![Order {
+                price: 102,
+                amount: 1.0,
+            }],
+        })
+    };
+    let execution = |time: f64, price: i32, taker_side: &str| {
+        MarketEvent::Execution(data::Execution {
+            received_at: time,
+            timestamp: time,
+            created_at: time as i64,
+            price,
+            quantity: 1.0,
+            taker_side: String::from(taker_side),
+            id: None,
+        })
+    };
+    let events = (0..4)
+        .flat_map(|i| {
+            let start = i as f64 * 200.0;
+            vec![
+                book(start),
+                execution(start + 50.0, 99, "sell"),
+                book(start + 100.0),
+                execution(start + 150.0, 103, "buy"),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let mut space = SearchSpace {
+        parameters: vec![
+            (
+                String::from("offset"),
+                Space::Range {
+                    min: 0.0,
+                    max: 2.0,
+                    step: 1.0,
+                },
+            ),
+            (String::from("quantity"), Space::Values(vec![0.1])),
+        ]
+        .into_iter()
+        .collect(),
+        search: Search::Grid,
+    };
+    let candidates = space.candidates().expect("failed to make candidates!");
+    assert_eq!(
+        candidates
+            .iter()
+            .map(|e| (e["offset"], e["quantity"]))
+            .collect::<Vec<_>>(),
+        vec![(0.0, 0.1), (1.0, 0.1), (2.0, 0.1)]
+    );
+    let json = serde_json::to_string(&space).expect("failed to serialize!");
+    assert_eq!(
+        serde_json::from_str::<SearchSpace>(&json).expect("failed to deserialize!"),
+        space
+    );
+    space.search = Search::Random { runs: 5, seed: 1 };
+    let drawn = space.candidates().expect("failed to make candidates!");
+    assert_eq!(drawn.len(), 5);
+    assert!(drawn.iter().all(|e| [0.0, 1.0, 2.0].contains(&e["offset"])));
+    assert_eq!(
+        drawn,
+        space.candidates().expect("failed to make candidates!")
+    );
+    assert!(Space::Range {
+        min: 0.0,
+        max: 1.0,
+        step: 0.0
+    }
+    .grid()
+    .is_err());
+
+    let new_strategy = |parameters: &sweep::Parameters| {
+        Ok(Quote {
+            offset: parameters["offset"] as i32,
+            quantity: parameters["quantity"],
+            placed: false,
+        })
+    };
+    let new_simulation = || simulation::Simulation::new(1_000.0, 1.0);
+    let mut config = SweepConfig {
+        objective: Objective::TotalPnl,
+        ..SweepConfig::default()
+    };
+
+    // the wider quotes earn more until they are not filled.
+    let results = sweep::run(&events, &candidates, &config, new_strategy, new_simulation)
+        .expect("failed to run!");
+    assert_eq!(
+        results
+            .iter()
+            .map(|e| (e.rank, e.parameters["offset"], e.phase))
+            .collect::<Vec<_>>(),
+        vec![
+            (1, 1.0, Phase::Full),
+            (2, 0.0, Phase::Full),
+            (3, 2.0, Phase::Full)
+        ]
+    );
+    assert!((results[0].score - 0.4).abs() < 1e-9);
+    assert!((results[1].score - 0.2).abs() < 1e-9);
+
+    // the windows of 200 seconds fit twice in 750 seconds.
+    config.walk_forward = Some(WalkForward {
+        train: 200.0,
+        test: 200.0,
+    });
+    let results = sweep::run(&events, &candidates, &config, new_strategy, new_simulation)
+        .expect("failed to run!");
+    assert_eq!(
+        results
+            .iter()
+            .map(|e| (e.window, e.phase, e.rank, e.start))
+            .collect::<Vec<_>>(),
+        vec![
+            (0, Phase::Train, 1, 0.0),
+            (0, Phase::Train, 2, 0.0),
+            (0, Phase::Train, 3, 0.0),
+            (0, Phase::Test, 1, 200.0),
+            (1, Phase::Train, 1, 200.0),
+            (1, Phase::Train, 2, 200.0),
+            (1, Phase::Train, 3, 200.0),
+            (1, Phase::Test, 1, 400.0),
+        ]
+    );
+    assert_eq!(results[3].parameters["offset"], 1.0);
+    assert!((results[3].analytics.total_pnl - 0.4).abs() < 1e-9);
+
+    let csv = sweep::to_csv(&results);
+    assert!(csv.starts_with("rank,phase,window,start,end,offset,quantity,score,"));
+    assert_eq!(csv.lines().count(), 9);
+
+    assert!(sweep::run(&[], &candidates, &config, new_strategy, new_simulation).is_err());
+}
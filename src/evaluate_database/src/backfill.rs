@@ -0,0 +1,224 @@
+use liquid::data::Execution;
+use liquid::{CurrencyPair, LiquidClientAsync};
+use serde::Serialize;
+
+use super::*;
+
+///
+/// liquid returns at most 1000 executions at once.
+pub const PAGE_LIMIT: u32 = 1000;
+///
+/// liquid allows 300 requests per 5 minutes.
+pub const REQUEST_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+///
+/// the first and the last time of records continuing without a gap.
+pub type Range = (Option<DateTime<Utc>>, Option<DateTime<Utc>>);
+
+///
+/// the result of backfilling a gap. times are in seconds.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct GapReport {
+    pub start: f64,
+    pub end: f64,
+    ///
+    /// executions returned by the exchange inside the gap
+    pub fetched: usize,
+    ///
+    /// executions which were not stored yet
+    pub inserted: u64,
+    ///
+    /// the exchange has no other execution between `start` and this time.
+    pub recovered_until: f64,
+    pub error: Option<String>,
+}
+
+impl GapReport {
+    pub fn is_recovered(&self) -> bool {
+        self.error.is_none() && self.recovered_until >= self.end
+    }
+}
+
+///
+/// the gaps between the ranges, from the end of a range to the start of the next one.
+pub fn gaps_of(ranges: &[Range]) -> Vec<(f64, f64)> {
+    ranges
+        .windows(2)
+        .filter_map(|e| match (e[0].1.or(e[0].0), e[1].0) {
+            (Some(start), Some(end)) => Some((
+                start.timestamp_nanos_opt().unwrap_or_default() as f64 / 1_000_000_000.0,
+                end.timestamp_nanos_opt().unwrap_or_default() as f64 / 1_000_000_000.0,
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+fn to_record(execution: Execution) -> data::Execution {
+    data::Execution {
+        // when it was received is unknown.
+        received_at: execution.timestamp,
+        timestamp: execution.timestamp,
+        created_at: i64::from(execution.created_at),
+        price: execution.price,
+        quantity: execution.quantity,
+        taker_side: execution.taker_side,
+        id: Some(execution.id),
+    }
+}
+
+///
+/// fetch the executions inside `gap` from the exchange page by page and insert them.
+/// executions already stored are skipped by their id.
+pub async fn backfill_gap(
+    database: &dyn Store,
+    client: &mut LiquidClientAsync,
+    gap: (f64, f64),
+    interval: std::time::Duration,
+) -> GapReport {
+    let (start, end) = gap;
+    let mut report = GapReport {
+        start,
+        end,
+        recovered_until: start,
+        ..Default::default()
+    };
+    let mut cursor = start.trunc() as u64;
+    loop {
+        let page = match client
+            .get_executions_by_timestamp(CurrencyPair::BtcJpy, cursor, PAGE_LIMIT)
+            .await
+        {
+            Ok(result) => result,
+            Err(result) => {
+                report.error = Some(result);
+                return report;
+            }
+        };
+        let last = match page.iter().map(|e| e.timestamp).reduce(f64::max) {
+            Some(content) => content,
+            // the exchange has nothing newer.
+            None => return report,
+        };
+        let is_last_page = page.len() < PAGE_LIMIT as usize;
+
+        let records = page
+            .into_iter()
+            .filter(|e| e.timestamp > start && e.timestamp < end)
+            .map(to_record)
+            .collect::<Vec<data::Execution>>();
+        report.fetched += records.len();
+        if !records.is_empty() {
+            match database
+                .insert_records(common_constants::DATABASE_COLLECTION_EXECUTIONS, &records)
+                .await
+            {
+                Ok(result) => report.inserted += result,
+                Err(result) => {
+                    report.error = Some(result);
+                    return report;
+                }
+            }
+        }
+
+        if last >= end {
+            report.recovered_until = end;
+            return report;
+        }
+        // the executions of the last second may continue on the next page.
+        report.recovered_until = report.recovered_until.max(last.trunc());
+        if is_last_page {
+            report.recovered_until = last;
+            return report;
+        }
+        if last.trunc() as u64 <= cursor {
+            report.error = Some(error_message!(
+                "more than {} executions in a second!\ntimestamp : {}",
+                PAGE_LIMIT,
+                cursor
+            ));
+            return report;
+        }
+        cursor = last.trunc() as u64;
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn executions(range: std::ops::Range<u64>) -> String {
+        serde_json::to_string(
+            &range
+                .map(|e| {
+                    serde_json::json!({
+                        "id": e,
+                        "quantity": "0.01",
+                        "price": "5000000.0",
+                        "taker_side": "buy",
+                        "created_at": e,
+                        "timestamp": format!("{}.5", e),
+                    })
+                })
+                .collect::<Vec<serde_json::Value>>(),
+        )
+        .expect("failed to serialize!")
+    }
+
+    #[tokio::test]
+    async fn check_backfill_gap() {
+        let database = open_store("sqlite://:memory:")
+            .await
+            .expect("failed to open!");
+        database.check_schema(true).await.expect("failed to check!");
+        let paths = [
+            ("100", executions(100..100 + PAGE_LIMIT as u64), 200),
+            ("1099", executions(1099..1120), 200),
+            ("1110", executions(1110..1120), 200),
+            ("2000", String::from("[]"), 500),
+        ];
+        let mocks = paths
+            .iter()
+            .map(|(timestamp, body, status)| {
+                mockito::mock("GET", "/executions")
+                    .match_query(mockito::Matcher::AllOf(vec![
+                        mockito::Matcher::UrlEncoded("product_id".into(), "5".into()),
+                        mockito::Matcher::UrlEncoded("timestamp".into(), (*timestamp).into()),
+                    ]))
+                    .with_status(*status)
+                    .expect_at_least(1)
+                    .with_body(body)
+                    .create()
+            })
+            .collect::<Vec<mockito::Mock>>();
+        let mut client = LiquidClientAsync::with_url(&mockito::server_url());
+        let interval = std::time::Duration::from_millis(0);
+
+        // the second page overlaps the first one in the second 1099.
+        let report = backfill_gap(database.as_ref(), &mut client, (100.5, 1110.5), interval).await;
+        assert!(report.is_recovered(), "{:?}", report);
+        assert_eq!((report.fetched, report.inserted), (1010, 1009));
+        let report = backfill_gap(database.as_ref(), &mut client, (100.5, 1110.5), interval).await;
+        assert_eq!((report.fetched, report.inserted), (1010, 0));
+        let stored = database
+            .count(
+                common_constants::DATABASE_COLLECTION_EXECUTIONS,
+                &Timeline::all(),
+            )
+            .await;
+        assert_eq!(stored, Ok(1009));
+
+        // the exchange has nothing after 1119.5.
+        let report = backfill_gap(database.as_ref(), &mut client, (1110.5, 1200.0), interval).await;
+        assert!(!report.is_recovered());
+        assert_eq!(report.recovered_until, 1119.5);
+
+        let report = backfill_gap(database.as_ref(), &mut client, (2000.0, 2100.0), interval).await;
+        assert!(!report.is_recovered());
+        assert!(report.error.is_some());
+        for mock in mocks {
+            mock.assert();
+        }
+    }
+}
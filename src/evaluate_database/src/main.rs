@@ -1,3 +1,4 @@
+mod backfill;
 mod handler;
 
 use chrono::prelude::*;
@@ -6,8 +7,9 @@ use chrono::Duration;
 use clap::{Arg, Command};
 use common::*;
 use database::*;
-use futures::stream::{Stream, StreamExt};
+use futures::stream::StreamExt;
 use indicatif::{ProgressBar, ProgressStyle};
+use liquid::LiquidClientAsync;
 
 #[tokio::main]
 async fn main() {
@@ -18,14 +20,20 @@ async fn main() {
         .arg(
             Arg::new("PERIOD")
                 .value_name("PERIOD")
-                .short("p")
+                .short('p')
                 .long("period")
                 .help("skipping period in seconds. It must be float number.")
         )
+        .arg(
+            Arg::new("SYMBOL")
+                .value_name("SYMBOL")
+                .long("symbol")
+                .help("exchange and product such as liquid:btc_jpy. liquid:btc_jpy by default.")
+        )
         .arg(
             Arg::new("URL")
                 .required(true)
-                .help("mongodb url")
+                .help("database url. mongodb:// or sqlite://")
         )
         .arg(
             Arg::new("TARGET")
@@ -35,7 +43,7 @@ async fn main() {
         .arg(
             Arg::new("COLLECTION")
                 .required(true)
-                .help("mongodb collection. Alternatives are ticker, executions, order_book_buy and order_book_sell.\norder_book_buy and order_book_sell are not supported csv files!")
+                .help("collection such as ticker, executions, order_book_buy or candles_1m.\norder_book_buy and order_book_sell are not supported csv files!")
         )
         .arg(
             Arg::new("FROM")
@@ -47,6 +55,17 @@ async fn main() {
                 .required(true)
                 .help("end time. Time format must be followed by ISO 8601. Such as 1996-12-19T16:39:57-08:00. Also you can use the \"newest\" keyword."),
         )
+        .arg(
+            Arg::new("BACKFILL")
+                .long("backfill")
+                .help("fetch the executions missing between the ranges from the exchange and insert them.")
+        )
+        .arg(
+            Arg::new("EXCHANGE_URL")
+                .long("exchange-url")
+                .takes_value(true)
+                .help("exchange api url used by --backfill. liquid by default.")
+        )
         .get_matches();
 
     if let (Some(url), Some(target), Some(collection), Some(from), Some(to)) = (
@@ -56,7 +75,17 @@ async fn main() {
         matches.value_of("FROM"),
         matches.value_of("TO"),
     ) {
-        let database = match Database::new(url, common_constants::DATABASE_NAME).await {
+        let symbol = match matches.value_of("SYMBOL") {
+            Some(result) => match Symbol::generate_from_string(result) {
+                Ok(result) => result,
+                Err(result) => {
+                    eprintln!("{}", result);
+                    return;
+                }
+            },
+            None => Symbol::default(),
+        };
+        let database = match open_store_for(url, &symbol).await {
             Ok(result) => result,
             Err(result) => {
                 eprintln!("{}", result);
@@ -64,7 +93,7 @@ async fn main() {
             }
         };
 
-        let file_type = match target.split('.').last() {
+        let file_type = match target.split('.').next_back() {
             Some(result) => result,
             None => {
                 eprintln!("target must has a extension! {}", target);
@@ -72,15 +101,10 @@ async fn main() {
             }
         };
 
-        match collection {
-            common_constants::DATABASE_COLLECTION_TICKER
-            | common_constants::DATABASE_COLLECTION_EXECUTIONS
-            | common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY
-            | common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL => {}
-            _ => {
-                eprintln!("{} is a invalid collection", collection);
-                return;
-            }
+        // every collection of a record type, since the records are handled as json objects.
+        if record::record_type(collection).is_err() {
+            eprintln!("{} is a invalid collection", collection);
+            return;
         }
 
         match file_type {
@@ -103,8 +127,8 @@ async fn main() {
         };
 
         let (start_time, end_time) = tokio::join!(
-            handler::from_handler(&database, collection, from),
-            handler::to_handler(&database, collection, to)
+            handler::from_handler(database.as_ref(), collection, from),
+            handler::to_handler(database.as_ref(), collection, to)
         );
         let start_time = match start_time {
             Ok(result) => result,
@@ -120,301 +144,159 @@ async fn main() {
             return;
         }
 
-        let query = match create_duration_query(collection, &start_time, &end_time) {
-            Ok(content) => content,
+        let field = match store::time_field(collection) {
+            Ok(result) => result,
             Err(result) => {
-                eprintln!("failed to create query!\ndetails : {}", result);
+                eprintln!("{}", result);
                 return;
             }
         };
-
-        let options = match create_find_option_timeline(collection, 1) {
-            Ok(content) => content,
+        let timeline = Timeline::new(&start_time, &end_time);
+        let document_count = match database.count(collection, &timeline).await {
+            Ok(result) => result,
             Err(result) => {
-                eprintln!("failed to create query!\ndetails : {}", result);
+                eprintln!("failed to count!\ndetails : {}", result);
                 return;
             }
         };
-
-        match collection {
-            common_constants::DATABASE_COLLECTION_TICKER => {
-                let mut ticker_stream = match stream::DatabaseStream::<data::Ticker>::request(
-                    &database,
-                    None,
-                    query.clone(),
-                    options.clone(),
-                )
-                .await
-                {
-                    Ok(result) => result,
-                    Err(result) => {
-                        eprintln!("failed to request!\ndetails : {}", result);
-                        return;
-                    }
-                };
-                let bar = ProgressBar::new(ticker_stream.size_hint().0 as u64);
-                bar.set_style(ProgressStyle::default_bar()
-                    .template("Downloading {spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta})",).progress_chars("#>-"));
-                let mut ranges = Vec::new();
-                let mut range = (Option::<DateTime<Utc>>::None, Option::<DateTime<Utc>>::None);
-                loop {
-                    tokio::select! {
-                        Some(result) = ticker_stream.next() => {
-                            bar.inc(1);
-                            match result {
-                                Ok(result) => {
-                                    let time = Utc.timestamp(
-                                        result.timestamp.trunc() as i64,
-                                        (result.timestamp.fract() * 1_000_000_000.0) as u32,
-                                    );
-                                    match range.0 {
-                                        Some(_) => {
-                                            match range.1 {
-                                                Some(content) => {
-                                                    if time > (content + duration) {
-                                                        ranges.push(range);
-                                                        range = (Option::<DateTime<Utc>>::None, Option::<DateTime<Utc>>::None);
-                                                        range.0 = Some(time);
-                                                    } else {
-                                                        range.1 = Some(time);
-                                                    }
-                                                }
-                                                None => {
-                                                    range.1 = Some(time);
-                                                }
-                                            }
-                                        }
-                                        None => {
-                                            range.0 = Some(time);
-                                        }
-                                    }
-                                }
-                                Err(result) => {
-                                    eprintln!("got malformed data!\ndetails : {}", result);
-                                }
-                            }
-                        }
-                        else => {
-                            ranges.push(range);
-                            break;
-                        }
-                    }
+        let mut record_stream = match database.stream(collection, &timeline).await {
+            Ok(result) => result,
+            Err(result) => {
+                eprintln!("failed to request!\ndetails : {}", result);
+                return;
+            }
+        };
+        let bar = ProgressBar::new(document_count);
+        bar.set_style(ProgressStyle::default_bar()
+            .template("Downloading {spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta})",).progress_chars("#>-"));
+        let mut ranges = Vec::new();
+        let mut range: backfill::Range = (None, None);
+        while let Some(result) = record_stream.next().await {
+            bar.inc(1);
+            let time = match result.map(|e| e.get(field).and_then(|e| e.as_f64())) {
+                Ok(Some(result)) => handler::to_date_time(result),
+                Ok(None) => {
+                    eprintln!("got malformed data!\ndetails : {} is missing", field);
+                    continue;
                 }
-                bar.finish();
-
-                match file_type {
-                    "csv" => {
-                        let mut csv_writer = match csv::Writer::from_path(target) {
-                            Ok(result) => result,
-                            Err(_) => {
-                                eprintln!("failed to create TARGET file.");
-                                return;
-                            }
-                        };
-                        for d in ranges {
-                            if let Err(result) = csv_writer.serialize(d) {
-                                eprintln!("failed to write! {:?}", result);
-                                return;
-                            }
-                        }
+                Err(result) => {
+                    eprintln!("got malformed data!\ndetails : {}", result);
+                    continue;
+                }
+            };
+            match range.0 {
+                Some(_) => match range.1 {
+                    Some(content) if time > (content + duration) => {
+                        ranges.push(range);
+                        range = (Some(time), None);
                     }
-
-                    "json" => {}
-
                     _ => {
-                        eprintln!("{} is not supported!", file_type);
-                        return;
+                        range.1 = Some(time);
                     }
+                },
+                None => {
+                    range.0 = Some(time);
                 }
             }
+        }
+        ranges.push(range);
+        bar.finish();
 
-            common_constants::DATABASE_COLLECTION_EXECUTIONS => {
-                let mut execution_stream = match stream::DatabaseStream::<data::Execution>::request(
-                    &database,
-                    None,
-                    query.clone(),
-                    options.clone(),
-                )
-                .await
-                {
+        match file_type {
+            "csv" => {
+                let mut csv_writer = match csv::Writer::from_path(target) {
                     Ok(result) => result,
-                    Err(result) => {
-                        eprintln!("failed to request!\ndetails : {}", result);
+                    Err(_) => {
+                        eprintln!("failed to create TARGET file.");
                         return;
                     }
                 };
-                let bar = ProgressBar::new(execution_stream.size_hint().0 as u64);
-                bar.set_style(ProgressStyle::default_bar()
-                    .template("Downloading {spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta})",).progress_chars("#>-"));
-                let mut ranges = Vec::new();
-                let mut range = (Option::<DateTime<Utc>>::None, Option::<DateTime<Utc>>::None);
-                loop {
-                    tokio::select! {
-                        Some(result) = execution_stream.next() => {
-                            bar.inc(1);
-                            match result {
-                                Ok(result) => {
-                                    let time = Utc.timestamp(
-                                        result.timestamp.trunc() as i64,
-                                        (result.timestamp.fract() * 1_000_000_000.0) as u32,
-                                    );
-                                    match range.0 {
-                                        Some(_) => {
-                                            match range.1 {
-                                                Some(content) => {
-                                                    if time > (content + duration) {
-                                                        ranges.push(range);
-                                                        range = (Option::<DateTime<Utc>>::None, Option::<DateTime<Utc>>::None);
-                                                        range.0 = Some(time);
-                                                    } else {
-                                                        range.1 = Some(time);
-                                                    }
-                                                }
-                                                None => {
-                                                    range.1 = Some(time);
-                                                }
-                                            }
-                                        }
-                                        None => {
-                                            range.0 = Some(time);
-                                        }
-                                    }
-                                }
-                                Err(result) => {
-                                    eprintln!("got malformed data!\ndetails : {}", result);
-                                }
-                            }
-                        }
-                        else => {
-                            ranges.push(range);
-                            break;
-                        }
-                    }
-                }
-                bar.finish();
-
-                match file_type {
-                    "csv" => {
-                        let mut csv_writer = match csv::Writer::from_path(target) {
-                            Ok(result) => result,
-                            Err(_) => {
-                                eprintln!("failed to create TARGET file.");
-                                return;
-                            }
-                        };
-                        for d in ranges {
-                            if let Err(result) = csv_writer.serialize(d) {
-                                eprintln!("failed to write! {:?}", result);
-                                return;
-                            }
-                        }
-                    }
-
-                    "json" => {}
-                    _ => {
-                        eprintln!("{} is not supported!", file_type);
+                for d in ranges.iter() {
+                    if let Err(result) = csv_writer.serialize(d) {
+                        eprintln!("failed to write! {:?}", result);
                         return;
                     }
                 }
             }
 
-            common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY
-            | common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL => {
-                let mut order_book_stream =
-                    match stream::DatabaseStream::<data::OrderBook>::request(
-                        &database,
-                        Some(collection),
-                        query.clone(),
-                        options.clone(),
-                    )
-                    .await
-                    {
-                        Ok(result) => result,
-                        Err(result) => {
-                            eprintln!("failed to request!\ndetails : {}", result);
-                            return;
-                        }
-                    };
-                let bar = ProgressBar::new(order_book_stream.size_hint().0 as u64);
-                bar.set_style(ProgressStyle::default_bar()
-                    .template("Downloading {spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta})",).progress_chars("#>-"));
-                let mut ranges = Vec::new();
-                let mut range = (Option::<DateTime<Utc>>::None, Option::<DateTime<Utc>>::None);
-                loop {
-                    tokio::select! {
-                        Some(result) = order_book_stream.next() => {
-                            bar.inc(1);
-                            match result {
-                                Ok(result) => {
-                                    let time = Utc.timestamp(
-                                        result.received_at.trunc() as i64,
-                                        (result.received_at.fract() * 1_000_000_000.0) as u32,
-                                    );
-                                    match range.0 {
-                                        Some(_) => {
-                                            match range.1 {
-                                                Some(content) => {
-                                                    if time > (content + duration) {
-                                                        ranges.push(range);
-                                                        range = (Option::<DateTime<Utc>>::None, Option::<DateTime<Utc>>::None);
-                                                        range.0 = Some(time);
-                                                    } else {
-                                                        range.1 = Some(time);
-                                                    }
-                                                }
-                                                None => {
-                                                    range.1 = Some(time);
-                                                }
-                                            }
-                                        }
-                                        None => {
-                                            range.0 = Some(time);
-                                        }
-                                    }
-                                }
-                                Err(result) => {
-                                    eprintln!("got malformed data!\ndetails : {}", result);
-                                }
-                            }
-                        }
-                        else => {
-                            ranges.push(range);
-                            break;
-                        }
-                    }
-                }
-                bar.finish();
+            "json" => {}
 
-                match file_type {
-                    "csv" => {
-                        let mut csv_writer = match csv::Writer::from_path(target) {
-                            Ok(result) => result,
-                            Err(_) => {
-                                eprintln!("failed to create TARGET file.");
-                                return;
-                            }
-                        };
-                        for d in ranges {
-                            if let Err(result) = csv_writer.serialize(d) {
-                                eprintln!("failed to write! {:?}", result);
-                                return;
-                            }
-                        }
-                    }
-
-                    "json" => {}
+            _ => {
+                eprintln!("{} is not supported!", file_type);
+                return;
+            }
+        }
 
-                    _ => {
-                        eprintln!("{} is not supported!", file_type);
-                        return;
-                    }
+        if !matches.is_present("BACKFILL") {
+            return;
+        }
+        if collection != common_constants::DATABASE_COLLECTION_EXECUTIONS {
+            eprintln!("only executions can be backfilled!");
+            return;
+        }
+        // the backfill requests the executions of the product recorded by liquid_loggerd.
+        if !symbol.is_default() {
+            eprintln!("only {} can be backfilled!", Symbol::default());
+            return;
+        }
+        match database.check_schema(true).await {
+            Ok(result) => {
+                for report in result.iter().filter(|e| !e.is_ok()) {
+                    eprintln!("{} : {}", report.collection, report.problems.join(" "));
                 }
             }
-
-            _ => {
-                eprintln!("invalid collection!");
+            Err(result) => {
+                eprintln!("failed to check the schema!\ndetails : {}", result);
                 return;
             }
         }
+        let mut client = match matches.value_of("EXCHANGE_URL") {
+            Some(result) => LiquidClientAsync::with_url(result),
+            None => LiquidClientAsync::new(),
+        };
+        let gaps = backfill::gaps_of(&ranges);
+        let bar = ProgressBar::new(gaps.len() as u64);
+        bar.set_style(ProgressStyle::default_bar()
+            .template("Backfilling {spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta})",).progress_chars("#>-"));
+        let mut reports = Vec::with_capacity(gaps.len());
+        for gap in gaps {
+            reports.push(
+                backfill::backfill_gap(
+                    database.as_ref(),
+                    &mut client,
+                    gap,
+                    backfill::REQUEST_INTERVAL,
+                )
+                .await,
+            );
+            bar.inc(1);
+        }
+        bar.finish();
+
+        println!(
+            "{:<32} {:<32} {:>8} {:>8}",
+            "START", "END", "FETCHED", "INSERTED"
+        );
+        for report in reports.iter() {
+            println!(
+                "{:<32} {:<32} {:>8} {:>8}",
+                handler::to_date_time(report.start).to_rfc3339(),
+                handler::to_date_time(report.end).to_rfc3339(),
+                report.fetched,
+                report.inserted
+            );
+        }
+        for report in reports.iter().filter(|e| !e.is_recovered()) {
+            eprintln!(
+                "could not recover from {} to {}. recovered until {}.{}",
+                handler::to_date_time(report.start).to_rfc3339(),
+                handler::to_date_time(report.end).to_rfc3339(),
+                handler::to_date_time(report.recovered_until).to_rfc3339(),
+                match &report.error {
+                    Some(content) => format!("\ndetails : {}", content),
+                    None => String::new(),
+                }
+            );
+        }
     }
 }
@@ -1,9 +1,7 @@
 use super::*;
 use std::io::prelude::*;
 use std::io::{BufReader, BufWriter};
-use tokio::io::{
-    AsyncBufReadExt, AsyncWriteExt, BufReader as tokio_BufReader, BufWriter as tokio_BufWriter,
-};
+use tokio::io::{AsyncReadExt, AsyncWriteExt, BufWriter as tokio_BufWriter};
 
 impl StreamBlocking {
     pub fn open(path: &str) -> Result<Self, String>
@@ -189,22 +187,29 @@ impl StreamAsync {
         Ok(StreamAsync {
             _socket_path: String::from(path),
             _stream: stream,
+            _pending: Vec::new(),
         })
     }
 
     pub async fn read(&mut self) -> Result<Vec<u8>, String> {
-        let mut reader = tokio_BufReader::new(&mut self._stream);
-
-        let mut received_data = Vec::new();
-        match reader.read_until(DELIMITER, &mut received_data).await {
-            Ok(_) => {
-                received_data.remove(received_data.len() - 1);
-                Ok(received_data)
+        // keep bytes after the delimiter since several commands can arrive at once.
+        loop {
+            if let Some(position) = self._pending.iter().position(|e| *e == DELIMITER) {
+                let mut received_data = self._pending.drain(..=position).collect::<Vec<u8>>();
+                received_data.pop();
+                return Ok(received_data);
+            }
+            let mut buffer = [0u8; 4096];
+            match self._stream.read(&mut buffer).await {
+                Ok(0) => return Err(error_message!("the stream was closed!")),
+                Ok(size) => self._pending.extend_from_slice(&buffer[..size]),
+                Err(result) => {
+                    return Err(error_message!(
+                        "failed to read from a stream!\ndetails : {:?}",
+                        result
+                    ))
+                }
             }
-            Err(result) => Err(error_message!(
-                "failed to read from a stream!\ndetails : {:?}",
-                result
-            )),
         }
     }
 
@@ -289,4 +294,29 @@ impl StreamAsync {
             )),
         }
     }
+
+    ///
+    /// send the events published by common::tail until the peer or the publisher is gone.
+    pub async fn forward_events(&mut self) -> Result<(), String> {
+        let mut receiver = common::tail::subscribe();
+        loop {
+            let event = match receiver.recv().await {
+                Ok(result) => result,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    format!("{{\"lagged\":{}}}", skipped)
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => return Ok(()),
+            };
+            self.send_command(Command::response(
+                [(
+                    String::from(common_constants::DEFINED_COMMAND_OPTION_EVENT),
+                    event,
+                )]
+                .iter()
+                .cloned()
+                .collect(),
+            ))
+            .await?;
+        }
+    }
 }
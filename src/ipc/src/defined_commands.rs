@@ -44,14 +44,79 @@ impl Command {
         }
     }
 
+    ///
+    /// ask a program to re-read the `log_directive` of `config_path`. nothing else is reloaded.
     pub fn reload(config_path: &str) -> Self {
         Command {
-            command: String::from(common_constants::DEFINED_COMMAND_SHUTDOWN),
+            command: String::from(common_constants::DEFINED_COMMAND_RELOAD),
+            messages: Some(
+                [(
+                    String::from(common_constants::DEFINED_COMMAND_OPTION_PATH),
+                    String::from(config_path),
+                )]
+                .iter()
+                .cloned()
+                .collect(),
+            ),
+        }
+    }
+
+    pub fn log_level(directive: &str) -> Self {
+        Command {
+            command: String::from(common_constants::DEFINED_COMMAND_SET_LOG_LEVEL),
+            messages: Some(
+                [(
+                    String::from(common_constants::DEFINED_COMMAND_OPTION_DIRECTIVE),
+                    String::from(directive),
+                )]
+                .iter()
+                .cloned()
+                .collect(),
+            ),
+        }
+    }
+
+    pub fn pause() -> Self {
+        Command {
+            command: String::from(common_constants::DEFINED_COMMAND_PAUSE),
+            messages: None,
+        }
+    }
+
+    pub fn resume() -> Self {
+        Command {
+            command: String::from(common_constants::DEFINED_COMMAND_RESUME),
+            messages: None,
+        }
+    }
+
+    pub fn subscribe() -> Self {
+        Command {
+            command: String::from(common_constants::DEFINED_COMMAND_SUBSCRIBE),
+            messages: None,
+        }
+    }
+
+    pub fn config() -> Self {
+        Command {
+            command: String::from(common_constants::DEFINED_COMMAND_GET_CONFIG),
+            messages: None,
+        }
+    }
+
+    ///
+    /// inspect the spool of liquid_loggerd, or replay it now if `drain` is set.
+    pub fn spool(drain: bool) -> Self {
+        Command {
+            command: String::from(common_constants::DEFINED_COMMAND_SPOOL),
             messages: Some(
-                [(String::from("path"), String::from(config_path))]
-                    .iter()
-                    .cloned()
-                    .collect(),
+                [(
+                    String::from(common_constants::DEFINED_COMMAND_OPTION_DRAIN),
+                    drain.to_string(),
+                )]
+                .iter()
+                .cloned()
+                .collect(),
             ),
         }
     }
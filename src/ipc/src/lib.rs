@@ -36,6 +36,7 @@ pub struct ListenerAsync {
 pub struct StreamAsync {
     _socket_path: String,
     _stream: tokio_UnixStream,
+    _pending: Vec<u8>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -155,4 +156,29 @@ mod tests {
         assert_eq!(client.send_command(command).await, Ok(()));
         assert_eq!(server.receive_command().await.is_ok(), true);
     }
+
+    #[tokio::test]
+    async fn check_commands_arriving_at_once() {
+        let path = std::env::temp_dir()
+            .join(format!("ipc_test_{}.sock", std::process::id()))
+            .to_string_lossy()
+            .into_owned();
+        let listener = ListenerAsync::open(&path).await.unwrap();
+        let mut client = StreamAsync::open(&path).await.unwrap();
+        let mut server = listener.listen().await.unwrap();
+
+        client.send_command(Command::pause()).await.unwrap();
+        client.send_command(Command::resume()).await.unwrap();
+        assert_eq!(
+            server.receive_command().await.unwrap().command,
+            common_constants::DEFINED_COMMAND_PAUSE
+        );
+        assert_eq!(
+            server.receive_command().await.unwrap().command,
+            common_constants::DEFINED_COMMAND_RESUME
+        );
+
+        drop(client);
+        assert!(server.receive_command().await.is_err());
+    }
 }
@@ -67,6 +67,7 @@ impl ListenerAsync {
             Ok((stream, _)) => Ok(StreamAsync {
                 _socket_path: self._socket_path.clone(),
                 _stream: stream,
+                _pending: Vec::new(),
             }),
             Err(result) => Err(result),
         }
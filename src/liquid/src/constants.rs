@@ -1,4 +1,5 @@
-//pub const LIQUID_URL: &str = "https://api.liquid.com";
+pub const LIQUID_URL: &str = "https://api.liquid.com";
+pub const EXECUTIONS_TAIL_PART: &str = "/executions";
 pub const GET_EXECUTIONS: &str = "https://api.liquid.com/executions";
 
 // private
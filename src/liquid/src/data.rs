@@ -381,13 +381,42 @@ impl DataGenerater<ExecutionReceiver> for Execution {
     }
 }
 
+///
+/// executions by timestamp have their quantity and price as strings and no timestamp.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExecutionByTimestampReceiver {
+    id: u64,
+    quantity: String,
+    price: String,
+    taker_side: String,
+    created_at: u32,
+    #[serde(default)]
+    timestamp: Option<String>,
+}
+
+impl DataGenerater<ExecutionByTimestampReceiver> for Execution {
+    fn generate_from_receiver(receiver: ExecutionByTimestampReceiver) -> Execution {
+        Execution {
+            id: receiver.id,
+            quantity: receiver.quantity.parse().expect("failed to parse"),
+            price: receiver.price.parse::<f64>().expect("failed to parse") as i32,
+            taker_side: receiver.taker_side,
+            created_at: receiver.created_at,
+            timestamp: match receiver.timestamp {
+                Some(content) => content.parse().expect("failed to parse"),
+                None => receiver.created_at as f64,
+            },
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct OrderReceiver {
     price: String,
     amount: String,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Order {
     pub price: i32,
     pub amount: f64,
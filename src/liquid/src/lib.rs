@@ -31,6 +31,7 @@ use constants::*;
 #[derive(Clone, Debug)]
 pub struct LiquidClientAsync {
     _client: reqwest::Client,
+    _url: String,
     //_last_called: std::time::Instant,
 }
 
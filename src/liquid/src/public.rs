@@ -5,8 +5,16 @@ use super::*;
 
 impl LiquidClientAsync {
     pub fn new() -> Self {
+        Self::with_url(LIQUID_URL)
+    }
+
+    ///
+    /// a client requesting `url` instead of the liquid api, such as a mock server.
+    /// only `get_executions_by_timestamp` follows it.
+    pub fn with_url(url: &str) -> Self {
         Self {
             _client: reqwest::Client::new(),
+            _url: String::from(url.trim_end_matches('/')),
             //_last_called: std::time::Instant::now(),
         }
     }
@@ -219,6 +227,62 @@ impl LiquidClientAsync {
             }
         }
     }
+
+    ///
+    /// executions at or after `timestamp` in seconds, the oldest first.
+    /// liquid returns at most 1000 executions at once.
+    pub async fn get_executions_by_timestamp(
+        &mut self,
+        pair: CurrencyPair,
+        timestamp: u64,
+        limit: u32,
+    ) -> std::result::Result<Vec<Execution>, String> {
+        let response = match self
+            ._client
+            .get(format!("{}{}", self._url, EXECUTIONS_TAIL_PART))
+            .query(&[
+                ("product_id", pair.generate_id().to_string()),
+                ("timestamp", timestamp.to_string()),
+                ("limit", limit.to_string()),
+            ])
+            .send()
+            .await
+        {
+            Ok(result) => result,
+            Err(result) => {
+                return Err(error_message!(
+                    "failed to get a data!\ndetails : {:?}",
+                    result
+                ))
+            }
+        };
+
+        if !response.status().is_success() {
+            return Err(error_message!(
+                "status code was not 200 OK!\ndetails : {:?}",
+                response
+            ));
+        }
+
+        let json_data = match response.text().await {
+            Ok(result) => result,
+            Err(result) => return Err(error_message!("not text!\ndetails : {:?}", result)),
+        };
+
+        match serde_json::from_str(&json_data)
+            as serde_json::Result<Vec<ExecutionByTimestampReceiver>>
+        {
+            Ok(result) => Ok(result
+                .into_iter()
+                .map(Execution::generate_from_receiver)
+                .collect()),
+            Err(result) => Err(error_message!(
+                "failed to deserialize json!\nserde_json message : {:?}\njson : {}",
+                result,
+                json_data
+            )),
+        }
+    }
 }
 
 impl Default for LiquidClientAsync {
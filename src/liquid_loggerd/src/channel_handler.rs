@@ -2,37 +2,57 @@ use super::event_handler;
 use super::*;
 //use liquid::*;
 
+#[tracing::instrument(
+    name = "tap_message",
+    skip_all,
+    fields(
+        channel = response.channel.as_deref().unwrap_or_default(),
+        event = response.event.as_deref().unwrap_or_default(),
+    )
+)]
 pub async fn channel_handler(
     client: &mut LiquidTapClientAsync,
     response: &data_for_tap::ResponseValue,
-    database: std::sync::Arc<Database>,
-    transmitter: &tokio::sync::mpsc::UnboundedSender<tokio::task::JoinHandle<()>>,
+    writer: &writer::WriterHandle,
+    recorders: &mut event_handler::BookRecorders,
 ) {
+    common::metrics::counter_increment(
+        common::metrics::TAP_MESSAGES_TOTAL,
+        &[("channel", response.channel.as_deref().unwrap_or_default())],
+        1.0,
+    );
+    if let Some(channel) = &response.channel {
+        common::health::record_tap_message(channel);
+    }
+    common::tail::publish(response);
+    if common::health::is_paused() {
+        return;
+    }
     if let Some(content) = &response.channel {
         match content.as_str() {
             "product_cash_btcjpy_5" => {
-                event_handler::product_handler(client, response, database, transmitter).await;
+                event_handler::product_handler(client, response, writer).await;
             }
 
             "executions_cash_btcjpy" => {
-                event_handler::executions_handler(client, response, database, transmitter).await;
+                event_handler::executions_handler(client, response, writer).await;
             }
 
             "price_ladders_cash_btcjpy_buy" => {
-                event_handler::order_book_buy(client, response, database, transmitter).await;
+                event_handler::order_book_buy(client, response, writer, recorders).await;
             }
 
             "price_ladders_cash_btcjpy_sell" => {
-                event_handler::order_book_sell(client, response, database, transmitter).await;
+                event_handler::order_book_sell(client, response, writer, recorders).await;
             }
 
             "execution_details_cash_btcjpy" => {
-                event_handler::executions_details_handler(client, response, database, transmitter);
+                event_handler::executions_details_handler(client, response, writer);
             }
 
             "time-signal" => {}
             _ => {
-                log::warn!("unknown channel!\n-->\ndetails : {:?}\n<--", response);
+                tracing::warn!(response = ?response, "unknown channel!");
             }
         }
     }
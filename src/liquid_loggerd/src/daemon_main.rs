@@ -1,75 +1,85 @@
 use super::*;
 use tokio::signal::unix::{signal, SignalKind};
-use tokio::sync::mpsc;
 use tokio::time;
 
 pub async fn daemon_main() {
     // load configurtation
     let env = match load_env("./env.toml").await {
-        Ok(result) => result,
+        Ok(result) => std::sync::Arc::new(result),
         Err(result) => {
-            log::error!("failed to load!\n-->\ndetails : {}\n<--", result);
+            tracing::error!(details = %result, "failed to load!");
             return;
         }
     };
     /*let (_log_dir, db_path, etc_dir, _bin_dir, _keybox_path) = match misc::load_env() {
         Ok(result) => result,
         Err(result) => {
-            log::error!("failed to load!\n-->\ndetails : {}\n<--", result);
+            tracing::error!(details = %result, "failed to load!");
             return;
         }
     };*/
 
     // prevent same program running
     if let Err(result) = misc::lock_file(&env.general.etc_directory_path) {
-        log::error!("failed to lock the file!\n-->\ndetails : {}\n<--", result);
+        tracing::error!(details = %result, "failed to lock the file!");
+        return;
+    }
+    if let Err(result) =
+        common::write_pid_file(&env.general.etc_directory_path, env!("CARGO_PKG_NAME"))
+    {
+        tracing::error!(details = %result, "failed to write the pid file!");
         return;
     }
 
     // check wether systemd is running or not
     if !daemon::booted() {
-        log::error!("systemd is not running!");
+        tracing::error!("systemd is not running!");
         return;
     };
     // check wether watchdog is enable or not and the duration
     let watchdog_duration = match daemon::watchdog_enabled(true) {
         Some(result) => result,
         None => {
-            log::error!("watchdog is diabled! WatchdogSec must be set.");
+            tracing::error!("watchdog is diabled! WatchdogSec must be set.");
             return;
         }
     };
 
-    // initialize systemd journal
-    if let Err(result) =
-        systemd_journal_logger::init_with_extra_fields(vec![("VERSION", env!("CARGO_PKG_VERSION"))])
-    {
-        log::error!(
-            "failed to initialize the journal!\n-->\ndetails : {:?}\n<--",
-            result
-        );
-        return;
+    // initialize the journal and the json lines log
+    let log_handle = match common::logging::init(
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        &env.general.log_directory_path,
+        env.general.log_directive.as_deref().unwrap_or("debug"),
+    ) {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", result);
+            return;
+        }
     };
-    log::set_max_level(log::LevelFilter::Debug);
+
+    // serve metrics if a port is configured
+    if let Some(port) = env.metrics.get(&(String::from(env!("CARGO_PKG_NAME")))) {
+        if let Err(result) = common::metrics::serve(*port).await {
+            tracing::error!(details = %result, "failed to serve metrics!");
+            return;
+        }
+        tracing::info!(port = port, "serving metrics.");
+    }
 
     // initiazlie signal handler
     let mut sig_term = match signal(SignalKind::terminate()) {
         Ok(result) => result,
         Err(result) => {
-            log::error!(
-                "failed to initialize SIGTERM!\n-->\ndetails : {:?}\n<--",
-                result
-            );
+            tracing::error!(details = ?result, "failed to initialize SIGTERM!");
             return;
         }
     };
     let mut sig_hangup = match signal(SignalKind::hangup()) {
         Ok(result) => result,
         Err(result) => {
-            log::error!(
-                "failed to initialize SIGHUP!\n-->\ndetails : {:?}\n<--",
-                result
-            );
+            tracing::error!(details = ?result, "failed to initialize SIGHUP!");
             return;
         }
     };
@@ -86,48 +96,83 @@ pub async fn daemon_main() {
     {
         Ok(result) => result,
         Err(_) => {
-            log::error!("failed to open the unix domai socket!");
+            tracing::error!("failed to open the unix domai socket!");
             return;
         }
     };
     let (transmitter, mut receiver) =
         tokio::sync::mpsc::channel::<ipc::Command>(std::mem::size_of::<[ipc::Command; 16]>());
-    let (task_transmitter, task_receiver) =
-        mpsc::unbounded_channel::<tokio::task::JoinHandle<()>>();
-
-    let task_handler = tokio::task::spawn(handler::task_handler(task_receiver));
     // initialize liquidn logger
     let (database, mut client) = match initialize::initialize(&env.general.database_url).await {
         Ok(result) => result,
         Err(_) => return,
     };
+    // create the collections and indexes missing from the database
+    match database.check_schema(true).await {
+        Ok(reports) => {
+            for report in reports {
+                if !report.created.is_empty() {
+                    tracing::info!(collection = %report.collection, created = ?report.created, "created the schema.");
+                }
+                if !report.is_ok() {
+                    tracing::warn!(collection = %report.collection, problems = ?report.problems, "the schema has problems!");
+                }
+            }
+        }
+        Err(result) => tracing::warn!(details = %result, "failed to check the schema!"),
+    }
 
-    log::info!("{} was initialized.", env!("CARGO_PKG_NAME"));
+    // records which could not be inserted are kept here until the database recovers
+    let spool = match spool::Spool::open(
+        env.general.working_directory_path.clone() + "spool/",
+        &env.spool,
+    ) {
+        Ok(result) => spool::SharedSpool::new(result),
+        Err(result) => {
+            tracing::error!(details = %result, "failed to open the spool!");
+            return;
+        }
+    };
+    let replay_task = spool::spawn_replay(spool.clone(), database.clone(), &env.spool);
+    // expire old records by the retention policies
+    let retention_task = retention::spawn(database.clone(), &env.retention, &env.order_book);
+    let (writer, writer_task) = writer::spawn(
+        database.clone(),
+        spool.clone(),
+        &env.writer,
+        env.spool.backlog_threshold,
+    );
+    // the order books are stored as snapshots, or snapshots and diffs
+    let mut recorders = event_handler::BookRecorders::new(&env.order_book);
+
+    tracing::info!("{} was initialized.", env!("CARGO_PKG_NAME"));
 
     // notify systemd that ready
     if let Err(result) = daemon::notify(false, &[daemon::NotifyState::Ready]) {
-        log::error!(
-            "failed to notify systemd!\n-->\ndetails : {:?}\n<--",
-            result
-        );
+        tracing::error!(details = ?result, "failed to notify systemd!");
         return;
     }
 
     loop {
         tokio::select! {
             _ = sig_term.recv() => {
-                return;
+                break;
+            }
+            _ = sig_hangup.recv() => {
+                match log_handle.reload("./env.toml").await {
+                    Ok(directive) => tracing::info!(directive = %directive, "reloaded."),
+                    Err(result) => tracing::error!(details = %result, "failed to reload!"),
+                }
             }
-            _ = sig_hangup.recv() => {}
             _ = watchdog_timer.tick() => {
                 if let Err(result) = daemon::notify(false, &[daemon::NotifyState::Watchdog]) {
-                    log::error!("failed to notify systemd that watchdog!\n-->\ndetails : {:?}\n<--", result);
+                    tracing::error!(details = ?result, "failed to notify systemd that watchdog!");
                     return;
                 }
             }
             Some(command) = receiver.recv() => {
                 match command.command.as_ref() {
-                    common_constants::DEFINED_COMMAND_SHUTDOWN => return,
+                    common_constants::DEFINED_COMMAND_SHUTDOWN => break,
                     _ => {}
                 }
             }
@@ -135,12 +180,12 @@ pub async fn daemon_main() {
                 match listened {
                     Ok(stream) => {
                         let transmitter_copied = transmitter.clone();
-                        tokio::spawn(handler::ipc_handler(stream, transmitter_copied));
+                        tokio::spawn(handler::ipc_handler(stream, transmitter_copied, log_handle.clone(), database.clone(), spool.clone(), env.clone()));
                     }
                     Err(result) => match result.kind() {
                         std::io::ErrorKind::WouldBlock => {}
                         _ => {
-                            log::error!("cannot listen to ipc!\n-->\ndetails : {}\n<--", result);
+                            tracing::error!(details = %result, "cannot listen to ipc!");
                             return;
                         }
                     }
@@ -150,13 +195,13 @@ pub async fn daemon_main() {
             Ok(checked) = time::timeout(watchdog_duration / 2, client.check()) => {
                 match checked {
                     Ok(result) => {
-                        channel_handler::channel_handler(&mut client, &result, database.clone(), &task_transmitter).await;
+                        channel_handler::channel_handler(&mut client, &result, &writer, &mut recorders).await;
                     }
                     Err(result) => {
-                        log::warn!(
-                            "cannot check!\n-->\ndetails : {}\n<--", result
-                        );
-                        log::info!("reinitialize connection to liquid tap due to a previous error.");
+                        tracing::warn!(details = %result, "cannot check!");
+                        common::health::set_tap_connected(false);
+                        tracing::info!("reinitialize connection to liquid tap due to a previous error.");
+                        common::metrics::counter_increment(common::metrics::TAP_RECONNECTS_TOTAL, &[], 1.0);
                         client = match initialize::initialize_liquid_tap().await {
                             Ok(result) => result,
                             Err(_) => break,
@@ -165,11 +210,16 @@ pub async fn daemon_main() {
                 };
             }
             else => {
-                log::error!("liquid tap was timeout! liquid_loggerd would shutdown!");
+                tracing::error!("liquid tap was timeout! liquid_loggerd would shutdown!");
                 break;
             }
         };
     }
-    drop(task_transmitter);
-    task_handler.await.expect("failed to run task!");
+    // flush the write buffers before exiting. what could not be inserted stays in the spool.
+    replay_task.abort();
+    if let Some(task) = retention_task {
+        task.abort();
+    }
+    drop(writer);
+    writer_task.await.expect("failed to run task!");
 }
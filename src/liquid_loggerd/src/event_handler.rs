@@ -3,15 +3,14 @@ use super::*;
 pub async fn product_handler(
     _client: &mut LiquidTapClientAsync,
     response: &data_for_tap::ResponseValue,
-    database: std::sync::Arc<Database>,
-    transmitter: &tokio::sync::mpsc::UnboundedSender<tokio::task::JoinHandle<()>>,
+    writer: &writer::WriterHandle,
 ) {
     if let Some(content) = &response.event {
         match content.as_str() {
             "pusher_internal:subscription_succeeded" => {
-                log::info!(
-                    "subscribing {} succeeded.",
-                    response.channel.as_ref().unwrap()
+                tracing::info!(
+                    channel = response.channel.as_deref().unwrap_or_default(),
+                    "subscribing succeeded."
                 );
             }
 
@@ -19,10 +18,11 @@ pub async fn product_handler(
                 let data = match liquid_tap::generate_product(&response.data) {
                     Ok(result) => result,
                     Err(result) => {
-                        log::warn!(
-                            "failed to generate!\n-->\ndetails : {}\nresponse : {:?}<--",
-                            result,
-                            response
+                        tracing::warn!(details = %result, response = ?response, "failed to generate!");
+                        common::metrics::counter_increment(
+                            common::metrics::TAP_PARSE_FAILURES_TOTAL,
+                            &[("channel", response.channel.as_deref().unwrap_or_default())],
+                            1.0,
                         );
                         return;
                     }
@@ -45,23 +45,15 @@ pub async fn product_handler(
                     high_market_price_24h: data.high_market_ask,
                 };
 
-                if let Err(result) = transmitter.send(tokio::task::spawn(async move {
-                    if let Err(result) = database
-                        .create(
-                            common_constants::DATABASE_COLLECTION_TICKER,
-                            &[ticker],
-                            None,
-                        )
-                        .await
-                    {
-                        log::error!("failed to create posts!\n-->\ndetails : {}\n<--", result);
-                    }
-                })) {
-                    log::error!("failed to send tasks!\n-->\ndetails : {}\n<--", result);
+                if let Err(result) = writer
+                    .write(common_constants::DATABASE_COLLECTION_TICKER, &ticker)
+                    .await
+                {
+                    tracing::error!(details = %result, "failed to queue a record!");
                 }
             }
             _ => {
-                log::warn!("unknown event!\n-->\ndetails : {:?}\n<--", response);
+                tracing::warn!(response = ?response, "unknown event!");
             }
         }
     }
@@ -70,15 +62,14 @@ pub async fn product_handler(
 pub async fn executions_handler(
     _client: &mut LiquidTapClientAsync,
     response: &data_for_tap::ResponseValue,
-    database: std::sync::Arc<Database>,
-    transmitter: &tokio::sync::mpsc::UnboundedSender<tokio::task::JoinHandle<()>>,
+    writer: &writer::WriterHandle,
 ) {
     if let Some(content) = &response.event {
         match content.as_str() {
             "pusher_internal:subscription_succeeded" => {
-                log::info!(
-                    "subscribing {} succeeded.",
-                    response.channel.as_ref().unwrap()
+                tracing::info!(
+                    channel = response.channel.as_deref().unwrap_or_default(),
+                    "subscribing succeeded."
                 );
             }
 
@@ -86,10 +77,11 @@ pub async fn executions_handler(
                 let data = match liquid_tap::generate_execution(&response.data) {
                     Ok(result) => result,
                     Err(result) => {
-                        log::warn!(
-                            "failed to generate!\n-->\ndetails : {}\nresponse : {:?}<--",
-                            result,
-                            response
+                        tracing::warn!(details = %result, response = ?response, "failed to generate!");
+                        common::metrics::counter_increment(
+                            common::metrics::TAP_PARSE_FAILURES_TOTAL,
+                            &[("channel", response.channel.as_deref().unwrap_or_default())],
+                            1.0,
                         );
                         return;
                     }
@@ -101,46 +93,73 @@ pub async fn executions_handler(
                         .unwrap()
                         .as_secs_f64(),
                     timestamp: data.timestamp,
-                    created_at: data.created_at as i32,
+                    created_at: i64::from(data.created_at),
                     price: data.price,
                     quantity: data.quantity,
                     taker_side: data.taker_side,
+                    id: Some(data.id),
                 };
-                if let Err(result) = transmitter.send(tokio::task::spawn(async move {
-                    if let Err(result) = database
-                        .create(
-                            common_constants::DATABASE_COLLECTION_EXECUTIONS,
-                            &[execution],
-                            None,
-                        )
-                        .await
-                    {
-                        log::error!("failed to create posts!\n-->\ndetails : {}\n<--", result);
-                    }
-                })) {
-                    log::error!("failed to send tasks!\n-->\ndetails : {}\n<--", result);
+                if let Err(result) = writer
+                    .write(common_constants::DATABASE_COLLECTION_EXECUTIONS, &execution)
+                    .await
+                {
+                    tracing::error!(details = %result, "failed to queue a record!");
                 }
             }
 
             _ => {
-                log::warn!("unknown event!\n-->\ndetails : {:?}\n<--", response);
+                tracing::warn!(response = ?response, "unknown event!");
             }
         }
     }
 }
 
+///
+/// the recorders deciding whether an update of each side is stored as a snapshot or a diff.
+pub struct BookRecorders {
+    pub buy: database::BookRecorder,
+    pub sell: database::BookRecorder,
+}
+
+impl BookRecorders {
+    pub fn new(settings: &common::OrderBookStorage) -> Self {
+        Self {
+            buy: database::BookRecorder::new(database::BookSide::Buy, settings),
+            sell: database::BookRecorder::new(database::BookSide::Sell, settings),
+        }
+    }
+}
+
+async fn write_book(
+    writer: &writer::WriterHandle,
+    recorder: &mut database::BookRecorder,
+    order_book: database::data::OrderBook,
+) {
+    let queued = match recorder.record(order_book) {
+        database::order_book::BookRecord::Snapshot(content) => {
+            writer.write(recorder.side().snapshots(), &content).await
+        }
+        database::order_book::BookRecord::Diff(content) => {
+            writer.write(recorder.side().diffs(), &content).await
+        }
+    };
+    if let Err(result) = queued {
+        tracing::error!(details = %result, "failed to queue a record!");
+    }
+}
+
 pub async fn order_book_buy(
     _client: &mut LiquidTapClientAsync,
     response: &data_for_tap::ResponseValue,
-    database: std::sync::Arc<Database>,
-    transmitter: &tokio::sync::mpsc::UnboundedSender<tokio::task::JoinHandle<()>>,
+    writer: &writer::WriterHandle,
+    recorders: &mut BookRecorders,
 ) {
     if let Some(content) = &response.event {
         match content.as_str() {
             "pusher_internal:subscription_succeeded" => {
-                log::info!(
-                    "subscribing {} succeeded.",
-                    response.channel.as_ref().unwrap()
+                tracing::info!(
+                    channel = response.channel.as_deref().unwrap_or_default(),
+                    "subscribing succeeded."
                 );
             }
 
@@ -148,10 +167,11 @@ pub async fn order_book_buy(
                 let data = match liquid_tap::generate_order_book(&response.data) {
                     Ok(result) => result,
                     Err(result) => {
-                        log::warn!(
-                            "failed to generate!\n-->\ndetails : {}\nresponse : {:?}<--",
-                            result,
-                            response
+                        tracing::warn!(details = %result, response = ?response, "failed to generate!");
+                        common::metrics::counter_increment(
+                            common::metrics::TAP_PARSE_FAILURES_TOTAL,
+                            &[("channel", response.channel.as_deref().unwrap_or_default())],
+                            1.0,
                         );
                         return;
                     }
@@ -164,24 +184,11 @@ pub async fn order_book_buy(
                         .as_secs_f64(),
                     orders: data,
                 };
-                if let Err(result) = transmitter.send(tokio::task::spawn(async move {
-                    if let Err(result) = database
-                        .create(
-                            common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY,
-                            &[order_book_buy],
-                            None,
-                        )
-                        .await
-                    {
-                        log::error!("failed to create posts!\n-->\ndetails : {}\n<--", result);
-                    }
-                })) {
-                    log::error!("failed to send tasks!\n-->\ndetails : {}\n<--", result);
-                }
+                write_book(writer, &mut recorders.buy, order_book_buy).await;
             }
 
             _ => {
-                log::warn!("unknown event!\n-->\ndetails : {:?}\n<--", response);
+                tracing::warn!(response = ?response, "unknown event!");
             }
         }
     }
@@ -190,15 +197,15 @@ pub async fn order_book_buy(
 pub async fn order_book_sell(
     _client: &mut LiquidTapClientAsync,
     response: &data_for_tap::ResponseValue,
-    database: std::sync::Arc<Database>,
-    transmitter: &tokio::sync::mpsc::UnboundedSender<tokio::task::JoinHandle<()>>,
+    writer: &writer::WriterHandle,
+    recorders: &mut BookRecorders,
 ) {
     if let Some(content) = &response.event {
         match content.as_str() {
             "pusher_internal:subscription_succeeded" => {
-                log::info!(
-                    "subscribing {} succeeded.",
-                    response.channel.as_ref().unwrap()
+                tracing::info!(
+                    channel = response.channel.as_deref().unwrap_or_default(),
+                    "subscribing succeeded."
                 );
             }
 
@@ -206,10 +213,11 @@ pub async fn order_book_sell(
                 let data = match liquid_tap::generate_order_book(&response.data) {
                     Ok(result) => result,
                     Err(result) => {
-                        log::warn!(
-                            "failed to generate!\n-->\ndetails : {}\nresponse : {:?}<--",
-                            result,
-                            response
+                        tracing::warn!(details = %result, response = ?response, "failed to generate!");
+                        common::metrics::counter_increment(
+                            common::metrics::TAP_PARSE_FAILURES_TOTAL,
+                            &[("channel", response.channel.as_deref().unwrap_or_default())],
+                            1.0,
                         );
                         return;
                     }
@@ -222,24 +230,11 @@ pub async fn order_book_sell(
                         .as_secs_f64(),
                     orders: data,
                 };
-                if let Err(result) = transmitter.send(tokio::task::spawn(async move {
-                    if let Err(result) = database
-                        .create(
-                            common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL,
-                            &[order_book_sell],
-                            None,
-                        )
-                        .await
-                    {
-                        log::error!("failed to create posts!\n-->\ndetails : {}\n<--", result);
-                    }
-                })) {
-                    log::error!("failed to send tasks!\n-->\ndetails : {}\n<--", result);
-                }
+                write_book(writer, &mut recorders.sell, order_book_sell).await;
             }
 
             _ => {
-                log::warn!("unknown event!\n-->\ndetails : {:?}\n<--", response);
+                tracing::warn!(response = ?response, "unknown event!");
             }
         }
     }
@@ -248,15 +243,14 @@ pub async fn order_book_sell(
 pub fn executions_details_handler(
     _client: &mut LiquidTapClientAsync,
     response: &data_for_tap::ResponseValue,
-    _database: std::sync::Arc<Database>,
-    _transmitter: &tokio::sync::mpsc::UnboundedSender<tokio::task::JoinHandle<()>>,
+    _writer: &writer::WriterHandle,
 ) {
     if let Some(content) = &response.event {
         match content.as_str() {
             "pusher_internal:subscription_succeeded" => {
-                log::info!(
-                    "subscribing {} succeeded.",
-                    response.channel.as_ref().unwrap()
+                tracing::info!(
+                    channel = response.channel.as_deref().unwrap_or_default(),
+                    "subscribing succeeded."
                 );
             }
 
@@ -264,10 +258,11 @@ pub fn executions_details_handler(
                 let _data = match liquid_tap::generate_execution_details(&response.data) {
                     Ok(result) => result,
                     Err(result) => {
-                        log::warn!(
-                            "failed to generate!\n-->\ndetails : {}\nresponse : {:?}<--",
-                            result,
-                            response
+                        tracing::warn!(details = %result, response = ?response, "failed to generate!");
+                        common::metrics::counter_increment(
+                            common::metrics::TAP_PARSE_FAILURES_TOTAL,
+                            &[("channel", response.channel.as_deref().unwrap_or_default())],
+                            1.0,
                         );
                         return;
                     }
@@ -275,7 +270,7 @@ pub fn executions_details_handler(
             }
 
             _ => {
-                log::warn!("unknown event!\n-->\ndetails : {:?}\n<--", response);
+                tracing::warn!(response = ?response, "unknown event!");
             }
         }
     }
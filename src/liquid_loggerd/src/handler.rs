@@ -1,13 +1,12 @@
 use super::*;
-use tokio::sync::mpsc;
 
-pub async fn status_handler() {
+pub async fn status_handler(show_metrics: bool, json: bool) -> i32 {
     // load configurtation
     let env = match load_env("./env.toml").await {
         Ok(result) => result,
         Err(result) => {
-            log::error!("failed to load!\n-->\ndetails : {}\n<--", result);
-            return;
+            tracing::error!(details = %result, "failed to load!");
+            return 1;
         }
     };
     /*let (_log_dir, _db_path, etc_dir, _bin_dir, _keybox_path) = match misc::load_env() {
@@ -17,7 +16,7 @@ pub async fn status_handler() {
                 "{}",
                 error_message_colored!("failed to load!\n-->\ndetails : {}\n<--", result)
             );
-            return;
+            return 1;
         }
     };*/
 
@@ -34,7 +33,7 @@ pub async fn status_handler() {
                 "{}",
                 error_message_colored!("failed connect to {}!", env!("CARGO_PKG_NAME"))
             );
-            return;
+            return 1;
         }
     };
 
@@ -43,7 +42,7 @@ pub async fn status_handler() {
             "{}",
             error_message_colored!("failed to send a command!\n-->\ndetails : {}\n<--", result)
         );
-        return;
+        return 1;
     }
 
     let result: ipc::Command = match stream.receive_command().await {
@@ -56,14 +55,11 @@ pub async fn status_handler() {
                     result
                 )
             );
-            return;
+            return 1;
         }
     };
     let data = result.messages.expect("should contain!");
-    println!(
-        "name : {}\nversion : {}\ndescription : {}\nstatus : {}",
-        data["name"], data["version"], data["description"], data["status"]
-    );
+    common::health::print_status(&data, json, show_metrics)
 }
 
 pub async fn start_handler() {
@@ -75,7 +71,7 @@ pub async fn shutdown_handler() {
     let env = match load_env("./env.toml").await {
         Ok(result) => result,
         Err(result) => {
-            log::error!("failed to load!\n-->\ndetails : {}\n<--", result);
+            tracing::error!(details = %result, "failed to load!");
             return;
         }
     };
@@ -119,23 +115,161 @@ pub async fn shutdown_handler() {
     }
 }
 
+pub async fn log_level_handler(directive: &str) {
+    // load configurtation
+    let env = match load_env("./env.toml").await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!(
+                "{}",
+                error_message_colored!("failed to load!\n-->\ndetails : {}\n<--", result)
+            );
+            return;
+        }
+    };
+
+    let mut stream = match ipc::StreamAsync::open(
+        &(env.general.etc_directory_path
+            + env!("CARGO_PKG_NAME")
+            + common_constants::SOCKET_EXTENSION),
+    )
+    .await
+    {
+        Ok(result) => result,
+        Err(_) => {
+            eprintln!(
+                "{}",
+                error_message_colored!("failed connect to {}!", env!("CARGO_PKG_NAME"))
+            );
+            return;
+        }
+    };
+
+    if let Err(result) = stream
+        .send_command(ipc::Command::log_level(directive))
+        .await
+    {
+        eprintln!(
+            "{}",
+            error_message_colored!("failed to send a command!\n-->\ndetails : {}\n<--", result)
+        );
+        return;
+    }
+
+    let result: ipc::Command = match stream.receive_command().await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!(
+                "{}",
+                error_message_colored!(
+                    "failed to receive a command!\n-->\ndetails : {}\n<--",
+                    result
+                )
+            );
+            return;
+        }
+    };
+    let data = result.messages.expect("should contain!");
+    if let Some(content) = data.get(common_constants::DEFINED_COMMAND_OPTION_ERROR) {
+        eprintln!("{}", error_message_colored!("{}", content));
+    } else if let Some(content) = data.get(common_constants::DEFINED_COMMAND_OPTION_SUCCESS) {
+        println!("{}", ok_message_colored!("directive : {}", content));
+    }
+}
+
+pub async fn spool_handler(drain: bool) {
+    // load configurtation
+    let env = match load_env("./env.toml").await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!(
+                "{}",
+                error_message_colored!("failed to load!\n-->\ndetails : {}\n<--", result)
+            );
+            return;
+        }
+    };
+
+    let mut stream = match ipc::StreamAsync::open(
+        &(env.general.etc_directory_path
+            + env!("CARGO_PKG_NAME")
+            + common_constants::SOCKET_EXTENSION),
+    )
+    .await
+    {
+        Ok(result) => result,
+        Err(_) => {
+            eprintln!(
+                "{}",
+                error_message_colored!("failed connect to {}!", env!("CARGO_PKG_NAME"))
+            );
+            return;
+        }
+    };
+
+    if let Err(result) = stream.send_command(ipc::Command::spool(drain)).await {
+        eprintln!(
+            "{}",
+            error_message_colored!("failed to send a command!\n-->\ndetails : {}\n<--", result)
+        );
+        return;
+    }
+
+    let result: ipc::Command = match stream.receive_command().await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!(
+                "{}",
+                error_message_colored!(
+                    "failed to receive a command!\n-->\ndetails : {}\n<--",
+                    result
+                )
+            );
+            return;
+        }
+    };
+    let data = result.messages.expect("should contain!");
+    if let Some(content) = data.get(common_constants::DEFINED_COMMAND_OPTION_ERROR) {
+        eprintln!("{}", error_message_colored!("{}", content));
+    } else if let Some(content) = data.get(common_constants::DEFINED_COMMAND_OPTION_SUCCESS) {
+        println!("{}", ok_message_colored!("spool : {}", content));
+    }
+}
+
 pub async fn ipc_handler(
     mut stream: ipc::StreamAsync,
     transmitter: tokio::sync::mpsc::Sender<ipc::Command>,
+    log_handle: common::logging::LogHandle,
+    database: std::sync::Arc<dyn Store>,
+    spool: spool::SharedSpool,
+    env: std::sync::Arc<Enviornment>,
 ) {
     let result: ipc::Command = match stream.receive_command().await {
         Ok(result) => result,
         Err(result) => {
-            log::error!(
-                "failed to receive a command!\n-->\ndetails : {}\n<--",
-                result
-            );
+            tracing::error!(details = %result, "failed to receive a command!");
             return;
         }
     };
 
     match result.command.as_ref() {
         common_constants::DEFINED_COMMAND_GET_STATUS => {
+            let spooled = spool.status().await;
+            let health = common::health::Health {
+                database: Some(database.health().await),
+                buffers: [(
+                    String::from("spool"),
+                    common::health::BufferHealth {
+                        length: spooled.records,
+                        bytes: spooled.bytes as usize,
+                    },
+                )]
+                .iter()
+                .cloned()
+                .collect(),
+                ..common::health::Health::new()
+            }
+            .evaluate();
             if let Err(result) = stream
                 .send_command(ipc::Command::response(
                     [
@@ -152,6 +286,14 @@ pub async fn ipc_handler(
                             String::from("status"),
                             format!("{}", console::style("active(running)").green()),
                         ),
+                        (
+                            String::from(common_constants::DEFINED_COMMAND_OPTION_METRICS),
+                            common::metrics::render(),
+                        ),
+                        (
+                            String::from(common_constants::DEFINED_COMMAND_OPTION_HEALTH),
+                            serde_json::to_string(&health).unwrap_or_default(),
+                        ),
                     ]
                     .iter()
                     .cloned()
@@ -159,28 +301,143 @@ pub async fn ipc_handler(
                 ))
                 .await
             {
-                log::error!("failed to send a command!\n-->\ndetails : {}\n<--", result);
+                tracing::error!(details = %result, "failed to send a command!");
                 return;
             }
         }
-        _ => {
-            if let Err(result) = transmitter.send(result).await {
-                log::error!(
-                    "failed to send to the main thread!\n-->\ndetails : {}\n<--",
-                    result
-                );
+        common_constants::DEFINED_COMMAND_SET_LOG_LEVEL => {
+            let response = match result
+                .messages
+                .as_ref()
+                .and_then(|e| e.get(common_constants::DEFINED_COMMAND_OPTION_DIRECTIVE))
+            {
+                Some(directive) => match log_handle.set_directive(directive) {
+                    Ok(()) => {
+                        tracing::info!(directive = %directive, "changed the logging directive.");
+                        (
+                            String::from(common_constants::DEFINED_COMMAND_OPTION_SUCCESS),
+                            log_handle.directive(),
+                        )
+                    }
+                    Err(result) => (
+                        String::from(common_constants::DEFINED_COMMAND_OPTION_ERROR),
+                        result,
+                    ),
+                },
+                None => (
+                    String::from(common_constants::DEFINED_COMMAND_OPTION_ERROR),
+                    error_message!("no directive!"),
+                ),
+            };
+            if let Err(result) = stream
+                .send_command(ipc::Command::response([response].iter().cloned().collect()))
+                .await
+            {
+                tracing::error!(details = %result, "failed to send a command!");
             }
         }
-    }
-}
-
-pub async fn task_handler(mut receiver: mpsc::UnboundedReceiver<tokio::task::JoinHandle<()>>) {
-    loop {
-        tokio::select! {
-            Some(content) = receiver.recv()=> {
-                content.await.expect("failed to run greenthread!");
+        common_constants::DEFINED_COMMAND_RELOAD => {
+            let response = match log_handle.reload("./env.toml").await {
+                Ok(directive) => {
+                    tracing::info!(directive = %directive, "reloaded.");
+                    (
+                        String::from(common_constants::DEFINED_COMMAND_OPTION_SUCCESS),
+                        directive,
+                    )
+                }
+                Err(result) => (
+                    String::from(common_constants::DEFINED_COMMAND_OPTION_ERROR),
+                    result,
+                ),
+            };
+            if let Err(result) = stream
+                .send_command(ipc::Command::response([response].iter().cloned().collect()))
+                .await
+            {
+                tracing::error!(details = %result, "failed to send a command!");
+            }
+        }
+        common_constants::DEFINED_COMMAND_PAUSE | common_constants::DEFINED_COMMAND_RESUME => {
+            let paused = result.command == common_constants::DEFINED_COMMAND_PAUSE;
+            common::health::set_paused(paused);
+            tracing::info!(paused = paused, "changed the pause state.");
+            let response = (
+                String::from(common_constants::DEFINED_COMMAND_OPTION_SUCCESS),
+                String::from(if paused { "paused" } else { "resumed" }),
+            );
+            if let Err(result) = stream
+                .send_command(ipc::Command::response([response].iter().cloned().collect()))
+                .await
+            {
+                tracing::error!(details = %result, "failed to send a command!");
+            }
+        }
+        common_constants::DEFINED_COMMAND_SUBSCRIBE => {
+            if let Err(result) = stream.forward_events().await {
+                tracing::debug!(details = %result, "a subscriber left.");
+            }
+        }
+        common_constants::DEFINED_COMMAND_SPOOL => {
+            let drain = result
+                .messages
+                .as_ref()
+                .and_then(|e| e.get(common_constants::DEFINED_COMMAND_OPTION_DRAIN))
+                .map(|e| e == "true")
+                .unwrap_or_default();
+            let response = if drain {
+                match spool.replay(database.as_ref()).await {
+                    Ok(replayed) => {
+                        tracing::info!(records = replayed, "drained the spool.");
+                        (
+                            String::from(common_constants::DEFINED_COMMAND_OPTION_SUCCESS),
+                            format!("replayed : {}, {}", replayed, spool.status().await),
+                        )
+                    }
+                    Err(result) => (
+                        String::from(common_constants::DEFINED_COMMAND_OPTION_ERROR),
+                        result,
+                    ),
+                }
+            } else {
+                (
+                    String::from(common_constants::DEFINED_COMMAND_OPTION_SUCCESS),
+                    spool.status().await.to_string(),
+                )
+            };
+            if let Err(result) = stream
+                .send_command(ipc::Command::response([response].iter().cloned().collect()))
+                .await
+            {
+                tracing::error!(details = %result, "failed to send a command!");
+            }
+        }
+        common_constants::DEFINED_COMMAND_GET_CONFIG => {
+            let response = match common::effective_config(
+                &env,
+                env!("CARGO_PKG_NAME"),
+                None::<&()>,
+                &log_handle.directive(),
+            ) {
+                Ok(content) => (
+                    String::from(common_constants::DEFINED_COMMAND_OPTION_CONFIG),
+                    content,
+                ),
+                Err(result) => (
+                    String::from(common_constants::DEFINED_COMMAND_OPTION_ERROR),
+                    result,
+                ),
+            };
+            if let Err(result) = stream
+                .send_command(ipc::Command::response([response].iter().cloned().collect()))
+                .await
+            {
+                tracing::error!(details = %result, "failed to send a command!");
+            }
+        }
+        _ => {
+            if let Err(result) = transmitter.send(result).await {
+                tracing::error!(details = %result, "failed to send to the main thread!");
             }
-            else => break,
         }
     }
 }
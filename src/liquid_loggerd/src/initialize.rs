@@ -2,23 +2,17 @@ use super::*;
 
 pub async fn initialize(
     db_path: &str,
-) -> Result<(std::sync::Arc<database::Database>, LiquidTapClientAsync), ()> {
-    let (returned_database, returned_client) = tokio::join!(
-        Database::new(db_path, common_constants::DATABASE_NAME),
-        initialize_liquid_tap()
-    );
+) -> Result<(std::sync::Arc<dyn Store>, LiquidTapClientAsync), ()> {
+    let (returned_database, returned_client) =
+        tokio::join!(open_store(db_path), initialize_liquid_tap());
 
     let database = match returned_database {
         Ok(result) => result,
         Err(result) => {
-            log::error!(
-                "failed to initialize database\n-->\ndetails : {}\n<--",
-                result
-            );
+            tracing::error!(details = %result, "failed to initialize database");
             return Err(());
         }
     };
-    let database = std::sync::Arc::new(database);
 
     let client = match returned_client {
         Ok(result) => result,
@@ -32,10 +26,7 @@ pub async fn initialize_liquid_tap() -> Result<LiquidTapClientAsync, ()> {
     let mut client = match LiquidTapClientAsync::connect().await {
         Ok(result) => result,
         Err(result) => {
-            log::error!(
-                "fatal error! cannnot connect liquid. \n-->\ndetails : {}\n<--",
-                result
-            );
+            tracing::error!(details = %result, "fatal error! cannnot connect liquid.");
             return Err(());
         }
     };
@@ -44,10 +35,7 @@ pub async fn initialize_liquid_tap() -> Result<LiquidTapClientAsync, ()> {
         .subscribe(liquid_tap::channel_product(CurrencyPair::BtcJpy))
         .await
     {
-        log::error!(
-            "failed to subscribe a channel! \n-->\ndetails : {}\n<--",
-            result
-        );
+        tracing::error!(details = %result, "failed to subscribe a channel!");
         return Err(());
     }
 
@@ -55,10 +43,7 @@ pub async fn initialize_liquid_tap() -> Result<LiquidTapClientAsync, ()> {
         .subscribe(liquid_tap::channel_executions(CurrencyPair::BtcJpy))
         .await
     {
-        log::error!(
-            "failed to subscribe a channel! \n-->\ndetails : {}\n<--",
-            result
-        );
+        tracing::error!(details = %result, "failed to subscribe a channel!");
         return Err(());
     }
 
@@ -69,10 +54,7 @@ pub async fn initialize_liquid_tap() -> Result<LiquidTapClientAsync, ()> {
         ))
         .await
     {
-        log::error!(
-            "failed to subscribe a channel! \n-->\ndetails : {}\n<--",
-            result
-        );
+        tracing::error!(details = %result, "failed to subscribe a channel!");
         return Err(());
     }
 
@@ -83,10 +65,7 @@ pub async fn initialize_liquid_tap() -> Result<LiquidTapClientAsync, ()> {
         ))
         .await
     {
-        log::error!(
-            "failed to subscribe a channel! \n-->\ndetails : {}\n<--",
-            result
-        );
+        tracing::error!(details = %result, "failed to subscribe a channel!");
         return Err(());
     }
 
@@ -94,12 +73,10 @@ pub async fn initialize_liquid_tap() -> Result<LiquidTapClientAsync, ()> {
         .subscribe(liquid_tap::channel_executions_details(CurrencyPair::BtcJpy))
         .await
     {
-        log::error!(
-            "failed to subscribe a channel!\n-->\ndetails : {}\n<--",
-            result
-        );
+        tracing::error!(details = %result, "failed to subscribe a channel!");
         return Err(());
     }
 
+    common::health::set_tap_connected(true);
     Ok(client)
 }
@@ -1,10 +1,10 @@
 use std::result::Result;
 //use std::sync::{Arc, Mutex};
 
-use clap::Command;
+use clap::{Arg, Command};
 use libsystemd::*;
 
-use common::{common_constants, error_message, error_message_colored, *};
+use common::{common_constants, error_message, error_message_colored, ok_message_colored, *};
 use database::*;
 use liquid::*;
 
@@ -14,6 +14,9 @@ mod event_handler;
 mod handler;
 mod initialize;
 mod misc;
+mod retention;
+mod spool;
+mod writer;
 
 #[tokio::main]
 async fn main() {
@@ -21,17 +24,57 @@ async fn main() {
         .version(env!("CARGO_PKG_VERSION"))
         .author(env!("CARGO_PKG_AUTHORS"))
         .about(env!("CARGO_PKG_DESCRIPTION"))
-        .subcommand(Command::new("status").about("show the status."))
+        .subcommand(
+            Command::new("status")
+                .about("show the status.")
+                .arg(
+                    Arg::new("metrics")
+                        .long("metrics")
+                        .help("show metrics in prometheus text format."),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("print the status as json."),
+                ),
+        )
         .subcommand(Command::new("start").about("start the program."))
         .subcommand(Command::new("shutdown").about("shutdown the program."))
+        .subcommand(
+            Command::new("log-level")
+                .about("change the logging directive of the running program.")
+                .arg(
+                    Arg::new("DIRECTIVE")
+                        .required(true)
+                        .help("such as info,liquid_loggerd=debug,database=trace"),
+                ),
+        )
+        .subcommand(
+            Command::new("spool")
+                .about("show the records waiting in the spool.")
+                .arg(
+                    Arg::new("drain")
+                        .long("drain")
+                        .help("replay the spool into the database now."),
+                ),
+        )
         .get_matches();
 
-    if let Some(_matched) = matches.subcommand_matches("status") {
-        handler::status_handler().await;
+    if let Some(matched) = matches.subcommand_matches("status") {
+        std::process::exit(
+            handler::status_handler(matched.is_present("metrics"), matched.is_present("json"))
+                .await,
+        );
     } else if let Some(_matched) = matches.subcommand_matches("start") {
         handler::start_handler().await;
     } else if let Some(_matched) = matches.subcommand_matches("shutdown") {
         handler::shutdown_handler().await;
+    } else if let Some(matched) = matches.subcommand_matches("log-level") {
+        if let Some(directive) = matched.value_of("DIRECTIVE") {
+            handler::log_level_handler(directive).await;
+        }
+    } else if let Some(matched) = matches.subcommand_matches("spool") {
+        handler::spool_handler(matched.is_present("drain")).await;
     } else {
     }
 }
@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use tracing::Instrument;
+
+use super::*;
+
+///
+/// enforce the retention policies every `interval` milliseconds.
+/// nothing is started without a policy.
+pub fn spawn(
+    database: Arc<dyn Store>,
+    settings: &common::Retention,
+    order_book: &common::OrderBookStorage,
+) -> Option<tokio::task::JoinHandle<()>> {
+    if settings.interval == 0 || settings.collections.is_empty() {
+        return None;
+    }
+    let settings = settings.clone();
+    let order_book = order_book.clone();
+    Some(tokio::spawn(async move {
+        let mut timer = tokio::time::interval(std::time::Duration::from_millis(settings.interval));
+        // the first enforcement goes through the whole history, the next ones through what aged out since.
+        let mut since = None;
+        loop {
+            timer.tick().await;
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs_f64();
+            match database::retention::enforce(
+                database.as_ref(),
+                &settings,
+                &order_book,
+                now,
+                since,
+                false,
+            )
+            .instrument(tracing::info_span!("retention"))
+            .await
+            {
+                Ok(reports) => {
+                    since = Some(now);
+                    for report in reports {
+                        tracing::info!(
+                            collection = %report.collection,
+                            downsampled = report.downsampled,
+                            deleted = report.deleted,
+                            "enforced the retention policy."
+                        );
+                    }
+                }
+                Err(result) => {
+                    tracing::warn!(details = %result, "failed to enforce the retention policies!")
+                }
+            }
+        }
+    }))
+}
@@ -0,0 +1,602 @@
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::Instrument;
+
+use super::*;
+
+const SEGMENT_EXTENSION: &str = "spool";
+
+///
+/// length and crc32 of the payload, both little endian u32.
+const HEADER_BYTES: u64 = 8;
+
+#[derive(Serialize)]
+struct PayloadRef<'a> {
+    collection: &'a str,
+    records: &'a [serde_json::Value],
+}
+
+///
+/// a batch of records of one collection in the order it was spooled.
+#[derive(Debug, Deserialize)]
+pub struct Entry {
+    pub collection: String,
+    pub records: Vec<serde_json::Value>,
+    #[serde(skip)]
+    next: u64,
+}
+
+#[derive(Debug)]
+enum Frame {
+    Entry(Entry),
+    ///
+    /// the entry failed the integrity check. reading continues at the offset.
+    Corrupt(u64),
+    ///
+    /// the segment ends within an entry, such as when the process died while appending.
+    Truncated,
+    End,
+}
+
+#[derive(Clone, Debug)]
+struct Segment {
+    sequence: u64,
+    path: PathBuf,
+    bytes: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Status {
+    pub segments: usize,
+    pub records: usize,
+    pub bytes: u64,
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "segments : {}, records : {}, bytes : {}",
+            self.segments, self.records, self.bytes
+        )
+    }
+}
+
+///
+/// append-only segment files holding the batches which could not be inserted.
+/// they are read from the oldest segment and removed once every entry was replayed.
+#[derive(Debug)]
+pub struct Spool {
+    directory: PathBuf,
+    max_bytes: u64,
+    segment_bytes: u64,
+    segments: VecDeque<Segment>,
+    ///
+    /// the segment being appended. segments found at startup are never appended.
+    active: Option<File>,
+    next_sequence: u64,
+    ///
+    /// offset of the next entry in the oldest segment
+    read_offset: u64,
+    records: usize,
+}
+
+impl Spool {
+    ///
+    /// open the spool in `directory`, creating it if needed.
+    pub fn open<P: AsRef<Path>>(directory: P, settings: &common::Spool) -> Result<Self, String> {
+        let directory = directory.as_ref().to_path_buf();
+        if let Err(result) = std::fs::create_dir_all(&directory) {
+            return Err(error_message!(
+                "failed to create the spool directory!\ndetails : {:?}\npath : {:?}",
+                result,
+                directory
+            ));
+        }
+        let read_dir = match std::fs::read_dir(&directory) {
+            Ok(result) => result,
+            Err(result) => {
+                return Err(error_message!(
+                    "failed to read the spool directory!\ndetails : {:?}\npath : {:?}",
+                    result,
+                    directory
+                ));
+            }
+        };
+        let mut segments = Vec::new();
+        for dir_entry in read_dir.flatten() {
+            let path = dir_entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(SEGMENT_EXTENSION) {
+                continue;
+            }
+            let sequence = match path
+                .file_stem()
+                .and_then(|e| e.to_str())
+                .and_then(|e| e.parse::<u64>().ok())
+            {
+                Some(result) => result,
+                None => continue,
+            };
+            let bytes = dir_entry.metadata().map(|e| e.len()).unwrap_or_default();
+            segments.push(Segment {
+                sequence,
+                path,
+                bytes,
+            });
+        }
+        segments.sort_by_key(|e| e.sequence);
+
+        let mut spool = Self {
+            directory,
+            max_bytes: settings.max_bytes,
+            segment_bytes: settings.segment_bytes.max(1),
+            next_sequence: segments.last().map(|e| e.sequence + 1).unwrap_or_default(),
+            segments: segments.into(),
+            active: None,
+            read_offset: 0,
+            records: 0,
+        };
+        for segment in &spool.segments {
+            let mut offset = 0;
+            loop {
+                match read_frame(&segment.path, offset)? {
+                    Frame::Entry(entry) => {
+                        spool.records += entry.records.len();
+                        offset = entry.next;
+                    }
+                    Frame::Corrupt(next) => offset = next,
+                    Frame::Truncated | Frame::End => break,
+                }
+            }
+        }
+        if spool.records == 0 {
+            spool.clear()?;
+        }
+        spool.update_metrics();
+        Ok(spool)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records == 0
+    }
+
+    pub fn status(&self) -> Status {
+        Status {
+            segments: self.segments.len(),
+            records: self.records,
+            bytes: self.bytes(),
+        }
+    }
+
+    fn bytes(&self) -> u64 {
+        self.segments.iter().map(|e| e.bytes).sum()
+    }
+
+    fn update_metrics(&self) {
+        common::metrics::gauge_set(common::metrics::SPOOL_BYTES, &[], self.bytes() as f64);
+    }
+
+    ///
+    /// append a batch and return a handle of its segment, which the caller syncs to the disk.
+    /// the batch is dropped if the spool would exceed max_bytes.
+    pub fn append(
+        &mut self,
+        collection: &str,
+        records: &[serde_json::Value],
+    ) -> Result<File, String> {
+        let payload = match serde_json::to_vec(&PayloadRef {
+            collection,
+            records,
+        }) {
+            Ok(result) => result,
+            Err(result) => {
+                return Err(error_message!(
+                    "failed to serialize!\ndetails : {:?}",
+                    result
+                ));
+            }
+        };
+        let size = HEADER_BYTES + payload.len() as u64;
+        if self.bytes() + size > self.max_bytes {
+            common::metrics::counter_increment(
+                common::metrics::SPOOL_DROPPED_RECORDS_TOTAL,
+                &[("collection", collection)],
+                records.len() as f64,
+            );
+            return Err(error_message!(
+                "the spool is full!\nbytes : {}\nmax bytes : {}",
+                self.bytes(),
+                self.max_bytes
+            ));
+        }
+
+        let rotate = match self.segments.back() {
+            Some(segment) => self.active.is_none() || segment.bytes >= self.segment_bytes,
+            None => true,
+        };
+        if rotate {
+            let sequence = self.next_sequence;
+            let path = self
+                .directory
+                .join(format!("{:020}.{}", sequence, SEGMENT_EXTENSION));
+            let file = match OpenOptions::new().create_new(true).append(true).open(&path) {
+                Ok(result) => result,
+                Err(result) => {
+                    return Err(error_message!(
+                        "failed to create a segment!\ndetails : {:?}\npath : {:?}",
+                        result,
+                        path
+                    ));
+                }
+            };
+            self.next_sequence += 1;
+            self.active = Some(file);
+            self.segments.push_back(Segment {
+                sequence,
+                path,
+                bytes: 0,
+            });
+        }
+
+        let mut frame = Vec::with_capacity(size as usize);
+        frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        frame.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+        frame.extend_from_slice(&payload);
+        let file = self.active.as_mut().expect("should be opened!");
+        let written = file.write_all(&frame).and_then(|_| file.try_clone());
+        let file = match written {
+            Ok(result) => result,
+            Err(result) => {
+                // the partial frame is detected as truncated and the segment is not appended any more.
+                self.active = None;
+                return Err(error_message!(
+                    "failed to append to the spool!\ndetails : {:?}",
+                    result
+                ));
+            }
+        };
+        if let Some(segment) = self.segments.back_mut() {
+            segment.bytes += size;
+        }
+        self.records += records.len();
+        common::metrics::counter_increment(
+            common::metrics::SPOOLED_RECORDS_TOTAL,
+            &[("collection", collection)],
+            records.len() as f64,
+        );
+        self.update_metrics();
+        Ok(file)
+    }
+
+    ///
+    /// the oldest entry which was not replayed yet.
+    /// corrupt entries are skipped and consumed segments are removed on the way.
+    pub fn peek(&mut self) -> Result<Option<Entry>, String> {
+        loop {
+            let segment = match self.segments.front() {
+                Some(result) => result.clone(),
+                None => {
+                    self.records = 0;
+                    return Ok(None);
+                }
+            };
+            let is_active = self.active.is_some() && self.segments.len() == 1;
+            match read_frame(&segment.path, self.read_offset)? {
+                Frame::Entry(entry) => return Ok(Some(entry)),
+                // everything was read. records of corrupt entries are not counted any more.
+                Frame::End if is_active => {
+                    self.clear()?;
+                    self.update_metrics();
+                    return Ok(None);
+                }
+                Frame::Corrupt(next) => {
+                    tracing::warn!(segment = ?segment.path, offset = self.read_offset, "skipped a corrupt entry of the spool!");
+                    common::metrics::counter_increment(
+                        common::metrics::SPOOL_CORRUPT_ENTRIES_TOTAL,
+                        &[],
+                        1.0,
+                    );
+                    self.read_offset = next;
+                }
+                Frame::Truncated if is_active => return Ok(None),
+                frame => {
+                    if let Frame::Truncated = frame {
+                        tracing::warn!(segment = ?segment.path, offset = self.read_offset, "dropped the truncated end of a segment!");
+                        common::metrics::counter_increment(
+                            common::metrics::SPOOL_CORRUPT_ENTRIES_TOTAL,
+                            &[],
+                            1.0,
+                        );
+                    }
+                    remove_segment(&segment.path)?;
+                    self.segments.pop_front();
+                    self.read_offset = 0;
+                    self.update_metrics();
+                }
+            }
+        }
+    }
+
+    ///
+    /// mark the entry returned by peek as replayed.
+    pub fn advance(&mut self, entry: &Entry) -> Result<(), String> {
+        self.read_offset = entry.next;
+        self.records = self.records.saturating_sub(entry.records.len());
+        if self.records == 0 {
+            self.clear()?;
+        }
+        self.update_metrics();
+        Ok(())
+    }
+
+    ///
+    /// remove every segment. appending starts with a new one.
+    fn clear(&mut self) -> Result<(), String> {
+        self.active = None;
+        for segment in self.segments.drain(..) {
+            remove_segment(&segment.path)?;
+        }
+        self.read_offset = 0;
+        self.records = 0;
+        Ok(())
+    }
+}
+
+fn remove_segment(path: &Path) -> Result<(), String> {
+    match std::fs::remove_file(path) {
+        Ok(_) => Ok(()),
+        Err(result) if result.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(result) => Err(error_message!(
+            "failed to remove a segment!\ndetails : {:?}\npath : {:?}",
+            result,
+            path
+        )),
+    }
+}
+
+fn read_frame(path: &Path, offset: u64) -> Result<Frame, String> {
+    let io_error = |result: std::io::Error| {
+        error_message!(
+            "failed to read a segment!\ndetails : {:?}\npath : {:?}",
+            result,
+            path
+        )
+    };
+    let mut file = File::open(path).map_err(io_error)?;
+    let length = file.metadata().map_err(io_error)?.len();
+    if offset >= length {
+        return Ok(Frame::End);
+    }
+    if length - offset < HEADER_BYTES {
+        return Ok(Frame::Truncated);
+    }
+    file.seek(SeekFrom::Start(offset)).map_err(io_error)?;
+    let mut header = [0u8; HEADER_BYTES as usize];
+    file.read_exact(&mut header).map_err(io_error)?;
+    let size = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as u64;
+    let checksum = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+    let next = offset + HEADER_BYTES + size;
+    if next > length {
+        return Ok(Frame::Truncated);
+    }
+    let mut payload = vec![0u8; size as usize];
+    file.read_exact(&mut payload).map_err(io_error)?;
+    if crc32fast::hash(&payload) != checksum {
+        return Ok(Frame::Corrupt(next));
+    }
+    match serde_json::from_slice::<Entry>(&payload) {
+        Ok(mut entry) => {
+            entry.next = next;
+            Ok(Frame::Entry(entry))
+        }
+        Err(_) => Ok(Frame::Corrupt(next)),
+    }
+}
+
+///
+/// the spool shared by the writer, the replay task and the ipc handler.
+#[derive(Clone, Debug)]
+pub struct SharedSpool {
+    _spool: Arc<Mutex<Spool>>,
+    ///
+    /// held while replaying so that an entry is not inserted twice.
+    _replaying: Arc<Mutex<()>>,
+}
+
+impl SharedSpool {
+    pub fn new(spool: Spool) -> Self {
+        Self {
+            _spool: Arc::new(Mutex::new(spool)),
+            _replaying: Arc::new(Mutex::new(())),
+        }
+    }
+
+    ///
+    /// run `task` on the spool in a blocking thread, as it reads and writes the segment files.
+    async fn run<F, T>(&self, task: F) -> Result<T, String>
+    where
+        F: FnOnce(&mut Spool) -> Result<T, String> + Send + 'static,
+        T: Send + 'static,
+    {
+        let spool = self._spool.clone();
+        tokio::task::spawn_blocking(move || task(&mut spool.blocking_lock()))
+            .await
+            .expect("failed to run blocking task!")
+    }
+
+    ///
+    /// append a batch and sync it to the disk. the spool is not locked while syncing.
+    pub async fn append(
+        &self,
+        collection: &str,
+        records: &[serde_json::Value],
+    ) -> Result<(), String> {
+        let (collection, records) = (String::from(collection), records.to_vec());
+        let file = self
+            .run(move |spool| spool.append(&collection, &records))
+            .await?;
+        let synced = tokio::task::spawn_blocking(move || file.sync_data())
+            .await
+            .expect("failed to run blocking task!");
+        if let Err(result) = synced {
+            // the batch may not be on the disk, so the segment is not appended any more.
+            self._spool.lock().await.active = None;
+            return Err(error_message!(
+                "failed to sync the spool!\ndetails : {:?}",
+                result
+            ));
+        }
+        Ok(())
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self._spool.lock().await.is_empty()
+    }
+
+    pub async fn status(&self) -> Status {
+        self._spool.lock().await.status()
+    }
+
+    ///
+    /// insert the spooled entries in order until the spool is empty or an insertion fails.
+    /// an entry is removed only after it was inserted, so it may be inserted again after a crash.
+    pub async fn replay(&self, database: &dyn Store) -> Result<usize, String> {
+        let _replaying = self._replaying.lock().await;
+        let mut replayed = 0;
+        loop {
+            let entry = match self.run(|spool| spool.peek()).await? {
+                Some(result) => result,
+                None => return Ok(replayed),
+            };
+            let size = entry.records.len();
+            if let Err(result) = database
+                .insert(&entry.collection, &entry.records)
+                .instrument(tracing::info_span!(
+                    "spool_replay",
+                    collection = %entry.collection,
+                    documents = size
+                ))
+                .await
+            {
+                return Err(error_message!(
+                    "failed to replay the spool!\nreplayed : {}\ndetails : {}",
+                    replayed,
+                    result
+                ));
+            }
+            writer::update_candles(database, &entry.collection, &entry.records).await;
+            common::metrics::counter_increment(
+                common::metrics::SPOOL_REPLAYED_RECORDS_TOTAL,
+                &[("collection", &entry.collection)],
+                size as f64,
+            );
+            self.run(move |spool| spool.advance(&entry)).await?;
+            replayed += size;
+        }
+    }
+}
+
+///
+/// replay the spool every `replay_interval` milliseconds.
+pub fn spawn_replay(
+    spool: SharedSpool,
+    database: Arc<dyn Store>,
+    settings: &common::Spool,
+) -> tokio::task::JoinHandle<()> {
+    let replay_interval = std::time::Duration::from_millis(settings.replay_interval.max(1));
+    tokio::spawn(async move {
+        let mut timer = tokio::time::interval(replay_interval);
+        loop {
+            timer.tick().await;
+            if spool.is_empty().await {
+                continue;
+            }
+            match spool.replay(database.as_ref()).await {
+                Ok(replayed) => tracing::info!(records = replayed, "replayed the spool."),
+                Err(result) => tracing::warn!(details = %result, "the spool is kept."),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn execution(i: usize) -> serde_json::Value {
+        serde_json::json!({
+            "received_at": i as f64,
+            "timestamp": i as f64,
+            "created_at": i,
+            "price": 5_000_000,
+            "quantity": 0.01,
+            "taker_side": "sell",
+        })
+    }
+
+    #[tokio::test]
+    async fn replay_in_order_and_skip_corrupt_entries() {
+        let directory =
+            std::env::temp_dir().join(format!("liquid_loggerd_spool_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        let settings = common::Spool {
+            max_bytes: 4096,
+            segment_bytes: 256,
+            ..common::Spool::default()
+        };
+
+        // batches of 2 records span several segments.
+        let mut spool = Spool::open(&directory, &settings).unwrap();
+        for i in 0..4 {
+            spool
+                .append(
+                    common_constants::DATABASE_COLLECTION_EXECUTIONS,
+                    &[execution(i * 2), execution(i * 2 + 1)],
+                )
+                .unwrap();
+        }
+        assert!(spool.status().segments > 1);
+        assert_eq!(spool.status().records, 8);
+        let oversized = (0..100).map(execution).collect::<Vec<_>>();
+        assert!(spool
+            .append(common_constants::DATABASE_COLLECTION_EXECUTIONS, &oversized)
+            .is_err());
+        drop(spool);
+
+        // flip the last byte of the first segment, which holds the second batch.
+        let first = directory.join(format!("{:020}.{}", 0, SEGMENT_EXTENSION));
+        let mut content = std::fs::read(&first).unwrap();
+        let last = content.len() - 1;
+        content[last] ^= 0xff;
+        std::fs::write(&first, &content).unwrap();
+        let spool = Spool::open(&directory, &settings).unwrap();
+        assert_eq!(spool.status().records, 6);
+
+        let database = open_store("sqlite://:memory:").await.unwrap();
+        let spool = SharedSpool::new(spool);
+        assert_eq!(spool.replay(database.as_ref()).await, Ok(6));
+        assert!(spool.is_empty().await);
+        assert_eq!(spool.status().await, Status::default());
+        assert_eq!(
+            database
+                .query(
+                    common_constants::DATABASE_COLLECTION_EXECUTIONS,
+                    &Timeline::all(),
+                )
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|e| e["created_at"].as_u64().unwrap())
+                .collect::<Vec<_>>(),
+            vec![0, 1, 4, 5, 6, 7]
+        );
+        assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 0);
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tracing::Instrument;
+
+use super::*;
+
+#[derive(Debug)]
+struct Record {
+    collection: &'static str,
+    value: serde_json::Value,
+    queued_at: Instant,
+}
+
+///
+/// sender side of the write buffers.
+/// the writer flushes everything and stops once every handle is dropped.
+#[derive(Clone, Debug)]
+pub struct WriterHandle {
+    _transmitter: mpsc::Sender<Record>,
+}
+
+impl WriterHandle {
+    ///
+    /// queue a record. this waits while the queue is full.
+    pub async fn write<T: Serialize + std::fmt::Debug>(
+        &self,
+        collection: &'static str,
+        record: &T,
+    ) -> Result<(), String> {
+        let value = match serde_json::to_value(record) {
+            Ok(result) => result,
+            Err(result) => {
+                return Err(error_message!(
+                    "failed to serialize!\ndetails : {:?}\nreceived data : {:?}",
+                    result,
+                    record
+                ));
+            }
+        };
+        common::metrics::gauge_add(common::metrics::TASK_QUEUE_DEPTH, &[], 1.0);
+        if let Err(result) = self
+            ._transmitter
+            .send(Record {
+                collection,
+                value,
+                queued_at: Instant::now(),
+            })
+            .await
+        {
+            common::metrics::gauge_add(common::metrics::TASK_QUEUE_DEPTH, &[], -1.0);
+            return Err(error_message!(
+                "the writer was stopped!\ndetails : {:?}",
+                result
+            ));
+        }
+        Ok(())
+    }
+}
+
+///
+/// start the writer which buffers records per collection and inserts them in batches.
+/// batches go to the spool while it holds records, while the backlog exceeds the threshold,
+/// or when the insertion failed.
+pub fn spawn(
+    database: Arc<dyn Store>,
+    spool: spool::SharedSpool,
+    settings: &common::Writer,
+    backlog_threshold: usize,
+) -> (WriterHandle, tokio::task::JoinHandle<()>) {
+    let (transmitter, receiver) = mpsc::channel(settings.queue_capacity.max(1));
+    (
+        WriterHandle {
+            _transmitter: transmitter,
+        },
+        tokio::spawn(writer(
+            Destination {
+                database,
+                spool,
+                backlog_threshold,
+            },
+            receiver,
+            settings.clone(),
+        )),
+    )
+}
+
+struct Destination {
+    database: Arc<dyn Store>,
+    spool: spool::SharedSpool,
+    backlog_threshold: usize,
+}
+
+async fn writer(
+    destination: Destination,
+    mut receiver: mpsc::Receiver<Record>,
+    settings: common::Writer,
+) {
+    let batch_size = settings.batch_size.max(1);
+    let mut buffers = HashMap::<&'static str, Vec<Record>>::new();
+    let flush_interval = Duration::from_millis(settings.flush_interval.max(1));
+    let mut flush_timer =
+        tokio::time::interval_at(tokio::time::Instant::now() + flush_interval, flush_interval);
+    loop {
+        tokio::select! {
+            received = receiver.recv() => {
+                let record = match received {
+                    Some(result) => result,
+                    None => break,
+                };
+                let collection = record.collection;
+                let buffer = buffers.entry(collection).or_insert_with(|| Vec::with_capacity(batch_size));
+                buffer.push(record);
+                common::metrics::gauge_set(
+                    common::metrics::WRITE_BUFFERED_RECORDS,
+                    &[("collection", collection)],
+                    buffer.len() as f64,
+                );
+                if buffer.len() >= batch_size {
+                    let batch = std::mem::take(buffer);
+                    flush(&destination, collection, batch).await;
+                }
+            }
+            _ = flush_timer.tick() => {
+                for (collection, buffer) in buffers.iter_mut() {
+                    if !buffer.is_empty() {
+                        let batch = std::mem::take(buffer);
+                        flush(&destination, collection, batch).await;
+                    }
+                }
+            }
+        }
+    }
+
+    // every handle was dropped, so write out what is left.
+    for (collection, buffer) in buffers {
+        if !buffer.is_empty() {
+            flush(&destination, collection, buffer).await;
+        }
+    }
+    tracing::info!("write buffers were flushed.");
+}
+
+async fn flush(destination: &Destination, collection: &'static str, batch: Vec<Record>) {
+    let labels = [("collection", collection)];
+    let size = batch.len();
+    if let Some(oldest) = batch.iter().map(|e| e.queued_at).min() {
+        common::metrics::histogram_observe(
+            common::metrics::WRITE_BUFFER_WAIT_SECONDS,
+            &labels,
+            oldest.elapsed().as_secs_f64(),
+        );
+    }
+    common::metrics::histogram_observe(common::metrics::WRITE_BATCH_SIZE, &labels, size as f64);
+    common::metrics::gauge_set(common::metrics::WRITE_BUFFERED_RECORDS, &labels, 0.0);
+
+    let values = batch.into_iter().map(|e| e.value).collect::<Vec<_>>();
+    // keep the order by spooling while older records wait in the spool.
+    let backlog =
+        common::metrics::value(common::metrics::TASK_QUEUE_DEPTH, &[]).unwrap_or_default() as usize;
+    let inserted = if !destination.spool.is_empty().await {
+        Err(String::from("the spool holds older records."))
+    } else if backlog > destination.backlog_threshold {
+        Err(format!("the backlog has {} records.", backlog))
+    } else {
+        destination
+            .database
+            .insert(collection, &values)
+            .instrument(tracing::info_span!(
+                "db_write",
+                collection = collection,
+                documents = size
+            ))
+            .await
+    };
+    if inserted.is_ok() {
+        update_candles(destination.database.as_ref(), collection, &values).await;
+    }
+    if let Err(reason) = inserted {
+        match destination.spool.append(collection, &values).await {
+            Ok(()) => {
+                tracing::debug!(reason = %reason, documents = size, "spooled the records.");
+            }
+            Err(result) => {
+                tracing::error!(details = %result, reason = %reason, documents = size, "failed to create posts!");
+            }
+        }
+    }
+    common::metrics::gauge_add(common::metrics::TASK_QUEUE_DEPTH, &[], -(size as f64));
+}
+
+///
+/// roll inserted executions up into the candles of every resolution.
+pub async fn update_candles(database: &dyn Store, collection: &str, values: &[serde_json::Value]) {
+    if collection != common_constants::DATABASE_COLLECTION_EXECUTIONS {
+        return;
+    }
+    let times = values
+        .iter()
+        .filter_map(|e| e.get("timestamp").and_then(|e| e.as_f64()));
+    let (start, end) = match times.fold(None, |range: Option<(f64, f64)>, e| match range {
+        Some((start, end)) => Some((start.min(e), end.max(e))),
+        None => Some((e, e)),
+    }) {
+        Some(content) => content,
+        None => return,
+    };
+    if let Err(result) = candles::update(database, start, end)
+        .instrument(tracing::debug_span!("candles_update", start, end))
+        .await
+    {
+        tracing::warn!(details = %result, "failed to update the candles.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn flush_on_size_and_on_shutdown() {
+        let database = open_store("sqlite://:memory:").await.unwrap();
+        let settings = common::Writer {
+            batch_size: 2,
+            flush_interval: 60_000,
+            queue_capacity: 4,
+        };
+        let directory =
+            std::env::temp_dir().join(format!("liquid_loggerd_writer_{}", std::process::id()));
+        let spool = spool::Spool::open(&directory, &common::Spool::default()).unwrap();
+        let (handle, task) = spawn(
+            database.clone(),
+            spool::SharedSpool::new(spool),
+            &settings,
+            usize::MAX,
+        );
+        for i in 0..3 {
+            let execution = database::data::Execution {
+                received_at: i as f64,
+                timestamp: i as f64,
+                created_at: i,
+                price: 5_000_000,
+                quantity: 0.01,
+                taker_side: String::from("sell"),
+                id: Some(i as u64),
+            };
+            handle
+                .write(common_constants::DATABASE_COLLECTION_EXECUTIONS, &execution)
+                .await
+                .unwrap();
+        }
+
+        // the first two records reach batch_size.
+        let mut count = 0;
+        for _ in 0..100 {
+            count = database
+                .count(
+                    common_constants::DATABASE_COLLECTION_EXECUTIONS,
+                    &Timeline::all(),
+                )
+                .await
+                .unwrap();
+            if count == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(count, 2);
+
+        // the last one is written when the handle is dropped.
+        drop(handle);
+        task.await.unwrap();
+        assert_eq!(
+            database
+                .count(
+                    common_constants::DATABASE_COLLECTION_EXECUTIONS,
+                    &Timeline::all()
+                )
+                .await,
+            Ok(3)
+        );
+        assert_eq!(
+            database
+                .count(candles::Resolution::Second.collection(), &Timeline::all())
+                .await,
+            Ok(3)
+        );
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+}
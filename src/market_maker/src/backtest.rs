@@ -0,0 +1,159 @@
+use common::*;
+use database::backtest::{Context, Fill, OrderId};
+use database::data;
+use database::sweep::Parameters;
+use database::{Book, BookSide, Strategy};
+use liquid::data::Order;
+
+use crate::misc;
+use crate::trader::{self, Stats};
+
+///
+/// the tunables of the quotes which can be swept.
+pub const PARAMETERS: [&str; 4] = ["evaluation_time", "limited_time", "offset_unit", "quantity"];
+
+#[derive(Clone, Debug)]
+struct Quote {
+    id: OrderId,
+    quantity: f64,
+    filled: f64,
+}
+
+impl Quote {
+    fn is_filled(&self) -> bool {
+        self.filled >= self.quantity - 1e-12
+    }
+}
+
+///
+/// the quoting of the trader replayed on the backtester.
+/// a buy and a sell are posted at the prices of the statistics of the order books,
+/// and they are judged as in `post_detailed_order` when both are filled or `limited_time` passed.
+#[derive(Clone, Debug)]
+pub struct QuoteStrategy {
+    evaluation_time: f64,
+    limited_time: f64,
+    quantity: f64,
+    stats: Stats,
+    ///
+    /// the buy, the sell and when they were posted
+    quotes: Option<(Quote, Quote, f64)>,
+}
+
+impl QuoteStrategy {
+    ///
+    /// the strategy of `config`, with the values of `parameters` in place of its own.
+    pub fn from_parameters(parameters: &Parameters, config: &misc::Config) -> Result<Self, String> {
+        if let Some(name) = parameters
+            .keys()
+            .find(|e| !PARAMETERS.contains(&e.as_str()))
+        {
+            return Err(error_message!("unknown parameter!\ndetails : {}", name));
+        }
+        let get = |name: &str, default: f64| *parameters.get(name).unwrap_or(&default);
+        let strategy = Self {
+            evaluation_time: get("evaluation_time", config.evaluation_time),
+            limited_time: get("limited_time", config.limited_time),
+            quantity: get("quantity", config.quantity),
+            stats: Stats::new(
+                get("offset_unit", config.offset_unit as f64).round() as i32,
+                &misc::State::default(),
+            ),
+            quotes: None,
+        };
+        if strategy.evaluation_time <= 0.0 || strategy.limited_time <= 0.0 {
+            return Err(error_message!("invalid periods! {:?}", parameters));
+        }
+        Ok(strategy)
+    }
+
+    fn update(&mut self, context: &mut Context) {
+        if let Some((buy, sell, posted_at)) = &self.quotes {
+            let (buy_filled, sell_filled) = (buy.is_filled(), sell.is_filled());
+            if !(buy_filled && sell_filled) && context.now() - posted_at < self.limited_time {
+                return;
+            }
+            match (buy_filled, sell_filled) {
+                (true, true) => self.stats.succeed(),
+                (true, false) => {
+                    self.stats.fail();
+                    self.stats.decrease_offset();
+                }
+                (false, true) => {
+                    self.stats.fail();
+                    self.stats.increase_offset();
+                }
+                (false, false) => {
+                    context.cancel(buy.id);
+                    context.cancel(sell.id);
+                }
+            }
+            self.quotes = None;
+        }
+
+        if self.stats.cross_point_price.is_empty() {
+            return;
+        }
+        let (lower_price, higher_price) = match trader::quote_prices(&self.stats) {
+            Ok(result) => result,
+            Err(()) => return,
+        };
+        let buy = match context.place(BookSide::Buy, lower_price, self.quantity) {
+            Ok(result) => result,
+            Err(result) => {
+                tracing::debug!(details = %result, "failed to place!");
+                return;
+            }
+        };
+        let sell = match context.place(BookSide::Sell, higher_price, self.quantity) {
+            Ok(result) => result,
+            Err(result) => {
+                tracing::debug!(details = %result, "failed to place!");
+                context.cancel(buy);
+                return;
+            }
+        };
+        let quote = |id| Quote {
+            id,
+            quantity: self.quantity,
+            filled: 0.0,
+        };
+        self.quotes = Some((quote(buy), quote(sell), context.now()));
+    }
+}
+
+fn order_book(received_at: f64, orders: &[Order]) -> data::OrderBook {
+    data::OrderBook {
+        received_at,
+        orders: orders.to_vec(),
+    }
+}
+
+impl Strategy for QuoteStrategy {
+    fn on_book(&mut self, book: &Book, context: &mut Context) {
+        if !book.buy.is_empty() && !book.sell.is_empty() {
+            self.stats.calculate_stats_at(
+                book.received_at,
+                &order_book(book.received_at, &book.buy),
+                &order_book(book.received_at, &book.sell),
+            );
+            self.stats
+                .vacuume_at(book.received_at, self.evaluation_time);
+        }
+        self.update(context);
+    }
+
+    fn on_execution(&mut self, _execution: &data::Execution, context: &mut Context) {
+        self.update(context);
+    }
+
+    fn on_fill(&mut self, fill: &Fill, _context: &mut Context) {
+        if let Some((buy, sell, _)) = &mut self.quotes {
+            for quote in [buy, sell] {
+                if quote.id == fill.order_id {
+                    quote.filled += fill.quantity;
+                }
+            }
+        }
+    }
+}
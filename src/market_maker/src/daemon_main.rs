@@ -13,22 +13,10 @@ use crate::misc;
 use crate::trader;
 
 pub async fn daemon_main(ident: &str) {
-    //
-    // initialize systemd journal
-    if let Err(result) =
-        systemd_journal_logger::init_with_extra_fields(vec![("VERSION", env!("CARGO_PKG_VERSION"))])
-    {
-        log::error!(
-            "failed to initialize the journal!\n-->\ndetails : {:?}\n<--",
-            result
-        );
-        return;
-    };
-
     //
     // check wether systemd is running or not
     if !daemon::booted() {
-        log::error!("systemd is reuqired!");
+        tracing::error!("systemd is reuqired!");
         return;
     };
 
@@ -37,21 +25,17 @@ pub async fn daemon_main(ident: &str) {
     let watchdog_duration = match daemon::watchdog_enabled(true) {
         Some(result) => result,
         None => {
-            log::error!("watchdog is diabled! WatchdogSec must be set.");
+            tracing::error!("watchdog is diabled! WatchdogSec must be set.");
             return;
         }
     };
 
-    //
-    // set logging level
-    log::set_max_level(log::LevelFilter::Info);
-
     //
     // load configurtation
     let env = match load_env("./env.toml").await {
         Ok(result) => Arc::new(result),
         Err(result) => {
-            log::error!("failed to load!\n-->\ndetails : {}\n<--", result);
+            tracing::error!(details = %result, "failed to load!");
             return;
         }
     };
@@ -65,10 +49,44 @@ pub async fn daemon_main(ident: &str) {
         }
     };
 
+    //
+    // initialize the journal and the json lines log
+    let log_handle = match common::logging::init(
+        &(String::from(env!("CARGO_PKG_NAME")) + "_" + &config.identifier),
+        env!("CARGO_PKG_VERSION"),
+        &env.general.log_directory_path,
+        env.general.log_directive.as_deref().unwrap_or("info"),
+    ) {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", result);
+            return;
+        }
+    };
+
+    // serve metrics if a port is configured
+    if let Some(port) = env
+        .metrics
+        .get(&(String::from(env!("CARGO_PKG_NAME")) + "_" + &config.identifier))
+    {
+        if let Err(result) = common::metrics::serve(*port).await {
+            tracing::error!(details = %result, "failed to serve metrics!");
+            return;
+        }
+        tracing::info!(port = port, "serving metrics.");
+    }
+
     //
     // prevent same program running
     if let Err(result) = misc::lock_file(&env.general.etc_directory_path, &config.identifier) {
-        log::error!("failed to lock the file!\n-->\ndetails : {}\n<--", result);
+        tracing::error!(details = %result, "failed to lock the file!");
+        return;
+    }
+    if let Err(result) = common::write_pid_file(
+        &env.general.etc_directory_path,
+        &(String::from(env!("CARGO_PKG_NAME")) + "_" + &config.identifier),
+    ) {
+        tracing::error!(details = %result, "failed to write the pid file!");
         return;
     }
 
@@ -77,20 +95,14 @@ pub async fn daemon_main(ident: &str) {
     let mut sig_term = match signal(SignalKind::terminate()) {
         Ok(result) => result,
         Err(result) => {
-            log::error!(
-                "failed to initialize SIGTERM!\n-->\ndetails : {:?}\n<--",
-                result
-            );
+            tracing::error!(details = ?result, "failed to initialize SIGTERM!");
             return;
         }
     };
     let mut sig_hangup = match signal(SignalKind::hangup()) {
         Ok(result) => result,
         Err(result) => {
-            log::error!(
-                "failed to initialize SIGHUP!\n-->\ndetails : {:?}\n<--",
-                result
-            );
+            tracing::error!(details = ?result, "failed to initialize SIGHUP!");
             return;
         }
     };
@@ -113,7 +125,7 @@ pub async fn daemon_main(ident: &str) {
     {
         Ok(result) => result,
         Err(_) => {
-            log::error!("failed to open the unix domai socket!");
+            tracing::error!("failed to open the unix domai socket!");
             return;
         }
     };
@@ -134,29 +146,32 @@ pub async fn daemon_main(ident: &str) {
     //
     // intialize trader
     let (trader_trans, trader_recv) = trader::new_trader_event_channel();
+    let trader_status = trader::SharedTraderStatus::default();
 
-    log::info!("{} was initialized.", env!("CARGO_PKG_NAME"));
+    tracing::info!("{} was initialized.", env!("CARGO_PKG_NAME"));
 
     // notify systemd that ready
     if let Err(result) = daemon::notify(false, &[daemon::NotifyState::Ready]) {
-        log::error!(
-            "failed to notify systemd!\n-->\ndetails : {:?}\n<--",
-            result
-        );
+        tracing::error!(details = ?result, "failed to notify systemd!");
         return;
     }
 
     //
     // start main trading tasks
     {
-        tokio::spawn(trader::trader(trader_recv, config.clone(), env.clone()));
+        tokio::spawn(trader::trader(
+            trader_recv,
+            config.clone(),
+            env.clone(),
+            trader_status.clone(),
+        ));
         if let Err(_) =
             trader::get_old_market(database.clone(), trader_trans.clone(), config.clone()).await
         {
             return;
         }
         if let Err(result) = trader_trans.post_booted().await {
-            log::error!("failed to shutdown!\n-->\ndetails : {}\n<--", result);
+            tracing::error!(details = %result, "failed to shutdown!");
         }
     }
 
@@ -167,10 +182,15 @@ pub async fn daemon_main(ident: &str) {
             _ = sig_term.recv() => {
                 break;
             }
-            _ = sig_hangup.recv() => {}
+            _ = sig_hangup.recv() => {
+                match log_handle.reload("./env.toml").await {
+                    Ok(directive) => tracing::info!(directive = %directive, "reloaded."),
+                    Err(result) => tracing::error!(details = %result, "failed to reload!"),
+                }
+            }
             _ = watchdog_timer.tick() => {
                 if let Err(result) = daemon::notify(false, &[daemon::NotifyState::Watchdog]) {
-                    log::error!("failed to notify systemd that watchdog!\n-->\ndetails : {:?}\n<--", result);
+                    tracing::error!(details = ?result, "failed to notify systemd that watchdog!");
                     break;
                 }
             }
@@ -184,12 +204,12 @@ pub async fn daemon_main(ident: &str) {
                 match listened {
                     Ok(stream) => {
                         let transmitter_copied = transmitter.clone();
-                        tokio::spawn(handler::ipc_handler(stream, transmitter_copied));
+                        tokio::spawn(handler::ipc_handler(stream, transmitter_copied, log_handle.clone(), database.clone(), trader_status.clone(), env.clone(), config.clone()));
                     }
                     Err(result) => match result.kind() {
                         std::io::ErrorKind::WouldBlock => {}
                         _ => {
-                            log::error!("cannot listen to ipc!\n-->\ndetails : {}\n<--", result);
+                            tracing::error!(details = %result, "cannot listen to ipc!");
                             break;
                         }
                     }
@@ -205,17 +225,17 @@ pub async fn daemon_main(ident: &str) {
                     }
                     Err(result) => {
                         'trials: for i in 1..=3 {
-                            log::warn!(
-                                "cannot check!\n-->\ndetails : {}\n<--", result
-                            );
-                            log::info!("reinitialize connection to liquid tap due to a previous error. {} / 3 trials", i);
+                            tracing::warn!(details = %result, "cannot check!");
+                            common::health::set_tap_connected(false);
+                            tracing::info!(trial = i, trials = 3, "reinitialize connection to liquid tap due to a previous error.");
+                            common::metrics::counter_increment(common::metrics::TAP_RECONNECTS_TOTAL, &[], 1.0);
                             match initialize::initialize_liquid_tap(&config.key, config.currency_pair).await {
                                 Ok(result) => {
                                     client = result;
                                     break 'trials;
                                 },
                                 Err(_) => if i >= 3 {
-                                    log::error!("failed to reinitialize connection to liquid tap due to exceeded trial limits!");
+                                    tracing::error!("failed to reinitialize connection to liquid tap due to exceeded trial limits!");
                                     break 'main;
                                 },
                             };
@@ -224,14 +244,14 @@ pub async fn daemon_main(ident: &str) {
                 };
             }
             else => {
-                log::error!("liquid tap was timeout! {} would shutdown!", env!("CARGO_PKG_NAME"));
+                tracing::error!("liquid tap was timeout! {} would shutdown!", env!("CARGO_PKG_NAME"));
                 break;
             }
         };
     }
 
     if let Err(result) = trader_trans.post_shudown().await {
-        log::error!("failed to shutdown!\n-->\ndetails : {}\n<--", result);
+        tracing::error!(details = %result, "failed to shutdown!");
     }
     tokio::task::yield_now().await;
 }
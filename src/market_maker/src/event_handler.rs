@@ -1,4 +1,3 @@
-use common::*;
 use liquid::*;
 
 use crate::trader::TraderEventSender;
@@ -11,9 +10,9 @@ pub async fn product_handler(
     if let Some(content) = &response.event {
         match content.as_str() {
             "pusher_internal:subscription_succeeded" => {
-                log::info!(
-                    "subscribing {} succeeded.",
-                    response.channel.as_ref().unwrap()
+                tracing::info!(
+                    channel = response.channel.as_deref().unwrap_or_default(),
+                    "subscribing succeeded."
                 );
             }
 
@@ -21,10 +20,11 @@ pub async fn product_handler(
                 let data = match liquid_tap::generate_product(&response.data) {
                     Ok(result) => result,
                     Err(result) => {
-                        log::warn!(
-                            "failed to generate!\n-->\ndetails : {}\nresponse : {:?}<--",
-                            result,
-                            response
+                        tracing::warn!(details = %result, response = ?response, "failed to generate!");
+                        common::metrics::counter_increment(
+                            common::metrics::TAP_PARSE_FAILURES_TOTAL,
+                            &[("channel", response.channel.as_deref().unwrap_or_default())],
+                            1.0,
                         );
                         return Err(());
                     }
@@ -46,12 +46,12 @@ pub async fn product_handler(
                     high_market_price_24h: data.high_market_ask,
                 };
                 if let Err(result) = trader_trans.post_ticker(ticker).await {
-                    log::error!("failed to post to trader!\n-->\ndetails : {}\n<--", result);
+                    tracing::error!(details = %result, "failed to post to trader!");
                     return Err(());
                 }
             }
             _ => {
-                log::warn!("unknown event!\n-->\ndetails : {:?}\n<--", response);
+                tracing::warn!(details = ?response, "unknown event!");
             }
         }
     }
@@ -66,9 +66,9 @@ pub async fn executions_handler(
     if response.event.as_ref().is_some() {
         match response.event.as_ref().unwrap().as_str() {
             "pusher_internal:subscription_succeeded" => {
-                log::info!(
-                    "subscribing {} succeeded.",
-                    response.channel.as_ref().unwrap()
+                tracing::info!(
+                    channel = response.channel.as_deref().unwrap_or_default(),
+                    "subscribing succeeded."
                 );
             }
 
@@ -76,10 +76,11 @@ pub async fn executions_handler(
                 let data = match liquid_tap::generate_execution(&response.data) {
                     Ok(result) => result,
                     Err(result) => {
-                        log::warn!(
-                            "failed to generate!\n-->\ndetails : {}\nresponse : {:?}<--",
-                            result,
-                            response
+                        tracing::warn!(details = %result, response = ?response, "failed to generate!");
+                        common::metrics::counter_increment(
+                            common::metrics::TAP_PARSE_FAILURES_TOTAL,
+                            &[("channel", response.channel.as_deref().unwrap_or_default())],
+                            1.0,
                         );
                         return Err(());
                     }
@@ -90,19 +91,20 @@ pub async fn executions_handler(
                         .unwrap()
                         .as_secs_f64(),
                     timestamp: data.timestamp,
-                    created_at: data.created_at as i32,
+                    created_at: i64::from(data.created_at),
                     price: data.price,
                     quantity: data.quantity,
                     taker_side: data.taker_side,
+                    id: Some(data.id),
                 };
                 if let Err(result) = trader_trans.post_execution(execution).await {
-                    log::error!("failed to post to trader!\n-->\ndetails : {}\n<--", result);
+                    tracing::error!(details = %result, "failed to post to trader!");
                     return Err(());
                 }
             }
 
             _ => {
-                log::warn!("unknown event!\n-->\ndetails : {:?}\n<--", response);
+                tracing::warn!(details = ?response, "unknown event!");
             }
         }
     }
@@ -117,9 +119,9 @@ pub async fn order_book_buy(
     if response.event.as_ref().is_some() {
         match response.event.as_ref().unwrap().as_str() {
             "pusher_internal:subscription_succeeded" => {
-                log::info!(
-                    "subscribing {} succeeded.",
-                    response.channel.as_ref().unwrap()
+                tracing::info!(
+                    channel = response.channel.as_deref().unwrap_or_default(),
+                    "subscribing succeeded."
                 );
             }
 
@@ -127,10 +129,11 @@ pub async fn order_book_buy(
                 let data = match liquid_tap::generate_order_book(&response.data) {
                     Ok(result) => result,
                     Err(result) => {
-                        log::warn!(
-                            "failed to generate!\n-->\ndetails : {}\nresponse : {:?}<--",
-                            result,
-                            response
+                        tracing::warn!(details = %result, response = ?response, "failed to generate!");
+                        common::metrics::counter_increment(
+                            common::metrics::TAP_PARSE_FAILURES_TOTAL,
+                            &[("channel", response.channel.as_deref().unwrap_or_default())],
+                            1.0,
                         );
                         return Err(());
                     }
@@ -146,13 +149,13 @@ pub async fn order_book_buy(
                     .post_order_book_buy(order_book_buy_element)
                     .await
                 {
-                    log::error!("failed to post to trader!\n-->\ndetails : {}\n<--", result);
+                    tracing::error!(details = %result, "failed to post to trader!");
                     return Err(());
                 }
             }
 
             _ => {
-                log::warn!("unknown event!\n-->\ndetails : {:?}\n<--", response);
+                tracing::warn!(details = ?response, "unknown event!");
             }
         }
     }
@@ -167,9 +170,9 @@ pub async fn order_book_sell(
     if response.event.as_ref().is_some() {
         match response.event.as_ref().unwrap().as_str() {
             "pusher_internal:subscription_succeeded" => {
-                log::info!(
-                    "subscribing {} succeeded.",
-                    response.channel.as_ref().unwrap()
+                tracing::info!(
+                    channel = response.channel.as_deref().unwrap_or_default(),
+                    "subscribing succeeded."
                 );
             }
 
@@ -177,10 +180,11 @@ pub async fn order_book_sell(
                 let data = match liquid_tap::generate_order_book(&response.data) {
                     Ok(result) => result,
                     Err(result) => {
-                        log::warn!(
-                            "failed to generate!\n-->\ndetails : {}\nresponse : {:?}<--",
-                            result,
-                            response
+                        tracing::warn!(details = %result, response = ?response, "failed to generate!");
+                        common::metrics::counter_increment(
+                            common::metrics::TAP_PARSE_FAILURES_TOTAL,
+                            &[("channel", response.channel.as_deref().unwrap_or_default())],
+                            1.0,
                         );
                         return Err(());
                     }
@@ -196,13 +200,13 @@ pub async fn order_book_sell(
                     .post_order_book_sell(order_book_sell_element)
                     .await
                 {
-                    log::error!("failed to post to trader!\n-->\ndetails : {}\n<--", result);
+                    tracing::error!(details = %result, "failed to post to trader!");
                     return Err(());
                 }
             }
 
             _ => {
-                log::warn!("unknown event!\n-->\ndetails : {:?}\n<--", response);
+                tracing::warn!(details = ?response, "unknown event!");
             }
         }
     }
@@ -216,14 +220,14 @@ pub async fn private_orders(
     if let Some(content) = &response.event {
         match content.as_str() {
             "pusher_internal:subscription_succeeded" => {
-                log::info!(
-                    "subscribing {} succeeded.",
-                    response.channel.as_ref().unwrap()
+                tracing::info!(
+                    channel = response.channel.as_deref().unwrap_or_default(),
+                    "subscribing succeeded."
                 );
             }
 
             "pusher_internal:subscription_failed" => {
-                log::error!("subscribing {} failed!", response.channel.as_ref().unwrap());
+                tracing::error!(channel = ?response.channel, "subscribing failed!");
                 return Err(());
             }
 
@@ -231,24 +235,22 @@ pub async fn private_orders(
                 let data = match liquid_tap::generate_order_statuts(&response.data) {
                     Ok(result) => result,
                     Err(result) => {
-                        log::warn!(
-                            "failed to generate!\n-->\ndetails : {}\nresponse : {:?}<--",
-                            result,
-                            response
+                        tracing::warn!(details = %result, response = ?response, "failed to generate!");
+                        common::metrics::counter_increment(
+                            common::metrics::TAP_PARSE_FAILURES_TOTAL,
+                            &[("channel", response.channel.as_deref().unwrap_or_default())],
+                            1.0,
                         );
                         return Err(());
                     }
                 };
                 if let Err(result) = trader_trans.post_postion_from_order(data).await {
-                    log::error!("failed to post to trader!\n-->\ndetails : {}\n<--", result);
+                    tracing::error!(details = %result, "failed to post to trader!");
                     return Err(());
                 }
             }
             _ => {
-                log::warn!(
-                    "{}",
-                    error_message!("unknown event!\n-->\ndetails : {:?}\n<--", response)
-                );
+                tracing::warn!(response = ?response, "unknown event!");
             }
         }
     }
@@ -262,14 +264,14 @@ pub async fn private_trades(
     if let Some(content) = &response.event {
         match content.as_str() {
             "pusher_internal:subscription_succeeded" => {
-                log::info!(
-                    "subscribing {} succeeded.",
-                    response.channel.as_ref().unwrap()
+                tracing::info!(
+                    channel = response.channel.as_deref().unwrap_or_default(),
+                    "subscribing succeeded."
                 );
             }
 
             "pusher_internal:subscription_failed" => {
-                log::error!("subscribing {} failed!", response.channel.as_ref().unwrap());
+                tracing::error!(channel = ?response.channel, "subscribing failed!");
                 return Err(());
             }
 
@@ -277,39 +279,38 @@ pub async fn private_trades(
                 let data = match liquid_tap::generate_trades_update(&response.data) {
                     Ok(result) => result,
                     Err(result) => {
-                        log::warn!(
-                            "failed to generate!\n-->\ndetails : {}\nresponse : {:?}<--",
-                            result,
-                            response
+                        tracing::warn!(details = %result, response = ?response, "failed to generate!");
+                        common::metrics::counter_increment(
+                            common::metrics::TAP_PARSE_FAILURES_TOTAL,
+                            &[("channel", response.channel.as_deref().unwrap_or_default())],
+                            1.0,
                         );
                         return Err(());
                     }
                 };
                 if let Err(result) = trader_trans.post_postion_from_trade(data).await {
-                    log::error!("failed to post to trader!\n-->\ndetails : {}\n<--", result);
+                    tracing::error!(details = %result, "failed to post to trader!");
                 }
             }
             "pnl_updated" => {
                 let data = match liquid_tap::generate_trades_panel_update(&response.data) {
                     Ok(result) => result,
                     Err(result) => {
-                        log::warn!(
-                            "failed to generate!\n-->\ndetails : {}\nresponse : {:?}<--",
-                            result,
-                            response
+                        tracing::warn!(details = %result, response = ?response, "failed to generate!");
+                        common::metrics::counter_increment(
+                            common::metrics::TAP_PARSE_FAILURES_TOTAL,
+                            &[("channel", response.channel.as_deref().unwrap_or_default())],
+                            1.0,
                         );
                         return Err(());
                     }
                 };
                 if let Err(result) = trader_trans.post_postion_from_panel(data).await {
-                    log::error!("failed to post to trader!\n-->\ndetails : {}\n<--", result);
+                    tracing::error!(details = %result, "failed to post to trader!");
                 }
             }
             _ => {
-                log::warn!(
-                    "{}",
-                    error_message!("unknown event!\n-->\ndetails : {:?}\n<--", response)
-                );
+                tracing::warn!(response = ?response, "unknown event!");
             }
         }
     }
@@ -323,23 +324,20 @@ pub async fn private_executions(
     if let Some(content) = &response.event {
         match content.as_str() {
             "pusher_internal:subscription_succeeded" => {
-                log::info!(
-                    "subscribing {} succeeded.",
-                    response.channel.as_ref().unwrap()
+                tracing::info!(
+                    channel = response.channel.as_deref().unwrap_or_default(),
+                    "subscribing succeeded."
                 );
             }
 
             "pusher_internal:subscription_failed" => {
-                log::error!("subscribing {} failed!", response.channel.as_ref().unwrap());
+                tracing::error!(channel = ?response.channel, "subscribing failed!");
                 return Err(());
             }
 
             "created" => {}
             _ => {
-                log::warn!(
-                    "{}",
-                    error_message!("unknown event!\n-->\ndetails : {:?}\n<--", response)
-                );
+                tracing::warn!(response = ?response, "unknown event!");
             }
         }
     }
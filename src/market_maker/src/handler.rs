@@ -1,14 +1,20 @@
 use common::*;
 
+use database::sweep::{self, SearchSpace, SweepConfig};
+use serde::Deserialize;
+
+use crate::backtest::QuoteStrategy;
 use crate::daemon_main;
+use crate::misc;
+use crate::trader;
 
-pub async fn status_handler(ident: &str) {
+pub async fn status_handler(ident: &str, show_metrics: bool, json: bool) -> i32 {
     // load configurtation
     let env = match load_env("./env.toml").await {
         Ok(result) => result,
         Err(result) => {
-            log::error!("failed to load!\n-->\ndetails : {}\n<--", result);
-            return;
+            tracing::error!(details = %result, "failed to load!");
+            return 1;
         }
     };
 
@@ -27,7 +33,7 @@ pub async fn status_handler(ident: &str) {
                 "{}",
                 error_message_colored!("failed connect to {}!", env!("CARGO_PKG_NAME"))
             );
-            return;
+            return 1;
         }
     };
 
@@ -36,7 +42,7 @@ pub async fn status_handler(ident: &str) {
             "{}",
             error_message_colored!("failed to send a command!\n-->\ndetails : {}\n<--", result)
         );
-        return;
+        return 1;
     }
 
     let result: ipc::Command = match stream.receive_command().await {
@@ -49,14 +55,11 @@ pub async fn status_handler(ident: &str) {
                     result
                 )
             );
-            return;
+            return 1;
         }
     };
     let data = result.messages.expect("should contain!");
-    println!(
-        "name : {}\nversion : {}\ndescription : {}\nstatus : {}",
-        data["name"], data["version"], data["description"], data["status"]
-    );
+    common::health::print_status(&data, json, show_metrics)
 }
 
 pub async fn start_handler(ident: &str) {
@@ -68,7 +71,7 @@ pub async fn shutdown_handler(ident: &str) {
     let env = match load_env("./env.toml").await {
         Ok(result) => result,
         Err(result) => {
-            log::error!("failed to load!\n-->\ndetails : {}\n<--", result);
+            tracing::error!(details = %result, "failed to load!");
             return;
         }
     };
@@ -104,23 +107,239 @@ pub async fn shutdown_handler(ident: &str) {
     }
 }
 
+pub async fn log_level_handler(ident: &str, directive: &str) {
+    // load configurtation
+    let env = match load_env("./env.toml").await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!(
+                "{}",
+                error_message_colored!("failed to load!\n-->\ndetails : {}\n<--", result)
+            );
+            return;
+        }
+    };
+
+    let mut stream = match ipc::StreamAsync::open(
+        &(env.general.etc_directory_path
+            + env!("CARGO_PKG_NAME")
+            + "_"
+            + ident
+            + common_constants::SOCKET_EXTENSION),
+    )
+    .await
+    {
+        Ok(result) => result,
+        Err(_) => {
+            eprintln!(
+                "{}",
+                error_message_colored!("failed connect to {}!", env!("CARGO_PKG_NAME"))
+            );
+            return;
+        }
+    };
+
+    if let Err(result) = stream
+        .send_command(ipc::Command::log_level(directive))
+        .await
+    {
+        eprintln!(
+            "{}",
+            error_message_colored!("failed to send a command!\n-->\ndetails : {}\n<--", result)
+        );
+        return;
+    }
+
+    let result: ipc::Command = match stream.receive_command().await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!(
+                "{}",
+                error_message_colored!(
+                    "failed to receive a command!\n-->\ndetails : {}\n<--",
+                    result
+                )
+            );
+            return;
+        }
+    };
+    let data = result.messages.expect("should contain!");
+    if let Some(content) = data.get(common_constants::DEFINED_COMMAND_OPTION_ERROR) {
+        eprintln!("{}", error_message_colored!("{}", content));
+    } else if let Some(content) = data.get(common_constants::DEFINED_COMMAND_OPTION_SUCCESS) {
+        println!("{}", ok_message_colored!("directive : {}", content));
+    }
+}
+
+///
+/// the file given to `sweep`.
+#[derive(Debug, Deserialize)]
+struct SweepSpec {
+    ///
+    /// the balances each run starts with
+    jpy: f64,
+    btc: f64,
+    space: SearchSpace,
+    #[serde(default)]
+    config: SweepConfig,
+}
+
+pub async fn sweep_handler(
+    ident: &str,
+    spec_path: &str,
+    from: &str,
+    to: &str,
+    output: &str,
+    symbol: Option<&str>,
+) -> i32 {
+    let symbol = match symbol {
+        Some(result) => match Symbol::generate_from_string(result) {
+            Ok(result) => result,
+            Err(result) => {
+                eprintln!("{}", error_message_colored!("{}", result));
+                return 1;
+            }
+        },
+        None => Symbol::default(),
+    };
+    // load configurtation
+    let env = match load_env("./env.toml").await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!(
+                "{}",
+                error_message_colored!("failed to load!\n-->\ndetails : {}\n<--", result)
+            );
+            return 1;
+        }
+    };
+    let config = match misc::Config::load(&(String::from(ident) + ".toml")).await {
+        Ok(result) => result,
+        Err(()) => {
+            eprintln!(
+                "{}",
+                error_message_colored!("failed to load {}.toml!", ident)
+            );
+            return 1;
+        }
+    };
+    let spec = match tokio::fs::read_to_string(spec_path).await {
+        Ok(content) => match toml::from_str::<SweepSpec>(&content) {
+            Ok(result) => result,
+            Err(result) => {
+                eprintln!(
+                    "{}",
+                    error_message_colored!(
+                        "failed to deserialize!\n-->\ndetails : {}\n<--",
+                        result
+                    )
+                );
+                return 1;
+            }
+        },
+        Err(result) => {
+            eprintln!(
+                "{}",
+                error_message_colored!("failed to load!\n-->\ndetails : {}\n<--", result)
+            );
+            return 1;
+        }
+    };
+    let timeline = match (
+        chrono::DateTime::parse_from_rfc3339(from),
+        chrono::DateTime::parse_from_rfc3339(to),
+    ) {
+        (Ok(from), Ok(to)) => database::Timeline::new(&from, &to),
+        _ => {
+            eprintln!(
+                "{}",
+                error_message_colored!("invalid period! {} - {}", from, to)
+            );
+            return 1;
+        }
+    };
+
+    let result = async {
+        let candidates = spec.space.candidates()?;
+        let database = database::open_store_for(&env.general.database_url, &symbol).await?;
+        // the events are loaded once and every run replays the same slice of them.
+        let events = database::backtest::load_events(&*database, &timeline).await?;
+        let fees = config.fees.clone();
+        let product = symbol.product;
+        let (jpy, btc) = (spec.jpy, spec.btc);
+        let sweep_config = spec.config;
+        let results = match tokio::task::spawn_blocking(move || {
+            sweep::run(
+                &events,
+                &candidates,
+                &sweep_config,
+                |parameters| QuoteStrategy::from_parameters(parameters, &config),
+                || {
+                    database::simulation::Simulation::new(jpy, btc)
+                        .with_fees(fees.clone(), &product)
+                },
+            )
+        })
+        .await
+        {
+            Ok(result) => result?,
+            Err(result) => return Err(error_message!("failed to sweep!\ndetails : {}", result)),
+        };
+        sweep::write_results(output, &results).await?;
+        Ok::<_, String>(results.len())
+    }
+    .await;
+
+    match result {
+        Ok(runs) => {
+            println!(
+                "{}",
+                ok_message_colored!(
+                    "{} runs were written to {}.csv and {}.json",
+                    runs,
+                    output,
+                    output
+                )
+            );
+            0
+        }
+        Err(result) => {
+            eprintln!(
+                "{}",
+                error_message_colored!("failed to sweep!\n-->\ndetails : {}\n<--", result)
+            );
+            1
+        }
+    }
+}
+
 pub async fn ipc_handler(
     mut stream: ipc::StreamAsync,
     transmitter: tokio::sync::mpsc::Sender<ipc::Command>,
+    log_handle: common::logging::LogHandle,
+    database: std::sync::Arc<dyn database::Store>,
+    trader_status: trader::SharedTraderStatus,
+    env: std::sync::Arc<Enviornment>,
+    config: std::sync::Arc<misc::Config>,
 ) {
     let result: ipc::Command = match stream.receive_command().await {
         Ok(result) => result,
         Err(result) => {
-            log::error!(
-                "failed to receive a command!\n-->\ndetails : {}\n<--",
-                result
-            );
+            tracing::error!(details = %result, "failed to receive a command!");
             return;
         }
     };
 
     match result.command.as_ref() {
         common_constants::DEFINED_COMMAND_GET_STATUS => {
+            let trader_status = trader_status.read().await.clone();
+            let health = common::health::Health {
+                database: Some(database.health().await),
+                buffers: trader_status.buffers,
+                trader: Some(trader_status.trader),
+                ..common::health::Health::new()
+            }
+            .evaluate();
             if let Err(result) = stream
                 .send_command(ipc::Command::response(
                     [
@@ -137,6 +356,14 @@ pub async fn ipc_handler(
                             String::from("status"),
                             format!("{}", console::style("active(running)").green()),
                         ),
+                        (
+                            String::from(common_constants::DEFINED_COMMAND_OPTION_METRICS),
+                            common::metrics::render(),
+                        ),
+                        (
+                            String::from(common_constants::DEFINED_COMMAND_OPTION_HEALTH),
+                            serde_json::to_string(&health).unwrap_or_default(),
+                        ),
                     ]
                     .iter()
                     .cloned()
@@ -144,16 +371,108 @@ pub async fn ipc_handler(
                 ))
                 .await
             {
-                log::error!("failed to send a command!\n-->\ndetails : {}\n<--", result);
+                tracing::error!(details = %result, "failed to send a command!");
                 return;
             }
         }
+        common_constants::DEFINED_COMMAND_SET_LOG_LEVEL => {
+            let response = match result
+                .messages
+                .as_ref()
+                .and_then(|e| e.get(common_constants::DEFINED_COMMAND_OPTION_DIRECTIVE))
+            {
+                Some(directive) => match log_handle.set_directive(directive) {
+                    Ok(()) => {
+                        tracing::info!(directive = %directive, "changed the logging directive.");
+                        (
+                            String::from(common_constants::DEFINED_COMMAND_OPTION_SUCCESS),
+                            log_handle.directive(),
+                        )
+                    }
+                    Err(result) => (
+                        String::from(common_constants::DEFINED_COMMAND_OPTION_ERROR),
+                        result,
+                    ),
+                },
+                None => (
+                    String::from(common_constants::DEFINED_COMMAND_OPTION_ERROR),
+                    error_message!("no directive!"),
+                ),
+            };
+            if let Err(result) = stream
+                .send_command(ipc::Command::response([response].iter().cloned().collect()))
+                .await
+            {
+                tracing::error!(details = %result, "failed to send a command!");
+            }
+        }
+        common_constants::DEFINED_COMMAND_RELOAD => {
+            let response = match log_handle.reload("./env.toml").await {
+                Ok(directive) => {
+                    tracing::info!(directive = %directive, "reloaded.");
+                    (
+                        String::from(common_constants::DEFINED_COMMAND_OPTION_SUCCESS),
+                        directive,
+                    )
+                }
+                Err(result) => (
+                    String::from(common_constants::DEFINED_COMMAND_OPTION_ERROR),
+                    result,
+                ),
+            };
+            if let Err(result) = stream
+                .send_command(ipc::Command::response([response].iter().cloned().collect()))
+                .await
+            {
+                tracing::error!(details = %result, "failed to send a command!");
+            }
+        }
+        common_constants::DEFINED_COMMAND_PAUSE | common_constants::DEFINED_COMMAND_RESUME => {
+            let paused = result.command == common_constants::DEFINED_COMMAND_PAUSE;
+            common::health::set_paused(paused);
+            tracing::info!(paused = paused, "changed the pause state.");
+            let response = (
+                String::from(common_constants::DEFINED_COMMAND_OPTION_SUCCESS),
+                String::from(if paused { "paused" } else { "resumed" }),
+            );
+            if let Err(result) = stream
+                .send_command(ipc::Command::response([response].iter().cloned().collect()))
+                .await
+            {
+                tracing::error!(details = %result, "failed to send a command!");
+            }
+        }
+        common_constants::DEFINED_COMMAND_SUBSCRIBE => {
+            if let Err(result) = stream.forward_events().await {
+                tracing::debug!(details = %result, "a subscriber left.");
+            }
+        }
+        common_constants::DEFINED_COMMAND_GET_CONFIG => {
+            let response = match common::effective_config(
+                &env,
+                env!("CARGO_PKG_NAME"),
+                Some(config.as_ref()),
+                &log_handle.directive(),
+            ) {
+                Ok(content) => (
+                    String::from(common_constants::DEFINED_COMMAND_OPTION_CONFIG),
+                    content,
+                ),
+                Err(result) => (
+                    String::from(common_constants::DEFINED_COMMAND_OPTION_ERROR),
+                    result,
+                ),
+            };
+            if let Err(result) = stream
+                .send_command(ipc::Command::response([response].iter().cloned().collect()))
+                .await
+            {
+                tracing::error!(details = %result, "failed to send a command!");
+            }
+        }
         _ => {
             if let Err(result) = transmitter.send(result).await {
-                log::error!(
-                    "failed to send to the main thread!\n-->\ndetails : {}\n<--",
-                    result
-                );
+                tracing::error!(details = %result, "failed to send to the main thread!");
             }
         }
     }
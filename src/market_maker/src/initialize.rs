@@ -1,6 +1,5 @@
 use std::sync::Arc;
 
-use common::*;
 use database::*;
 use liquid::*;
 
@@ -8,19 +7,16 @@ pub async fn initialize(
     key: &LiquidApiKey,
     currency_pair: CurrencyPair,
     db_path: &str,
-) -> Result<(LiquidTapClientAsync, Arc<database::Database>), ()> {
+) -> Result<(LiquidTapClientAsync, Arc<dyn Store>), ()> {
     let (returned_database, returned_client) = tokio::join!(
-        Database::new(db_path, common_constants::DATABASE_NAME),
+        open_store(db_path),
         initialize_liquid_tap(key, currency_pair)
     );
 
     let database = match returned_database {
         Ok(result) => result,
         Err(result) => {
-            log::error!(
-                "failed to initialize database\n-->\ndetails : {}\n<--",
-                result
-            );
+            tracing::error!(details = %result, "failed to initialize database");
             return Err(());
         }
     };
@@ -30,7 +26,7 @@ pub async fn initialize(
         Err(_) => return Err(()),
     };
 
-    Ok((client, Arc::new(database)))
+    Ok((client, database))
 }
 
 pub async fn initialize_liquid_tap(
@@ -40,10 +36,7 @@ pub async fn initialize_liquid_tap(
     let mut client = match LiquidTapClientAsync::connect().await {
         Ok(result) => result,
         Err(result) => {
-            log::error!(
-                "fatal error! cannnot connect liquid. \n-->\ndetails : {}\n<--",
-                result
-            );
+            tracing::error!(details = %result, "fatal error! cannnot connect liquid.");
             return Err(());
         }
     };
@@ -52,22 +45,16 @@ pub async fn initialize_liquid_tap(
         .subscribe(liquid_tap::channel_product(currency_pair))
         .await
     {
-        log::error!(
-            "failed to subscribe a channel! \n-->\ndetails : {}\n<--",
-            result
-        );
+        tracing::error!(details = %result, "failed to subscribe a channel!");
         return Err(());
     }
 
     match client.authenticate(&key).await {
         Ok(()) => {
-            log::info!("initialized LiquidTap : authentication");
+            tracing::info!("initialized LiquidTap : authentication");
         }
         Err(result) => {
-            log::error!(
-                "{}",
-                error_message!("failed to authenticate! \n-->\ndetails : {}\n<--", result)
-            );
+            tracing::error!(details = %result, "failed to authenticate!");
             return Err(());
         }
     }
@@ -77,16 +64,10 @@ pub async fn initialize_liquid_tap(
         .await
     {
         Ok(()) => {
-            log::info!("initialized LiquidTap : sending subscribing signal succeeded.");
+            tracing::info!("initialized LiquidTap : sending subscribing signal succeeded.");
         }
         Err(result) => {
-            log::error!(
-                "{}",
-                error_message!(
-                    "failed to subscribe a channel! \n-->\ndetails : {}\n<--",
-                    result
-                )
-            );
+            tracing::error!(details = %result, "failed to subscribe a channel!");
             return Err(());
         }
     }
@@ -96,16 +77,10 @@ pub async fn initialize_liquid_tap(
         .await
     {
         Ok(()) => {
-            log::info!("initialized LiquidTap : sending subscribing signal succeeded.");
+            tracing::info!("initialized LiquidTap : sending subscribing signal succeeded.");
         }
         Err(result) => {
-            log::error!(
-                "{}",
-                error_message!(
-                    "failed to subscribe a channel! \n-->\ndetails : {}\n<--",
-                    result
-                )
-            );
+            tracing::error!(details = %result, "failed to subscribe a channel!");
             return Err(());
         }
     }
@@ -115,16 +90,10 @@ pub async fn initialize_liquid_tap(
         .await
     {
         Ok(()) => {
-            log::info!("initialized LiquidTap : sending subscribing signal succeeded.");
+            tracing::info!("initialized LiquidTap : sending subscribing signal succeeded.");
         }
         Err(result) => {
-            log::error!(
-                "{}",
-                error_message!(
-                    "failed to subscribe a channel! \n-->\ndetails : {}\n<--",
-                    result
-                )
-            );
+            tracing::error!(details = %result, "failed to subscribe a channel!");
             return Err(());
         }
     }
@@ -133,10 +102,7 @@ pub async fn initialize_liquid_tap(
         .subscribe(liquid_tap::channel_executions(currency_pair))
         .await
     {
-        log::error!(
-            "failed to subscribe a channel! \n-->\ndetails : {}\n<--",
-            result
-        );
+        tracing::error!(details = %result, "failed to subscribe a channel!");
         return Err(());
     }
 
@@ -144,10 +110,7 @@ pub async fn initialize_liquid_tap(
         .subscribe(liquid_tap::channel_order_book(currency_pair, Side::Buy))
         .await
     {
-        log::error!(
-            "failed to subscribe a channel! \n-->\ndetails : {}\n<--",
-            result
-        );
+        tracing::error!(details = %result, "failed to subscribe a channel!");
         return Err(());
     }
 
@@ -155,10 +118,7 @@ pub async fn initialize_liquid_tap(
         .subscribe(liquid_tap::channel_order_book(currency_pair, Side::Sell))
         .await
     {
-        log::error!(
-            "failed to subscribe a channel! \n-->\ndetails : {}\n<--",
-            result
-        );
+        tracing::error!(details = %result, "failed to subscribe a channel!");
         return Err(());
     }
 
@@ -166,12 +126,10 @@ pub async fn initialize_liquid_tap(
         .subscribe(liquid_tap::channel_executions_details(currency_pair))
         .await
     {
-        log::error!(
-            "failed to subscribe a channel!\n-->\ndetails : {}\n<--",
-            result
-        );
+        tracing::error!(details = %result, "failed to subscribe a channel!");
         return Err(());
     }
 
+    common::health::set_tap_connected(true);
     Ok(client)
 }
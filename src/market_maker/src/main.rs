@@ -1,5 +1,6 @@
 use clap::{Arg, Command};
 
+mod backtest;
 mod channel_handler;
 mod daemon_main;
 mod event_handler;
@@ -18,7 +19,17 @@ async fn main() {
         .subcommand(
             Command::new("status")
                 .about("show the status.")
-                .arg(Arg::new("IDENT").required(true).help("identifier")),
+                .arg(Arg::new("IDENT").required(true).help("identifier"))
+                .arg(
+                    Arg::new("metrics")
+                        .long("metrics")
+                        .help("show metrics in prometheus text format."),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("print the status as json."),
+                ),
         )
         .subcommand(
             Command::new("start").about("start the program.").arg(
@@ -32,11 +43,60 @@ async fn main() {
                 .about("shutdown the program.")
                 .arg(Arg::new("IDENT").required(true).help("identifier")),
         )
+        .subcommand(
+            Command::new("log-level")
+                .about("change the logging directive of the running program.")
+                .arg(Arg::new("IDENT").required(true).help("identifier"))
+                .arg(
+                    Arg::new("DIRECTIVE")
+                        .required(true)
+                        .help("such as info,market_maker=debug,database=trace"),
+                ),
+        )
+        .subcommand(
+            Command::new("sweep")
+                .about("backtest the quotes over a search space of parameters.")
+                .arg(
+                    Arg::new("IDENT")
+                        .required(true)
+                        .help("config file path. it will load [IDENT].toml."),
+                )
+                .arg(
+                    Arg::new("SPACE")
+                        .required(true)
+                        .help("toml file of the balances, the search space and the sweep config."),
+                )
+                .arg(
+                    Arg::new("FROM")
+                        .required(true)
+                        .help("start of the period in rfc3339."),
+                )
+                .arg(
+                    Arg::new("TO")
+                        .required(true)
+                        .help("end of the period in rfc3339."),
+                )
+                .arg(
+                    Arg::new("OUTPUT")
+                        .required(true)
+                        .help("it will write [OUTPUT].csv and [OUTPUT].json."),
+                )
+                .arg(Arg::new("SYMBOL").value_name("SYMBOL").long("symbol").help(
+                    "exchange and product such as liquid:btc_jpy. liquid:btc_jpy by default.",
+                )),
+        )
         .get_matches();
 
     if let Some(matched) = matches.subcommand_matches("status") {
         if let Some(ident) = matched.value_of("IDENT") {
-            handler::status_handler(ident).await;
+            std::process::exit(
+                handler::status_handler(
+                    ident,
+                    matched.is_present("metrics"),
+                    matched.is_present("json"),
+                )
+                .await,
+            );
         }
     } else if let Some(matched) = matches.subcommand_matches("start") {
         if let Some(ident) = matched.value_of("IDENT") {
@@ -46,6 +106,25 @@ async fn main() {
         if let Some(ident) = matched.value_of("IDENT") {
             handler::shutdown_handler(ident).await;
         }
+    } else if let Some(matched) = matches.subcommand_matches("log-level") {
+        if let (Some(ident), Some(directive)) =
+            (matched.value_of("IDENT"), matched.value_of("DIRECTIVE"))
+        {
+            handler::log_level_handler(ident, directive).await;
+        }
+    } else if let Some(matched) = matches.subcommand_matches("sweep") {
+        if let (Some(ident), Some(space), Some(from), Some(to), Some(output)) = (
+            matched.value_of("IDENT"),
+            matched.value_of("SPACE"),
+            matched.value_of("FROM"),
+            matched.value_of("TO"),
+            matched.value_of("OUTPUT"),
+        ) {
+            std::process::exit(
+                handler::sweep_handler(ident, space, from, to, output, matched.value_of("SYMBOL"))
+                    .await,
+            );
+        }
     } else {
     }
 }
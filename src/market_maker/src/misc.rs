@@ -15,6 +15,10 @@ pub struct Config {
     pub offset_unit: i32,
     pub dry_trade: bool,
     pub key: LiquidApiKey,
+    ///
+    /// the fees of the exchange and of its products, free when they are not written.
+    #[serde(default)]
+    pub fees: database::FeeSchedule,
 }
 
 impl Config {
@@ -22,22 +26,31 @@ impl Config {
         let content = match tokio::fs::read_to_string(path).await {
             Ok(content) => content,
             Err(result) => {
-                log::error!("failed to load!\n-->\ndetails : {}\n<--", result);
+                tracing::error!(details = %result, "failed to load!");
                 return Err(());
             }
         };
         let config = match toml::from_str::<Config>(&content) {
             Ok(content) => content,
             Err(result) => {
-                log::error!(
-                    "failed to deserializes config file!\n-->\ndetails : {}\n<--",
-                    result
-                );
+                tracing::error!(details = %result, "failed to deserializes config file!");
                 return Err(());
             }
         };
         Ok(config)
     }
+
+    ///
+    /// the product of the traded pair such as `btc_jpy`, keying its fees. a custom pair is keyed by its id.
+    pub fn product(&self) -> String {
+        match self.currency_pair {
+            liquid::currency::CurrencyPair::Custom(id) => id.to_string(),
+            pair => {
+                let code = pair.generate_pair_code();
+                format!("{}_{}", &code[..3], &code[3..])
+            }
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -63,21 +76,18 @@ impl State {
             Ok(content) => match toml::from_str::<State>(&content) {
                 Ok(content) => content,
                 Err(result) => {
-                    log::error!(
-                        "failed to deserializes config file!\n-->\ndetails : {}\n<--",
-                        result
-                    );
+                    tracing::error!(details = %result, "failed to deserializes config file!");
                     return Err(());
                 }
             },
             Err(result) => {
-                log::warn!("failed to load!\n-->\ndetails : {}\n<--", result);
-                log::warn!("loaded default!");
+                tracing::warn!(details = %result, "failed to load!");
+                tracing::warn!("loaded default!");
                 State::default()
             }
         };
         if state.succeeded_trade < 1 && state.whole_trade < 1 {
-            log::error!("invalid values!\n-->\ndetails : {:?}\n<--", state);
+            tracing::error!(details = ?state, "invalid values!");
             return Err(());
         }
         Ok(state)
@@ -90,24 +100,21 @@ impl State {
         succeeded_trade: usize,
         whole_trade: usize,
     ) -> Result<(), ()> {
-        log::info!("Saved states");
+        tracing::info!("Saved states");
         self.offset = offset;
         self.succeeded_trade = succeeded_trade;
         self.whole_trade = whole_trade;
         let string = match toml::to_string(self) {
             Ok(content) => content,
             Err(result) => {
-                log::error!(
-                    "failed to serializes config file!\n-->\ndetails : {}\n<--",
-                    result
-                );
+                tracing::error!(details = %result, "failed to serializes config file!");
                 return Err(());
             }
         };
         match tokio::fs::write(path, string.as_bytes()).await {
             Ok(_) => Ok(()),
             Err(result) => {
-                log::error!("failed to save!\n-->\ndetails : {}\n<--", result);
+                tracing::error!(details = %result, "failed to save!");
                 Err(())
             }
         }
@@ -20,20 +20,25 @@ pub async fn post_close_order(position: trader::Position, config: Arc<Config>) -
         .await
     {
         Ok(result) => {
-            log::info!(
-                "succeeded to close positions!\n-->\ndetails : {:?}\n<--",
-                result
-            );
+            tracing::info!(details = ?result, "succeeded to close positions!");
             result
         }
         Err(result) => {
-            log::error!("failed to close positions!\n-->\ndetails : {}\n<--", result);
+            tracing::error!(details = %result, "failed to close positions!");
             return Err(());
         }
     };
     Ok(())
 }
 
+#[tracing::instrument(
+    name = "order",
+    skip_all,
+    fields(
+        buy_order_id = tracing::field::Empty,
+        sell_order_id = tracing::field::Empty,
+    )
+)]
 pub async fn post_detailed_order(
     buy_order: Order,
     sell_order: Order,
@@ -51,17 +56,23 @@ pub async fn post_detailed_order(
 ) {
     let client = LiquidClientAsync::new();
 
+    let posted_at = std::time::Instant::now();
     let (task_buy, task_sell) = tokio::join!(
         client.post_order(&config.key, &buy_order),
         client.post_order(&config.key, &sell_order)
     );
+    common::metrics::histogram_observe(
+        common::metrics::ORDER_ROUND_TRIP_SECONDS,
+        &[],
+        posted_at.elapsed().as_secs_f64(),
+    );
 
     let buy_order_response = match task_buy {
         Ok(result) => result,
         Err(result) => {
-            log::error!("failed to post orders!\n-->\ndetails : {}\n<--", result);
+            tracing::error!(details = %result, "failed to post orders!");
             if let Err(result) = positions_sender.send(Err(())) {
-                log::error!("failed to post to trader!\n-->\ndetails : {}\n<--", result);
+                tracing::error!(details = %result, "failed to post to trader!");
             }
             return;
         }
@@ -70,14 +81,29 @@ pub async fn post_detailed_order(
     let sell_order_response = match task_sell {
         Ok(result) => result,
         Err(result) => {
-            log::error!("failed to post orders!\n-->\ndetails : {}\n<--", result);
+            tracing::error!(details = %result, "failed to post orders!");
             if let Err(result) = positions_sender.send(Err(())) {
-                log::error!("failed to post to trader!\n-->\ndetails : {}\n<--", result);
+                tracing::error!(details = %result, "failed to post to trader!");
             }
             return;
         }
     };
 
+    common::metrics::counter_increment(
+        common::metrics::ORDERS_POSTED_TOTAL,
+        &[("side", "buy")],
+        1.0,
+    );
+    common::metrics::counter_increment(
+        common::metrics::ORDERS_POSTED_TOTAL,
+        &[("side", "sell")],
+        1.0,
+    );
+    tracing::Span::current()
+        .record("buy_order_id", &buy_order_response.id)
+        .record("sell_order_id", &sell_order_response.id);
+    tracing::info!("posted orders.");
+
     let (task_wait_buy, task_wait_sell) = tokio::join!(
         positions_checker.wait_until_with_timeout(
             &buy_order_response,
@@ -91,30 +117,45 @@ pub async fn post_detailed_order(
         )
     );
 
+    if task_wait_buy.is_ok() {
+        common::metrics::counter_increment(
+            common::metrics::ORDERS_FILLED_TOTAL,
+            &[("side", "buy")],
+            1.0,
+        );
+    }
+    if task_wait_sell.is_ok() {
+        common::metrics::counter_increment(
+            common::metrics::ORDERS_FILLED_TOTAL,
+            &[("side", "sell")],
+            1.0,
+        );
+    }
+
     match (task_wait_buy, task_wait_sell) {
         (Ok(()), Ok(())) => {
             if let Err(result) =
                 positions_sender.send(Ok((Ok(buy_order_response), Ok(sell_order_response))))
             {
-                log::error!("failed to post to trader!\n-->\ndetails : {}\n<--", result);
+                tracing::error!(details = %result, "failed to post to trader!");
             }
         }
         (Ok(()), Err(_)) => {
             if let Err(result) =
                 positions_sender.send(Ok((Ok(buy_order_response), Err(sell_order_response))))
             {
-                log::error!("failed to post to trader!\n-->\ndetails : {}\n<--", result);
+                tracing::error!(details = %result, "failed to post to trader!");
             }
         }
         (Err(_), Ok(())) => {
             if let Err(result) =
                 positions_sender.send(Ok((Err(buy_order_response), Ok(sell_order_response))))
             {
-                log::error!("failed to post to trader!\n-->\ndetails : {}\n<--", result);
+                tracing::error!(details = %result, "failed to post to trader!");
             }
         }
         (Err(_), Err(_)) => {
-            log::warn!(
+            tracing::warn!(
                 "failed to excute the both of orders in limited_time therefore, keep old order."
             );
             let (task_cancel_buy, task_cancel_sell) = tokio::join!(
@@ -123,28 +164,22 @@ pub async fn post_detailed_order(
             );
             match task_cancel_buy {
                 Ok(result) => {
-                    log::info!(
-                        "succeeded to cancel orders!\n-->\ndetails : {:?}\n<--",
-                        result
-                    );
+                    tracing::info!(details = ?result, "succeeded to cancel orders!");
                 }
                 Err(result) => {
-                    log::error!("failed to cancel orders!\n-->\ndetails : {}\n<--", result);
+                    tracing::error!(details = %result, "failed to cancel orders!");
                 }
             }
             match task_cancel_sell {
                 Ok(result) => {
-                    log::info!(
-                        "succeeded to cancel orders!\n-->\ndetails : {:?}\n<--",
-                        result
-                    );
+                    tracing::info!(details = ?result, "succeeded to cancel orders!");
                 }
                 Err(result) => {
-                    log::error!("failed to cancel orders!\n-->\ndetails : {}\n<--", result);
+                    tracing::error!(details = %result, "failed to cancel orders!");
                 }
             }
             if let Err(result) = positions_sender.send(Err(())) {
-                log::error!("failed to post to trader!\n-->\ndetails : {}\n<--", result);
+                tracing::error!(details = %result, "failed to post to trader!");
             }
         }
     }
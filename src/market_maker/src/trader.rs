@@ -15,7 +15,7 @@ use tokio::time;
 use common::*;
 use database::data;
 use liquid::*;
-use misc::{Config, State};
+use misc::State;
 use stats::*;
 
 use crate::misc;
@@ -362,6 +362,16 @@ impl PositionsChecker {
     }
 }
 
+///
+/// snapshot of the trader reported by the status command.
+#[derive(Clone, Debug, Default)]
+pub struct TraderStatus {
+    pub trader: health::TraderHealth,
+    pub buffers: std::collections::BTreeMap<String, health::BufferHealth>,
+}
+
+pub type SharedTraderStatus = Arc<RwLock<TraderStatus>>;
+
 #[derive(Clone, Debug)]
 enum Mode {
     Await,
@@ -373,8 +383,10 @@ pub async fn trader(
     mut trader_recv: TraderEventReceiver,
     config: Arc<misc::Config>,
     env: Arc<Enviornment>,
+    trader_status: SharedTraderStatus,
 ) {
     let mut market_info = MarketInfo::new();
+    let mut quotes: (Option<i32>, Option<i32>) = (None, None);
     let (positions_sender, mut positions_receiver) = mpsc::unbounded_channel::<
         Result<
             (
@@ -400,7 +412,8 @@ pub async fn trader(
             return;
         }
     };
-    let mut stats = Stats::new(config.clone(), &state);
+    let mut stats = Stats::new(config.offset_unit, &state);
+    let mut pnl = PnlRecord::default();
     loop {
         tokio::select! {
             Ok(event) = trader_recv.recv() => {
@@ -411,7 +424,8 @@ pub async fn trader(
                             Mode::Await => {}
                             Mode::PostOrder => {
                                 if let Ok((buy_order, sell_order)) = build_strategy(config.clone(), &stats).await {
-                                    if !config.dry_trade {
+                                    quotes = (buy_order.price, sell_order.price);
+                                    if !config.dry_trade && !health::is_paused() {
                                         tokio::spawn(order::post_detailed_order(buy_order, sell_order, config.clone(), positions_checker.clone(), positions_sender.clone()));
                                         mode = Mode::PostingOrder;
                                     }
@@ -444,7 +458,8 @@ pub async fn trader(
                             Mode::Await => {}
                             Mode::PostOrder => {
                                 if let Ok((buy_order, sell_order)) = build_strategy(config.clone(), &stats).await {
-                                    if !config.dry_trade {
+                                    quotes = (buy_order.price, sell_order.price);
+                                    if !config.dry_trade && !health::is_paused() {
                                         tokio::spawn(order::post_detailed_order(buy_order, sell_order, config.clone(), positions_checker.clone(), positions_sender.clone()));
                                         mode = Mode::PostingOrder;
                                     }
@@ -464,33 +479,45 @@ pub async fn trader(
                     Ok(result) => {
                         mode = Mode::PostOrder;
                         match result {
-                            (Ok(_), Ok(_)) => {
+                            (Ok(buy), Ok(sell)) => {
                                 stats.succeed();
+                                let (bought, sold) = (buy.price * buy.quantity, sell.price * sell.quantity);
+                                record(&mut pnl, env.clone(), config.clone(), sold - bought, bought + sold).await;
                             }
                             (Ok(_), Err(_)) => {
-                                log::error!("failed to make market!");
+                                tracing::error!("failed to make market!");
                                 stats.fail();
                                 stats.decrease_offset();
                             }
                             (Err(_), Ok(_)) => {
-                                log::error!("failed to make market!");
+                                tracing::error!("failed to make market!");
                                 stats.fail();
                                 stats.increase_offset();
                             }
                             (Err(_), Err(_)) => {
-                                log::error!("failed to make market!");
+                                tracing::error!("failed to make market!");
 
                             }
                         }
                     }
                     Err(_) => {
-                        log::error!("retry!");
+                        tracing::error!("retry!");
                         mode = Mode::PostOrder;
                     }
                 }
             }
             else => break,
         };
+
+        let mut status = trader_status.write().await;
+        status.trader = health::TraderHealth {
+            mode: format!("{:?}", mode),
+            buy_price: quotes.0,
+            sell_price: quotes.1,
+            success_rate: stats.succeeded_trade as f64 / stats.whole_trade as f64,
+            offset: stats.offset,
+        };
+        status.buffers = market_info.buffers();
     }
     if let Err(_) = state
         .save(
@@ -507,12 +534,37 @@ pub async fn trader(
     {}
 }
 
-#[allow(dead_code)]
-async fn record(num: &mut u32, env: Arc<Enviornment>, config: Arc<misc::Config>, profit: f64) {
+///
+/// the rows written by `record` and the traded value deciding the fee tier.
+#[derive(Clone, Debug, Default)]
+struct PnlRecord {
+    num: u32,
+    volume: f64,
+    fees: f64,
+}
+
+///
+/// append the realized profit of a settled quote pair and the maker fees of its orders,
+/// which traded `value` jpy, as `num,time,profit,fee,net profit`.
+async fn record(
+    pnl: &mut PnlRecord,
+    env: Arc<Enviornment>,
+    config: Arc<misc::Config>,
+    profit: f64,
+    value: f64,
+) {
     let now = std::time::SystemTime::now()
         .duration_since(std::time::SystemTime::UNIX_EPOCH)
         .unwrap()
         .as_secs_f64();
+    let fee = config.fees.fee(
+        &config.product(),
+        database::Liquidity::Maker,
+        pnl.volume,
+        value,
+    );
+    pnl.volume += value;
+    pnl.fees += fee;
     match OpenOptions::new()
         .create(true)
         .append(true)
@@ -527,14 +579,16 @@ async fn record(num: &mut u32, env: Arc<Enviornment>, config: Arc<misc::Config>,
     {
         Ok(mut result) => {
             if let Err(result) = result
-                .write_all(format!("{},{},{}\n", num, now, profit).as_bytes())
+                .write_all(
+                    format!("{},{},{},{},{}\n", pnl.num, now, profit, fee, profit - fee).as_bytes(),
+                )
                 .await
             {
-                log::warn!("failed to write!\n-->\ndetails : {:?}\n<--", result);
+                tracing::warn!(details = ?result, "failed to write!");
             }
-            *num += 1;
+            pnl.num += 1;
         }
-        Err(result) => log::warn!("failed to open csv file!\n-->\ndetails : {:?}\n<--", result),
+        Err(result) => tracing::warn!(details = ?result, "failed to open csv file!"),
     };
 }
 
@@ -563,7 +617,7 @@ async fn check_market(market_info: &MarketInfo) -> bool {
     let f_dist = match FisherSnedecor::new(n_1 - 1.0, n_2 - 1.0) {
         Ok(result) => result,
         Err(result) => {
-            log::error!("failed to check market!\n-->\ndetails : {:?}\n<--", result);
+            tracing::error!(details = ?result, "failed to check market!");
             return false;
         }
     };
@@ -575,16 +629,37 @@ async fn check_market(market_info: &MarketInfo) -> bool {
     if p > 0.95 || t.abs() > 1.96 {
         false
     } else {
-        log::info!("range market! p : {}, t : {}", p, t.abs());
+        tracing::info!(p, t = t.abs(), "range market!");
         true
     }
 }
 
 async fn build_strategy(config: Arc<misc::Config>, stats: &Stats) -> Result<(Order, Order), ()> {
+    let (lower_price, higher_price) = quote_prices(stats)?;
+
+    let buy_order = Order::limit(
+        config.currency_pair.generate_id(),
+        Side::Buy,
+        config.quantity,
+        lower_price,
+    );
+    let sell_order = Order::limit(
+        config.currency_pair.generate_id(),
+        Side::Sell,
+        config.quantity,
+        higher_price,
+    );
+
+    Ok((buy_order, sell_order))
+}
+
+///
+/// the prices of the buy and the sell quotes from the cross point of the order book lines.
+pub fn quote_prices(stats: &Stats) -> Result<(i32, i32), ()> {
     let cross_point_price = match stats.cross_point_price.back() {
         Some(content) => content,
         None => {
-            log::error!("must contain!");
+            tracing::error!("must contain!");
             return Err(());
         }
     };
@@ -597,37 +672,23 @@ async fn build_strategy(config: Arc<misc::Config>, stats: &Stats) -> Result<(Ord
     );
     let rate = stats.succeeded_trade as f64 / stats.whole_trade as f64;
 
-    log::info!(
-        "[ STRATEGY ] cross_point_price : {}, buy_spread : {}, sell_spread : {}, offset : {}, succeeded_trade : {}, whole_trade : {}, success_rate : {}, trend : {}, R : {}",
+    tracing::info!(
         cross_point_price,
-        buy_spread_mean,
-        sell_spread_mean,
-        stats.offset,
-        stats.succeeded_trade,
-        stats.whole_trade,
-        rate,
+        buy_spread = buy_spread_mean,
+        sell_spread = sell_spread_mean,
+        offset = stats.offset,
+        succeeded_trade = stats.succeeded_trade,
+        whole_trade = stats.whole_trade,
+        success_rate = rate,
         trend,
-        r_mean,
+        r = r_mean,
+        "strategy"
     );
     let lower_price =
         (cross_point_price + stats.offset as f64 + buy_spread_mean * rate + trend).round() as i32;
     let higher_price =
         (cross_point_price + stats.offset as f64 + sell_spread_mean * rate + trend).round() as i32;
-
-    let buy_order = Order::limit(
-        config.currency_pair.generate_id(),
-        Side::Buy,
-        config.quantity,
-        lower_price,
-    );
-    let sell_order = Order::limit(
-        config.currency_pair.generate_id(),
-        Side::Sell,
-        config.quantity,
-        higher_price,
-    );
-
-    Ok((buy_order, sell_order))
+    Ok((lower_price, higher_price))
 }
 
 fn calculate_r(order_book: &Vec<(f64, f64)>, model: &Array<f64, Ix1>) -> f64 {
@@ -665,7 +726,7 @@ fn get_cross_point(
 }
 
 #[derive(Clone, Debug)]
-struct Stats {
+pub struct Stats {
     pub offset: i32,
     pub offset_unit: i32,
     pub succeeded_trade: usize,
@@ -687,10 +748,10 @@ struct Stats {
 }
 
 impl Stats {
-    pub fn new(config: Arc<Config>, state: &State) -> Self {
+    pub fn new(offset_unit: i32, state: &State) -> Self {
         Self {
             offset: state.offset,
-            offset_unit: config.offset_unit,
+            offset_unit,
             succeeded_trade: state.succeeded_trade,
             whole_trade: state.whole_trade,
             timestamps: VecDeque::new(),
@@ -719,10 +780,20 @@ impl Stats {
     pub fn succeed(&mut self) {
         self.succeeded_trade += 1;
         self.whole_trade += 1;
+        self.record_fill_rate();
     }
 
     pub fn fail(&mut self) {
         self.whole_trade += 1;
+        self.record_fill_rate();
+    }
+
+    fn record_fill_rate(&self) {
+        common::metrics::gauge_set(
+            common::metrics::ORDER_FILL_RATE,
+            &[],
+            self.succeeded_trade as f64 / self.whole_trade as f64,
+        );
     }
 
     pub fn vacuume(&mut self, period: f64) {
@@ -730,6 +801,12 @@ impl Stats {
             .duration_since(std::time::SystemTime::UNIX_EPOCH)
             .unwrap()
             .as_secs_f64();
+        self.vacuume_at(now, period);
+    }
+
+    ///
+    /// drop the statistics older than `period` seconds before `now`.
+    pub fn vacuume_at(&mut self, now: f64, period: f64) {
         let index = match self.timestamps.iter().position(|&e| e > now - period) {
             Some(content) => content,
             None => return,
@@ -770,6 +847,27 @@ impl Stats {
             }
         };
 
+        self.calculate_stats_at(
+            [
+                ticker.received_at,
+                execution.received_at,
+                order_book_buy.received_at,
+                order_book_sell.received_at,
+            ]
+            .max(),
+            order_book_buy,
+            order_book_sell,
+        );
+    }
+
+    ///
+    /// add the statistics of the order books at `timestamp`.
+    pub fn calculate_stats_at(
+        &mut self,
+        timestamp: f64,
+        order_book_buy: &data::OrderBook,
+        order_book_sell: &data::OrderBook,
+    ) {
         let (order_book_buy_acumulated, order_book_sell_acumulated) = (
             convert_order_book_to_order_book_acumulated(&order_book_buy),
             convert_order_book_to_order_book_acumulated(&order_book_sell),
@@ -778,20 +876,14 @@ impl Stats {
         let buy_line = match calculate_line(&order_book_buy_acumulated) {
             Ok(result) => Arc::new(result),
             Err(result) => {
-                log::error!(
-                    "failed to linear regression!\n-->\ndetails : {:?}\n<--",
-                    result
-                );
+                tracing::error!(details = ?result, "failed to linear regression!");
                 return;
             }
         };
         let sell_line = match calculate_line(&order_book_sell_acumulated) {
             Ok(result) => Arc::new(result),
             Err(result) => {
-                log::error!(
-                    "failed to linear regression!\n-->\ndetails : {:?}\n<--",
-                    result
-                );
+                tracing::error!(details = ?result, "failed to linear regression!");
                 return;
             }
         };
@@ -801,23 +893,12 @@ impl Stats {
         let cross_point = match get_cross_point(&buy_line, &sell_line) {
             Ok(result) => result,
             Err(result) => {
-                log::error!(
-                    "failed to get cross point!\n-->\ndetails : {:?}\n<--",
-                    result
-                );
+                tracing::error!(details = ?result, "failed to get cross point!");
                 return;
             }
         };
 
-        self.timestamps.push_back(
-            [
-                ticker.received_at,
-                execution.received_at,
-                order_book_buy.received_at,
-                order_book_sell.received_at,
-            ]
-            .max(),
-        );
+        self.timestamps.push_back(timestamp);
         self.cross_point_price.push_back(cross_point[1]);
         self.buy_spread
             .push_back(order_book_buy.orders[0].price as f64 - cross_point[1]);
@@ -838,7 +919,7 @@ impl Stats {
 }
 
 pub async fn get_old_market(
-    database: Arc<database::Database>,
+    database: Arc<dyn database::Store>,
     trader_trans: TraderEventSender,
     config: Arc<misc::Config>,
 ) -> Result<(), ()> {
@@ -902,10 +983,7 @@ pub async fn get_old_market(
                     Some(0) => {
                         if let Some(t) = t {
                             if let Err(result) = trader_trans.post_ticker(t).await {
-                                log::error!(
-                                    "failed to post to trader!\n-->\ndetails : {}\n<--",
-                                    result
-                                );
+                                tracing::error!(details = %result, "failed to post to trader!");
                                 return Err(());
                             }
                         } else {
@@ -916,10 +994,7 @@ pub async fn get_old_market(
                     Some(1) => {
                         if let Some(e) = e {
                             if let Err(result) = trader_trans.post_execution(e).await {
-                                log::error!(
-                                    "failed to post to trader!\n-->\ndetails : {}\n<--",
-                                    result
-                                );
+                                tracing::error!(details = %result, "failed to post to trader!");
                                 return Err(());
                             }
                         }
@@ -928,10 +1003,7 @@ pub async fn get_old_market(
                     Some(2) => {
                         if let Some(b) = b {
                             if let Err(result) = trader_trans.post_order_book_buy(b).await {
-                                log::error!(
-                                    "failed to post to trader!\n-->\ndetails : {}\n<--",
-                                    result
-                                );
+                                tracing::error!(details = %result, "failed to post to trader!");
                                 return Err(());
                             }
                         }
@@ -940,10 +1012,7 @@ pub async fn get_old_market(
                     Some(3) => {
                         if let Some(s) = s {
                             if let Err(result) = trader_trans.post_order_book_sell(s).await {
-                                log::error!(
-                                    "failed to post to trader!\n-->\ndetails : {}\n<--",
-                                    result
-                                );
+                                tracing::error!(details = %result, "failed to post to trader!");
                                 return Err(());
                             }
                         }
@@ -954,10 +1023,7 @@ pub async fn get_old_market(
             }
         }
         result => {
-            log::error!(
-                "failed to get old market data\n-->\ndetails : {:?}\n<--",
-                result
-            );
+            tracing::error!(details = ?result, "failed to get old market data");
             return Err(());
         }
     };
@@ -2,10 +2,30 @@ use super::event_handler;
 use super::*;
 //use liquid::*;
 
+#[tracing::instrument(
+    name = "tap_message",
+    skip_all,
+    fields(
+        channel = response.channel.as_deref().unwrap_or_default(),
+        event = response.event.as_deref().unwrap_or_default(),
+    )
+)]
 pub async fn channel_handler(
     _client: &mut LiquidTapClientAsync,
     response: &data_for_tap::ResponseValue,
 ) {
+    common::metrics::counter_increment(
+        common::metrics::TAP_MESSAGES_TOTAL,
+        &[("channel", response.channel.as_deref().unwrap_or_default())],
+        1.0,
+    );
+    if let Some(channel) = &response.channel {
+        common::health::record_tap_message(channel);
+    }
+    common::tail::publish(response);
+    if common::health::is_paused() {
+        return;
+    }
     if let Some(content) = &response.channel {
         match content.as_str() {
             //"product_cash_btcjpy_5" => {
@@ -48,24 +68,21 @@ pub async fn channel_handler(
             //}
             "time-signal" => {}
             _ => {
-                log::warn!("unknown channel!\n-->\ndetails : {:?}\n<--", response);
+                tracing::warn!(details = ?response, "unknown channel!");
             }
         }
     } else if let Some(content) = &response.event {
         match content.as_str() {
             "quoine:auth_success" => {
-                log::info!("authentication succeeded.",);
+                tracing::info!("authentication succeeded.");
             }
 
             "quoine:auth_failure" => {
-                log::error!("authentication failed!");
+                tracing::error!("authentication failed!");
             }
 
             _ => {
-                log::warn!(
-                    "{}",
-                    error_message!("unknown event!\n-->\ndetails : {:?}\n<--", response)
-                );
+                tracing::warn!(response = ?response, "unknown event!");
             }
         }
     }
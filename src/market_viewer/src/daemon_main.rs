@@ -5,22 +5,11 @@ use tokio::sync::*;
 use tokio::time;
 
 pub async fn daemon_main(config_path: &str) {
-    // initialize systemd journal
-    if let Err(result) =
-        systemd_journal_logger::init_with_extra_fields(vec![("VERSION", env!("CARGO_PKG_VERSION"))])
-    {
-        log::error!(
-            "failed to initialize the journal!\n-->\ndetails : {:?}\n<--",
-            result
-        );
-        return;
-    };
-    log::set_max_level(log::LevelFilter::Debug);
     // load configurtation
     let env = match load_env("./env.toml").await {
-        Ok(result) => result,
+        Ok(result) => std::sync::Arc::new(result),
         Err(result) => {
-            log::error!("failed to load!\n-->\ndetails : {}\n<--", result);
+            tracing::error!(details = %result, "failed to load!");
             return;
         }
     };
@@ -32,22 +21,51 @@ pub async fn daemon_main(config_path: &str) {
     };
     let config = Arc::new(config);
 
+    // initialize the journal and the json lines log
+    let log_handle = match common::logging::init(
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        &env.general.log_directory_path,
+        env.general.log_directive.as_deref().unwrap_or("debug"),
+    ) {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", result);
+            return;
+        }
+    };
+
+    // serve metrics if a port is configured
+    if let Some(port) = env.metrics.get(&(String::from(env!("CARGO_PKG_NAME")))) {
+        if let Err(result) = common::metrics::serve(*port).await {
+            tracing::error!(details = %result, "failed to serve metrics!");
+            return;
+        }
+        tracing::info!(port = port, "serving metrics.");
+    }
+
     // prevent same program running
     if let Err(result) = misc::lock_file(&env.general.etc_directory_path, &config.identifier) {
-        log::error!("failed to lock the file!\n-->\ndetails : {}\n<--", result);
+        tracing::error!(details = %result, "failed to lock the file!");
+        return;
+    }
+    if let Err(result) =
+        common::write_pid_file(&env.general.etc_directory_path, env!("CARGO_PKG_NAME"))
+    {
+        tracing::error!(details = %result, "failed to write the pid file!");
         return;
     }
 
     // check wether systemd is running or not
     if !daemon::booted() {
-        log::error!("systemd is not running!");
+        tracing::error!("systemd is not running!");
         return;
     };
     // check wether watchdog is enable or not and the duration
     let watchdog_duration = match daemon::watchdog_enabled(true) {
         Some(result) => result,
         None => {
-            log::error!("watchdog is diabled! WatchdogSec must be set.");
+            tracing::error!("watchdog is diabled! WatchdogSec must be set.");
             return;
         }
     };
@@ -56,20 +74,14 @@ pub async fn daemon_main(config_path: &str) {
     let mut sig_term = match signal(SignalKind::terminate()) {
         Ok(result) => result,
         Err(result) => {
-            log::error!(
-                "failed to initialize SIGTERM!\n-->\ndetails : {:?}\n<--",
-                result
-            );
+            tracing::error!(details = ?result, "failed to initialize SIGTERM!");
             return;
         }
     };
     let mut sig_hangup = match signal(SignalKind::hangup()) {
         Ok(result) => result,
         Err(result) => {
-            log::error!(
-                "failed to initialize SIGHUP!\n-->\ndetails : {:?}\n<--",
-                result
-            );
+            tracing::error!(details = ?result, "failed to initialize SIGHUP!");
             return;
         }
     };
@@ -86,7 +98,7 @@ pub async fn daemon_main(config_path: &str) {
     {
         Ok(result) => result,
         Err(_) => {
-            log::error!("failed to open the unix domai socket!");
+            tracing::error!("failed to open the unix domai socket!");
             return;
         }
     };
@@ -100,14 +112,11 @@ pub async fn daemon_main(config_path: &str) {
             Err(_) => return,
         };
 
-    log::info!("{} was initialized.", env!("CARGO_PKG_NAME"));
+    tracing::info!("{} was initialized.", env!("CARGO_PKG_NAME"));
 
     // notify systemd that ready
     if let Err(result) = daemon::notify(false, &[daemon::NotifyState::Ready]) {
-        log::error!(
-            "failed to notify systemd!\n-->\ndetails : {:?}\n<--",
-            result
-        );
+        tracing::error!(details = ?result, "failed to notify systemd!");
         return;
     }
 
@@ -116,10 +125,15 @@ pub async fn daemon_main(config_path: &str) {
             _ = sig_term.recv() => {
                 return;
             }
-            _ = sig_hangup.recv() => {}
+            _ = sig_hangup.recv() => {
+                match log_handle.reload("./env.toml").await {
+                    Ok(directive) => tracing::info!(directive = %directive, "reloaded."),
+                    Err(result) => tracing::error!(details = %result, "failed to reload!"),
+                }
+            }
             _ = watchdog_timer.tick() => {
                 if let Err(result) = daemon::notify(false, &[daemon::NotifyState::Watchdog]) {
-                    log::error!("failed to notify systemd that watchdog!\n-->\ndetails : {:?}\n<--", result);
+                    tracing::error!(details = ?result, "failed to notify systemd that watchdog!");
                     return;
                 }
             }
@@ -133,12 +147,12 @@ pub async fn daemon_main(config_path: &str) {
                 match listened {
                     Ok(stream) => {
                         let transmitter_copied = transmitter.clone();
-                        tokio::spawn(handler::ipc_handler(stream, transmitter_copied));
+                        tokio::spawn(handler::ipc_handler(stream, transmitter_copied, log_handle.clone(), env.clone(), config.clone()));
                     }
                     Err(result) => match result.kind() {
                         std::io::ErrorKind::WouldBlock => {}
                         _ => {
-                            log::error!("cannot listen to ipc!\n-->\ndetails : {}\n<--", result);
+                            tracing::error!(details = %result, "cannot listen to ipc!");
                             return;
                         }
                     }
@@ -151,10 +165,10 @@ pub async fn daemon_main(config_path: &str) {
                         channel_handler::channel_handler(&mut client, &result).await;
                     }
                     Err(result) => {
-                        log::warn!(
-                            "cannot check!\n-->\ndetails : {}\n<--", result
-                        );
-                        log::info!("reinitialize connection to liquid tap due to a previous error.");
+                        tracing::warn!(details = %result, "cannot check!");
+                        common::health::set_tap_connected(false);
+                        tracing::info!("reinitialize connection to liquid tap due to a previous error.");
+                        common::metrics::counter_increment(common::metrics::TAP_RECONNECTS_TOTAL, &[], 1.0);
                         client = match initialize::initialize_liquid_tap(&config.key).await {
                             Ok(result) => result,
                             Err(_) => break,
@@ -163,7 +177,7 @@ pub async fn daemon_main(config_path: &str) {
                 };
             }
             else => {
-                log::error!("liquid tap was timeout! {} would shutdown!", env!("CARGO_PKG_NAME"));
+                tracing::error!("liquid tap was timeout! {} would shutdown!", env!("CARGO_PKG_NAME"));
                 break;
             }
         };
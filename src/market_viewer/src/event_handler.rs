@@ -8,9 +8,9 @@ use super::*;
     if let Some(content) = &response.event {
         match content.as_str() {
             "pusher_internal:subscription_succeeded" => {
-                log::info!(
-                    "subscribing {} succeeded.",
-                    response.channel.as_ref().unwrap()
+                tracing::info!(
+                    channel = response.channel.as_deref().unwrap_or_default(),
+                    "subscribing succeeded."
                 );
             }
 
@@ -18,16 +18,16 @@ use super::*;
                 let data = match liquid_tap::generate_product(&response.data) {
                     Ok(result) => result,
                     Err(result) => {
-                        log::warn!(
-                            "failed to generate!\n-->\ndetails : {}\nresponse : {:?}<--",
-                            result,
-                            response
+                        tracing::warn!(details = %result, response = ?response, "failed to generate!");
+                        common::metrics::counter_increment(
+                            common::metrics::TAP_PARSE_FAILURES_TOTAL,
+                            &[("channel", response.channel.as_deref().unwrap_or_default())],
+                            1.0,
                         );
                         return;
                     }
                 };
                 let ticker = database::data::Ticker {
-                    _id: mongodb::bson::oid::ObjectId::new(),
                     received_at: std::time::SystemTime::now()
                         .duration_since(std::time::SystemTime::UNIX_EPOCH)
                         .unwrap()
@@ -45,12 +45,12 @@ use super::*;
                 };
                 if !transmitter.is_closed() {
                     if let Err(result) = transmitter.send(ticker) {
-                        log::error!("failed to send!\n-->\ndetails : {}\n<--", result);
+                        tracing::error!(details = %result, "failed to send!");
                     }
                 }
             }
             _ => {
-                log::warn!("unknown event!\n-->\ndetails : {:?}\n<--", response);
+                tracing::warn!(details = ?response, "unknown event!");
             }
         }
     }
@@ -60,32 +60,29 @@ pub async fn logging_response(response: &data_for_tap::ResponseValue) {
     if let Some(content) = &response.event {
         match content.as_str() {
             "pusher_internal:subscription_succeeded" => {
-                log::info!(
-                    "subscribing {} succeeded.",
-                    response.channel.as_ref().unwrap()
+                tracing::info!(
+                    channel = response.channel.as_deref().unwrap_or_default(),
+                    "subscribing succeeded."
                 );
             }
 
             "pusher_internal:subscription_failed" => {
-                log::error!("subscribing {} failed!", response.channel.as_ref().unwrap());
+                tracing::error!(channel = ?response.channel, "subscribing failed!");
             }
 
             "created" => {
-                log::debug!("{:?},{:?}", response.channel, response.data);
+                tracing::debug!(channel = ?response.channel, data = ?response.data, "received");
             }
 
             "updated" => {
-                log::debug!("{:?},{:?}", response.channel, response.data);
+                tracing::debug!(channel = ?response.channel, data = ?response.data, "received");
             }
             "orders_updated" => {
-                log::debug!("{:?},{:?}", response.channel, response.data);
+                tracing::debug!(channel = ?response.channel, data = ?response.data, "received");
             }
 
             _ => {
-                log::warn!(
-                    "{}",
-                    error_message!("unknown event!\n-->\ndetails : {:?}\n<--", response)
-                );
+                tracing::warn!(response = ?response, "unknown event!");
             }
         }
     }
@@ -122,9 +119,9 @@ pub async fn logging_response(response: &data_for_tap::ResponseValue) {
     if response.event.as_ref().is_some() {
         match response.event.as_ref().unwrap().as_str() {
             "pusher_internal:subscription_succeeded" => {
-                log::info!(
-                    "subscribing {} succeeded.",
-                    response.channel.as_ref().unwrap()
+                tracing::info!(
+                    channel = response.channel.as_deref().unwrap_or_default(),
+                    "subscribing succeeded."
                 );
             }
 
@@ -132,22 +129,22 @@ pub async fn logging_response(response: &data_for_tap::ResponseValue) {
                 let data = match liquid_tap::generate_execution(&response.data) {
                     Ok(result) => result,
                     Err(result) => {
-                        log::warn!(
-                            "failed to generate!\n-->\ndetails : {}\nresponse : {:?}<--",
-                            result,
-                            response
+                        tracing::warn!(details = %result, response = ?response, "failed to generate!");
+                        common::metrics::counter_increment(
+                            common::metrics::TAP_PARSE_FAILURES_TOTAL,
+                            &[("channel", response.channel.as_deref().unwrap_or_default())],
+                            1.0,
                         );
                         return;
                     }
                 };
                 let execution = database::data::Execution {
-                    _id: mongodb::bson::oid::ObjectId::new(),
                     received_at: std::time::SystemTime::now()
                         .duration_since(std::time::SystemTime::UNIX_EPOCH)
                         .unwrap()
                         .as_secs_f64(),
                     timestamp: data.timestamp,
-                    created_at: data.created_at as i32,
+                    created_at: i64::from(data.created_at),
                     price: data.price,
                     quantity: data.quantity,
                     taker_side: data.taker_side,
@@ -160,12 +157,12 @@ pub async fn logging_response(response: &data_for_tap::ResponseValue) {
                     )
                     .await
                 {
-                    log::error!("failed to create posts!\n-->\ndetails : {}\n<--", result);
+                    tracing::error!(details = %result, "failed to create posts!");
                 }
             }
 
             _ => {
-                log::warn!("unknown event!\n-->\ndetails : {:?}\n<--", response);
+                tracing::warn!(details = ?response, "unknown event!");
             }
         }
     }
@@ -179,9 +176,9 @@ pub async fn order_book_buy(
     if response.event.as_ref().is_some() {
         match response.event.as_ref().unwrap().as_str() {
             "pusher_internal:subscription_succeeded" => {
-                log::info!(
-                    "subscribing {} succeeded.",
-                    response.channel.as_ref().unwrap()
+                tracing::info!(
+                    channel = response.channel.as_deref().unwrap_or_default(),
+                    "subscribing succeeded."
                 );
             }
 
@@ -189,16 +186,16 @@ pub async fn order_book_buy(
                 let data = match liquid_tap::generate_order_book(&response.data) {
                     Ok(result) => result,
                     Err(result) => {
-                        log::warn!(
-                            "failed to generate!\n-->\ndetails : {}\nresponse : {:?}<--",
-                            result,
-                            response
+                        tracing::warn!(details = %result, response = ?response, "failed to generate!");
+                        common::metrics::counter_increment(
+                            common::metrics::TAP_PARSE_FAILURES_TOTAL,
+                            &[("channel", response.channel.as_deref().unwrap_or_default())],
+                            1.0,
                         );
                         return;
                     }
                 };
                 let order_book_buy = database::data::OrderBook {
-                    _id: mongodb::bson::oid::ObjectId::new(),
                     received_at: std::time::SystemTime::now()
                         .duration_since(std::time::SystemTime::UNIX_EPOCH)
                         .unwrap()
@@ -213,12 +210,12 @@ pub async fn order_book_buy(
                     )
                     .await
                 {
-                    log::error!("failed to create posts!\n-->\ndetails : {}\n<--", result);
+                    tracing::error!(details = %result, "failed to create posts!");
                 }
             }
 
             _ => {
-                log::warn!("unknown event!\n-->\ndetails : {:?}\n<--", response);
+                tracing::warn!(details = ?response, "unknown event!");
             }
         }
     }
@@ -232,9 +229,9 @@ pub async fn order_book_sell(
     if response.event.as_ref().is_some() {
         match response.event.as_ref().unwrap().as_str() {
             "pusher_internal:subscription_succeeded" => {
-                log::info!(
-                    "subscribing {} succeeded.",
-                    response.channel.as_ref().unwrap()
+                tracing::info!(
+                    channel = response.channel.as_deref().unwrap_or_default(),
+                    "subscribing succeeded."
                 );
             }
 
@@ -242,16 +239,16 @@ pub async fn order_book_sell(
                 let data = match liquid_tap::generate_order_book(&response.data) {
                     Ok(result) => result,
                     Err(result) => {
-                        log::warn!(
-                            "failed to generate!\n-->\ndetails : {}\nresponse : {:?}<--",
-                            result,
-                            response
+                        tracing::warn!(details = %result, response = ?response, "failed to generate!");
+                        common::metrics::counter_increment(
+                            common::metrics::TAP_PARSE_FAILURES_TOTAL,
+                            &[("channel", response.channel.as_deref().unwrap_or_default())],
+                            1.0,
                         );
                         return;
                     }
                 };
                 let order_book_sell = database::data::OrderBook {
-                    _id: mongodb::bson::oid::ObjectId::new(),
                     received_at: std::time::SystemTime::now()
                         .duration_since(std::time::SystemTime::UNIX_EPOCH)
                         .unwrap()
@@ -266,12 +263,12 @@ pub async fn order_book_sell(
                     )
                     .await
                 {
-                    log::error!("failed to create posts!\n-->\ndetails : {}\n<--", result);
+                    tracing::error!(details = %result, "failed to create posts!");
                 }
             }
 
             _ => {
-                log::warn!("unknown event!\n-->\ndetails : {:?}\n<--", response);
+                tracing::warn!(details = ?response, "unknown event!");
             }
         }
     }
@@ -285,9 +282,9 @@ pub fn executions_details_handler(
     if response.event.as_ref().is_some() {
         match response.event.as_ref().unwrap().as_str() {
             "pusher_internal:subscription_succeeded" => {
-                log::info!(
-                    "subscribing {} succeeded.",
-                    response.channel.as_ref().unwrap()
+                tracing::info!(
+                    channel = response.channel.as_deref().unwrap_or_default(),
+                    "subscribing succeeded."
                 );
             }
 
@@ -295,10 +292,11 @@ pub fn executions_details_handler(
                 let _data = match liquid_tap::generate_execution_details(&response.data) {
                     Ok(result) => result,
                     Err(result) => {
-                        log::warn!(
-                            "failed to generate!\n-->\ndetails : {}\nresponse : {:?}<--",
-                            result,
-                            response
+                        tracing::warn!(details = %result, response = ?response, "failed to generate!");
+                        common::metrics::counter_increment(
+                            common::metrics::TAP_PARSE_FAILURES_TOTAL,
+                            &[("channel", response.channel.as_deref().unwrap_or_default())],
+                            1.0,
                         );
                         return;
                     }
@@ -306,7 +304,7 @@ pub fn executions_details_handler(
             }
 
             _ => {
-                log::warn!("unknown event!\n-->\ndetails : {:?}\n<--", response);
+                tracing::warn!(details = ?response, "unknown event!");
             }
         }
     }
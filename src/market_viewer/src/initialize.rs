@@ -32,23 +32,17 @@ pub async fn initialize_liquid_tap(key: &LiquidApiKey) -> Result<LiquidTapClient
     let mut client = match LiquidTapClientAsync::connect().await {
         Ok(result) => result,
         Err(result) => {
-            log::error!(
-                "fatal error! cannnot connect liquid. \n-->\ndetails : {}\n<--",
-                result
-            );
+            tracing::error!(details = %result, "fatal error! cannnot connect liquid.");
             return Err(());
         }
     };
 
     match client.authenticate(&key).await {
         Ok(()) => {
-            log::info!("initialized LiquidTap : authentication");
+            tracing::info!("initialized LiquidTap : authentication");
         }
         Err(result) => {
-            log::error!(
-                "{}",
-                error_message!("failed to authenticate! \n-->\ndetails : {}\n<--", result)
-            );
+            tracing::error!(details = %result, "failed to authenticate!");
             return Err(());
         }
     }
@@ -58,16 +52,10 @@ pub async fn initialize_liquid_tap(key: &LiquidApiKey) -> Result<LiquidTapClient
         .await
     {
         Ok(()) => {
-            log::info!("initialized LiquidTap : sending subscribing signal succeeded.");
+            tracing::info!("initialized LiquidTap : sending subscribing signal succeeded.");
         }
         Err(result) => {
-            log::error!(
-                "{}",
-                error_message!(
-                    "failed to subscribe a channel! \n-->\ndetails : {}\n<--",
-                    result
-                )
-            );
+            tracing::error!(details = %result, "failed to subscribe a channel!");
             return Err(());
         }
     }
@@ -77,16 +65,10 @@ pub async fn initialize_liquid_tap(key: &LiquidApiKey) -> Result<LiquidTapClient
         .await
     {
         Ok(()) => {
-            log::info!("initialized LiquidTap : sending subscribing signal succeeded.");
+            tracing::info!("initialized LiquidTap : sending subscribing signal succeeded.");
         }
         Err(result) => {
-            log::error!(
-                "{}",
-                error_message!(
-                    "failed to subscribe a channel! \n-->\ndetails : {}\n<--",
-                    result
-                )
-            );
+            tracing::error!(details = %result, "failed to subscribe a channel!");
             return Err(());
         }
     }
@@ -96,16 +78,10 @@ pub async fn initialize_liquid_tap(key: &LiquidApiKey) -> Result<LiquidTapClient
         .await
     {
         Ok(()) => {
-            log::info!("initialized LiquidTap : sending subscribing signal succeeded.");
+            tracing::info!("initialized LiquidTap : sending subscribing signal succeeded.");
         }
         Err(result) => {
-            log::error!(
-                "{}",
-                error_message!(
-                    "failed to subscribe a channel! \n-->\ndetails : {}\n<--",
-                    result
-                )
-            );
+            tracing::error!(details = %result, "failed to subscribe a channel!");
             return Err(());
         }
     }
@@ -115,16 +91,10 @@ pub async fn initialize_liquid_tap(key: &LiquidApiKey) -> Result<LiquidTapClient
         .await
     {
         Ok(()) => {
-            log::info!("initialized LiquidTap : sending subscribing signal succeeded.");
+            tracing::info!("initialized LiquidTap : sending subscribing signal succeeded.");
         }
         Err(result) => {
-            log::error!(
-                "{}",
-                error_message!(
-                    "failed to subscribe a channel! \n-->\ndetails : {}\n<--",
-                    result
-                )
-            );
+            tracing::error!(details = %result, "failed to subscribe a channel!");
             return Err(());
         }
     }
@@ -134,16 +104,10 @@ pub async fn initialize_liquid_tap(key: &LiquidApiKey) -> Result<LiquidTapClient
         .await
     {
         Ok(()) => {
-            log::info!("initialized LiquidTap : sending subscribing signal succeeded.");
+            tracing::info!("initialized LiquidTap : sending subscribing signal succeeded.");
         }
         Err(result) => {
-            log::error!(
-                "{}",
-                error_message!(
-                    "failed to subscribe a channel! \n-->\ndetails : {}\n<--",
-                    result
-                )
-            );
+            tracing::error!(details = %result, "failed to subscribe a channel!");
             return Err(());
         }
     }
@@ -198,5 +162,6 @@ pub async fn initialize_liquid_tap(key: &LiquidApiKey) -> Result<LiquidTapClient
     //    return Err(());
     //}
 
+    common::health::set_tap_connected(true);
     Ok(client)
 }
@@ -4,7 +4,7 @@ use std::result::Result;
 use clap::{Arg, Command};
 use libsystemd::*;
 
-use common::{common_constants, error_message, error_message_colored, *};
+use common::{common_constants, error_message, error_message_colored, ok_message_colored, *};
 //use database::*;
 use liquid::*;
 
@@ -21,23 +21,52 @@ async fn main() {
         .version(env!("CARGO_PKG_VERSION"))
         .author(env!("CARGO_PKG_AUTHORS"))
         .about(env!("CARGO_PKG_DESCRIPTION"))
-        .subcommand(Command::new("status").about("show the status."))
+        .subcommand(
+            Command::new("status")
+                .about("show the status.")
+                .arg(
+                    Arg::new("metrics")
+                        .long("metrics")
+                        .help("show metrics in prometheus text format."),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("print the status as json."),
+                ),
+        )
         .subcommand(
             Command::new("start")
                 .about("start the program.")
                 .arg(Arg::new("CONFIG").required(true).help("config file path.")),
         )
         .subcommand(Command::new("shutdown").about("shutdown the program."))
+        .subcommand(
+            Command::new("log-level")
+                .about("change the logging directive of the running program.")
+                .arg(
+                    Arg::new("DIRECTIVE")
+                        .required(true)
+                        .help("such as info,market_viewer=debug,liquid=trace"),
+                ),
+        )
         .get_matches();
 
-    if let Some(_matched) = matches.subcommand_matches("status") {
-        handler::status_handler().await;
+    if let Some(matched) = matches.subcommand_matches("status") {
+        std::process::exit(
+            handler::status_handler(matched.is_present("metrics"), matched.is_present("json"))
+                .await,
+        );
     } else if let Some(matched) = matches.subcommand_matches("start") {
         if let Some(config_path) = matched.value_of("CONFIG") {
             handler::start_handler(config_path).await;
         }
     } else if let Some(_matched) = matches.subcommand_matches("shutdown") {
         handler::shutdown_handler().await;
+    } else if let Some(matched) = matches.subcommand_matches("log-level") {
+        if let Some(directive) = matched.value_of("DIRECTIVE") {
+            handler::log_level_handler(directive).await;
+        }
     } else {
     }
 }
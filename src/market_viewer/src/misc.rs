@@ -13,17 +13,14 @@ pub async fn load_config(path: &str) -> Result<Config, ()> {
     let content = match tokio::fs::read_to_string(path).await {
         Ok(content) => content,
         Err(result) => {
-            log::error!("failed to load!\n-->\ndetails : {}\n<--", result);
+            tracing::error!(details = %result, "failed to load!");
             return Err(());
         }
     };
     let config = match toml::from_str::<Config>(&content) {
         Ok(content) => content,
         Err(result) => {
-            log::error!(
-                "failed to deserializes config file!\n-->\ndetails : {}\n<--",
-                result
-            );
+            tracing::error!(details = %result, "failed to deserializes config file!");
             return Err(());
         }
     };
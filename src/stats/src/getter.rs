@@ -74,50 +74,45 @@ impl Stats {
     }
 }
 
-pub async fn get_stats_ticker(
+///
+/// the stages summarizing `name` over the records matched by `query`.
+fn summary_pipeline<T: Record>(query: &Query<T>, name: &str) -> Vec<bson::Document> {
+    let timeline = query.timeline();
+    let mut pipeline = query.pipeline();
+    pipeline.push(doc! {"$group": {
+      "_id": null,
+      "size": { "$sum": 1 },
+      "first": { "$first": name },
+      "last": { "$last": name },
+      "sum": { "$sum": name },
+      "min": {
+        "$min": name
+      },
+      "max": {
+        "$max": name
+      },
+      "mean": {
+        "$avg": name
+      },
+      "std_dev": {
+        "$stdDevPop": name
+      },
+    }});
+    pipeline.push(doc! {"$addFields": {
+        "start_time": timeline.start,
+        "end_time": timeline.end,
+    }});
+    pipeline
+}
+
+async fn get_stats<T: Record>(
     database: &Database,
-    name: &str,
-    start_time: DateTime<Utc>,
-    end_time: DateTime<Utc>,
+    query: &Query<T>,
+    pipeline: Vec<bson::Document>,
 ) -> Result<Option<Stats>, String> {
-    let name = String::from("$") + name;
-    let pipeline = vec![
-        doc! {"$match": {
-          "received_at": {
-          "$gte": start_time.timestamp(),
-          "$lt": end_time.timestamp()
-         }
-        }},
-        doc! {"$sort": {
-          "received_at": 1
-        }},
-        doc! {"$group": {
-          "_id": null,
-          "size": { "$sum": 1 },
-          "first": { "$first": &name },
-          "last": { "$last": &name },
-          "sum": { "$sum": &name },
-          "min": {
-            "$min": &name
-          },
-          "max": {
-            "$max": &name
-          },
-          "mean": {
-            "$avg": &name
-          },
-          "std_dev": {
-            "$stdDevPop": &name
-          },
-        }},
-        doc! {"$addFields": {
-            "start_time": start_time.timestamp(),
-            "end_time": end_time.timestamp(),
-        }},
-    ];
     let mut stats_stream = match stream::DatabaseStream::<Stats>::aggregate(
-        &database,
-        Some(common_constants::DATABASE_COLLECTION_TICKER),
+        database,
+        query.collection_name(),
         pipeline,
         None,
     )
@@ -151,90 +146,55 @@ pub async fn get_stats_ticker(
     }
 }
 
-pub async fn get_stats_order_book_buy(
+pub async fn get_stats_ticker(
     database: &Database,
     name: &str,
     start_time: DateTime<Utc>,
     end_time: DateTime<Utc>,
+) -> Result<Option<Stats>, String> {
+    let query = Query::<data::Ticker>::between(&start_time, &end_time);
+    let pipeline = summary_pipeline(&query, &(String::from("$") + name));
+    get_stats(database, &query, pipeline).await
+}
+
+async fn get_stats_order_book(
+    database: &Database,
+    collection: &str,
+    name: &str,
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
 ) -> Result<Stats, String> {
-    let name = String::from("$orders.") + name;
-    let pipeline = vec![
-        doc! {
-            "$match": {
-                "received_at": {
-                    "$gte": start_time.timestamp(),
-                    "$lt": end_time.timestamp()
-                }
-            }
-        },
-        doc! {"$sort": {
-          "received_at": 1
-        }},
+    let query = Query::<data::OrderBook>::between(&start_time, &end_time).collection(collection)?;
+    let mut pipeline = summary_pipeline(&query, &(String::from("$orders.") + name));
+    // the orders are summarized across the books, after the books are matched and sorted.
+    pipeline.insert(
+        2,
         doc! {
             "$unwind": {
                 "path": "$orders",
             }
         },
-        doc! {
-            "$group": {
-                "_id": null,
-                "size": {
-                    "$sum": 1
-                },
-                "first": { "$first": &name },
-                "last": { "$last": &name },
-                "sum": { "$sum": &name },
-                "min": {
-                    "$min": &name
-                },
-                "max": {
-                    "$max": &name
-                },
-                "mean": {
-                    "$avg": &name
-                },
-                "std_dev": {
-                    "$stdDevPop": &name
-                },
-            }
-        },
-    ];
-    let mut stats_stream = match stream::DatabaseStream::<Stats>::aggregate(
-        &database,
-        Some(common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY),
-        pipeline,
-        None,
+    );
+    match get_stats(database, &query, pipeline).await? {
+        Some(result) => Ok(result),
+        None => Err(error_message!("nothing!")),
+    }
+}
+
+pub async fn get_stats_order_book_buy(
+    database: &Database,
+    name: &str,
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+) -> Result<Stats, String> {
+    get_stats_order_book(
+        database,
+        common_constants::DATABASE_COLLECTION_ORDER_BOOK_BUY,
+        name,
+        start_time,
+        end_time,
     )
     .await
-    {
-        Ok(result) => result,
-        Err(result) => {
-            return Err(error_message!(
-                "failed to send a query!\ndetails : {}",
-                result
-            ));
-        }
-    };
-    loop {
-        tokio::select! {
-            Some(result) = stats_stream.next() => {
-                match result {
-                    Ok(result) => {
-                        return Ok(result);
-                    }
-                    Err(result) => {
-                        return Err(error_message!(
-                            "failed to get!\ndetails : {}",
-                            result
-                        ));
-                    }
-                }
-            }
-            else => return Err(error_message!(
-                "nothing!"
-            )),
-        }
-    }
 }
 
 pub async fn get_stats_order_book_sell(
@@ -243,267 +203,62 @@ pub async fn get_stats_order_book_sell(
     start_time: DateTime<Utc>,
     end_time: DateTime<Utc>,
 ) -> Result<Stats, String> {
-    let name = String::from("$orders.") + name;
-    let pipeline = vec![
-        doc! {
-            "$match": {
-                "received_at": {
-                    "$gte": start_time.timestamp(),
-                    "$lt": end_time.timestamp()
-                }
-            }
-        },
-        doc! {"$sort": {
-          "received_at": 1
-        }},
-        doc! {
-            "$unwind": {
-                "path": "$orders",
-            }
-        },
-        doc! {
-            "$group": {
-                "_id": null,
-                "size": {
-                    "$sum": 1
-                },
-                "first": { "$first": &name },
-                "last": { "$last": &name },
-                "sum": { "$sum": &name },
-                "min": {
-                    "$min": &name
-                },
-                "max": {
-                    "$max": &name
-                },
-                "mean": {
-                    "$avg": &name
-                },
-                "std_dev": {
-                    "$stdDevPop": &name
-                },
-            }
-        },
-    ];
-    let mut stats_stream = match stream::DatabaseStream::<Stats>::aggregate(
-        &database,
-        Some(common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL),
-        pipeline,
-        None,
+    get_stats_order_book(
+        database,
+        common_constants::DATABASE_COLLECTION_ORDER_BOOK_SELL,
+        name,
+        start_time,
+        end_time,
     )
     .await
-    {
-        Ok(result) => result,
-        Err(result) => {
-            return Err(error_message!(
-                "failed to send a query!\ndetails : {}",
-                result
-            ));
-        }
-    };
-    loop {
-        tokio::select! {
-            Some(result) = stats_stream.next() => {
-                match result {
-                    Ok(result) => {
-                        return Ok(result);
-                    }
-                    Err(result) => {
-                        return Err(error_message!(
-                            "failed to get!\ndetails : {}",
-                            result
-                        ));
-                    }
-                }
-            }
-            else => return Err(error_message!(
-                "nothing!"
-            )),
-        }
-    }
 }
 
 pub async fn get_tickers(
-    database: Arc<Database>,
+    database: Arc<dyn Store>,
     start_time: DateTime<Utc>,
     end_time: DateTime<Utc>,
 ) -> Result<Vec<data::Ticker>, String> {
-    let collection = common_constants::DATABASE_COLLECTION_TICKER;
-    let query = match create_duration_query(collection, &start_time, &end_time) {
-        Ok(content) => content,
-        Err(result) => {
-            return Err(error_message!(
-                "failed to create query!\ndetails : {}",
-                result
-            ));
-        }
-    };
-
-    let options = match create_find_option_timeline(collection, 1, None) {
-        Ok(content) => content,
-        Err(result) => {
-            return Err(error_message!(
-                "failed to create query!\ndetails : {}",
-                result
-            ));
-        }
-    };
-
-    let mut ticker_stream = match stream::DatabaseStream::<data::Ticker>::request(
-        &database, None, query, options,
-    )
-    .await
+    match Query::<data::Ticker>::between(&start_time, &end_time)
+        .fetch(database.as_ref())
+        .await
     {
-        Ok(result) => result,
-        Err(result) => {
-            return Err(error_message!(
-                "failed to send a query!\ndetails : {}",
-                result
-            ));
-        }
-    };
-    let mut data = Vec::with_capacity(ticker_stream.size_hint().0);
-    loop {
-        tokio::select! {
-            Some(result) = ticker_stream.next() => {
-                match result {
-                    Ok(result) => {
-                        data.push(result);
-                    }
-                    Err(result) => {
-                        return Err(error_message!(
-                            "failed to get!\ndetails : {}",
-                            result
-                        ));
-                    }
-                }
-            }
-            else => break,
-        }
+        Ok(result) => Ok(result),
+        Err(result) => Err(error_message!(
+            "failed to send a query!\ndetails : {}",
+            result
+        )),
     }
-    Ok(data)
 }
 
 pub async fn get_executions(
-    database: Arc<Database>,
+    database: Arc<dyn Store>,
     start_time: DateTime<Utc>,
     end_time: DateTime<Utc>,
 ) -> Result<Vec<data::Execution>, String> {
-    let collection = common_constants::DATABASE_COLLECTION_EXECUTIONS;
-    let query = match create_duration_query(collection, &start_time, &end_time) {
-        Ok(content) => content,
-        Err(result) => {
-            return Err(error_message!(
-                "failed to create query!\ndetails : {}",
-                result
-            ));
-        }
-    };
-
-    let options = match create_find_option_timeline(collection, 1, None) {
-        Ok(content) => content,
-        Err(result) => {
-            return Err(error_message!(
-                "failed to create query!\ndetails : {}",
-                result
-            ));
-        }
-    };
-
-    let mut ticker_stream =
-        match stream::DatabaseStream::<data::Execution>::request(&database, None, query, options)
-            .await
-        {
-            Ok(result) => result,
-            Err(result) => {
-                return Err(error_message!(
-                    "failed to send a query!\ndetails : {}",
-                    result
-                ));
-            }
-        };
-    let mut data = Vec::with_capacity(ticker_stream.size_hint().0);
-    loop {
-        tokio::select! {
-            Some(result) = ticker_stream.next() => {
-                match result {
-                    Ok(result) => {
-                        data.push(result);
-                    }
-                    Err(result) => {
-                        return Err(error_message!(
-                            "failed to get!\ndetails : {}",
-                            result
-                        ));
-                    }
-                }
-            }
-            else => break,
-        }
+    match Query::<data::Execution>::between(&start_time, &end_time)
+        .fetch(database.as_ref())
+        .await
+    {
+        Ok(result) => Ok(result),
+        Err(result) => Err(error_message!(
+            "failed to send a query!\ndetails : {}",
+            result
+        )),
     }
-    Ok(data)
 }
 
 pub async fn get_order_book(
-    database: Arc<Database>,
+    database: Arc<dyn Store>,
     collection: &str,
     start_time: DateTime<Utc>,
     end_time: DateTime<Utc>,
 ) -> Result<Vec<data::OrderBook>, String> {
-    let query = match create_duration_query(collection, &start_time, &end_time) {
-        Ok(content) => content,
-        Err(result) => {
-            return Err(error_message!(
-                "failed to create query!\ndetails : {}",
-                result
-            ));
-        }
-    };
-
-    let options = match create_find_option_timeline(collection, 1, None) {
-        Ok(content) => content,
-        Err(result) => {
-            return Err(error_message!(
-                "failed to create query!\ndetails : {}",
-                result
-            ));
-        }
-    };
-
-    let mut ticker_stream = match stream::DatabaseStream::<data::OrderBook>::request(
-        &database,
-        Some(collection),
-        query,
-        options,
-    )
-    .await
-    {
-        Ok(result) => result,
-        Err(result) => {
-            return Err(error_message!(
-                "failed to send a query!\ndetails : {}",
-                result
-            ));
-        }
-    };
-    let mut data = Vec::with_capacity(ticker_stream.size_hint().0);
-    loop {
-        tokio::select! {
-            Some(result) = ticker_stream.next() => {
-                match result {
-                    Ok(result) => {
-                        data.push(result);
-                    }
-                    Err(result) => {
-                        return Err(error_message!(
-                            "failed to get!\ndetails : {}",
-                            result
-                        ));
-                    }
-                }
-            }
-            else => break,
-        }
+    let query = Query::<data::OrderBook>::between(&start_time, &end_time).collection(collection)?;
+    match query.fetch(database.as_ref()).await {
+        Ok(result) => Ok(result),
+        Err(result) => Err(error_message!(
+            "failed to send a query!\ndetails : {}",
+            result
+        )),
     }
-    Ok(data)
 }
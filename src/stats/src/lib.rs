@@ -6,7 +6,7 @@ use mongodb::bson::doc;
 use serde::{Deserialize, Serialize};
 #[macro_use]
 extern crate ndarray;
-use futures::stream::{Stream, StreamExt};
+use futures::stream::StreamExt;
 
 use ndarray::prelude::*;
 use ndarray_linalg::*;
@@ -76,12 +76,57 @@ impl MarketInfo {
         self.order_book_sell
             .retain(|e| e.received_at > now - period);
     }
+
+    ///
+    /// length and allocated bytes of each buffer.
+    pub fn buffers(&self) -> std::collections::BTreeMap<String, health::BufferHealth> {
+        fn allocated<T>(buffer: &Vec<T>) -> usize {
+            buffer.capacity() * std::mem::size_of::<T>()
+        }
+        let order_book_bytes = |order_books: &Vec<data::OrderBook>| {
+            allocated(order_books)
+                + order_books
+                    .iter()
+                    .map(|e| allocated(&e.orders))
+                    .sum::<usize>()
+        };
+        [
+            ("tickers", self.tickers.len(), allocated(&self.tickers)),
+            (
+                "executions",
+                self.executions.len(),
+                allocated(&self.executions),
+            ),
+            (
+                "order_book_buy",
+                self.order_book_buy.len(),
+                order_book_bytes(&self.order_book_buy),
+            ),
+            (
+                "order_book_sell",
+                self.order_book_sell.len(),
+                order_book_bytes(&self.order_book_sell),
+            ),
+        ]
+        .iter()
+        .map(|(name, length, bytes)| {
+            (
+                String::from(*name),
+                health::BufferHealth {
+                    length: *length,
+                    bytes: *bytes,
+                },
+            )
+        })
+        .collect()
+    }
 }
 
 #[test]
 fn check_iter() {
     for (i, t) in DateTimeIter::new(
-        Utc.ymd(2021, 9, 13).and_hms(15, 58, 0)..Utc.ymd(2021, 9, 13).and_hms(16, 58, 0),
+        Utc.with_ymd_and_hms(2021, 9, 13, 15, 58, 0).unwrap()
+            ..Utc.with_ymd_and_hms(2021, 9, 13, 16, 58, 0).unwrap(),
         Duration::minutes(1),
     )
     .enumerate()
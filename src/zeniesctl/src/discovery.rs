@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+
+use common::*;
+
+///
+/// a daemon found in the etc directory.
+/// the name is the file stem shared by its socket and pid file such as `market_maker_alpha`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Service {
+    pub name: String,
+    pub socket_path: Option<String>,
+    pub pid: Option<u32>,
+}
+
+impl Service {
+    ///
+    /// whether the process written in the pid file still exists.
+    pub fn is_alive(&self) -> bool {
+        match self.pid {
+            Some(pid) => std::path::Path::new(&format!("/proc/{}", pid)).exists(),
+            None => false,
+        }
+    }
+}
+
+///
+/// find every daemon which left a socket or a pid file in etc_directory_path.
+pub fn discover(etc_directory_path: &str) -> Result<Vec<Service>, String> {
+    let entries = match std::fs::read_dir(etc_directory_path) {
+        Ok(result) => result,
+        Err(result) => {
+            return Err(error_message!(
+                "failed to read the etc directory!\ndetails : {:?}\npath : {}",
+                result,
+                etc_directory_path
+            ));
+        }
+    };
+
+    let mut services = BTreeMap::<String, Service>::new();
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let path = entry.path().to_string_lossy().into_owned();
+        if let Some(name) = file_name.strip_suffix(common_constants::SOCKET_EXTENSION) {
+            services
+                .entry(String::from(name))
+                .or_insert_with(|| new_service(name))
+                .socket_path = Some(path);
+        } else if let Some(name) = file_name.strip_suffix(common_constants::PID_EXTENSION) {
+            let pid = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|e| e.trim().parse::<u32>().ok());
+            services
+                .entry(String::from(name))
+                .or_insert_with(|| new_service(name))
+                .pid = pid;
+        }
+    }
+    Ok(services.into_values().collect())
+}
+
+///
+/// pick the services named in `names`, or every service when `all` is set.
+pub fn select(services: Vec<Service>, names: &[&str], all: bool) -> Result<Vec<Service>, String> {
+    if all {
+        return Ok(services);
+    }
+    if names.is_empty() {
+        return Err(error_message!("specify NAME or --all!"));
+    }
+    let mut selected = Vec::new();
+    for name in names {
+        match services.iter().find(|e| e.name == *name) {
+            Some(service) => selected.push(service.clone()),
+            None => return Err(error_message!("{} is not found!", name)),
+        }
+    }
+    Ok(selected)
+}
+
+fn new_service(name: &str) -> Service {
+    Service {
+        name: String::from(name),
+        socket_path: None,
+        pid: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discover_sockets_and_pid_files() {
+        let directory = std::env::temp_dir().join(format!("zeniesctl_test_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("liquid_loggerd.sock"), "").unwrap();
+        std::fs::write(
+            directory.join("liquid_loggerd.pid"),
+            std::process::id().to_string(),
+        )
+        .unwrap();
+        std::fs::write(directory.join("market_maker_alpha.pid"), "4294967295").unwrap();
+        std::fs::write(directory.join("market_maker_alpha.lock"), "").unwrap();
+
+        let etc_directory_path = directory.to_string_lossy().into_owned() + "/";
+        let services = discover(&etc_directory_path).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(services.len(), 2);
+        assert_eq!(services[0].name, "liquid_loggerd");
+        assert!(services[0].socket_path.is_some());
+        assert!(services[0].is_alive());
+        assert_eq!(services[1].name, "market_maker_alpha");
+        assert_eq!(services[1].socket_path, None);
+        assert!(!services[1].is_alive());
+
+        assert_eq!(
+            select(services.clone(), &["market_maker_alpha"], false)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(select(services.clone(), &[], true).unwrap().len(), 2);
+        assert!(select(services.clone(), &[], false).is_err());
+        assert!(select(services, &["market_viewer"], false).is_err());
+    }
+}
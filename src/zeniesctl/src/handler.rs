@@ -0,0 +1,946 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::{TimeZone, Utc};
+use common::*;
+
+use crate::discovery::{self, Service};
+
+///
+/// time to wait for a daemon to answer a request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+async fn load_services(names: &[&str], all: bool) -> Result<Vec<Service>, String> {
+    let env = load_env("./env.toml").await?;
+    let services = discovery::discover(&env.general.etc_directory_path)?;
+    discovery::select(services, names, all)
+}
+
+async fn open(service: &Service) -> Result<ipc::StreamAsync, String> {
+    let socket_path = match &service.socket_path {
+        Some(content) => content,
+        None => return Err(error_message!("{} has no socket!", service.name)),
+    };
+    ipc::StreamAsync::open(socket_path).await
+}
+
+///
+/// send a command and wait for the response if `expect_response` is set.
+async fn request(
+    service: &Service,
+    command: ipc::Command,
+    expect_response: bool,
+) -> Result<Option<HashMap<String, String>>, String> {
+    let requested = async {
+        let mut stream = open(service).await?;
+        stream.send_command(command).await?;
+        if !expect_response {
+            return Ok(None);
+        }
+        let result = stream.receive_command().await?;
+        Ok(Some(result.messages.unwrap_or_default()))
+    };
+    match tokio::time::timeout(REQUEST_TIMEOUT, requested).await {
+        Ok(result) => result,
+        Err(_) => Err(error_message!("{} did not respond!", service.name)),
+    }
+}
+
+pub async fn list_handler(json: bool) -> i32 {
+    let services = match load_services(&[], true).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+
+    let mut rows = Vec::new();
+    for service in &services {
+        let (state, healthy) = match request(service, ipc::Command::status(), true).await {
+            Ok(Some(data)) => (
+                "running",
+                data.get(common_constants::DEFINED_COMMAND_OPTION_HEALTH)
+                    .and_then(|e| serde_json::from_str::<health::Health>(e).ok())
+                    .map(|e| e.healthy),
+            ),
+            _ if service.is_alive() => ("unresponsive", Some(false)),
+            _ => ("dead", None),
+        };
+        rows.push((service, state, healthy));
+    }
+
+    if json {
+        let array = rows
+            .iter()
+            .map(|(service, state, healthy)| {
+                serde_json::json!({
+                    "name": service.name,
+                    "pid": service.pid,
+                    "state": state,
+                    "healthy": healthy,
+                })
+            })
+            .collect::<Vec<serde_json::Value>>();
+        println!("{}", serde_json::Value::Array(array));
+    } else {
+        println!("{:<32} {:>8} {:<14} HEALTH", "NAME", "PID", "STATE");
+        for (service, state, healthy) in &rows {
+            let health = match healthy {
+                Some(true) => format!("{}", console::style("healthy").green()),
+                Some(false) => format!("{}", console::style("unhealthy").red()),
+                None => String::from("-"),
+            };
+            println!(
+                "{:<32} {:>8} {:<14} {}",
+                service.name,
+                service
+                    .pid
+                    .map(|e| e.to_string())
+                    .unwrap_or_else(|| String::from("-")),
+                state,
+                health
+            );
+        }
+    }
+    0
+}
+
+pub async fn status_handler(names: &[&str], all: bool, json: bool, show_metrics: bool) -> i32 {
+    let services = match load_services(names, all).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+
+    let mut code = 0;
+    for (i, service) in services.iter().enumerate() {
+        if !json && i > 0 {
+            println!();
+        }
+        match request(service, ipc::Command::status(), true).await {
+            Ok(Some(data)) => {
+                code = code.max(health::print_status(&data, json, show_metrics));
+            }
+            Ok(None) => {}
+            Err(result) => {
+                eprintln!(
+                    "{}",
+                    error_message_colored!(
+                        "failed to get the status of {}!\n{}",
+                        service.name,
+                        result
+                    )
+                );
+                code = 1;
+            }
+        }
+    }
+    code
+}
+
+///
+/// send a command answered with `success` or `error` such as reload-log-level, pause, resume and log_level.
+pub async fn control_handler(names: &[&str], all: bool, command: ipc::Command) -> i32 {
+    let services = match load_services(names, all).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+
+    let mut code = 0;
+    for service in &services {
+        match request(service, command.clone(), true).await {
+            Ok(Some(data)) => {
+                if let Some(content) = data.get(common_constants::DEFINED_COMMAND_OPTION_ERROR) {
+                    eprintln!(
+                        "{}",
+                        error_message_colored!("{} : {}", service.name, content)
+                    );
+                    code = 1;
+                } else if let Some(content) =
+                    data.get(common_constants::DEFINED_COMMAND_OPTION_SUCCESS)
+                {
+                    println!("{}", ok_message_colored!("{} : {}", service.name, content));
+                }
+            }
+            Ok(None) => {}
+            Err(result) => {
+                eprintln!(
+                    "{}",
+                    error_message_colored!("{} : {}", service.name, result)
+                );
+                code = 1;
+            }
+        }
+    }
+    code
+}
+
+pub async fn shutdown_handler(names: &[&str], all: bool) -> i32 {
+    let services = match load_services(names, all).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+
+    let mut code = 0;
+    for service in &services {
+        match request(service, ipc::Command::gracefull_shutdown(), false).await {
+            Ok(_) => println!(
+                "{}",
+                ok_message_colored!("{} : requested to shutdown.", service.name)
+            ),
+            Err(result) => {
+                eprintln!(
+                    "{}",
+                    error_message_colored!("{} : {}", service.name, result)
+                );
+                code = 1;
+            }
+        }
+    }
+    code
+}
+
+///
+/// print the events of the services until all of them disconnect.
+pub async fn tail_handler(names: &[&str], all: bool) -> i32 {
+    let services = match load_services(names, all).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+
+    let tasks = services.into_iter().map(|service| async move {
+        let mut stream = match open(&service).await {
+            Ok(result) => result,
+            Err(result) => return Err(error_message!("{} : {}", service.name, result)),
+        };
+        if let Err(result) = stream.send_command(ipc::Command::subscribe()).await {
+            return Err(error_message!("{} : {}", service.name, result));
+        }
+        loop {
+            let result = match stream.receive_command().await {
+                Ok(result) => result,
+                Err(_) => return Ok(()),
+            };
+            if let Some(event) = result
+                .messages
+                .as_ref()
+                .and_then(|e| e.get(common_constants::DEFINED_COMMAND_OPTION_EVENT))
+            {
+                println!("{} {}", console::style(&service.name).cyan(), event);
+            }
+        }
+    });
+
+    let mut code = 0;
+    for result in futures::future::join_all(tasks).await {
+        if let Err(result) = result {
+            eprintln!("{}", error_message_colored!("{}", result));
+            code = 1;
+        }
+    }
+    code
+}
+
+pub async fn config_handler(names: &[&str], all: bool) -> i32 {
+    let services = match load_services(names, all).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+
+    let mut code = 0;
+    for service in &services {
+        match request(service, ipc::Command::config(), true).await {
+            Ok(Some(data)) => {
+                if let Some(content) = data.get(common_constants::DEFINED_COMMAND_OPTION_CONFIG) {
+                    println!("# {}\n{}", service.name, content);
+                } else if let Some(content) =
+                    data.get(common_constants::DEFINED_COMMAND_OPTION_ERROR)
+                {
+                    eprintln!(
+                        "{}",
+                        error_message_colored!("{} : {}", service.name, content)
+                    );
+                    code = 1;
+                }
+            }
+            Ok(None) => {}
+            Err(result) => {
+                eprintln!(
+                    "{}",
+                    error_message_colored!("{} : {}", service.name, result)
+                );
+                code = 1;
+            }
+        }
+    }
+    code
+}
+
+///
+/// check the schema of the database in env.toml directly, without a daemon.
+pub async fn schema_handler(repair: bool, json: bool, symbol: &Symbol) -> i32 {
+    let env = match load_env("./env.toml").await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let store = match database::open_store_for(&env.general.database_url, symbol).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let reports = match store.check_schema(repair).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+
+    if json {
+        println!("{}", serde_json::to_string(&reports).unwrap_or_default());
+    } else {
+        println!("{:<20} {:<12} STATE", "COLLECTION", "TYPE");
+        for report in &reports {
+            println!(
+                "{:<20} {:<12} {}",
+                report.collection,
+                if report.time_series {
+                    "time-series"
+                } else {
+                    "collection"
+                },
+                if report.is_ok() {
+                    format!("{}", console::style("ok").green())
+                } else {
+                    format!("{}", console::style(report.problems.join(" ")).red())
+                }
+            );
+        }
+        for report in reports.iter().filter(|e| !e.created.is_empty()) {
+            println!(
+                "{}",
+                ok_message_colored!(
+                    "{} : created {}",
+                    report.collection,
+                    report.created.join(", ")
+                )
+            );
+        }
+    }
+    if reports.iter().all(|e| e.is_ok()) {
+        0
+    } else {
+        1
+    }
+}
+
+///
+/// seconds since the unix epoch of an ISO 8601 time. `keyword` means no bound.
+fn parse_bound(time: &str, keyword: &str) -> Result<Option<f64>, String> {
+    if time == keyword {
+        return Ok(None);
+    }
+    match chrono::DateTime::parse_from_rfc3339(time) {
+        Ok(result) => Ok(Some(
+            result.timestamp_nanos_opt().unwrap_or_default() as f64 / 1_000_000_000.0,
+        )),
+        Err(result) => Err(error_message!("invalid time! {}, {:?}", time, result)),
+    }
+}
+
+pub async fn dedupe_handler(
+    collection: &str,
+    from: &str,
+    to: &str,
+    dry_run: bool,
+    symbol: &Symbol,
+) -> i32 {
+    let timeline = match parse_timeline(from, to) {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let env = match load_env("./env.toml").await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let store = match database::open_store_for(&env.general.database_url, symbol).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    match store.dedupe(collection, &timeline, dry_run).await {
+        Ok(duplicates) => {
+            println!(
+                "{}",
+                ok_message_colored!(
+                    "{} : {} duplicates {}.",
+                    collection,
+                    duplicates,
+                    if dry_run { "found" } else { "removed" }
+                )
+            );
+            0
+        }
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            1
+        }
+    }
+}
+
+pub async fn retention_handler(dry_run: bool, json: bool, symbol: &Symbol) -> i32 {
+    let env = match load_env("./env.toml").await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let store = match database::open_store_for(&env.general.database_url, symbol).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let now = Utc::now().timestamp_nanos_opt().unwrap_or_default() as f64 / 1_000_000_000.0;
+    let reports = match database::retention::enforce(
+        store.as_ref(),
+        &env.retention,
+        &env.order_book,
+        now,
+        None,
+        dry_run,
+    )
+    .await
+    {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+
+    if json {
+        println!("{}", serde_json::to_string(&reports).unwrap_or_default());
+    } else {
+        println!(
+            "{:<20} {:>12} {:>12}",
+            "COLLECTION", "DOWNSAMPLED", "DELETED"
+        );
+        for report in &reports {
+            println!(
+                "{:<20} {:>12} {:>12}",
+                report.collection, report.downsampled, report.deleted
+            );
+        }
+        if dry_run {
+            println!(
+                "{}",
+                ok_message_colored!("nothing was removed by the dry run.")
+            );
+        }
+    }
+    0
+}
+
+pub async fn migrate_status_handler(json: bool, symbol: &Symbol) -> i32 {
+    let env = match load_env("./env.toml").await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let store = match database::open_store_for(&env.general.database_url, symbol).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let statuses = match database::migrations::status(store.as_ref()).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+
+    if json {
+        println!("{}", serde_json::to_string(&statuses).unwrap_or_default());
+    } else {
+        println!(
+            "{:<20} {:>8} {:>12} VERSIONS",
+            "COLLECTION", "CURRENT", "OUTDATED"
+        );
+        for status in &statuses {
+            println!(
+                "{:<20} {:>8} {:>12} {}",
+                status.collection,
+                status.current_version,
+                status.outdated(),
+                status
+                    .versions
+                    .iter()
+                    .map(|(version, count)| format!("v{}:{}", version, count))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            );
+        }
+    }
+    0
+}
+
+pub async fn migrate_up_handler(dry_run: bool, json: bool, symbol: &Symbol) -> i32 {
+    let env = match load_env("./env.toml").await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let store = match database::open_store_for(&env.general.database_url, symbol).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let reports = match database::migrations::up(store.as_ref(), dry_run).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+
+    if json {
+        println!("{}", serde_json::to_string(&reports).unwrap_or_default());
+    } else {
+        println!("{:<20} {:>12} {:>12}", "COLLECTION", "MIGRATED", "FAILED");
+        for report in &reports {
+            println!(
+                "{:<20} {:>12} {:>12}",
+                report.collection, report.migrated, report.failed
+            );
+        }
+        if dry_run {
+            println!(
+                "{}",
+                ok_message_colored!("nothing was written by the dry run.")
+            );
+        }
+    }
+    if reports.iter().all(|e| e.failed == 0) {
+        0
+    } else {
+        1
+    }
+}
+
+pub async fn dataset_convert_handler(source: &str, target: &str) -> i32 {
+    match database::dataset::convert_json(source, target).await {
+        Ok(result) => {
+            println!(
+                "{}",
+                ok_message_colored!("wrote {} records to {}.", result, target)
+            );
+            0
+        }
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            1
+        }
+    }
+}
+
+pub async fn dataset_info_handler(path: &str, json: bool) -> i32 {
+    let reader = match database::DatasetReader::open(path).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({ "header": reader.header, "index": reader.index })
+        );
+    } else {
+        let header = &reader.header;
+        println!("collection     : {}", header.collection);
+        println!("downloaded at  : {}", header.downloaded_at.to_rfc3339());
+        println!(
+            "range          : {} - {}",
+            header.start.to_rfc3339(),
+            header.end.to_rfc3339()
+        );
+        println!("schema version : {}", header.schema_version);
+        println!("records        : {}", reader.len());
+        println!();
+        println!(
+            "{:>12} {:>12} {:>8} {:<32} {:<32}",
+            "OFFSET", "LENGTH", "RECORDS", "FIRST", "LAST"
+        );
+        let format = |time: f64| {
+            Utc.timestamp_nanos((time * 1_000_000_000.0) as i64)
+                .to_rfc3339()
+        };
+        for block in &reader.index {
+            println!(
+                "{:>12} {:>12} {:>8} {:<32} {:<32}",
+                block.offset,
+                block.length,
+                block.records,
+                format(block.first),
+                format(block.last)
+            );
+        }
+    }
+    0
+}
+
+pub async fn import_handler(collection: &str, path: &str, symbol: &Symbol) -> i32 {
+    let env = match load_env("./env.toml").await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let store = match database::open_store_for(&env.general.database_url, symbol).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    match database::columnar::import_parquet(store.as_ref(), collection, path).await {
+        Ok(result) => {
+            println!(
+                "{}",
+                ok_message_colored!("inserted {} records into {}.", result, collection)
+            );
+            0
+        }
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            1
+        }
+    }
+}
+
+///
+/// the timeline between FROM and TO accepting "oldest" and "newest".
+fn parse_timeline(from: &str, to: &str) -> Result<database::Timeline, String> {
+    Ok(database::Timeline {
+        start: parse_bound(from, "oldest")?,
+        end: parse_bound(to, "newest")?,
+        ..database::Timeline::all()
+    })
+}
+
+pub async fn candles_rebuild_handler(from: &str, to: &str, symbol: &Symbol) -> i32 {
+    let timeline = match parse_timeline(from, to) {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let env = match load_env("./env.toml").await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let store = match database::open_store_for(&env.general.database_url, symbol).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    match database::candles::rebuild(store.as_ref(), &timeline).await {
+        Ok(written) => {
+            println!(
+                "{}",
+                ok_message_colored!("{} candles were written.", written)
+            );
+            0
+        }
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            1
+        }
+    }
+}
+
+pub async fn candles_show_handler(
+    resolution: &str,
+    from: &str,
+    to: &str,
+    json: bool,
+    symbol: &Symbol,
+) -> i32 {
+    let resolution = match database::candles::Resolution::generate_from_string(resolution) {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let timeline = match parse_timeline(from, to) {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let env = match load_env("./env.toml").await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let store = match database::open_store_for(&env.general.database_url, symbol).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let candles = match database::candles::query(store.as_ref(), resolution, &timeline).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+
+    if json {
+        println!("{}", serde_json::to_string(&candles).unwrap_or_default());
+    } else {
+        println!(
+            "{:<26} {:>10} {:>10} {:>10} {:>10} {:>12} {:>12} {:>8}",
+            "TIME", "OPEN", "HIGH", "LOW", "CLOSE", "VOLUME", "VWAP", "TRADES"
+        );
+        for candle in &candles {
+            println!(
+                "{:<26} {:>10} {:>10} {:>10} {:>10} {:>12.4} {:>12.1} {:>8}",
+                Utc.timestamp_nanos((candle.timestamp * 1_000_000_000.0) as i64)
+                    .to_rfc3339(),
+                candle.open,
+                candle.high,
+                candle.low,
+                candle.close,
+                candle.volume,
+                candle.vwap,
+                candle.trades
+            );
+        }
+    }
+    0
+}
+
+pub async fn order_book_at_handler(time: &str, json: bool, symbol: &Symbol) -> i32 {
+    let time = match parse_bound(time, "newest") {
+        Ok(result) => result.unwrap_or(f64::MAX),
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let env = match load_env("./env.toml").await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let store = match database::open_store_for(&env.general.database_url, symbol).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let book = match database::order_book::book_at(store.as_ref(), time).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+
+    if json {
+        println!("{}", serde_json::to_string(&book).unwrap_or_default());
+    } else {
+        println!(
+            "received at {}",
+            Utc.timestamp_nanos((book.received_at * 1_000_000_000.0) as i64)
+                .to_rfc3339()
+        );
+        println!(
+            "{:>10} {:>12} | {:>10} {:>12}",
+            "BID", "AMOUNT", "ASK", "AMOUNT"
+        );
+        for i in 0..book.buy.len().max(book.sell.len()) {
+            let bid = match book.buy.get(i) {
+                Some(content) => format!("{:>10} {:>12.4}", content.price, content.amount),
+                None => format!("{:>10} {:>12}", "", ""),
+            };
+            let ask = match book.sell.get(i) {
+                Some(content) => format!("{:>10} {:>12.4}", content.price, content.amount),
+                None => String::new(),
+            };
+            println!("{} | {}", bid, ask);
+        }
+    }
+    0
+}
+
+pub async fn order_book_convert_handler(
+    from: &str,
+    to: &str,
+    dry_run: bool,
+    symbol: &Symbol,
+) -> i32 {
+    let timeline = match parse_timeline(from, to) {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let env = match load_env("./env.toml").await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let store = match database::open_store_for(&env.general.database_url, symbol).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+
+    println!(
+        "{:<20} {:>10} {:>10} {:>10}",
+        "COLLECTION", "SNAPSHOTS", "DIFFS", "REMOVED"
+    );
+    for side in database::BookSide::ALL {
+        match database::order_book::convert(
+            store.as_ref(),
+            side,
+            &timeline,
+            &env.order_book,
+            dry_run,
+        )
+        .await
+        {
+            Ok(report) => println!(
+                "{:<20} {:>10} {:>10} {:>10}",
+                report.collection, report.snapshots, report.diffs, report.removed
+            ),
+            Err(result) => {
+                eprintln!("{}", error_message_colored!("{}", result));
+                return 1;
+            }
+        }
+    }
+    if dry_run {
+        println!(
+            "{}",
+            ok_message_colored!("nothing was written by the dry run.")
+        );
+    }
+    0
+}
+
+///
+/// the catalog of `symbol`, or of every stored symbol when it is not given.
+pub async fn catalog_handler(symbol: Option<&Symbol>, json: bool) -> i32 {
+    let env = match load_env("./env.toml").await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+    let symbols = match symbol {
+        Some(content) => vec![content.clone()],
+        None => match database::catalog::symbols(&env.general.database_url).await {
+            Ok(result) => result,
+            Err(result) => {
+                eprintln!("{}", error_message_colored!("{}", result));
+                return 1;
+            }
+        },
+    };
+    let entries = match database::catalog::catalog(&env.general.database_url, &symbols).await {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", error_message_colored!("{}", result));
+            return 1;
+        }
+    };
+
+    if json {
+        println!("{}", serde_json::to_string(&entries).unwrap_or_default());
+    } else {
+        println!(
+            "{:<24} {:<22} {:>12} {:<26} {:<26}",
+            "SYMBOL", "COLLECTION", "RECORDS", "FIRST", "LAST"
+        );
+        for entry in &entries {
+            println!(
+                "{:<24} {:<22} {:>12} {:<26} {:<26}",
+                entry.symbol.to_string(),
+                entry.collection,
+                entry.records,
+                Utc.timestamp_nanos((entry.first * 1_000_000_000.0) as i64)
+                    .to_rfc3339(),
+                Utc.timestamp_nanos((entry.last * 1_000_000_000.0) as i64)
+                    .to_rfc3339()
+            );
+        }
+    }
+    0
+}
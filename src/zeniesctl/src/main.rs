@@ -0,0 +1,480 @@
+use clap::{Arg, ArgMatches, Command};
+
+mod discovery;
+mod handler;
+
+fn target_args(command: Command<'static>) -> Command<'static> {
+    command
+        .arg(
+            Arg::new("NAME")
+                .multiple_values(true)
+                .help("such as liquid_loggerd, market_viewer or market_maker_alpha"),
+        )
+        .arg(
+            Arg::new("all")
+                .long("all")
+                .conflicts_with("NAME")
+                .help("target every discovered program."),
+        )
+}
+
+fn targets(matched: &ArgMatches) -> (Vec<&str>, bool) {
+    (
+        matched
+            .values_of("NAME")
+            .map(|e| e.collect())
+            .unwrap_or_default(),
+        matched.is_present("all"),
+    )
+}
+
+///
+/// the symbol given to the deepest subcommand, where the global argument is propagated.
+fn symbol(matches: &ArgMatches) -> Result<common::Symbol, String> {
+    let mut matched = matches;
+    while let Some((_, content)) = matched.subcommand() {
+        matched = content;
+    }
+    match matched.value_of("symbol") {
+        Some(content) => common::Symbol::generate_from_string(content),
+        None => Ok(common::Symbol::default()),
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let matches =
+        Command::new(env!("CARGO_PKG_NAME"))
+            .version(env!("CARGO_PKG_VERSION"))
+            .about("discover and control the running zenies daemons.")
+            .arg(
+                Arg::new("symbol")
+                    .long("symbol")
+                    .takes_value(true)
+                    .global(true)
+                    .help("the exchange and the product of the database such as liquid:btc_jpy. liquid:btc_jpy by default."),
+            )
+            .subcommand(
+                Command::new("list")
+                    .about("list the programs found in the etc directory with their health.")
+                    .arg(
+                        Arg::new("json")
+                            .long("json")
+                            .help("print the list as json."),
+                    ),
+            )
+            .subcommand(
+                target_args(Command::new("status").about("show the status."))
+                    .arg(
+                        Arg::new("metrics")
+                            .long("metrics")
+                            .help("show metrics in prometheus text format."),
+                    )
+                    .arg(
+                        Arg::new("json")
+                            .long("json")
+                            .help("print the status as json."),
+                    ),
+            )
+            .subcommand(target_args(
+                Command::new("shutdown").about("shutdown the programs."),
+            ))
+            .subcommand(target_args(
+                Command::new("reload-log-level").about("reload the log level from env.toml."),
+            ))
+            .subcommand(target_args(
+                Command::new("pause").about("stop writing and trading without disconnecting."),
+            ))
+            .subcommand(target_args(
+                Command::new("resume").about("resume the paused programs."),
+            ))
+            .subcommand(target_args(
+                Command::new("tail").about("print the live events of the programs."),
+            ))
+            .subcommand(target_args(
+                Command::new("config").about("show the effective config of the programs."),
+            ))
+            .subcommand(
+                target_args(
+                    Command::new("log-level")
+                        .about("change the logging directive of the running programs."),
+                )
+                .arg(
+                    Arg::new("directive")
+                        .long("directive")
+                        .short('d')
+                        .takes_value(true)
+                        .required(true)
+                        .help("such as info,liquid_loggerd=debug,database=trace"),
+                ),
+            )
+            .subcommand(
+                target_args(
+                    Command::new("spool")
+                        .about("show the records waiting in the spool of liquid_loggerd."),
+                )
+                .arg(
+                    Arg::new("drain")
+                        .long("drain")
+                        .help("replay the spool into the database now."),
+                ),
+            )
+            .subcommand(
+                Command::new("catalog")
+                    .about("list the stored symbols with the range of each collection.")
+                    .arg(
+                        Arg::new("json")
+                            .long("json")
+                            .help("print the catalog as json."),
+                    ),
+            )
+            .subcommand(
+                Command::new("schema")
+                    .about("verify the collections and indexes of the database.")
+                    .arg(
+                        Arg::new("repair")
+                            .long("repair")
+                            .help("create the missing collections and indexes."),
+                    )
+                    .arg(
+                        Arg::new("json")
+                            .long("json")
+                            .help("print the reports as json."),
+                    ),
+            )
+            .subcommand(
+                Command::new("dedupe")
+                    .about("remove the records stored more than once.")
+                    .arg(
+                        Arg::new("COLLECTION")
+                            .required(true)
+                            .help("ticker, executions, order_book_buy or order_book_sell"),
+                    )
+                    .arg(Arg::new("FROM").required(true).help(
+                        "start time in ISO 8601 such as 1996-12-19T16:39:57-08:00, or \"oldest\".",
+                    ))
+                    .arg(Arg::new("TO").required(true).help(
+                        "end time in ISO 8601 such as 1996-12-19T16:39:57-08:00, or \"newest\".",
+                    ))
+                    .arg(
+                        Arg::new("dry-run")
+                            .long("dry-run")
+                            .help("count the duplicates without removing them."),
+                    ),
+            )
+            .subcommand(
+                Command::new("retention")
+                    .about("enforce the retention policies of env.toml once.")
+                    .arg(
+                        Arg::new("dry-run")
+                            .long("dry-run")
+                            .help("report what would be removed without removing it."),
+                    )
+                    .arg(
+                        Arg::new("json")
+                            .long("json")
+                            .help("print the reports as json."),
+                    ),
+            )
+            .subcommand(
+                Command::new("migrate")
+                    .about("show or upgrade the schema versions of the stored records.")
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("status")
+                            .about("count the records of each schema version.")
+                            .arg(
+                                Arg::new("json")
+                                    .long("json")
+                                    .help("print the statuses as json."),
+                            ),
+                    )
+                    .subcommand(
+                        Command::new("up")
+                            .about("upgrade the outdated records to the current schema version.")
+                            .arg(
+                                Arg::new("dry-run")
+                                    .long("dry-run")
+                                    .help("count the outdated records without writing them."),
+                            )
+                            .arg(
+                                Arg::new("json")
+                                    .long("json")
+                                    .help("print the reports as json."),
+                            ),
+                    ),
+            )
+            .subcommand(
+                Command::new("dataset")
+                    .about("inspect or convert the compressed local datasets.")
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("convert")
+                            .about("convert a json file written by data_picker to a dataset.")
+                            .arg(Arg::new("SOURCE").required(true).help("the json file."))
+                            .arg(Arg::new("TARGET").required(true).help("the dataset to write.")),
+                    )
+                    .subcommand(
+                        Command::new("info")
+                            .about("print the header and the blocks of a dataset.")
+                            .arg(Arg::new("PATH").required(true).help("the dataset."))
+                            .arg(
+                                Arg::new("json")
+                                    .long("json")
+                                    .help("print the header and the index as json."),
+                            ),
+                    ),
+            )
+            .subcommand(
+                Command::new("import")
+                    .about("insert the records of a parquet file written by data_picker.")
+                    .arg(
+                        Arg::new("COLLECTION")
+                            .required(true)
+                            .help("ticker, executions, order_book_buy or order_book_sell"),
+                    )
+                    .arg(Arg::new("PATH").required(true).help("the parquet file.")),
+            )
+            .subcommand(
+                Command::new("candles")
+                    .about("rebuild or show the candles built from executions.")
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("rebuild")
+                            .about("rebuild every resolution of the candles in the days of a range.")
+                            .arg(Arg::new("FROM").required(true).help(
+                                "start time in ISO 8601 such as 1996-12-19T16:39:57-08:00, or \"oldest\".",
+                            ))
+                            .arg(Arg::new("TO").required(true).help(
+                                "end time in ISO 8601 such as 1996-12-19T16:39:57-08:00, or \"newest\".",
+                            )),
+                    )
+                    .subcommand(
+                        Command::new("show")
+                            .about("print the candles of a range.")
+                            .arg(
+                                Arg::new("RESOLUTION")
+                                    .required(true)
+                                    .help("1s, 1m, 5m, 1h or 1d"),
+                            )
+                            .arg(Arg::new("FROM").required(true).help(
+                                "start time in ISO 8601 such as 1996-12-19T16:39:57-08:00, or \"oldest\".",
+                            ))
+                            .arg(Arg::new("TO").required(true).help(
+                                "end time in ISO 8601 such as 1996-12-19T16:39:57-08:00, or \"newest\".",
+                            ))
+                            .arg(
+                                Arg::new("json")
+                                    .long("json")
+                                    .help("print the candles as json."),
+                            ),
+                    ),
+            )
+            .subcommand(
+                Command::new("order-book")
+                    .about("rebuild the order book at an instant or convert stored snapshots to diffs.")
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("at")
+                            .about("print both sides of the order book at an instant.")
+                            .arg(Arg::new("TIME").required(true).help(
+                                "time in ISO 8601 such as 1996-12-19T16:39:57-08:00, or \"newest\".",
+                            ))
+                            .arg(
+                                Arg::new("json")
+                                    .long("json")
+                                    .help("print the order book as json."),
+                            ),
+                    )
+                    .subcommand(
+                        Command::new("convert")
+                            .about("replace the snapshots of a range by the diffs and the snapshots of the configured interval.")
+                            .arg(Arg::new("FROM").required(true).help(
+                                "start time in ISO 8601 such as 1996-12-19T16:39:57-08:00, or \"oldest\".",
+                            ))
+                            .arg(Arg::new("TO").required(true).help(
+                                "end time in ISO 8601 such as 1996-12-19T16:39:57-08:00, or \"newest\".",
+                            ))
+                            .arg(
+                                Arg::new("dry-run")
+                                    .long("dry-run")
+                                    .help("count the records without writing or removing them."),
+                            ),
+                    ),
+            )
+            .get_matches();
+
+    let symbol = match symbol(&matches) {
+        Ok(result) => result,
+        Err(result) => {
+            eprintln!("{}", common::error_message_colored!("{}", result));
+            std::process::exit(1);
+        }
+    };
+    let code = match matches.subcommand() {
+        Some(("list", matched)) => handler::list_handler(matched.is_present("json")).await,
+        Some(("status", matched)) => {
+            let (names, all) = targets(matched);
+            handler::status_handler(
+                &names,
+                all,
+                matched.is_present("json"),
+                matched.is_present("metrics"),
+            )
+            .await
+        }
+        Some(("shutdown", matched)) => {
+            let (names, all) = targets(matched);
+            handler::shutdown_handler(&names, all).await
+        }
+        Some(("reload-log-level", matched)) => {
+            let (names, all) = targets(matched);
+            handler::control_handler(&names, all, ipc::Command::reload("./env.toml")).await
+        }
+        Some(("pause", matched)) => {
+            let (names, all) = targets(matched);
+            handler::control_handler(&names, all, ipc::Command::pause()).await
+        }
+        Some(("resume", matched)) => {
+            let (names, all) = targets(matched);
+            handler::control_handler(&names, all, ipc::Command::resume()).await
+        }
+        Some(("tail", matched)) => {
+            let (names, all) = targets(matched);
+            handler::tail_handler(&names, all).await
+        }
+        Some(("config", matched)) => {
+            let (names, all) = targets(matched);
+            handler::config_handler(&names, all).await
+        }
+        Some(("log-level", matched)) => {
+            let (names, all) = targets(matched);
+            let directive = matched.value_of("directive").unwrap_or_default();
+            handler::control_handler(&names, all, ipc::Command::log_level(directive)).await
+        }
+        Some(("spool", matched)) => {
+            let (names, all) = targets(matched);
+            handler::control_handler(
+                &names,
+                all,
+                ipc::Command::spool(matched.is_present("drain")),
+            )
+            .await
+        }
+        Some(("catalog", matched)) => {
+            handler::catalog_handler(
+                matched.value_of("symbol").map(|_| &symbol),
+                matched.is_present("json"),
+            )
+            .await
+        }
+        Some(("schema", matched)) => {
+            handler::schema_handler(
+                matched.is_present("repair"),
+                matched.is_present("json"),
+                &symbol,
+            )
+            .await
+        }
+        Some(("dedupe", matched)) => {
+            handler::dedupe_handler(
+                matched.value_of("COLLECTION").unwrap_or_default(),
+                matched.value_of("FROM").unwrap_or_default(),
+                matched.value_of("TO").unwrap_or_default(),
+                matched.is_present("dry-run"),
+                &symbol,
+            )
+            .await
+        }
+        Some(("retention", matched)) => {
+            handler::retention_handler(
+                matched.is_present("dry-run"),
+                matched.is_present("json"),
+                &symbol,
+            )
+            .await
+        }
+        Some(("migrate", matched)) => match matched.subcommand() {
+            Some(("status", matched)) => {
+                handler::migrate_status_handler(matched.is_present("json"), &symbol).await
+            }
+            Some(("up", matched)) => {
+                handler::migrate_up_handler(
+                    matched.is_present("dry-run"),
+                    matched.is_present("json"),
+                    &symbol,
+                )
+                .await
+            }
+            _ => 0,
+        },
+        Some(("dataset", matched)) => match matched.subcommand() {
+            Some(("convert", matched)) => {
+                handler::dataset_convert_handler(
+                    matched.value_of("SOURCE").unwrap_or_default(),
+                    matched.value_of("TARGET").unwrap_or_default(),
+                )
+                .await
+            }
+            Some(("info", matched)) => {
+                handler::dataset_info_handler(
+                    matched.value_of("PATH").unwrap_or_default(),
+                    matched.is_present("json"),
+                )
+                .await
+            }
+            _ => 0,
+        },
+        Some(("import", matched)) => {
+            handler::import_handler(
+                matched.value_of("COLLECTION").unwrap_or_default(),
+                matched.value_of("PATH").unwrap_or_default(),
+                &symbol,
+            )
+            .await
+        }
+        Some(("candles", matched)) => match matched.subcommand() {
+            Some(("rebuild", matched)) => {
+                handler::candles_rebuild_handler(
+                    matched.value_of("FROM").unwrap_or_default(),
+                    matched.value_of("TO").unwrap_or_default(),
+                    &symbol,
+                )
+                .await
+            }
+            Some(("show", matched)) => {
+                handler::candles_show_handler(
+                    matched.value_of("RESOLUTION").unwrap_or_default(),
+                    matched.value_of("FROM").unwrap_or_default(),
+                    matched.value_of("TO").unwrap_or_default(),
+                    matched.is_present("json"),
+                    &symbol,
+                )
+                .await
+            }
+            _ => 0,
+        },
+        Some(("order-book", matched)) => match matched.subcommand() {
+            Some(("at", matched)) => {
+                handler::order_book_at_handler(
+                    matched.value_of("TIME").unwrap_or_default(),
+                    matched.is_present("json"),
+                    &symbol,
+                )
+                .await
+            }
+            Some(("convert", matched)) => {
+                handler::order_book_convert_handler(
+                    matched.value_of("FROM").unwrap_or_default(),
+                    matched.value_of("TO").unwrap_or_default(),
+                    matched.is_present("dry-run"),
+                    &symbol,
+                )
+                .await
+            }
+            _ => 0,
+        },
+        _ => 0,
+    };
+    std::process::exit(code);
+}